@@ -4,7 +4,7 @@
 
 use rustc_hir::def_id::LocalDefId;
 use rustc_index::{IndexSlice, IndexVec};
-use rustc_middle::mir::{Body, Promoted};
+use rustc_middle::mir::{Body, BorrowCheckResult, Promoted};
 use rustc_middle::ty::TyCtxt;
 
 pub use super::constraints::OutlivesConstraint;
@@ -106,3 +106,18 @@ pub fn get_body_with_borrowck_facts(
     let promoted: &IndexSlice<_, _> = &promoted.borrow();
     *super::do_mir_borrowck(tcx, input_body, promoted, Some(options)).1.unwrap()
 }
+
+/// Borrow-checks the given body like the `mir_borrowck` query does, and additionally returns
+/// the facts determined by the [`ConsumerOptions`], like [`get_body_with_borrowck_facts`].
+///
+/// This is meant for overrides of the `mir_borrowck` query that need the facts of the bodies
+/// the compiler borrow-checks, without borrow-checking them a second time. No facts are
+/// returned for bodies that are not borrow-checked because they are injected or tainted by
+/// errors.
+pub fn mir_borrowck_with_facts(
+    tcx: TyCtxt<'_>,
+    def: LocalDefId,
+    options: ConsumerOptions,
+) -> (&BorrowCheckResult<'_>, Option<Box<BodyWithBorrowckFacts<'_>>>) {
+    super::mir_borrowck_with_options(tcx, def, Some(options))
+}
@@ -104,6 +104,16 @@ pub fn provide(providers: &mut Providers) {
 }
 
 fn mir_borrowck(tcx: TyCtxt<'_>, def: LocalDefId) -> &BorrowCheckResult<'_> {
+    mir_borrowck_with_options(tcx, def, None).0
+}
+
+/// Implements the `mir_borrowck` query, additionally returning the [`BodyWithBorrowckFacts`]
+/// requested by `consumer_options`. No facts are returned if the body isn't borrow-checked.
+fn mir_borrowck_with_options(
+    tcx: TyCtxt<'_>,
+    def: LocalDefId,
+    consumer_options: Option<ConsumerOptions>,
+) -> (&BorrowCheckResult<'_>, Option<Box<BodyWithBorrowckFacts<'_>>>) {
     let (input_body, promoted) = tcx.mir_promoted(def);
     debug!("run query mir_borrowck: {}", tcx.def_path_str(def));
 
@@ -118,14 +128,15 @@ fn mir_borrowck(tcx: TyCtxt<'_>, def: LocalDefId) -> &BorrowCheckResult<'_> {
             used_mut_upvars: SmallVec::new(),
             tainted_by_errors: input_body.tainted_by_errors,
         };
-        return tcx.arena.alloc(result);
+        return (tcx.arena.alloc(result), None);
     }
 
     let promoted: &IndexSlice<_, _> = &promoted.borrow();
-    let opt_closure_req = do_mir_borrowck(tcx, input_body, promoted, None).0;
+    let (opt_closure_req, body_with_facts) =
+        do_mir_borrowck(tcx, input_body, promoted, consumer_options);
     debug!("mir_borrowck done");
 
-    tcx.arena.alloc(opt_closure_req)
+    (tcx.arena.alloc(opt_closure_req), body_with_facts)
 }
 
 /// Perform the actual borrow checking.
@@ -107,6 +107,10 @@ const fn jit_bin(config: &'static str, source: &'static str, args: &'static str)
         runner.run_out_command("gen_block_iterate", &[]);
     }),
     TestCase::build_bin_and_run("aot.raw-dylib", "example/raw-dylib.rs", &[]),
+    TestCase::custom("aot.integrated_asm", &|runner| {
+        runner.run_rustc(["example/integrated_asm.rs", "-Cllvm-args=disable_asm_fallback=true"]);
+        runner.run_out_command("integrated_asm", &[]);
+    }),
 ];
 
 pub(crate) static RAND_REPO: GitRepo = GitRepo::github(
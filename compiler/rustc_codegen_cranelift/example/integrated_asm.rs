@@ -0,0 +1,145 @@
+// Tests inline asm and global_asm! assembled by the integrated assembler. This is compiled with
+// `-Cllvm-args=disable_asm_fallback=true` to ensure that no external assembler gets used.
+
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+use std::arch::asm;
+#[cfg(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+use std::arch::global_asm;
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+global_asm!(
+    ".text",
+    ".globl integrated_asm_sum",
+    ".type integrated_asm_sum,@function",
+    "integrated_asm_sum:",
+    "    push rbx",
+    "    push r12",
+    // Keep the stack aligned for the calls below
+    "    push rax",
+    "    mov rbx, rdi",
+    "    xor r12d, r12d",
+    "    test rbx, rbx",
+    "    jz 3f",
+    "2:",
+    "    mov rdi, rbx",
+    "    call integrated_asm_add_one",
+    "    add r12, rax",
+    "    dec rbx",
+    "    jnz 2b",
+    "3:",
+    "    mov rax, qword ptr [rip + integrated_asm_multiplier]",
+    "    imul rax, r12",
+    "    pop rcx",
+    "    pop r12",
+    "    pop rbx",
+    "    ret",
+    ".size integrated_asm_sum, .-integrated_asm_sum",
+    "",
+    ".section .rodata",
+    ".p2align 3",
+    "integrated_asm_multiplier:",
+    "    .quad 3",
+    ".text",
+);
+
+#[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+global_asm!(
+    ".text",
+    ".globl integrated_asm_sum",
+    ".type integrated_asm_sum,%function",
+    ".p2align 2",
+    "integrated_asm_sum:",
+    "    stp x29, x30, [sp, #-32]!",
+    "    mov x29, sp",
+    "    stp x19, x20, [sp, #16]",
+    "    mov x19, x0",
+    "    mov x20, #0",
+    "    cbz x19, 3f",
+    "2:",
+    "    mov x0, x19",
+    "    bl integrated_asm_add_one",
+    "    add x20, x20, x0",
+    "    subs x19, x19, #1",
+    "    b.ne 2b",
+    "3:",
+    "    adrp x8, integrated_asm_multiplier",
+    "    ldr x8, [x8, :lo12:integrated_asm_multiplier]",
+    "    mul x0, x20, x8",
+    "    ldp x19, x20, [sp, #16]",
+    "    ldp x29, x30, [sp], #32",
+    "    ret",
+    ".size integrated_asm_sum, .-integrated_asm_sum",
+    "",
+    ".section .rodata",
+    ".p2align 3",
+    "integrated_asm_multiplier:",
+    "    .quad 3",
+    ".text",
+);
+
+#[cfg(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+extern "C" {
+    fn integrated_asm_sum(n: u64) -> u64;
+}
+
+#[cfg(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[no_mangle]
+extern "C" fn integrated_asm_add_one(x: u64) -> u64 {
+    x + 1
+}
+
+#[cfg(target_arch = "x86_64")]
+fn sum(values: &[u64]) -> u64 {
+    assert!(!values.is_empty());
+    let sum: u64;
+    unsafe {
+        asm!(
+            "xor {sum:e}, {sum:e}",
+            "2:",
+            "dec {i}",
+            "add {sum}, qword ptr [{ptr} + {i}*8]",
+            "test {i}, {i}",
+            "jnz 2b",
+            ptr = in(reg) values.as_ptr(),
+            i = inout(reg) values.len() => _,
+            sum = out(reg) sum,
+            options(nostack, readonly),
+        );
+    }
+    sum
+}
+
+#[cfg(target_arch = "aarch64")]
+fn sum(values: &[u64]) -> u64 {
+    assert!(!values.is_empty());
+    let sum: u64;
+    unsafe {
+        asm!(
+            "mov {sum}, #0",
+            "2:",
+            "sub {i}, {i}, #1",
+            "ldr {tmp}, [{ptr}, {i}, lsl #3]",
+            "add {sum}, {sum}, {tmp}",
+            "cbnz {i}, 2b",
+            ptr = in(reg) values.as_ptr(),
+            i = inout(reg) values.len() => _,
+            tmp = out(reg) _,
+            sum = out(reg) sum,
+            options(nostack, readonly),
+        );
+    }
+    sum
+}
+
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+fn main() {
+    assert_eq!(sum(&[1, 2, 3, 4]), 10);
+    assert_eq!(sum(&[u64::MAX, 2]), 1);
+
+    // (2 + 3 + ... + 11) * 3
+    #[cfg(target_os = "linux")]
+    assert_eq!(unsafe { integrated_asm_sum(10) }, 195);
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn main() {}
@@ -231,6 +231,54 @@ unsafe fn test_vaddvq_f32() {
     assert_eq!(r, e);
 }
 
+#[cfg(target_arch = "aarch64")]
+unsafe fn test_vmaxvq_f32() {
+    // AArch64 llvm intrinsic: llvm.aarch64.neon.fmaxv.f32.v4f32
+    let a = f32x4::from([1., -2., 5., 3.]);
+    let r = vmaxvq_f32(transmute(a));
+    assert_eq!(r, 5.);
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn test_vminvq_f32() {
+    // AArch64 llvm intrinsic: llvm.aarch64.neon.fminv.f32.v4f32
+    let a = f32x4::from([1., -2., 5., 3.]);
+    let r = vminvq_f32(transmute(a));
+    assert_eq!(r, -2.);
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn test_vaddvq_s32() {
+    // AArch64 llvm intrinsic: llvm.aarch64.neon.saddv.i32.v4i32
+    let a = i32x4::from([1, -2, 3, 4]);
+    let r = vaddvq_s32(transmute(a));
+    assert_eq!(r, 6);
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn test_vaddvq_u8() {
+    // AArch64 llvm intrinsic: llvm.aarch64.neon.uaddv.i32.v16i8
+    let a = u8x16::from([1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]);
+    let r = vaddvq_u8(transmute(a));
+    assert_eq!(r, 136);
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn test_vaddlvq_u8() {
+    // AArch64 llvm intrinsic: llvm.aarch64.neon.uaddlv.i32.v16i8
+    let a = u8x16::splat(0xff);
+    let r = vaddlvq_u8(transmute(a));
+    assert_eq!(r, 16 * 0xff);
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn test_vaddlvq_s16() {
+    // AArch64 llvm intrinsic: llvm.aarch64.neon.saddlv.i32.v8i16
+    let a = i16x8::from([i16::MIN, i16::MIN, -1, -1, 1, 2, 3, 4]);
+    let r = vaddlvq_s16(transmute(a));
+    assert_eq!(r, 2 * i16::MIN as i32 + 8);
+}
+
 #[cfg(target_arch = "aarch64")]
 unsafe fn test_vrndnq_f32() {
     // AArch64 llvm intrinsic: llvm.aarch64.neon.frintn.v4f32
@@ -271,6 +319,12 @@ fn main() {
         test_vmaxq_f32();
         test_vminq_f32();
         test_vaddvq_f32();
+        test_vmaxvq_f32();
+        test_vminvq_f32();
+        test_vaddvq_s32();
+        test_vaddvq_u8();
+        test_vaddlvq_u8();
+        test_vaddlvq_s16();
         test_vrndnq_f32();
     }
 }
@@ -123,6 +123,9 @@ fn main() {
         test_simd();
     }
 
+    #[cfg(all(not(jit), any(target_arch = "x86_64", target_arch = "aarch64")))]
+    test_asm();
+
     Box::pin(
         #[coroutine]
         move |mut _task_context| {
@@ -209,6 +212,48 @@ fn rust_call_abi() {
 #[allow(improper_ctypes_definitions)]
 extern "C" fn foo(_a: I64X2) {}
 
+#[cfg(all(not(jit), any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn test_asm() {
+    let mut x: u64 = 3;
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        std::arch::asm!("add {0}, {1}", inout(reg) x, in(reg) 4u64);
+    }
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        std::arch::asm!("add {0}, {0}, {1}", inout(reg) x, in(reg) 4u64);
+    }
+    assert_eq!(x, 7);
+
+    #[cfg(target_os = "linux")]
+    assert_eq!(unsafe { global_asm_add_one(41) }, 42);
+}
+
+#[cfg(all(not(jit), any(target_arch = "x86_64", target_arch = "aarch64"), target_os = "linux"))]
+extern "C" {
+    fn global_asm_add_one(x: u64) -> u64;
+}
+
+#[cfg(all(not(jit), target_arch = "x86_64", target_os = "linux"))]
+std::arch::global_asm! {
+    "
+    .global global_asm_add_one
+    global_asm_add_one:
+    lea rax, [rdi + 1]
+    ret
+    "
+}
+
+#[cfg(all(not(jit), target_arch = "aarch64", target_os = "linux"))]
+std::arch::global_asm! {
+    "
+    .global global_asm_add_one
+    global_asm_add_one:
+    add x0, x0, #1
+    ret
+    "
+}
+
 #[cfg(target_arch = "x86_64")]
 #[target_feature(enable = "sse4.2")]
 #[cfg(not(jit))]
@@ -264,6 +309,24 @@ unsafe fn test_simd() {
     test_mm256_shuffle_epi8();
     test_mm256_permute2x128_si256();
     test_mm256_permutevar8x32_epi32();
+    test_mm_sll_epi32();
+    test_mm_sign_epi16();
+    test_mm_blendv_epi8();
+    test_mm_srlv_epi32();
+    test_mm256_hadd_epi16();
+    test_mm_hadds_epi16();
+    test_mm_sra_epi32();
+    test_mm_srav_epi32();
+    test_mm256_sllv_epi64();
+    test_mm_max_min_pd();
+    test_mm256_abs_epi32();
+    test_mm256_avg_epu8();
+    test_mm_blendv_ps();
+    test_mm_mulhi_epi16();
+    test_mm_mulhi_epu16();
+    test_mm256_mulhrs_epi16();
+    test_mm_movemask_ps();
+    test_mm256_movemask_ps();
 
     #[rustfmt::skip]
     let mask1 = _mm_movemask_epi8(dbg!(_mm_setr_epi8(255u8 as i8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0)));
@@ -505,6 +568,190 @@ unsafe fn test_mm256_permutevar8x32_epi32() {
     assert_eq_m256i(r, e);
 }
 
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+unsafe fn test_mm_sll_epi32() {
+    let a = _mm_setr_epi32(1, 2, -1, 0x4000_0000);
+    let r = _mm_sll_epi32(a, _mm_set_epi64x(0, 4));
+    let e = _mm_setr_epi32(16, 32, -16, 0);
+    assert_eq_m128i(r, e);
+
+    let r = _mm_sll_epi32(a, _mm_set_epi64x(0, 32));
+    assert_eq_m128i(r, _mm_setzero_si128());
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "ssse3")]
+unsafe fn test_mm_sign_epi16() {
+    let a = _mm_setr_epi16(1, 2, 3, 4, 5, 6, 7, 8);
+    let b = _mm_setr_epi16(-1, 0, 1, -5, 0, 3, -7, i16::MIN);
+    let r = _mm_sign_epi16(a, b);
+    let e = _mm_setr_epi16(-1, 0, 3, -4, 0, 6, -7, -8);
+    assert_eq_m128i(r, e);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse4.1")]
+unsafe fn test_mm_blendv_epi8() {
+    let a = _mm_set1_epi8(1);
+    let b = _mm_set1_epi8(2);
+    let mask = _mm_setr_epi8(0, -1, 0, -128, 127, -2, 0, 0, 1, -1, 0, 0, 0, 0, 0, -1);
+    let r = _mm_blendv_epi8(a, b, mask);
+    let e = _mm_setr_epi8(1, 2, 1, 2, 1, 2, 1, 1, 1, 2, 1, 1, 1, 1, 1, 2);
+    assert_eq_m128i(r, e);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn test_mm_srlv_epi32() {
+    let a = _mm_setr_epi32(16, 16, 16, -1);
+    let count = _mm_setr_epi32(1, 4, 32, 31);
+    let r = _mm_srlv_epi32(a, count);
+    let e = _mm_setr_epi32(8, 1, 0, 1);
+    assert_eq_m128i(r, e);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn test_mm256_hadd_epi16() {
+    let a = _mm256_setr_epi16(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15);
+    #[rustfmt::skip]
+    let b = _mm256_setr_epi16(
+        100, 101, 102, 103, 104, 105, 106, 107,
+        108, 109, 110, 111, 112, 113, 114, 115,
+    );
+    let r = _mm256_hadd_epi16(a, b);
+    #[rustfmt::skip]
+    let e = _mm256_setr_epi16(
+        1, 5, 9, 13, 201, 205, 209, 213,
+        17, 21, 25, 29, 217, 221, 225, 229,
+    );
+    assert_eq_m256i(r, e);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "ssse3")]
+unsafe fn test_mm_hadds_epi16() {
+    let a = _mm_setr_epi16(i16::MAX, 1, 2, 3, -4, -5, i16::MIN, -1);
+    let b = _mm_setr_epi16(1, 1, 2, 2, 3, 3, 4, 4);
+    let r = _mm_hadds_epi16(a, b);
+    let e = _mm_setr_epi16(i16::MAX, 5, -9, i16::MIN, 2, 4, 6, 8);
+    assert_eq_m128i(r, e);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+unsafe fn test_mm_sra_epi32() {
+    let a = _mm_setr_epi32(-16, 16, i32::MIN, 1);
+    let r = _mm_sra_epi32(a, _mm_set_epi64x(0, 2));
+    let e = _mm_setr_epi32(-4, 4, i32::MIN >> 2, 0);
+    assert_eq_m128i(r, e);
+
+    let r = _mm_sra_epi32(a, _mm_set_epi64x(0, 40));
+    assert_eq_m128i(r, _mm_setr_epi32(-1, 0, -1, 0));
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn test_mm_srav_epi32() {
+    let a = _mm_setr_epi32(-16, 16, -1, 1 << 30);
+    let count = _mm_setr_epi32(2, 40, 31, 32);
+    let r = _mm_srav_epi32(a, count);
+    let e = _mm_setr_epi32(-4, 0, -1, 0);
+    assert_eq_m128i(r, e);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn test_mm256_sllv_epi64() {
+    let a = _mm256_setr_epi64x(1, 2, 3, -1);
+    let count = _mm256_setr_epi64x(0, 1, 64, 63);
+    let r = _mm256_sllv_epi64(a, count);
+    let e = _mm256_setr_epi64x(1, 4, 0, i64::MIN);
+    assert_eq_m256i(r, e);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+unsafe fn test_mm_max_min_pd() {
+    let a = _mm_setr_pd(1.0, 5.0);
+    let b = _mm_setr_pd(4.0, 2.0);
+    assert_eq_m128d(_mm_max_pd(a, b), _mm_setr_pd(4.0, 5.0));
+    assert_eq_m128d(_mm_min_pd(a, b), _mm_setr_pd(1.0, 2.0));
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn test_mm256_abs_epi32() {
+    let a = _mm256_setr_epi32(0, -1, 2, -3, i32::MAX, i32::MIN, -7, 8);
+    let r = _mm256_abs_epi32(a);
+    let e = _mm256_setr_epi32(0, 1, 2, 3, i32::MAX, i32::MIN, 7, 8);
+    assert_eq_m256i(r, e);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn test_mm256_avg_epu8() {
+    let r = _mm256_avg_epu8(_mm256_set1_epi8(3), _mm256_set1_epi8(8));
+    assert_eq_m256i(r, _mm256_set1_epi8(6));
+
+    // The intermediate sum doesn't fit in a lane.
+    let r = _mm256_avg_epu8(_mm256_set1_epi8(-1), _mm256_set1_epi8(-2));
+    assert_eq_m256i(r, _mm256_set1_epi8(-1));
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse4.1")]
+unsafe fn test_mm_blendv_ps() {
+    let a = _mm_setr_ps(1.0, 2.0, 3.0, 4.0);
+    let b = _mm_setr_ps(5.0, 6.0, 7.0, 8.0);
+    let mask = _mm_setr_ps(0.0, -0.0, -1.0, 1.0);
+    let r = _mm_blendv_ps(a, b, mask);
+    assert_eq!(std::mem::transmute::<_, [f32; 4]>(r), [1.0, 6.0, 7.0, 4.0]);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+unsafe fn test_mm_mulhi_epi16() {
+    let a = _mm_set1_epi16(1000);
+    let b = _mm_setr_epi16(1000, -1000, 100, 65, 66, i16::MIN, -1, 0);
+    let r = _mm_mulhi_epi16(a, b);
+    let e = _mm_setr_epi16(15, -16, 1, 0, 1, -500, -1, 0);
+    assert_eq_m128i(r, e);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+unsafe fn test_mm_mulhi_epu16() {
+    let a = _mm_set1_epi16(-1);
+    let b = _mm_setr_epi16(-1, 2, 1, 0, -1, 2, 1, 0);
+    let r = _mm_mulhi_epu16(a, b);
+    let e = _mm_setr_epi16(-2, 1, 0, 0, -2, 1, 0, 0);
+    assert_eq_m128i(r, e);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn test_mm256_mulhrs_epi16() {
+    let a = _mm256_set1_epi16(0x4000);
+    let r = _mm256_mulhrs_epi16(a, a);
+    assert_eq_m256i(r, _mm256_set1_epi16(0x2000));
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse")]
+unsafe fn test_mm_movemask_ps() {
+    let r = _mm_movemask_ps(_mm_setr_ps(-1.0, 2.0, -0.0, 4.0));
+    assert_eq!(r, 0b0101);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx")]
+unsafe fn test_mm256_movemask_ps() {
+    let r = _mm256_movemask_ps(_mm256_setr_ps(-1.0, 1.0, -1.0, 1.0, 1.0, 1.0, 1.0, -1.0));
+    assert_eq!(r, 0b1000_0101);
+}
+
 #[cfg(target_arch = "x86_64")]
 #[target_feature(enable = "avx2")]
 #[cfg(not(jit))]
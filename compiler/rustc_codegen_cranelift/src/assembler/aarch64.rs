@@ -0,0 +1,975 @@
+//! Encoding of AArch64 instructions.
+//!
+//! The supported instructions are:
+//!
+//! * `mov`, `movz`, `movn`, `movk`, `mvn` and `neg`
+//! * `add`, `adds`, `sub`, `subs`, `cmp` and `cmn` with immediates or shifted registers
+//! * `and`, `ands`, `orr`, `eor`, `bic`, `bics`, `orn`, `eon` and `tst` with bitmask
+//!   immediates or shifted registers
+//! * `lsl`, `lsr`, `asr`, `ror`, `ubfm`, `sbfm`, `uxtb`, `uxth`, `sxtb`, `sxth` and `sxtw`
+//! * `mul`, `madd`, `msub`, `mneg`, `smulh`, `umulh`, `udiv` and `sdiv`
+//! * `csel`, `csinc`, `csinv`, `csneg`, `cset`, `csetm` and `cinc`
+//! * `ldr`, `str`, `ldrb`, `strb`, `ldrh`, `strh`, `ldrsw`, `ldur`, `stur`, `ldp` and `stp`
+//!   for general purpose and SIMD registers
+//! * `b`, `bl`, `b.cond`, `cbz`, `cbnz`, `tbz`, `tbnz`, `br`, `blr` and `ret`
+//! * `adr` and `adrp` with `:lo12:` for the low bits of a symbol in `add` and loads and stores,
+//!   which are only supported for ELF
+//! * `nop`, `brk`, `svc`, `hvc`, `udf`, `hint`, `bti`, `paciasp`, `autiasp`, `yield`, `wfe`,
+//!   `wfi`, `sev`, `isb`, `dmb` and `dsb`
+//!
+//! Branches to other sections are only supported for `b` and `bl`.
+
+use super::{Assembler, Expr, FixupKind, fits_signed, split_operands};
+
+pub(super) const NOP: u32 = 0xd503201f;
+
+#[derive(Copy, Clone, PartialEq)]
+enum RegKind {
+    X,
+    W,
+    B,
+    H,
+    S,
+    D,
+    Q,
+}
+
+#[derive(Copy, Clone)]
+struct Reg {
+    num: u32,
+    kind: RegKind,
+    /// Whether register 31 is `sp` or `wsp` rather than `xzr` or `wzr`.
+    sp: bool,
+}
+
+impl Reg {
+    fn is_gpr(self) -> bool {
+        matches!(self.kind, RegKind::X | RegKind::W)
+    }
+
+    fn sf(self) -> u32 {
+        u32::from(self.kind == RegKind::X)
+    }
+
+    fn is_zr(self) -> bool {
+        self.num == 31 && !self.sp
+    }
+}
+
+fn parse_reg(name: &str) -> Option<Reg> {
+    let name = name.trim().to_ascii_lowercase();
+    let gpr = |num, kind| Some(Reg { num, kind, sp: false });
+    match &*name {
+        "sp" => return Some(Reg { num: 31, kind: RegKind::X, sp: true }),
+        "wsp" => return Some(Reg { num: 31, kind: RegKind::W, sp: true }),
+        "xzr" => return gpr(31, RegKind::X),
+        "wzr" => return gpr(31, RegKind::W),
+        "fp" => return gpr(29, RegKind::X),
+        "lr" => return gpr(30, RegKind::X),
+        "ip0" => return gpr(16, RegKind::X),
+        "ip1" => return gpr(17, RegKind::X),
+        _ => {}
+    }
+    let kind = match name.as_bytes().first()? {
+        b'x' => RegKind::X,
+        b'w' => RegKind::W,
+        b'b' => RegKind::B,
+        b'h' => RegKind::H,
+        b's' => RegKind::S,
+        b'd' => RegKind::D,
+        // Saving and restoring a whole vector register uses `v0` rather than `q0`.
+        b'q' | b'v' => RegKind::Q,
+        _ => return None,
+    };
+    let num = name[1..].parse::<u32>().ok()?;
+    let max = if kind == RegKind::X || kind == RegKind::W { 30 } else { 31 };
+    if num > max || name[1..].starts_with('+') {
+        return None;
+    }
+    gpr(num, kind)
+}
+
+fn reg(operand: &str) -> Result<Reg, String> {
+    parse_reg(operand).ok_or_else(|| format!("expected register, found `{operand}`"))
+}
+
+fn gpr(operand: &str) -> Result<Reg, String> {
+    let reg = reg(operand)?;
+    if !reg.is_gpr() {
+        return Err(format!("expected general purpose register, found `{operand}`"));
+    }
+    Ok(reg)
+}
+
+/// Parse general purpose registers which must all have the same size.
+fn gprs<const N: usize>(operands: &[&str]) -> Result<[Reg; N], String> {
+    if operands.len() < N {
+        return Err(format!("expected {N} registers"));
+    }
+    let mut regs = [Reg { num: 0, kind: RegKind::X, sp: false }; N];
+    for (reg, operand) in regs.iter_mut().zip(operands) {
+        *reg = gpr(operand)?;
+    }
+    if regs.iter().any(|reg| reg.kind != regs[0].kind) {
+        return Err("register size mismatch".to_owned());
+    }
+    Ok(regs)
+}
+
+fn no_sp(regs: &[Reg]) -> Result<(), String> {
+    if regs.iter().any(|reg| reg.sp) {
+        return Err("`sp` is not allowed here".to_owned());
+    }
+    Ok(())
+}
+
+fn imm(asm: &Assembler, operand: &str) -> Result<i64, String> {
+    let operand = operand.trim();
+    asm.parse_constant(operand.strip_prefix('#').unwrap_or(operand))
+}
+
+/// Parse `:lo12:symbol` with an optional `#` prefix.
+fn lo12(asm: &Assembler, operand: &str) -> Result<Option<Expr>, String> {
+    let operand = operand.trim();
+    match operand.strip_prefix('#').unwrap_or(operand).strip_prefix(":lo12:") {
+        Some(symbol) => Ok(Some(asm.parse_expr(symbol)?)),
+        None => Ok(None),
+    }
+}
+
+fn label(asm: &Assembler, operand: &str) -> Result<Expr, String> {
+    let expr = asm.parse_expr(operand)?;
+    if expr.symbol.is_none() {
+        return Err(format!("expected label, found `{operand}`"));
+    }
+    Ok(expr)
+}
+
+#[derive(Copy, Clone)]
+enum Shift {
+    Lsl,
+    Lsr,
+    Asr,
+    Ror,
+}
+
+/// Parse an optional shift like `lsl #12`.
+fn shift(asm: &Assembler, operand: Option<&&str>) -> Result<Option<(Shift, u32)>, String> {
+    let Some(operand) = operand else {
+        return Ok(None);
+    };
+    let lower = operand.trim().to_ascii_lowercase();
+    let (kind, amount) = lower.split_at(lower.len().min(3));
+    let kind = match kind {
+        "lsl" => Shift::Lsl,
+        "lsr" => Shift::Lsr,
+        "asr" => Shift::Asr,
+        "ror" => Shift::Ror,
+        _ => return Err(format!("expected shift, found `{operand}`")),
+    };
+    let amount = imm(asm, amount)?;
+    if !(0..64).contains(&amount) {
+        return Err(format!("invalid shift amount {amount}"));
+    }
+    Ok(Some((kind, amount as u32)))
+}
+
+fn condition_code(cond: &str) -> Option<u32> {
+    Some(match &*cond.trim().to_ascii_lowercase() {
+        "eq" => 0,
+        "ne" => 1,
+        "cs" | "hs" => 2,
+        "cc" | "lo" => 3,
+        "mi" => 4,
+        "pl" => 5,
+        "vs" => 6,
+        "vc" => 7,
+        "hi" => 8,
+        "ls" => 9,
+        "ge" => 10,
+        "lt" => 11,
+        "gt" => 12,
+        "le" => 13,
+        "al" => 14,
+        "nv" => 15,
+        _ => return None,
+    })
+}
+
+/// Encode `imm` as a logical immediate for a register of `width` bits. Returns the `N`, `immr` and
+/// `imms` fields.
+fn encode_bitmask(imm: u64, width: u32) -> Option<(u32, u32, u32)> {
+    let imm = if width == 32 { (imm & 0xffff_ffff) | (imm << 32) } else { imm };
+    if imm == 0 || imm == u64::MAX {
+        return None;
+    }
+
+    // Find the smallest element size for which `imm` is a repetition of the element.
+    let mut size = 64;
+    while size > 2 {
+        let half = size / 2;
+        let mask = (1u64 << half) - 1;
+        if imm & mask != (imm >> half) & mask {
+            break;
+        }
+        size = half;
+    }
+    let mask = if size == 64 { u64::MAX } else { (1u64 << size) - 1 };
+    let element = imm & mask;
+
+    // The element must be a rotated run of ones.
+    let ones = element.count_ones();
+    for rotation in 0..size {
+        let rotated = if rotation == 0 {
+            element
+        } else {
+            ((element >> rotation) | (element << (size - rotation))) & mask
+        };
+        if rotated == (1u64 << ones) - 1 {
+            let immr = (size - rotation) % size;
+            let imms = ((!(size - 1) << 1) | (ones - 1)) & 0x3f;
+            return Some((u32::from(size == 64), immr, imms));
+        }
+    }
+    None
+}
+
+pub(super) fn assemble_instruction(
+    asm: &mut Assembler,
+    mnemonic: &str,
+    operands: &str,
+) -> Result<(), String> {
+    let ops = split_operands(operands);
+    let ops = &ops[..];
+
+    let fixed = match mnemonic {
+        "nop" => Some(NOP),
+        "yield" => Some(0xd503203f),
+        "wfe" => Some(0xd503205f),
+        "wfi" => Some(0xd503207f),
+        "sev" => Some(0xd503209f),
+        "paciasp" => Some(0xd503233f),
+        "autiasp" => Some(0xd50323bf),
+        "isb" => Some(0xd5033fdf),
+        "ret" => Some(0xd65f03c0),
+        _ => None,
+    };
+    if let (Some(fixed), []) = (fixed, ops) {
+        asm.emit_u32(fixed);
+        return Ok(());
+    }
+
+    let word = match mnemonic {
+        "mov" => mov(asm, ops)?,
+        "movz" | "movn" | "movk" => {
+            let [rd] = gprs(ops)?;
+            no_sp(&[rd])?;
+            let value = imm(asm, ops.get(1).ok_or("expected immediate")?)?;
+            let shift = match shift(asm, ops.get(2))? {
+                None => 0,
+                Some((Shift::Lsl, amount)) if amount % 16 == 0 && amount < 32 << rd.sf() => amount,
+                _ => return Err("invalid shift".to_owned()),
+            };
+            if !(0..=0xffff).contains(&value) {
+                return Err(format!("immediate {value} doesn't fit in 16 bits"));
+            }
+            let opc = match mnemonic {
+                "movn" => 0x12800000,
+                "movz" => 0x52800000,
+                _ => 0x72800000,
+            };
+            opc | rd.sf() << 31 | (shift / 16) << 21 | (value as u32) << 5 | rd.num
+        }
+        "add" | "adds" | "sub" | "subs" => {
+            let [rd, rn] = gprs(ops)?;
+            add_sub(asm, mnemonic.starts_with("sub"), mnemonic.ends_with('s'), rd, rn, &ops[2..])?
+        }
+        "cmp" | "cmn" => {
+            let [rn] = gprs(ops)?;
+            let zr = Reg { num: 31, kind: rn.kind, sp: false };
+            add_sub(asm, mnemonic == "cmp", true, zr, rn, &ops[1..])?
+        }
+        "neg" | "negs" => {
+            let [rd, rm] = gprs(ops)?;
+            no_sp(&[rd, rm])?;
+            let zr = Reg { num: 31, kind: rd.kind, sp: false };
+            add_sub(asm, true, mnemonic == "negs", rd, zr, &ops[1..])?
+        }
+        "and" | "orr" | "eor" | "ands" | "bic" | "orn" | "eon" | "bics" => {
+            let [rd, rn] = gprs(ops)?;
+            logical(asm, mnemonic, rd, rn, &ops[2..])?
+        }
+        "tst" => {
+            let [rn] = gprs(ops)?;
+            let zr = Reg { num: 31, kind: rn.kind, sp: false };
+            logical(asm, "ands", zr, rn, &ops[1..])?
+        }
+        "mvn" => {
+            let [rd] = gprs(ops)?;
+            let zr = Reg { num: 31, kind: rd.kind, sp: false };
+            logical(asm, "orn", rd, zr, &ops[1..])?
+        }
+        "lsl" | "lsr" | "asr" | "ror" => {
+            let [rd, rn] = gprs(ops)?;
+            no_sp(&[rd, rn])?;
+            let operand = ops.get(2).ok_or("expected shift amount")?;
+            if parse_reg(operand).is_some() {
+                let [_, _, rm] = gprs(ops)?;
+                no_sp(&[rm])?;
+                let op2 = match mnemonic {
+                    "lsl" => 0,
+                    "lsr" => 1,
+                    "asr" => 2,
+                    _ => 3,
+                };
+                0x1ac02000 | rd.sf() << 31 | rm.num << 16 | op2 << 10 | rn.num << 5 | rd.num
+            } else {
+                let width = 32 << rd.sf();
+                let amount = imm(asm, operand)?;
+                if !(0..i64::from(width)).contains(&amount) {
+                    return Err(format!("invalid shift amount {amount}"));
+                }
+                let amount = amount as u32;
+                match mnemonic {
+                    "lsl" => {
+                        bitfield(0x53000000, rd, rn, (width - amount) % width, width - 1 - amount)
+                    }
+                    "lsr" => bitfield(0x53000000, rd, rn, amount, width - 1),
+                    "asr" => bitfield(0x13000000, rd, rn, amount, width - 1),
+                    // `ror` is an alias of `extr` with both source registers the same.
+                    _ => {
+                        0x13800000
+                            | rd.sf() << 31
+                            | rd.sf() << 22
+                            | rn.num << 16
+                            | amount << 10
+                            | rn.num << 5
+                            | rd.num
+                    }
+                }
+            }
+        }
+        "ubfm" | "sbfm" => {
+            let [rd, rn] = gprs(ops)?;
+            no_sp(&[rd, rn])?;
+            let immr = imm(asm, ops.get(2).ok_or("expected immr")?)?;
+            let imms = imm(asm, ops.get(3).ok_or("expected imms")?)?;
+            let width = i64::from(32 << rd.sf());
+            if !(0..width).contains(&immr) || !(0..width).contains(&imms) {
+                return Err("invalid bitfield".to_owned());
+            }
+            let base = if mnemonic == "ubfm" { 0x53000000 } else { 0x13000000 };
+            bitfield(base, rd, rn, immr as u32, imms as u32)
+        }
+        "uxtb" | "uxth" | "sxtb" | "sxth" | "sxtw" => {
+            let [rd] = gprs(ops)?;
+            let rn = gpr(ops.get(1).ok_or("expected source register")?)?;
+            no_sp(&[rd, rn])?;
+            let (base, imms) = match mnemonic {
+                "uxtb" => (0x53000000, 7),
+                "uxth" => (0x53000000, 15),
+                "sxtb" => (0x13000000, 7),
+                "sxth" => (0x13000000, 15),
+                _ => (0x13000000, 31),
+            };
+            if rn.kind != RegKind::W || (base == 0x53000000 && rd.kind != RegKind::W) {
+                return Err("invalid operands".to_owned());
+            }
+            bitfield(base, rd, Reg { kind: rd.kind, ..rn }, 0, imms)
+        }
+        "mul" | "mneg" | "madd" | "msub" => {
+            let [rd, rn, rm] = gprs(ops)?;
+            let ra = if matches!(mnemonic, "mul" | "mneg") {
+                Reg { num: 31, kind: rd.kind, sp: false }
+            } else {
+                let [_, _, _, ra] = gprs(ops)?;
+                ra
+            };
+            no_sp(&[rd, rn, rm, ra])?;
+            let o0 = u32::from(mnemonic == "mneg" || mnemonic == "msub");
+            0x1b000000
+                | rd.sf() << 31
+                | rm.num << 16
+                | o0 << 15
+                | ra.num << 10
+                | rn.num << 5
+                | rd.num
+        }
+        "udiv" | "sdiv" | "smulh" | "umulh" => {
+            let [rd, rn, rm] = gprs(ops)?;
+            no_sp(&[rd, rn, rm])?;
+            let base = match mnemonic {
+                "udiv" => 0x1ac00800 | rd.sf() << 31,
+                "sdiv" => 0x1ac00c00 | rd.sf() << 31,
+                _ if rd.kind != RegKind::X => return Err("expected 64-bit registers".to_owned()),
+                "umulh" => 0x9bc07c00,
+                _ => 0x9b407c00,
+            };
+            base | rm.num << 16 | rn.num << 5 | rd.num
+        }
+        "csel" | "csinc" | "csinv" | "csneg" => {
+            let [rd, rn, rm] = gprs(ops)?;
+            no_sp(&[rd, rn, rm])?;
+            let cond =
+                ops.get(3).and_then(|cond| condition_code(cond)).ok_or("expected condition")?;
+            conditional_select(mnemonic, rd, rn, rm, cond)
+        }
+        "cset" | "csetm" => {
+            let [rd] = gprs(ops)?;
+            no_sp(&[rd])?;
+            let cond =
+                ops.get(1).and_then(|cond| condition_code(cond)).ok_or("expected condition")?;
+            if cond >= 14 {
+                return Err("invalid condition".to_owned());
+            }
+            let zr = Reg { num: 31, kind: rd.kind, sp: false };
+            let mnemonic = if mnemonic == "cset" { "csinc" } else { "csinv" };
+            conditional_select(mnemonic, rd, zr, zr, cond ^ 1)
+        }
+        "cinc" => {
+            let [rd, rn] = gprs(ops)?;
+            no_sp(&[rd, rn])?;
+            let cond =
+                ops.get(2).and_then(|cond| condition_code(cond)).ok_or("expected condition")?;
+            if cond >= 14 {
+                return Err("invalid condition".to_owned());
+            }
+            conditional_select("csinc", rd, rn, rn, cond ^ 1)
+        }
+        "ldr" | "str" | "ldrb" | "strb" | "ldrh" | "strh" | "ldrsw" | "ldur" | "stur" => {
+            load_store(asm, mnemonic, ops)?
+        }
+        "ldp" | "stp" => load_store_pair(asm, mnemonic, ops)?,
+        "b" | "bl" => {
+            let [target] = ops else {
+                return Err("expected branch target".to_owned());
+            };
+            let target = label(asm, target)?;
+            let offset = asm.offset();
+            asm.add_fixup(offset, FixupKind::AArch64Branch26, target);
+            if mnemonic == "b" { 0x14000000 } else { 0x94000000 }
+        }
+        "cbz" | "cbnz" => {
+            let [rt] = gprs(ops)?;
+            no_sp(&[rt])?;
+            let target = label(asm, ops.get(1).ok_or("expected branch target")?)?;
+            let offset = asm.offset();
+            asm.add_fixup(offset, FixupKind::AArch64Branch19, target);
+            (if mnemonic == "cbz" { 0x34000000 } else { 0x35000000 }) | rt.sf() << 31 | rt.num
+        }
+        "tbz" | "tbnz" => {
+            let [rt] = gprs(ops)?;
+            no_sp(&[rt])?;
+            let bit = imm(asm, ops.get(1).ok_or("expected bit number")?)?;
+            if !(0..i64::from(32 << rt.sf())).contains(&bit) {
+                return Err(format!("invalid bit number {bit}"));
+            }
+            let bit = bit as u32;
+            let target = label(asm, ops.get(2).ok_or("expected branch target")?)?;
+            let offset = asm.offset();
+            asm.add_fixup(offset, FixupKind::AArch64Branch14, target);
+            (if mnemonic == "tbz" { 0x36000000 } else { 0x37000000 })
+                | (bit >> 5) << 31
+                | (bit & 0x1f) << 19
+                | rt.num
+        }
+        "br" | "blr" | "ret" => {
+            let rn = match ops {
+                [rn] => gpr(rn)?,
+                _ => return Err("expected register".to_owned()),
+            };
+            if rn.kind != RegKind::X || rn.sp {
+                return Err("expected 64-bit register".to_owned());
+            }
+            (match mnemonic {
+                "br" => 0xd61f0000,
+                "blr" => 0xd63f0000,
+                _ => 0xd65f0000,
+            }) | rn.num << 5
+        }
+        "adr" | "adrp" => {
+            let [rd] = gprs(ops)?;
+            no_sp(&[rd])?;
+            if rd.kind != RegKind::X {
+                return Err("expected 64-bit register".to_owned());
+            }
+            let target = label(asm, ops.get(1).ok_or("expected label")?)?;
+            let offset = asm.offset();
+            if mnemonic == "adr" {
+                asm.add_fixup(offset, FixupKind::AArch64Adr, target);
+                0x10000000 | rd.num
+            } else {
+                asm.add_fixup(offset, FixupKind::AArch64AdrPage, target);
+                0x90000000 | rd.num
+            }
+        }
+        "brk" | "svc" | "hvc" | "udf" | "hint" => {
+            let [value] = ops else {
+                return Err("expected immediate".to_owned());
+            };
+            let value = imm(asm, value)?;
+            let max = if mnemonic == "hint" { 0x7f } else { 0xffff };
+            if !(0..=max).contains(&value) {
+                return Err(format!("invalid immediate {value}"));
+            }
+            let value = value as u32;
+            match mnemonic {
+                "brk" => 0xd4200000 | value << 5,
+                "svc" => 0xd4000001 | value << 5,
+                "hvc" => 0xd4000002 | value << 5,
+                "udf" => value,
+                _ => NOP | value << 5,
+            }
+        }
+        "bti" => {
+            let target = match ops {
+                [] => 0,
+                [target] => match &*target.to_ascii_lowercase() {
+                    "c" => 1,
+                    "j" => 2,
+                    "jc" => 3,
+                    _ => return Err(format!("invalid `bti` target `{target}`")),
+                },
+                _ => return Err("invalid operands".to_owned()),
+            };
+            NOP | (32 | target << 1) << 5
+        }
+        "dmb" | "dsb" => {
+            let [option] = ops else {
+                return Err("expected barrier option".to_owned());
+            };
+            let option = match &*option.to_ascii_lowercase() {
+                "oshld" => 1,
+                "oshst" => 2,
+                "osh" => 3,
+                "nshld" => 5,
+                "nshst" => 6,
+                "nsh" => 7,
+                "ishld" => 9,
+                "ishst" => 10,
+                "ish" => 11,
+                "ld" => 13,
+                "st" => 14,
+                "sy" => 15,
+                _ => return Err(format!("invalid barrier option `{option}`")),
+            };
+            (if mnemonic == "dmb" { 0xd50330bf } else { 0xd503309f }) | option << 8
+        }
+        _ => {
+            let cond = mnemonic
+                .strip_prefix("b.")
+                .or_else(|| mnemonic.strip_prefix('b'))
+                .and_then(condition_code)
+                .ok_or_else(|| format!("unsupported instruction `{mnemonic}`"))?;
+            let [target] = ops else {
+                return Err("expected branch target".to_owned());
+            };
+            let target = label(asm, target)?;
+            let offset = asm.offset();
+            asm.add_fixup(offset, FixupKind::AArch64Branch19, target);
+            0x54000000 | cond
+        }
+    };
+    asm.emit_u32(word);
+    Ok(())
+}
+
+fn mov(asm: &Assembler, ops: &[&str]) -> Result<u32, String> {
+    let [rd] = gprs(ops)?;
+    let src = ops.get(1).ok_or("expected source operand")?;
+    if ops.len() != 2 {
+        return Err("invalid operands".to_owned());
+    }
+    if parse_reg(src).is_some() {
+        let [_, rm] = gprs(ops)?;
+        return Ok(if rd.sp || rm.sp {
+            // `add rd, rm, #0`
+            0x11000000 | rd.sf() << 31 | rm.num << 5 | rd.num
+        } else {
+            // `orr rd, zr, rm`
+            0x2a0003e0 | rd.sf() << 31 | rm.num << 16 | rd.num
+        });
+    }
+
+    let width = 32 << rd.sf();
+    let value = imm(asm, src)?;
+    if width == 32 && !(-0x8000_0000..=0xffff_ffff).contains(&value) {
+        return Err(format!("immediate {value} doesn't fit in 32 bits"));
+    }
+    let mask = if width == 64 { u64::MAX } else { 0xffff_ffff };
+    let value = value as u64 & mask;
+    if !rd.sp {
+        for (base, value) in [(0x52800000, value), (0x12800000, !value & mask)] {
+            for hw in 0..width / 16 {
+                if value & !(0xffff << (hw * 16)) == 0 {
+                    let imm16 = (value >> (hw * 16)) as u32;
+                    return Ok(base | rd.sf() << 31 | hw << 21 | imm16 << 5 | rd.num);
+                }
+            }
+        }
+    }
+    match encode_bitmask(value, width) {
+        // `orr rd, zr, #imm`
+        Some((n, immr, imms)) if !rd.is_zr() => {
+            Ok(0x320003e0 | rd.sf() << 31 | n << 22 | immr << 16 | imms << 10 | rd.num)
+        }
+        _ => Err(format!("immediate {value:#x} can't be encoded in a single `mov`")),
+    }
+}
+
+fn add_sub(
+    asm: &mut Assembler,
+    sub: bool,
+    set_flags: bool,
+    rd: Reg,
+    rn: Reg,
+    rest: &[&str],
+) -> Result<u32, String> {
+    let op = u32::from(sub) << 30 | u32::from(set_flags) << 29 | rd.sf() << 31;
+    let operand = rest.first().ok_or("expected second source operand")?;
+    if parse_reg(operand).is_some() {
+        let rm = gpr(operand)?;
+        if rm.kind != rd.kind || rn.kind != rd.kind {
+            return Err("register size mismatch".to_owned());
+        }
+        no_sp(&[rm])?;
+        let shift = shift(asm, rest.get(1))?;
+        if (rd.sp && !set_flags) || rn.sp {
+            // The extended register form is the only one which can use `sp`.
+            let amount = match shift {
+                None => 0,
+                Some((Shift::Lsl, amount)) if amount <= 4 => amount,
+                _ => return Err("invalid shift".to_owned()),
+            };
+            let option = if rd.sf() == 1 { 0b011 } else { 0b010 };
+            return Ok(0x0b200000
+                | op
+                | rm.num << 16
+                | option << 13
+                | amount << 10
+                | rn.num << 5
+                | rd.num);
+        }
+        no_sp(&[rd])?;
+        let (shift, amount) = match shift {
+            None => (0, 0),
+            Some((Shift::Ror, _)) => return Err("invalid shift".to_owned()),
+            Some((shift, amount)) if amount < 32 << rd.sf() => (shift as u32, amount),
+            Some(_) => return Err("invalid shift amount".to_owned()),
+        };
+        return Ok(0x0b000000
+            | op
+            | shift << 22
+            | rm.num << 16
+            | amount << 10
+            | rn.num << 5
+            | rd.num);
+    }
+
+    if rn.is_zr() || (rd.is_zr() && !set_flags) || (rd.sp && set_flags) {
+        return Err("invalid use of `sp` or the zero register".to_owned());
+    }
+    if let Some(symbol) = lo12(asm, operand)? {
+        if sub || set_flags || rest.len() > 1 {
+            return Err("`:lo12:` is only supported for `add`".to_owned());
+        }
+        let offset = asm.offset();
+        asm.add_fixup(offset, FixupKind::AArch64AddLo12, symbol);
+        return Ok(0x11000000 | op | rn.num << 5 | rd.num);
+    }
+    let mut value = imm(asm, operand)?;
+    let mut op = op;
+    if value < 0 {
+        value = -value;
+        op ^= 1 << 30;
+    }
+    let shifted = match shift(asm, rest.get(1))? {
+        None => false,
+        Some((Shift::Lsl, 0)) => false,
+        Some((Shift::Lsl, 12)) => true,
+        _ => return Err("invalid shift".to_owned()),
+    };
+    let (shifted, value) = if value < 0x1000 {
+        (shifted, value)
+    } else if !shifted && value & 0xfff == 0 && value >> 12 < 0x1000 {
+        (true, value >> 12)
+    } else {
+        return Err(format!("immediate {value} can't be encoded"));
+    };
+    Ok(0x11000000 | op | u32::from(shifted) << 22 | (value as u32) << 10 | rn.num << 5 | rd.num)
+}
+
+fn logical(
+    asm: &Assembler,
+    mnemonic: &str,
+    rd: Reg,
+    rn: Reg,
+    rest: &[&str],
+) -> Result<u32, String> {
+    let (opc, negate) = match mnemonic {
+        "and" => (0, false),
+        "orr" => (1, false),
+        "eor" => (2, false),
+        "ands" => (3, false),
+        "bic" => (0, true),
+        "orn" => (1, true),
+        "eon" => (2, true),
+        _ => (3, true),
+    };
+    let operand = rest.first().ok_or("expected second source operand")?;
+    no_sp(&[rn])?;
+    if parse_reg(operand).is_some() {
+        let rm = gpr(operand)?;
+        if rm.kind != rd.kind || rn.kind != rd.kind {
+            return Err("register size mismatch".to_owned());
+        }
+        no_sp(&[rd, rm])?;
+        let (shift, amount) = match shift(asm, rest.get(1))? {
+            None => (0, 0),
+            Some((shift, amount)) if amount < 32 << rd.sf() => (shift as u32, amount),
+            Some(_) => return Err("invalid shift amount".to_owned()),
+        };
+        return Ok(0x0a000000
+            | rd.sf() << 31
+            | opc << 29
+            | shift << 22
+            | u32::from(negate) << 21
+            | rm.num << 16
+            | amount << 10
+            | rn.num << 5
+            | rd.num);
+    }
+
+    if negate || rest.len() > 1 {
+        return Err("invalid immediate operand".to_owned());
+    }
+    // `ands` can't write to `sp`, while the other instructions can't write to the zero register.
+    if (opc == 3 && rd.sp) || (opc != 3 && rd.is_zr()) {
+        return Err("invalid destination register".to_owned());
+    }
+    let width = 32 << rd.sf();
+    let value = imm(asm, operand)? as u64;
+    let (n, immr, imms) = encode_bitmask(value, width)
+        .filter(|_| width == 64 || value >> 32 == 0 || value >> 32 == 0xffff_ffff)
+        .ok_or_else(|| format!("immediate {value:#x} is not a valid bitmask immediate"))?;
+    Ok(0x12000000
+        | rd.sf() << 31
+        | opc << 29
+        | n << 22
+        | immr << 16
+        | imms << 10
+        | rn.num << 5
+        | rd.num)
+}
+
+fn bitfield(base: u32, rd: Reg, rn: Reg, immr: u32, imms: u32) -> u32 {
+    base | rd.sf() << 31 | rd.sf() << 22 | immr << 16 | imms << 10 | rn.num << 5 | rd.num
+}
+
+fn conditional_select(mnemonic: &str, rd: Reg, rn: Reg, rm: Reg, cond: u32) -> u32 {
+    let base = match mnemonic {
+        "csel" => 0x1a800000,
+        "csinc" => 0x1a800400,
+        "csinv" => 0x5a800000,
+        _ => 0x5a800400,
+    };
+    base | rd.sf() << 31 | rm.num << 16 | cond << 12 | rn.num << 5 | rd.num
+}
+
+/// The `size`, `V` and `opc` fields of a load or store and the log2 of the access size.
+fn load_store_fields(mnemonic: &str, rt: Reg) -> Result<(u32, u32, u32, u32), String> {
+    let load = mnemonic.starts_with("ld");
+    let invalid = || Err(format!("invalid register for `{mnemonic}`"));
+    let (size, v, opc) = match (mnemonic, rt.kind) {
+        ("ldrb" | "strb", RegKind::W) => (0, 0, u32::from(load)),
+        ("ldrh" | "strh", RegKind::W) => (1, 0, u32::from(load)),
+        ("ldrsw", RegKind::X) => (2, 0, 2),
+        ("ldr" | "str" | "ldur" | "stur", kind) => match kind {
+            RegKind::X => (3, 0, u32::from(load)),
+            RegKind::W => (2, 0, u32::from(load)),
+            RegKind::B => (0, 1, u32::from(load)),
+            RegKind::H => (1, 1, u32::from(load)),
+            RegKind::S => (2, 1, u32::from(load)),
+            RegKind::D => (3, 1, u32::from(load)),
+            RegKind::Q => (0, 1, 2 | u32::from(load)),
+        },
+        _ => return invalid(),
+    };
+    if rt.sp {
+        return invalid();
+    }
+    let scale = if rt.kind == RegKind::Q { 4 } else { size };
+    Ok((size, v, opc, scale))
+}
+
+/// A parsed `[base, offset]` memory operand.
+enum Address {
+    Offset { base: Reg, offset: i64, pre_index: bool },
+    Lo12 { base: Reg, symbol: Expr },
+    Register { base: Reg, index: Reg, option: u32, amount: Option<u32> },
+}
+
+fn address(asm: &Assembler, operand: &str) -> Result<Address, String> {
+    let operand = operand.trim();
+    let (inner, pre_index) = match operand.strip_suffix('!') {
+        Some(inner) => (inner.trim(), true),
+        None => (operand, false),
+    };
+    let inner = inner
+        .strip_prefix('[')
+        .and_then(|inner| inner.strip_suffix(']'))
+        .ok_or_else(|| format!("expected memory operand, found `{operand}`"))?;
+    let parts = split_operands(inner);
+    let base = gpr(parts.first().ok_or("expected base register")?)?;
+    if base.kind != RegKind::X || base.is_zr() {
+        return Err("expected 64-bit base register".to_owned());
+    }
+    match parts[1..] {
+        [] => Ok(Address::Offset { base, offset: 0, pre_index }),
+        [offset] if parse_reg(offset).is_some() => {
+            let index = gpr(offset)?;
+            if pre_index || index.sp {
+                return Err("invalid index register".to_owned());
+            }
+            let option = if index.kind == RegKind::X { 0b011 } else { 0b010 };
+            Ok(Address::Register { base, index, option, amount: None })
+        }
+        [offset] => match lo12(asm, offset)? {
+            Some(symbol) => Ok(Address::Lo12 { base, symbol }),
+            None => Ok(Address::Offset { base, offset: imm(asm, offset)?, pre_index }),
+        },
+        [index, extend] if !pre_index => {
+            let index = gpr(index)?;
+            let lower = extend.to_ascii_lowercase();
+            let (kind, amount) = lower.split_at(lower.len().min(4));
+            let (option, expected_kind) = match kind.trim() {
+                "lsl" => (0b011, RegKind::X),
+                "uxtw" => (0b010, RegKind::W),
+                "sxtw" => (0b110, RegKind::W),
+                "sxtx" => (0b111, RegKind::X),
+                _ => return Err(format!("invalid extend `{extend}`")),
+            };
+            if index.kind != expected_kind || index.sp {
+                return Err("invalid index register".to_owned());
+            }
+            let amount =
+                if amount.trim().is_empty() { None } else { Some(imm(asm, amount)? as u32) };
+            Ok(Address::Register { base, index, option, amount })
+        }
+        _ => Err(format!("invalid memory operand `{operand}`")),
+    }
+}
+
+fn load_store(asm: &mut Assembler, mnemonic: &str, ops: &[&str]) -> Result<u32, String> {
+    let rt = reg(ops.first().ok_or("expected register")?)?;
+    let (size, v, opc, scale) = load_store_fields(mnemonic, rt)?;
+    let mem = ops.get(1).ok_or("expected memory operand")?;
+    let fields = size << 30 | v << 26 | opc << 22 | rt.num;
+
+    if !mem.trim_start().starts_with('[') {
+        // A pc-relative literal load.
+        if mnemonic != "ldr" || ops.len() != 2 {
+            return Err("invalid operands".to_owned());
+        }
+        let opc = match rt.kind {
+            RegKind::W | RegKind::S => 0,
+            RegKind::X | RegKind::D => 1,
+            RegKind::Q => 2,
+            _ => return Err("invalid register for literal load".to_owned()),
+        };
+        let target = label(asm, mem)?;
+        let offset = asm.offset();
+        asm.add_fixup(offset, FixupKind::AArch64Branch19, target);
+        return Ok(0x18000000 | opc << 30 | v << 26 | rt.num);
+    }
+
+    let unscaled = mnemonic == "ldur" || mnemonic == "stur";
+    let imm9 = |offset: i64| {
+        if !fits_signed(offset, 9) {
+            return Err(format!("offset {offset} out of range"));
+        }
+        Ok((offset as u32 & 0x1ff) << 12)
+    };
+    match (address(asm, mem)?, ops.get(2)) {
+        (Address::Offset { base, offset: 0, pre_index: false }, Some(post)) => {
+            if ops.len() != 3 || unscaled {
+                return Err("invalid operands".to_owned());
+            }
+            Ok(0x38000400 | fields | imm9(imm(asm, post)?)? | base.num << 5)
+        }
+        (_, Some(_)) => Err("invalid operands".to_owned()),
+        (Address::Offset { base, offset, pre_index: true }, None) => {
+            if unscaled {
+                return Err("invalid operands".to_owned());
+            }
+            Ok(0x38000c00 | fields | imm9(offset)? | base.num << 5)
+        }
+        (Address::Offset { base, offset, pre_index: false }, None) => {
+            let scaled = offset >> scale;
+            if !unscaled && offset >= 0 && scaled << scale == offset && scaled < 0x1000 {
+                Ok(0x39000000 | fields | (scaled as u32) << 10 | base.num << 5)
+            } else {
+                Ok(0x38000000 | fields | imm9(offset)? | base.num << 5)
+            }
+        }
+        (Address::Lo12 { base, symbol }, None) => {
+            if unscaled {
+                return Err("invalid operands".to_owned());
+            }
+            let offset = asm.offset();
+            asm.add_fixup(offset, FixupKind::AArch64LdstLo12 { shift: scale as u8 }, symbol);
+            Ok(0x39000000 | fields | base.num << 5)
+        }
+        (Address::Register { base, index, option, amount }, None) => {
+            let s = match amount {
+                None | Some(0) => 0,
+                Some(amount) if amount == scale => 1,
+                Some(amount) => return Err(format!("invalid shift amount {amount}")),
+            };
+            if unscaled {
+                return Err("invalid operands".to_owned());
+            }
+            Ok(0x38200800 | fields | index.num << 16 | option << 13 | s << 12 | base.num << 5)
+        }
+    }
+}
+
+fn load_store_pair(asm: &Assembler, mnemonic: &str, ops: &[&str]) -> Result<u32, String> {
+    let (Some(rt), Some(rt2), Some(mem)) = (ops.first(), ops.get(1), ops.get(2)) else {
+        return Err("invalid operands".to_owned());
+    };
+    let (rt, rt2) = (reg(rt)?, reg(rt2)?);
+    if rt.kind != rt2.kind || rt.sp || rt2.sp {
+        return Err("invalid register pair".to_owned());
+    }
+    let (opc, v, scale) = match rt.kind {
+        RegKind::W => (0, 0, 2),
+        RegKind::X => (2, 0, 3),
+        RegKind::S => (0, 1, 2),
+        RegKind::D => (1, 1, 3),
+        RegKind::Q => (2, 1, 4),
+        _ => return Err("invalid register pair".to_owned()),
+    };
+    let (mode, base, offset) = match (address(asm, mem)?, ops.get(3)) {
+        (Address::Offset { base, offset: 0, pre_index: false }, Some(post)) => {
+            (0b001, base, imm(asm, post)?)
+        }
+        (Address::Offset { base, offset, pre_index: true }, None) => (0b011, base, offset),
+        (Address::Offset { base, offset, pre_index: false }, None) => (0b010, base, offset),
+        _ => return Err("invalid operands".to_owned()),
+    };
+    let scaled = offset >> scale;
+    if scaled << scale != offset || !fits_signed(scaled, 7) {
+        return Err(format!("offset {offset} out of range"));
+    }
+    let load = u32::from(mnemonic == "ldp");
+    Ok(opc << 30
+        | 0b101 << 27
+        | v << 26
+        | mode << 23
+        | load << 22
+        | (scaled as u32 & 0x7f) << 15
+        | rt2.num << 10
+        | base.num << 5
+        | rt.num)
+}
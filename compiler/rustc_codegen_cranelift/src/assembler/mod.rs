@@ -0,0 +1,1245 @@
+//! An assembler for the x86_64 and AArch64 assembly emitted for `global_asm!`, naked functions
+//! and the `asm!` wrappers generated in [`crate::inline_asm`]. It writes object files using the
+//! [`object`] crate, which avoids spawning an external assembler for every codegen unit.
+//!
+//! Only a subset of the instructions and directives is supported. See the `x86_64` and `aarch64`
+//! modules for the supported instructions and [`Assembler::directive`] for the supported
+//! directives. Anything else results in an error, in which case
+//! [`crate::global_asm::compile_global_asm`] falls back to an external assembler. Branches always
+//! use the largest displacement, so no relaxation is necessary.
+
+mod aarch64;
+mod x86_64;
+
+use object::write::{Mangling, Object, Relocation, StandardSection, Symbol, SymbolSection};
+use object::{
+    BinaryFormat, Endianness, RelocationEncoding, RelocationFlags, RelocationKind, SectionKind,
+    SymbolFlags, SymbolKind, SymbolScope,
+};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet, FxIndexMap};
+
+/// Assemble `source` into an object file for `triple`.
+pub(crate) fn assemble(triple: &target_lexicon::Triple, source: &str) -> Result<Vec<u8>, String> {
+    let arch = match triple.architecture {
+        target_lexicon::Architecture::X86_64 => Arch::X86_64,
+        target_lexicon::Architecture::Aarch64(_) => Arch::AArch64,
+        arch => return Err(format!("unsupported architecture `{arch}`")),
+    };
+    let format = match triple.binary_format {
+        target_lexicon::BinaryFormat::Elf => BinaryFormat::Elf,
+        target_lexicon::BinaryFormat::Macho => BinaryFormat::MachO,
+        target_lexicon::BinaryFormat::Coff => BinaryFormat::Coff,
+        format => return Err(format!("unsupported binary format `{format}`")),
+    };
+
+    let mut asm = Assembler::new(arch, format);
+    for (line_idx, line) in strip_block_comments(source)?.lines().enumerate() {
+        for statement in asm.split_statements(line)? {
+            asm.statement(statement)
+                .map_err(|err| format!("line {}: {err}: `{}`", line_idx + 1, statement.trim()))?;
+        }
+    }
+    asm.finish()
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Arch {
+    X86_64,
+    AArch64,
+}
+
+/// An expression of the form `symbol - minus_symbol + addend`, where both symbols are optional.
+///
+/// The symbol `.` refers to the location of the instruction or data item containing the
+/// expression.
+#[derive(Clone, Debug)]
+pub(super) struct Expr {
+    pub(super) symbol: Option<String>,
+    pub(super) minus_symbol: Option<String>,
+    pub(super) addend: i64,
+}
+
+impl Expr {
+    pub(super) fn constant(value: i64) -> Self {
+        Expr { symbol: None, minus_symbol: None, addend: value }
+    }
+
+    pub(super) fn as_constant(&self) -> Option<i64> {
+        if self.symbol.is_none() && self.minus_symbol.is_none() { Some(self.addend) } else { None }
+    }
+}
+
+/// How the value of an [`Expr`] is written to the location of a [`Fixup`].
+#[derive(Copy, Clone, Debug)]
+pub(super) enum FixupKind {
+    /// A little endian value of the given number of bytes.
+    Data(u8),
+    /// A 32-bit displacement relative to `end`, the end of the x86_64 instruction.
+    X86Pcrel32 { end: u64, branch: bool },
+    /// The 26-bit word offset of an AArch64 `b` or `bl`.
+    AArch64Branch26,
+    /// The 19-bit word offset of an AArch64 `b.cond`, `cbz`, `cbnz` or literal load.
+    AArch64Branch19,
+    /// The 14-bit word offset of an AArch64 `tbz` or `tbnz`.
+    AArch64Branch14,
+    /// The 21-bit byte offset of an AArch64 `adr`.
+    AArch64Adr,
+    /// The 21-bit page offset of an AArch64 `adrp`.
+    AArch64AdrPage,
+    /// The low 12 bits of an address in an AArch64 `add`.
+    AArch64AddLo12,
+    /// The low 12 bits of an address in an AArch64 load or store scaled down by `1 << shift`.
+    AArch64LdstLo12 { shift: u8 },
+}
+
+struct Fixup {
+    section: usize,
+    offset: u64,
+    /// The location `.` refers to.
+    dot: u64,
+    kind: FixupKind,
+    expr: Expr,
+}
+
+/// A relocation that can only be added once all symbols are known.
+struct PendingRelocation {
+    section: usize,
+    offset: u64,
+    symbol: String,
+    addend: i64,
+    flags: RelocationFlags,
+}
+
+#[derive(Clone, PartialEq)]
+enum SectionName {
+    Standard(StandardSection),
+    Custom { segment: Vec<u8>, name: Vec<u8> },
+}
+
+struct Section {
+    name: SectionName,
+    kind: SectionKind,
+    data: Vec<u8>,
+    align: u64,
+}
+
+pub(super) struct Assembler {
+    arch: Arch,
+    format: BinaryFormat,
+    sections: Vec<Section>,
+    current_section: usize,
+    previous_section: usize,
+    section_stack: Vec<(usize, usize)>,
+    /// The start of the current instruction or data item.
+    dot: u64,
+    /// The section and offset of every label in definition order.
+    labels: FxIndexMap<String, (usize, u64)>,
+    /// The number of definitions of each numeric local label like `1:` so far.
+    local_label_counts: FxHashMap<u32, u32>,
+    equates: FxHashMap<String, i64>,
+    globals: FxHashSet<String>,
+    weak: FxHashSet<String>,
+    hidden: FxHashSet<String>,
+    symbol_kinds: FxHashMap<String, SymbolKind>,
+    symbol_sizes: FxHashMap<String, u64>,
+    fixups: Vec<Fixup>,
+    /// Whether `.intel_syntax noprefix` is in effect. AT&T syntax is not supported.
+    intel_syntax: bool,
+}
+
+impl Assembler {
+    fn new(arch: Arch, format: BinaryFormat) -> Self {
+        Assembler {
+            arch,
+            format,
+            sections: vec![Section {
+                name: SectionName::Standard(StandardSection::Text),
+                kind: SectionKind::Text,
+                data: vec![],
+                align: 1,
+            }],
+            current_section: 0,
+            previous_section: 0,
+            section_stack: vec![],
+            dot: 0,
+            labels: FxIndexMap::default(),
+            local_label_counts: FxHashMap::default(),
+            equates: FxHashMap::default(),
+            globals: FxHashSet::default(),
+            weak: FxHashSet::default(),
+            hidden: FxHashSet::default(),
+            symbol_kinds: FxHashMap::default(),
+            symbol_sizes: FxHashMap::default(),
+            fixups: vec![],
+            intel_syntax: false,
+        }
+    }
+
+    pub(super) fn offset(&self) -> u64 {
+        self.sections[self.current_section].data.len() as u64
+    }
+
+    pub(super) fn emit(&mut self, bytes: &[u8]) {
+        self.sections[self.current_section].data.extend_from_slice(bytes);
+    }
+
+    pub(super) fn emit_u32(&mut self, value: u32) {
+        self.emit(&value.to_le_bytes());
+    }
+
+    /// Write the value of `expr` to `offset` in the current section once all labels are known.
+    pub(super) fn add_fixup(&mut self, offset: u64, kind: FixupKind, expr: Expr) {
+        self.fixups.push(Fixup {
+            section: self.current_section,
+            offset,
+            dot: self.dot,
+            kind,
+            expr,
+        });
+    }
+
+    pub(super) fn parse_expr(&self, s: &str) -> Result<Expr, String> {
+        ExprParser { asm: self, s: s.as_bytes(), pos: 0 }.parse()
+    }
+
+    pub(super) fn parse_constant(&self, s: &str) -> Result<i64, String> {
+        self.parse_expr(s)?.as_constant().ok_or_else(|| format!("`{s}` is not a constant"))
+    }
+
+    /// Split a line into statements, dropping the comment at the end of the line if any.
+    fn split_statements<'a>(&self, line: &'a str) -> Result<Vec<&'a str>, String> {
+        let (comment, separator) = match (self.arch, self.format) {
+            (Arch::X86_64, _) => (Some(b'#'), Some(b';')),
+            (Arch::AArch64, BinaryFormat::MachO) => (Some(b';'), None),
+            (Arch::AArch64, _) => (None, Some(b';')),
+        };
+
+        let bytes = line.as_bytes();
+        let mut statements = vec![];
+        let mut start = 0;
+        let mut in_string = false;
+        let mut i = 0;
+        while i < bytes.len() {
+            let c = bytes[i];
+            if in_string {
+                if c == b'\\' {
+                    i += 1;
+                } else if c == b'"' {
+                    in_string = false;
+                }
+            } else if c == b'"' {
+                in_string = true;
+            } else if Some(c) == comment || (c == b'/' && bytes.get(i + 1) == Some(&b'/')) {
+                break;
+            } else if Some(c) == separator {
+                statements.push(&line[start..i]);
+                start = i + 1;
+            }
+            i += 1;
+        }
+        if in_string {
+            return Err(format!("unterminated string in `{line}`"));
+        }
+        statements.push(&line[start..i]);
+        Ok(statements)
+    }
+
+    fn statement(&mut self, mut statement: &str) -> Result<(), String> {
+        loop {
+            statement = statement.trim();
+            let label_len = statement.bytes().take_while(|&c| is_ident_char(c)).count();
+            if label_len == 0 || statement.as_bytes().get(label_len) != Some(&b':') {
+                break;
+            }
+            self.define_label(&statement[..label_len])?;
+            statement = &statement[label_len + 1..];
+        }
+        if statement.is_empty() {
+            return Ok(());
+        }
+
+        let (mnemonic, operands) = split_mnemonic(statement);
+        if let Some(value) = operands.strip_prefix('=') {
+            let value = self.parse_constant(value)?;
+            self.equates.insert(mnemonic.to_owned(), value);
+            return Ok(());
+        }
+        let mnemonic = mnemonic.to_ascii_lowercase();
+
+        self.dot = self.offset();
+        if mnemonic.starts_with('.') {
+            return self.directive(&mnemonic, operands);
+        }
+        match self.arch {
+            Arch::X86_64 => {
+                if !self.intel_syntax {
+                    return Err("AT&T syntax is not supported".to_owned());
+                }
+                x86_64::assemble_instruction(self, &mnemonic, operands)
+            }
+            Arch::AArch64 => {
+                // AArch64 instructions must be 4 byte aligned.
+                let section = &mut self.sections[self.current_section];
+                section.align = section.align.max(4);
+                if section.data.len() % 4 != 0 {
+                    return Err("misaligned instruction".to_owned());
+                }
+                aarch64::assemble_instruction(self, &mnemonic, operands)
+            }
+        }
+    }
+
+    fn define_label(&mut self, name: &str) -> Result<(), String> {
+        let name = if let Ok(number) = name.parse::<u32>() {
+            let count = self.local_label_counts.entry(number).or_insert(0);
+            *count += 1;
+            local_label_name(number, *count - 1)
+        } else {
+            name.to_owned()
+        };
+        let location = (self.current_section, self.offset());
+        if self.labels.insert(name.clone(), location).is_some() || self.equates.contains_key(&name)
+        {
+            return Err(format!("symbol `{name}` is already defined"));
+        }
+        Ok(())
+    }
+
+    /// Handle the directive `name`. The supported directives are:
+    ///
+    /// * Sections: `.text`, `.data`, `.bss`, `.rodata`, `.section`, `.pushsection`,
+    ///   `.popsection` and `.previous`.
+    /// * Symbols: `.globl`, `.global`, `.weak`, `.hidden`, `.private_extern`, `.type`, `.size`,
+    ///   `.set`, `.equ` and `sym = value`.
+    /// * Data: `.byte`, `.short`, `.hword`, `.2byte`, `.value`, `.word`, `.long`, `.int`,
+    ///   `.4byte`, `.inst`, `.quad`, `.8byte`, `.xword`, `.dword`, `.ascii`, `.asciz`,
+    ///   `.string`, `.zero`, `.skip`, `.space` and `.fill`.
+    /// * Alignment: `.p2align`, `.balign` and `.align`.
+    /// * `.intel_syntax noprefix` and `.att_syntax` on x86_64.
+    ///
+    /// A couple of directives which don't affect the object file like `.file` are ignored.
+    fn directive(&mut self, name: &str, operands: &str) -> Result<(), String> {
+        let args = split_operands(operands);
+        match name {
+            ".text" => self.switch_section(SectionName::Standard(StandardSection::Text)),
+            ".data" => self.switch_section(SectionName::Standard(StandardSection::Data)),
+            ".bss" => {
+                self.switch_section(SectionName::Standard(StandardSection::UninitializedData))
+            }
+            ".rodata" | ".rdata" | ".const" => {
+                self.switch_section(SectionName::Standard(StandardSection::ReadOnlyData))
+            }
+            ".section" => {
+                if let Some(name) = self.parse_section(&args)? {
+                    self.switch_section(name);
+                }
+            }
+            ".pushsection" => {
+                self.section_stack.push((self.current_section, self.previous_section));
+                if let Some(name) = self.parse_section(&args)? {
+                    self.switch_section(name);
+                }
+            }
+            ".popsection" => {
+                let (current, previous) =
+                    self.section_stack.pop().ok_or("`.popsection` without `.pushsection`")?;
+                self.current_section = current;
+                self.previous_section = previous;
+            }
+            ".previous" => {
+                std::mem::swap(&mut self.current_section, &mut self.previous_section);
+            }
+
+            ".globl" | ".global" => {
+                self.globals.extend(args.iter().map(|&arg| arg.to_owned()));
+            }
+            ".weak" => {
+                self.globals.extend(args.iter().map(|&arg| arg.to_owned()));
+                self.weak.extend(args.iter().map(|&arg| arg.to_owned()));
+            }
+            ".hidden" | ".private_extern" => {
+                self.hidden.extend(args.iter().map(|&arg| arg.to_owned()));
+            }
+            ".type" => match args[..] {
+                [symbol, kind] => {
+                    let kind = match kind.trim_start_matches(['@', '%']) {
+                        "function" | "STT_FUNC" => SymbolKind::Text,
+                        "object" | "STT_OBJECT" => SymbolKind::Data,
+                        "notype" | "STT_NOTYPE" => return Ok(()),
+                        _ => return Err(format!("unsupported symbol type `{kind}`")),
+                    };
+                    self.symbol_kinds.insert(symbol.to_owned(), kind);
+                }
+                // Part of a `.def` block on COFF.
+                [_] if self.format == BinaryFormat::Coff => {}
+                _ => return Err("expected symbol and type".to_owned()),
+            },
+            ".size" => {
+                let [symbol, size] = args[..] else {
+                    return Err("expected symbol and size".to_owned());
+                };
+                let size = self.parse_expr(size)?;
+                let size = self
+                    .resolve_now(&size)
+                    .ok_or_else(|| format!("`{operands}` doesn't have a constant size"))?;
+                self.symbol_sizes.insert(symbol.to_owned(), size as u64);
+            }
+            ".set" | ".equ" | ".equiv" => {
+                let [symbol, value] = args[..] else {
+                    return Err("expected symbol and value".to_owned());
+                };
+                let value = self.parse_constant(value)?;
+                if self.labels.contains_key(symbol)
+                    || (name == ".equiv" && self.equates.contains_key(symbol))
+                {
+                    return Err(format!("symbol `{symbol}` is already defined"));
+                }
+                self.equates.insert(symbol.to_owned(), value);
+            }
+
+            ".byte" => self.data(1, &args)?,
+            ".short" | ".hword" | ".2byte" | ".value" => self.data(2, &args)?,
+            ".word" if self.arch == Arch::X86_64 => self.data(2, &args)?,
+            ".word" | ".long" | ".int" | ".4byte" | ".inst" => self.data(4, &args)?,
+            ".quad" | ".8byte" | ".xword" | ".dword" => self.data(8, &args)?,
+            ".ascii" | ".asciz" | ".string" => {
+                for arg in args {
+                    let mut string = parse_string(arg)?;
+                    if name != ".ascii" {
+                        string.push(0);
+                    }
+                    self.emit(&string);
+                }
+            }
+            ".zero" | ".skip" | ".space" => {
+                let (size, fill) = match args[..] {
+                    [size] => (size, 0),
+                    [size, fill] => (size, self.parse_constant(fill)? as u8),
+                    _ => return Err("expected size and optional fill value".to_owned()),
+                };
+                let size = self.parse_constant(size)?;
+                let size = usize::try_from(size).map_err(|_| format!("invalid size {size}"))?;
+                self.emit(&vec![fill; size]);
+            }
+            ".fill" => {
+                let repeat = self.parse_constant(args.first().ok_or("expected repeat count")?)?;
+                let size = args.get(1).map_or(Ok(1), |size| self.parse_constant(size))?;
+                let value = args.get(2).map_or(Ok(0), |value| self.parse_constant(value))?;
+                if !(0..=8).contains(&size) {
+                    return Err(format!("invalid size {size}"));
+                }
+                for _ in 0..repeat {
+                    self.emit(&value.to_le_bytes()[..size as usize]);
+                }
+            }
+
+            ".p2align" | ".balign" | ".align" => {
+                let Some(align) = args.first() else {
+                    return Err("expected alignment".to_owned());
+                };
+                let align = self.parse_constant(align)?;
+                let is_power_of_two = name == ".p2align"
+                    || (name == ".align"
+                        && (self.arch == Arch::AArch64 || self.format == BinaryFormat::MachO));
+                let align = if is_power_of_two {
+                    if !(0..=16).contains(&align) {
+                        return Err(format!("invalid alignment {align}"));
+                    }
+                    1 << align
+                } else {
+                    align as u64
+                };
+                let fill = match args.get(1) {
+                    Some(fill) if !fill.is_empty() => Some(self.parse_constant(fill)? as u8),
+                    _ => None,
+                };
+                let max_skip = args.get(2).map(|max| self.parse_constant(max)).transpose()?;
+                self.align(align, fill, max_skip.map(|max| max as u64))?;
+            }
+
+            ".intel_syntax" if self.arch == Arch::X86_64 => {
+                if operands != "noprefix" {
+                    return Err("only `.intel_syntax noprefix` is supported".to_owned());
+                }
+                self.intel_syntax = true;
+            }
+            ".att_syntax" if self.arch == Arch::X86_64 => self.intel_syntax = false,
+
+            ".file"
+            | ".ident"
+            | ".extern"
+            | ".local"
+            | ".addrsig"
+            | ".addrsig_sym"
+            | ".subsections_via_symbols"
+            | ".build_version"
+            | ".def"
+            | ".scl"
+            | ".endef" => {}
+            ".code64" if self.arch == Arch::X86_64 => {}
+            ".arch" | ".arch_extension" | ".cpu" if self.arch == Arch::AArch64 => {}
+
+            _ => return Err(format!("unsupported directive `{name}`")),
+        }
+        Ok(())
+    }
+
+    /// Parse the arguments of `.section`. Returns `None` for sections that only carry
+    /// information for the linker which doesn't need to be preserved.
+    fn parse_section(&self, args: &[&str]) -> Result<Option<SectionName>, String> {
+        let Some(&name) = args.first() else {
+            return Err("expected section name".to_owned());
+        };
+        if self.format == BinaryFormat::MachO {
+            let [segment, section, ..] = args[..] else {
+                return Err("expected segment and section name".to_owned());
+            };
+            return Ok(Some(match (segment, section) {
+                ("__TEXT", "__text") => SectionName::Standard(StandardSection::Text),
+                ("__TEXT", "__const") => SectionName::Standard(StandardSection::ReadOnlyData),
+                ("__DATA", "__data") => SectionName::Standard(StandardSection::Data),
+                ("__DATA", "__bss") => SectionName::Standard(StandardSection::UninitializedData),
+                ("__TEXT" | "__DATA", _) => SectionName::Custom {
+                    segment: segment.as_bytes().to_vec(),
+                    name: section.as_bytes().to_vec(),
+                },
+                _ => return Err(format!("unsupported section `{segment},{section}`")),
+            }));
+        }
+
+        if name == ".note.GNU-stack" {
+            return Ok(None);
+        }
+        let standard = match name {
+            ".text" => Some(StandardSection::Text),
+            ".data" => Some(StandardSection::Data),
+            ".bss" => Some(StandardSection::UninitializedData),
+            ".rodata" if self.format == BinaryFormat::Elf => Some(StandardSection::ReadOnlyData),
+            ".rdata" if self.format == BinaryFormat::Coff => Some(StandardSection::ReadOnlyData),
+            _ => None,
+        };
+        if let Some(standard) = standard {
+            return Ok(Some(SectionName::Standard(standard)));
+        }
+        Ok(Some(SectionName::Custom { segment: vec![], name: name.as_bytes().to_vec() }))
+    }
+
+    fn switch_section(&mut self, name: SectionName) {
+        let index = match self.sections.iter().position(|section| section.name == name) {
+            Some(index) => index,
+            None => {
+                self.sections.push(Section {
+                    kind: self.section_kind(&name),
+                    name,
+                    data: vec![],
+                    align: 1,
+                });
+                self.sections.len() - 1
+            }
+        };
+        self.previous_section = self.current_section;
+        self.current_section = index;
+    }
+
+    fn section_kind(&self, name: &SectionName) -> SectionKind {
+        match name {
+            SectionName::Standard(StandardSection::Text) => SectionKind::Text,
+            SectionName::Standard(StandardSection::Data) => SectionKind::Data,
+            SectionName::Standard(StandardSection::UninitializedData) => {
+                SectionKind::UninitializedData
+            }
+            SectionName::Standard(_) => SectionKind::ReadOnlyData,
+            SectionName::Custom { segment, name } => {
+                let name = std::str::from_utf8(name).unwrap();
+                if segment == b"__TEXT" {
+                    SectionKind::ReadOnlyData
+                } else if segment == b"__DATA" {
+                    if name.contains("bss") || name.contains("zerofill") {
+                        SectionKind::UninitializedData
+                    } else {
+                        SectionKind::Data
+                    }
+                } else if name.starts_with(".text") {
+                    SectionKind::Text
+                } else if name.starts_with(".bss") || name.starts_with(".tbss") {
+                    SectionKind::UninitializedData
+                } else if name.starts_with(".rodata") || name.starts_with(".rdata") {
+                    SectionKind::ReadOnlyData
+                } else {
+                    SectionKind::Data
+                }
+            }
+        }
+    }
+
+    fn data(&mut self, size: u8, args: &[&str]) -> Result<(), String> {
+        for arg in args {
+            let expr = self.parse_expr(arg)?;
+            self.dot = self.offset();
+            match expr.as_constant() {
+                Some(value) => {
+                    check_fits(value, size)?;
+                    self.emit(&value.to_le_bytes()[..usize::from(size)]);
+                }
+                None => {
+                    let offset = self.offset();
+                    self.emit(&[0; 8][..usize::from(size)]);
+                    self.add_fixup(offset, FixupKind::Data(size), expr);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn align(&mut self, align: u64, fill: Option<u8>, max_skip: Option<u64>) -> Result<(), String> {
+        if !align.is_power_of_two() || align > 1 << 16 {
+            return Err(format!("invalid alignment {align}"));
+        }
+        let is_text = self.sections[self.current_section].kind == SectionKind::Text;
+        let section = &mut self.sections[self.current_section];
+        section.align = section.align.max(align);
+        let offset = section.data.len() as u64;
+        let padding = (align - offset % align) % align;
+        if max_skip.is_some_and(|max_skip| padding > max_skip) {
+            return Ok(());
+        }
+        match (fill, is_text, self.arch) {
+            (Some(fill), _, _) => section.data.resize((offset + padding) as usize, fill),
+            (None, true, Arch::X86_64) => section.data.resize((offset + padding) as usize, 0x90),
+            (None, true, Arch::AArch64) => {
+                section.data.resize(offset.next_multiple_of(4) as usize, 0);
+                while (section.data.len() as u64) < offset + padding {
+                    section.data.extend_from_slice(&aarch64::NOP.to_le_bytes());
+                }
+            }
+            (None, false, _) => section.data.resize((offset + padding) as usize, 0),
+        }
+        Ok(())
+    }
+
+    fn local_label_ref(&self, number: u32, forward: bool) -> Result<String, String> {
+        let count = self.local_label_counts.get(&number).copied().unwrap_or(0);
+        if forward {
+            Ok(local_label_name(number, count))
+        } else if count == 0 {
+            Err(format!("`{number}b` doesn't refer to a preceding label"))
+        } else {
+            Ok(local_label_name(number, count - 1))
+        }
+    }
+
+    fn location(&self, name: &str, section: usize, dot: u64) -> Option<(usize, u64)> {
+        if name == "." { Some((section, dot)) } else { self.labels.get(name).copied() }
+    }
+
+    /// Evaluate `expr` at the current location if all symbols it refers to are already defined.
+    fn resolve_now(&self, expr: &Expr) -> Option<i64> {
+        let location = |name: &Option<String>| match name {
+            Some(name) => self.location(name, self.current_section, self.offset()).map(Some),
+            None => Some(None),
+        };
+        match (location(&expr.symbol)?, location(&expr.minus_symbol)?) {
+            (None, None) => Some(expr.addend),
+            (Some((section, offset)), Some((minus_section, minus_offset)))
+                if section == minus_section =>
+            {
+                Some(offset as i64 - minus_offset as i64 + expr.addend)
+            }
+            _ => None,
+        }
+    }
+
+    fn is_temporary(&self, name: &str) -> bool {
+        name.starts_with('\0')
+            || match self.format {
+                BinaryFormat::MachO => name.starts_with('L'),
+                _ => name.starts_with(".L"),
+            }
+    }
+
+    fn resolve_fixup(
+        &mut self,
+        fixup: &Fixup,
+        relocations: &mut Vec<PendingRelocation>,
+    ) -> Result<(), String> {
+        let Fixup { section, offset, dot, kind, ref expr } = *fixup;
+        let mut symbol = expr.symbol.clone();
+        let mut addend = expr.addend;
+
+        // Fold `a - b` into a constant if both are in the same section or into a pc-relative
+        // value if `b` is in the same section as the fixup.
+        let mut pcrel_base = None;
+        if let Some(minus_symbol) = &expr.minus_symbol {
+            let (minus_section, minus_offset) = self
+                .location(minus_symbol, section, dot)
+                .ok_or_else(|| format!("can't subtract undefined symbol `{minus_symbol}`"))?;
+            match symbol.as_deref().and_then(|symbol| self.location(symbol, section, dot)) {
+                Some((symbol_section, symbol_offset)) if symbol_section == minus_section => {
+                    addend += symbol_offset as i64 - minus_offset as i64;
+                    symbol = None;
+                }
+                _ if minus_section == section && symbol.is_some() => {
+                    pcrel_base = Some(minus_offset);
+                }
+                _ => return Err(format!("can't subtract `{minus_symbol}` across sections")),
+            }
+        }
+        if symbol.as_deref() == Some(".") {
+            return Err("`.` can only be used in differences".to_owned());
+        }
+
+        let local_target = symbol
+            .as_deref()
+            .and_then(|symbol| self.labels.get(symbol))
+            .filter(|&&(target_section, _)| target_section == section)
+            .map(|&(_, target_offset)| target_offset as i64 + addend);
+        let is_undefined =
+            symbol.as_deref().is_some_and(|symbol| !self.labels.contains_key(symbol));
+        let mut relocation = |flags, addend| match &symbol {
+            Some(symbol) => {
+                relocations.push(PendingRelocation {
+                    section,
+                    offset,
+                    symbol: symbol.clone(),
+                    addend,
+                    flags,
+                });
+                Ok(())
+            }
+            None => Err("expected a symbol".to_owned()),
+        };
+        let generic = |kind, encoding, size| RelocationFlags::Generic { kind, encoding, size };
+        let format = self.format;
+        let elf = |r_type| match format {
+            BinaryFormat::Elf => Ok(RelocationFlags::Elf { r_type }),
+            _ => Err("this relocation is only supported for ELF".to_owned()),
+        };
+
+        let data = &mut self.sections[section].data;
+        let patch_u32 = |data: &mut Vec<u8>, bits: u32| {
+            let location = &mut data[offset as usize..offset as usize + 4];
+            let value = u32::from_le_bytes(location.try_into().unwrap()) | bits;
+            location.copy_from_slice(&value.to_le_bytes());
+        };
+        let branch_offset = |target: i64, bits: u32| {
+            let delta = target - offset as i64;
+            if delta % 4 != 0 || !fits_signed(delta / 4, bits) {
+                return Err(format!("branch target out of range: {delta}"));
+            }
+            Ok(((delta / 4) as u32) & ((1 << bits) - 1))
+        };
+
+        match kind {
+            FixupKind::Data(size) => {
+                if let Some(base) = pcrel_base {
+                    if size != 4 && size != 8 {
+                        return Err(format!("unsupported pc-relative data of size {size}"));
+                    }
+                    relocation(
+                        generic(RelocationKind::Relative, RelocationEncoding::Generic, size * 8),
+                        addend + offset as i64 - base as i64,
+                    )?;
+                } else if symbol.is_some() {
+                    if size != 4 && size != 8 {
+                        return Err(format!("unsupported relocation of size {size}"));
+                    }
+                    relocation(
+                        generic(RelocationKind::Absolute, RelocationEncoding::Generic, size * 8),
+                        addend,
+                    )?;
+                } else {
+                    check_fits(addend, size)?;
+                    let size = usize::from(size);
+                    data[offset as usize..offset as usize + size]
+                        .copy_from_slice(&addend.to_le_bytes()[..size]);
+                }
+            }
+            _ if pcrel_base.is_some() => {
+                return Err("unsupported symbol difference".to_owned());
+            }
+            FixupKind::X86Pcrel32 { end, branch } => {
+                if let Some(target) = local_target {
+                    let delta = target - end as i64;
+                    let delta = i32::try_from(delta)
+                        .map_err(|_| format!("displacement out of range: {delta}"))?;
+                    data[offset as usize..offset as usize + 4]
+                        .copy_from_slice(&delta.to_le_bytes());
+                } else {
+                    let (kind, encoding) = match (branch, is_undefined) {
+                        (true, true) => {
+                            (RelocationKind::PltRelative, RelocationEncoding::X86Branch)
+                        }
+                        (true, false) => (RelocationKind::Relative, RelocationEncoding::X86Branch),
+                        (false, _) => {
+                            (RelocationKind::Relative, RelocationEncoding::X86RipRelative)
+                        }
+                    };
+                    relocation(generic(kind, encoding, 32), addend + offset as i64 - end as i64)?;
+                }
+            }
+            FixupKind::AArch64Branch26 => {
+                if let Some(target) = local_target {
+                    patch_u32(data, branch_offset(target, 26)?);
+                } else {
+                    relocation(
+                        generic(RelocationKind::Relative, RelocationEncoding::AArch64Call, 26),
+                        addend,
+                    )?;
+                }
+            }
+            FixupKind::AArch64Branch19 | FixupKind::AArch64Branch14 => {
+                let bits = if let FixupKind::AArch64Branch19 = kind { 19 } else { 14 };
+                let target = local_target
+                    .ok_or_else(|| format!("branch target `{expr:?}` not in the same section"))?;
+                patch_u32(data, branch_offset(target, bits)? << 5);
+            }
+            FixupKind::AArch64Adr => {
+                let target = local_target
+                    .ok_or_else(|| format!("`adr` target `{expr:?}` not in the same section"))?;
+                let delta = target - offset as i64;
+                if !fits_signed(delta, 21) {
+                    return Err(format!("`adr` target out of range: {delta}"));
+                }
+                let delta = delta as u32;
+                patch_u32(data, (delta & 3) << 29 | ((delta >> 2) & 0x7ffff) << 5);
+            }
+            FixupKind::AArch64AdrPage => {
+                relocation(elf(object::elf::R_AARCH64_ADR_PREL_PG_HI21)?, addend)?;
+            }
+            FixupKind::AArch64AddLo12 => {
+                relocation(elf(object::elf::R_AARCH64_ADD_ABS_LO12_NC)?, addend)?;
+            }
+            FixupKind::AArch64LdstLo12 { shift } => {
+                let r_type = match shift {
+                    0 => object::elf::R_AARCH64_LDST8_ABS_LO12_NC,
+                    1 => object::elf::R_AARCH64_LDST16_ABS_LO12_NC,
+                    2 => object::elf::R_AARCH64_LDST32_ABS_LO12_NC,
+                    3 => object::elf::R_AARCH64_LDST64_ABS_LO12_NC,
+                    _ => object::elf::R_AARCH64_LDST128_ABS_LO12_NC,
+                };
+                relocation(elf(r_type)?, addend)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Vec<u8>, String> {
+        let mut relocations = vec![];
+        for fixup in std::mem::take(&mut self.fixups) {
+            self.resolve_fixup(&fixup, &mut relocations)?;
+        }
+
+        let architecture = match self.arch {
+            Arch::X86_64 => object::Architecture::X86_64,
+            Arch::AArch64 => object::Architecture::Aarch64,
+        };
+        let mut object = Object::new(self.format, architecture, Endianness::Little);
+        // Symbol names in the asm source already include the platform specific prefix.
+        object.set_mangling(Mangling::None);
+
+        let mut section_ids = vec![];
+        for section in &self.sections {
+            let id = match &section.name {
+                SectionName::Standard(standard) => object.section_id(*standard),
+                SectionName::Custom { segment, name } => {
+                    object.add_section(segment.clone(), name.clone(), section.kind)
+                }
+            };
+            if section.kind == SectionKind::UninitializedData {
+                if section.data.iter().any(|&byte| byte != 0) {
+                    return Err("non-zero data in an uninitialized data section".to_owned());
+                }
+                object.section_mut(id).append_bss(section.data.len() as u64, section.align);
+            } else {
+                object.section_mut(id).set_data(section.data.clone(), section.align);
+            }
+            section_ids.push(id);
+        }
+
+        let mut symbol_ids = FxHashMap::default();
+        for (name, &(section, offset)) in &self.labels {
+            if self.is_temporary(name) && !self.globals.contains(name) {
+                continue;
+            }
+            let scope = if !self.globals.contains(name) {
+                SymbolScope::Compilation
+            } else if self.hidden.contains(name) {
+                SymbolScope::Linkage
+            } else {
+                SymbolScope::Dynamic
+            };
+            let kind = self.symbol_kinds.get(name).copied().unwrap_or(
+                if self.sections[section].kind == SectionKind::Text {
+                    SymbolKind::Text
+                } else {
+                    SymbolKind::Data
+                },
+            );
+            let symbol_id = object.add_symbol(Symbol {
+                name: name.as_bytes().to_vec(),
+                value: offset,
+                size: self.symbol_sizes.get(name).copied().unwrap_or(0),
+                kind,
+                scope,
+                weak: self.weak.contains(name),
+                section: SymbolSection::Section(section_ids[section]),
+                flags: SymbolFlags::None,
+            });
+            symbol_ids.insert(name.clone(), symbol_id);
+        }
+
+        for relocation in relocations {
+            let (symbol, addend) =
+                match (symbol_ids.get(&relocation.symbol), self.labels.get(&relocation.symbol)) {
+                    (Some(&symbol_id), _) => (symbol_id, relocation.addend),
+                    (None, Some(&(section, offset))) => (
+                        object.section_symbol(section_ids[section]),
+                        relocation.addend + offset as i64,
+                    ),
+                    (None, None) => {
+                        if self.is_temporary(&relocation.symbol) {
+                            return Err(format!(
+                                "undefined temporary label `{}`",
+                                relocation.symbol
+                            ));
+                        }
+                        let symbol_id = object.add_symbol(Symbol {
+                            name: relocation.symbol.as_bytes().to_vec(),
+                            value: 0,
+                            size: 0,
+                            kind: SymbolKind::Text,
+                            scope: SymbolScope::Unknown,
+                            weak: self.weak.contains(&relocation.symbol),
+                            section: SymbolSection::Undefined,
+                            flags: SymbolFlags::None,
+                        });
+                        symbol_ids.insert(relocation.symbol.clone(), symbol_id);
+                        (symbol_id, relocation.addend)
+                    }
+                };
+            object
+                .add_relocation(
+                    section_ids[relocation.section],
+                    Relocation {
+                        offset: relocation.offset,
+                        symbol,
+                        addend,
+                        flags: relocation.flags,
+                    },
+                )
+                .map_err(|err| err.to_string())?;
+        }
+
+        object.write().map_err(|err| err.to_string())
+    }
+}
+
+fn local_label_name(number: u32, index: u32) -> String {
+    format!("\0{number}:{index}")
+}
+
+fn is_ident_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'_' | b'.' | b'$')
+}
+
+pub(super) fn fits_signed(value: i64, bits: u32) -> bool {
+    (-(1 << (bits - 1))..(1 << (bits - 1))).contains(&value)
+}
+
+fn check_fits(value: i64, size: u8) -> Result<(), String> {
+    if size >= 8 || (-(1 << (size * 8 - 1))..(1 << (size * 8))).contains(&value) {
+        Ok(())
+    } else {
+        Err(format!("value {value} doesn't fit in {size} bytes"))
+    }
+}
+
+/// Split a statement into the mnemonic or directive and its operands.
+pub(super) fn split_mnemonic(statement: &str) -> (&str, &str) {
+    let statement = statement.trim();
+    match statement.find(|c: char| c.is_ascii_whitespace() || c == '=') {
+        Some(index) => (&statement[..index], statement[index..].trim()),
+        None => (statement, ""),
+    }
+}
+
+/// Split operands at the commas which are not nested in brackets, braces or strings.
+pub(super) fn split_operands(operands: &str) -> Vec<&str> {
+    let operands = operands.trim();
+    if operands.is_empty() {
+        return vec![];
+    }
+    let mut result = vec![];
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in operands.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '[' | '{' | '(' if !in_string => depth += 1,
+            ']' | '}' | ')' if !in_string => depth -= 1,
+            ',' if !in_string && depth == 0 => {
+                result.push(operands[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    result.push(operands[start..].trim());
+    result
+}
+
+fn strip_block_comments(source: &str) -> Result<String, String> {
+    let mut result = String::with_capacity(source.len());
+    let mut rest = source;
+    let mut in_string = false;
+    while let Some(c) = rest.chars().next() {
+        if !in_string && rest.starts_with("/*") {
+            let end = rest.find("*/").ok_or("unterminated block comment")?;
+            // Keep the newlines to preserve line numbers in errors.
+            result.extend(rest[..end].chars().filter(|&c| c == '\n'));
+            rest = &rest[end + 2..];
+            continue;
+        }
+        match c {
+            '"' => in_string = !in_string,
+            '\\' if in_string && rest.len() > 1 => {
+                let escaped = rest[1..].chars().next().unwrap();
+                result.push(c);
+                result.push(escaped);
+                rest = &rest[1 + escaped.len_utf8()..];
+                continue;
+            }
+            '\n' => in_string = false,
+            _ => {}
+        }
+        result.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    Ok(result)
+}
+
+fn parse_string(s: &str) -> Result<Vec<u8>, String> {
+    let s = s
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .ok_or_else(|| format!("expected string, found `{s}`"))?;
+    let mut result = vec![];
+    let mut bytes = s.bytes().peekable();
+    while let Some(c) = bytes.next() {
+        if c != b'\\' {
+            result.push(c);
+            continue;
+        }
+        let escaped = bytes.next().ok_or("unterminated escape sequence")?;
+        result.push(match escaped {
+            b'n' => b'\n',
+            b't' => b'\t',
+            b'r' => b'\r',
+            b'b' => 8,
+            b'f' => 12,
+            b'0'..=b'7' => {
+                let mut value = u32::from(escaped - b'0');
+                for _ in 0..2 {
+                    match bytes.peek() {
+                        Some(&digit @ b'0'..=b'7') => {
+                            value = value * 8 + u32::from(digit - b'0');
+                            bytes.next();
+                        }
+                        _ => break,
+                    }
+                }
+                value as u8
+            }
+            b'x' => {
+                let mut value = 0u32;
+                while let Some(digit) = bytes.peek().and_then(|&c| (c as char).to_digit(16)) {
+                    value = value * 16 + digit;
+                    bytes.next();
+                }
+                value as u8
+            }
+            _ => escaped,
+        });
+    }
+    Ok(result)
+}
+
+struct ExprParser<'a> {
+    asm: &'a Assembler,
+    s: &'a [u8],
+    pos: usize,
+}
+
+impl ExprParser<'_> {
+    fn parse(mut self) -> Result<Expr, String> {
+        let expr = self.binary(1)?;
+        self.skip_whitespace();
+        if self.pos != self.s.len() {
+            return Err(format!(
+                "unexpected `{}` in expression",
+                String::from_utf8_lossy(&self.s[self.pos..])
+            ));
+        }
+        Ok(expr)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.s.get(self.pos).is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn binary(&mut self, min_precedence: u8) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+        loop {
+            self.skip_whitespace();
+            let rest = &self.s[self.pos..];
+            let Some((op, precedence)) = [
+                ("<<", 3),
+                (">>", 3),
+                ("*", 3),
+                ("/", 3),
+                ("%", 3),
+                ("|", 2),
+                ("&", 2),
+                ("^", 2),
+                ("+", 1),
+                ("-", 1),
+            ]
+            .into_iter()
+            .find(|(op, _)| rest.starts_with(op.as_bytes())) else {
+                return Ok(lhs);
+            };
+            if precedence < min_precedence {
+                return Ok(lhs);
+            }
+            self.pos += op.len();
+            let rhs = self.binary(precedence + 1)?;
+            lhs = match op {
+                "+" => {
+                    if (lhs.symbol.is_some() && rhs.symbol.is_some())
+                        || (lhs.minus_symbol.is_some() && rhs.minus_symbol.is_some())
+                    {
+                        return Err("unsupported expression".to_owned());
+                    }
+                    Expr {
+                        symbol: lhs.symbol.or(rhs.symbol),
+                        minus_symbol: lhs.minus_symbol.or(rhs.minus_symbol),
+                        addend: lhs.addend.wrapping_add(rhs.addend),
+                    }
+                }
+                "-" => {
+                    if rhs.minus_symbol.is_some()
+                        || (rhs.symbol.is_some() && lhs.minus_symbol.is_some())
+                    {
+                        return Err("unsupported expression".to_owned());
+                    }
+                    Expr {
+                        symbol: lhs.symbol,
+                        minus_symbol: lhs.minus_symbol.or(rhs.symbol),
+                        addend: lhs.addend.wrapping_sub(rhs.addend),
+                    }
+                }
+                _ => {
+                    let (Some(lhs), Some(rhs)) = (lhs.as_constant(), rhs.as_constant()) else {
+                        return Err(format!("`{op}` requires constant operands"));
+                    };
+                    Expr::constant(match op {
+                        "<<" => lhs.wrapping_shl(rhs as u32),
+                        ">>" => lhs.wrapping_shr(rhs as u32),
+                        "*" => lhs.wrapping_mul(rhs),
+                        "/" | "%" if rhs == 0 => return Err("division by zero".to_owned()),
+                        "/" => lhs.wrapping_div(rhs),
+                        "%" => lhs.wrapping_rem(rhs),
+                        "|" => lhs | rhs,
+                        "&" => lhs & rhs,
+                        "^" => lhs ^ rhs,
+                        _ => unreachable!(),
+                    })
+                }
+            };
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        self.skip_whitespace();
+        let Some(&c) = self.s.get(self.pos) else {
+            return Err("expected expression".to_owned());
+        };
+        match c {
+            b'-' | b'~' | b'+' => {
+                self.pos += 1;
+                let expr = self.unary()?;
+                if c == b'+' {
+                    return Ok(expr);
+                }
+                let value = expr.as_constant().ok_or("can't negate a symbol")?;
+                Ok(Expr::constant(if c == b'-' { value.wrapping_neg() } else { !value }))
+            }
+            b'(' => {
+                self.pos += 1;
+                let expr = self.binary(1)?;
+                self.skip_whitespace();
+                if self.s.get(self.pos) != Some(&b')') {
+                    return Err("expected `)`".to_owned());
+                }
+                self.pos += 1;
+                Ok(expr)
+            }
+            b'\'' => {
+                let value = *self.s.get(self.pos + 1).ok_or("unterminated character")?;
+                self.pos += 2;
+                if self.s.get(self.pos) == Some(&b'\'') {
+                    self.pos += 1;
+                }
+                Ok(Expr::constant(i64::from(value)))
+            }
+            b'0'..=b'9' => {
+                let start = self.pos;
+                while self.s.get(self.pos).is_some_and(|c| c.is_ascii_alphanumeric()) {
+                    self.pos += 1;
+                }
+                let token = std::str::from_utf8(&self.s[start..self.pos]).unwrap();
+                self.number(token)
+            }
+            _ if is_ident_char(c) => {
+                let start = self.pos;
+                while self.s.get(self.pos).copied().is_some_and(is_ident_char) {
+                    self.pos += 1;
+                }
+                let name = std::str::from_utf8(&self.s[start..self.pos]).unwrap();
+                if let Some(&value) = self.asm.equates.get(name) {
+                    return Ok(Expr::constant(value));
+                }
+                Ok(Expr { symbol: Some(name.to_owned()), minus_symbol: None, addend: 0 })
+            }
+            _ => Err(format!("unexpected `{}` in expression", c as char)),
+        }
+    }
+
+    fn number(&self, token: &str) -> Result<Expr, String> {
+        let invalid = || format!("invalid number `{token}`");
+        let (digits, radix) = if let Some(hex) =
+            token.strip_prefix("0x").or_else(|| token.strip_prefix("0X"))
+        {
+            (hex, 16)
+        } else if let Some(binary) = token.strip_prefix("0b").filter(|binary| !binary.is_empty()) {
+            (binary, 2)
+        } else if let Some(label) = token.strip_suffix(['f', 'b']) {
+            // A reference to a numeric local label like `1f` or `1b`.
+            let number = label.parse().map_err(|_| invalid())?;
+            let name = self.asm.local_label_ref(number, token.ends_with('f'))?;
+            return Ok(Expr { symbol: Some(name), minus_symbol: None, addend: 0 });
+        } else if token.len() > 1 && token.starts_with('0') {
+            (&token[1..], 8)
+        } else {
+            (token, 10)
+        };
+        let value = u64::from_str_radix(digits, radix).map_err(|_| invalid())?;
+        Ok(Expr::constant(value as i64))
+    }
+}
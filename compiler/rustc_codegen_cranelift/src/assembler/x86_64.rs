@@ -0,0 +1,809 @@
+//! Encoding of x86_64 instructions in Intel syntax with `noprefix`.
+//!
+//! The supported instructions are:
+//!
+//! * `mov`, `movzx`, `movsx`, `movsxd`, `lea`, `xchg`, `push` and `pop`
+//! * `add`, `or`, `adc`, `sbb`, `and`, `sub`, `xor`, `cmp` and `test`
+//! * `inc`, `dec`, `not`, `neg`, `mul`, `imul`, `div` and `idiv`
+//! * `rol`, `ror`, `rcl`, `rcr`, `shl`, `sal`, `shr` and `sar`
+//! * `call`, `jmp`, `jcc`, `ret`, `setcc` and `cmovcc`
+//! * `movups`, `movaps`, `movdqu` and `movdqa`
+//! * `nop`, `ud2`, `int3`, `int`, `syscall`, `hlt`, `pause`, `cpuid`, `rdtsc`, `mfence`,
+//!   `lfence`, `sfence`, `leave`, `cdq`, `cdqe`, `cqo`, `clc`, `stc`, `cld`, `std`, `endbr64`,
+//!   `movsb`, `movsq`, `stosb` and `stosq`
+//! * the `lock`, `rep`, `repe`, `repz`, `repne` and `repnz` prefixes
+//!
+//! Registers can be general purpose or SSE registers. Memory operands consist of an optional
+//! `byte ptr`, `word ptr`, `dword ptr`, `qword ptr` or `xmmword ptr` size, an optional `fs:` or
+//! `gs:` segment and a `[base + index * scale + displacement]` address, where the base may be
+//! `rip`. Jump and call targets are always encoded with a 32-bit displacement.
+
+use super::{Assembler, Expr, FixupKind, split_mnemonic, split_operands};
+
+#[derive(Copy, Clone, PartialEq)]
+struct Reg {
+    num: u8,
+    /// The size in bytes. SSE registers have a size of 16.
+    size: u8,
+    /// Whether this is one of `ah`, `ch`, `dh` or `bh`.
+    high_byte: bool,
+}
+
+impl Reg {
+    fn is_gpr(self) -> bool {
+        self.size <= 8
+    }
+
+    /// Whether this register can only be encoded with a REX prefix.
+    fn needs_rex(self) -> bool {
+        self.num >= 8 || (self.size == 1 && self.num >= 4 && !self.high_byte)
+    }
+}
+
+struct Mem {
+    size: Option<u8>,
+    segment: Option<u8>,
+    base: Option<Reg>,
+    index: Option<(Reg, u8)>,
+    rip: bool,
+    disp: Expr,
+}
+
+enum Operand {
+    Reg(Reg),
+    Mem(Mem),
+    Imm(Expr),
+}
+
+impl Operand {
+    fn size(&self) -> Option<u8> {
+        match self {
+            Operand::Reg(reg) => Some(reg.size),
+            Operand::Mem(mem) => mem.size,
+            Operand::Imm(_) => None,
+        }
+    }
+}
+
+/// The contents of the reg field of the ModRM byte.
+#[derive(Copy, Clone)]
+enum RegField {
+    Reg(Reg),
+    /// An opcode extension like the `/4` in `FF /4`.
+    Ext(u8),
+}
+
+fn parse_reg(name: &str) -> Option<Reg> {
+    const GPR64: [&str; 16] = [
+        "rax", "rcx", "rdx", "rbx", "rsp", "rbp", "rsi", "rdi", "r8", "r9", "r10", "r11", "r12",
+        "r13", "r14", "r15",
+    ];
+    const GPR32: [&str; 16] = [
+        "eax", "ecx", "edx", "ebx", "esp", "ebp", "esi", "edi", "r8d", "r9d", "r10d", "r11d",
+        "r12d", "r13d", "r14d", "r15d",
+    ];
+    const GPR16: [&str; 16] = [
+        "ax", "cx", "dx", "bx", "sp", "bp", "si", "di", "r8w", "r9w", "r10w", "r11w", "r12w",
+        "r13w", "r14w", "r15w",
+    ];
+    const GPR8: [&str; 16] = [
+        "al", "cl", "dl", "bl", "spl", "bpl", "sil", "dil", "r8b", "r9b", "r10b", "r11b", "r12b",
+        "r13b", "r14b", "r15b",
+    ];
+    const HIGH8: [&str; 4] = ["ah", "ch", "dh", "bh"];
+
+    let name = name.to_ascii_lowercase();
+    for (names, size) in [(&GPR64, 8), (&GPR32, 4), (&GPR16, 2), (&GPR8, 1)] {
+        if let Some(num) = names.iter().position(|&reg| reg == name) {
+            return Some(Reg { num: num as u8, size, high_byte: false });
+        }
+    }
+    if let Some(num) = HIGH8.iter().position(|&reg| reg == name) {
+        return Some(Reg { num: num as u8 + 4, size: 1, high_byte: true });
+    }
+    let num = name.strip_prefix("xmm")?.parse::<u8>().ok().filter(|&num| num < 16)?;
+    Some(Reg { num, size: 16, high_byte: false })
+}
+
+fn parse_operand(asm: &Assembler, operand: &str) -> Result<Operand, String> {
+    let lower = operand.to_ascii_lowercase();
+    if let Some(reg) = parse_reg(&lower) {
+        return Ok(Operand::Reg(reg));
+    }
+
+    let mut rest = operand;
+    let mut size = None;
+    for (keyword, keyword_size) in
+        [("byte", 1), ("word", 2), ("dword", 4), ("qword", 8), ("xmmword", 16)]
+    {
+        let after_ptr = lower
+            .strip_prefix(keyword)
+            .and_then(|after_keyword| after_keyword.trim_start().strip_prefix("ptr"));
+        if let Some(after_ptr) = after_ptr {
+            size = Some(keyword_size);
+            rest = operand[operand.len() - after_ptr.len()..].trim_start();
+            break;
+        }
+    }
+
+    let mut segment = None;
+    let lower_rest = rest.to_ascii_lowercase();
+    if let Some(after_segment) = lower_rest.strip_prefix("fs:") {
+        segment = Some(0x64);
+        rest = rest[rest.len() - after_segment.len()..].trim_start();
+    } else if let Some(after_segment) = lower_rest.strip_prefix("gs:") {
+        segment = Some(0x65);
+        rest = rest[rest.len() - after_segment.len()..].trim_start();
+    }
+
+    let Some(open) = rest.find('[') else {
+        if size.is_some() || segment.is_some() {
+            return Err(format!("expected memory operand, found `{operand}`"));
+        }
+        let imm = rest.strip_prefix("offset ").unwrap_or(rest);
+        // Branch targets may refer to the PLT entry of a symbol, which is what they use anyway.
+        let imm = imm.strip_suffix("@PLT").unwrap_or(imm);
+        return Ok(Operand::Imm(asm.parse_expr(imm)?));
+    };
+    let close = rest.rfind(']').ok_or_else(|| format!("expected `]` in `{operand}`"))?;
+    if !rest[close + 1..].trim().is_empty() {
+        return Err(format!("unexpected `{}` after memory operand", &rest[close + 1..]));
+    }
+
+    // Split the address into terms and separate the registers from the displacement.
+    let mut base = None;
+    let mut index = None;
+    let mut rip = false;
+    let mut disp = rest[..open].trim().to_owned();
+    let address = &rest[open + 1..close];
+    let mut term_start = 0;
+    let mut depth = 0;
+    for (i, c) in address.char_indices().chain([(address.len(), '+')]) {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            '+' | '-' if depth == 0 => {
+                let term = address[term_start..i].trim();
+                let sign = if term_start == 0 { "+" } else { &address[term_start - 1..term_start] };
+                term_start = i + 1;
+                if term.is_empty() {
+                    if sign == "-" || i == address.len() {
+                        return Err(format!("invalid memory operand `{operand}`"));
+                    }
+                    continue;
+                }
+
+                let lower_term = term.to_ascii_lowercase();
+                let scaled = lower_term.split_once('*').map(|(lhs, rhs)| (lhs.trim(), rhs.trim()));
+                let (reg, scale) = match scaled {
+                    Some((lhs, rhs)) => match (parse_reg(lhs), parse_reg(rhs)) {
+                        (Some(reg), None) => (Some(reg), Some(rhs)),
+                        (None, Some(reg)) => (Some(reg), Some(lhs)),
+                        _ => (None, None),
+                    },
+                    None => (parse_reg(&lower_term), None),
+                };
+                if let Some(reg) = reg {
+                    if sign == "-" || reg.size != 8 {
+                        return Err(format!("invalid address register in `{operand}`"));
+                    }
+                    match scale {
+                        Some(scale) => {
+                            let scale = asm.parse_constant(scale)?;
+                            if ![1, 2, 4, 8].contains(&scale) || index.is_some() || reg.num == 4 {
+                                return Err(format!("invalid index in `{operand}`"));
+                            }
+                            index = Some((reg, scale as u8));
+                        }
+                        None if base.is_none() => base = Some(reg),
+                        None if index.is_none() && reg.num != 4 => index = Some((reg, 1)),
+                        None => return Err(format!("too many registers in `{operand}`")),
+                    }
+                } else if lower_term == "rip" {
+                    if sign == "-" || rip {
+                        return Err(format!("invalid use of `rip` in `{operand}`"));
+                    }
+                    rip = true;
+                } else {
+                    disp.push_str(sign);
+                    disp.push_str(term);
+                }
+            }
+            _ => {}
+        }
+    }
+    if rip && (base.is_some() || index.is_some()) {
+        return Err(format!("`rip` can't be combined with other registers in `{operand}`"));
+    }
+    let disp = if disp.is_empty() { Expr::constant(0) } else { asm.parse_expr(&disp)? };
+    Ok(Operand::Mem(Mem { size, segment, base, index, rip, disp }))
+}
+
+/// Emit an instruction with a ModRM byte, followed by an immediate of the given size if any.
+fn emit_modrm(
+    asm: &mut Assembler,
+    size: u8,
+    prefixes: &[u8],
+    opcode: &[u8],
+    reg: RegField,
+    rm: &Operand,
+    imm: Option<(&Expr, u8)>,
+) -> Result<(), String> {
+    let mut rex = if size == 8 { 0x48 } else { 0 };
+    let mut regs = vec![];
+    if let RegField::Reg(reg) = reg {
+        if reg.num >= 8 {
+            rex |= 0x44;
+        }
+        regs.push(reg);
+    }
+    match rm {
+        Operand::Reg(reg) => {
+            if reg.num >= 8 {
+                rex |= 0x41;
+            }
+            regs.push(*reg);
+        }
+        Operand::Mem(mem) => {
+            if mem.base.is_some_and(|base| base.num >= 8) {
+                rex |= 0x41;
+            }
+            if mem.index.is_some_and(|(index, _)| index.num >= 8) {
+                rex |= 0x42;
+            }
+        }
+        Operand::Imm(_) => unreachable!(),
+    }
+    if regs.iter().any(|reg| reg.needs_rex()) {
+        rex |= 0x40;
+    }
+    if rex != 0 && regs.iter().any(|reg| reg.high_byte) {
+        return Err("`ah`, `ch`, `dh` and `bh` can't be encoded with a REX prefix".to_owned());
+    }
+
+    if let Operand::Mem(Mem { segment: Some(segment), .. }) = rm {
+        asm.emit(&[*segment]);
+    }
+    if size == 2 {
+        asm.emit(&[0x66]);
+    }
+    asm.emit(prefixes);
+    if rex != 0 {
+        asm.emit(&[rex]);
+    }
+    asm.emit(opcode);
+
+    let reg = match reg {
+        RegField::Reg(reg) => reg.num & 7,
+        RegField::Ext(ext) => ext,
+    };
+    let imm_size = imm.map_or(0, |(_, imm_size)| imm_size);
+    match rm {
+        Operand::Reg(rm) => asm.emit(&[0xc0 | reg << 3 | rm.num & 7]),
+        Operand::Mem(mem) => emit_address(asm, reg, mem, imm_size)?,
+        Operand::Imm(_) => unreachable!(),
+    }
+    if let Some((imm, imm_size)) = imm {
+        emit_imm(asm, imm, imm_size)?;
+    }
+    Ok(())
+}
+
+/// Emit the ModRM byte, SIB byte and displacement for a memory operand. `imm_size` is the size of
+/// the immediate following the displacement.
+fn emit_address(asm: &mut Assembler, reg: u8, mem: &Mem, imm_size: u8) -> Result<(), String> {
+    if mem.rip {
+        asm.emit(&[reg << 3 | 0b101]);
+        let offset = asm.offset();
+        asm.emit(&[0; 4]);
+        let end = offset + 4 + u64::from(imm_size);
+        asm.add_fixup(offset, FixupKind::X86Pcrel32 { end, branch: false }, mem.disp.clone());
+        return Ok(());
+    }
+
+    let disp = mem.disp.as_constant();
+    let (mode, disp_size) = match (mem.base, disp) {
+        (None, _) => (0b00, 4),
+        (Some(base), Some(0)) if base.num & 7 != 0b101 => (0b00, 0),
+        (Some(_), Some(disp)) if i8::try_from(disp).is_ok() => (0b01, 1),
+        (Some(_), _) => (0b10, 4),
+    };
+    match (mem.base, mem.index) {
+        (Some(base), None) if base.num & 7 != 0b100 => {
+            asm.emit(&[mode << 6 | reg << 3 | base.num & 7]);
+        }
+        _ => {
+            asm.emit(&[mode << 6 | reg << 3 | 0b100]);
+            let (scale, index) = match mem.index {
+                Some((index, scale)) => (scale.trailing_zeros() as u8, index.num & 7),
+                None => (0, 0b100),
+            };
+            let base = mem.base.map_or(0b101, |base| base.num & 7);
+            asm.emit(&[scale << 6 | index << 3 | base]);
+        }
+    }
+    match (disp_size, disp) {
+        (0, _) => {}
+        (1, Some(disp)) => asm.emit(&[disp as u8]),
+        (_, Some(disp)) => {
+            let disp = i32::try_from(disp)
+                .map_err(|_| format!("displacement {disp} doesn't fit in 32 bits"))?;
+            asm.emit(&disp.to_le_bytes());
+        }
+        (_, None) => emit_imm(asm, &mem.disp, 4)?,
+    }
+    Ok(())
+}
+
+fn emit_imm(asm: &mut Assembler, imm: &Expr, size: u8) -> Result<(), String> {
+    match imm.as_constant() {
+        Some(value) => {
+            let fits = match size {
+                1 => (-0x80..=0xff).contains(&value),
+                2 => (-0x8000..=0xffff).contains(&value),
+                4 => (-0x8000_0000..=0xffff_ffff).contains(&value),
+                _ => true,
+            };
+            if !fits {
+                return Err(format!("immediate {value} doesn't fit in {size} bytes"));
+            }
+            asm.emit(&value.to_le_bytes()[..usize::from(size)]);
+        }
+        None => {
+            let offset = asm.offset();
+            asm.emit(&[0; 8][..usize::from(size)]);
+            asm.add_fixup(offset, FixupKind::Data(size), imm.clone());
+        }
+    }
+    Ok(())
+}
+
+/// Emit an instruction which encodes a register in the low 3 bits of the opcode.
+fn emit_opcode_reg(asm: &mut Assembler, size: u8, opcode: u8, reg: Reg) -> Result<(), String> {
+    let mut rex = if size == 8 { 0x48 } else { 0 };
+    if reg.num >= 8 {
+        rex |= 0x41;
+    }
+    if reg.needs_rex() {
+        rex |= 0x40;
+    }
+    if rex != 0 && reg.high_byte {
+        return Err("`ah`, `ch`, `dh` and `bh` can't be encoded with a REX prefix".to_owned());
+    }
+    if size == 2 {
+        asm.emit(&[0x66]);
+    }
+    if rex != 0 {
+        asm.emit(&[rex]);
+    }
+    asm.emit(&[opcode + (reg.num & 7)]);
+    Ok(())
+}
+
+/// Emit a `call`, `jmp` or `jcc` with a 32-bit displacement to `target`.
+fn emit_branch(asm: &mut Assembler, opcode: &[u8], target: &Expr) {
+    asm.emit(opcode);
+    let offset = asm.offset();
+    asm.emit(&[0; 4]);
+    asm.add_fixup(offset, FixupKind::X86Pcrel32 { end: offset + 4, branch: true }, target.clone());
+}
+
+fn condition_code(cond: &str) -> Option<u8> {
+    Some(match cond {
+        "o" => 0,
+        "no" => 1,
+        "b" | "c" | "nae" => 2,
+        "ae" | "nb" | "nc" => 3,
+        "e" | "z" => 4,
+        "ne" | "nz" => 5,
+        "be" | "na" => 6,
+        "a" | "nbe" => 7,
+        "s" => 8,
+        "ns" => 9,
+        "p" | "pe" => 10,
+        "np" | "po" => 11,
+        "l" | "nge" => 12,
+        "ge" | "nl" => 13,
+        "le" | "ng" => 14,
+        "g" | "nle" => 15,
+        _ => return None,
+    })
+}
+
+/// The operand size of an instruction whose operands must all have the same size.
+fn operand_size(operands: &[&Operand]) -> Result<u8, String> {
+    let mut size = None;
+    for operand in operands {
+        match (size, operand.size()) {
+            (_, None) => {}
+            (None, Some(operand_size)) => size = Some(operand_size),
+            (Some(size), Some(operand_size)) if size == operand_size => {}
+            _ => return Err("operand size mismatch".to_owned()),
+        }
+    }
+    match size {
+        Some(size @ (1 | 2 | 4 | 8)) => Ok(size),
+        Some(_) => Err("expected general purpose register".to_owned()),
+        None => Err("unknown operand size, use for example `qword ptr`".to_owned()),
+    }
+}
+
+fn check_integer_operands(operands: &[Operand]) -> Result<(), String> {
+    if operands.iter().any(|operand| matches!(operand, Operand::Reg(reg) if !reg.is_gpr())) {
+        return Err("unexpected SSE register".to_owned());
+    }
+    Ok(())
+}
+
+pub(super) fn assemble_instruction(
+    asm: &mut Assembler,
+    mnemonic: &str,
+    operands: &str,
+) -> Result<(), String> {
+    let prefix = match mnemonic {
+        "lock" => Some(0xf0),
+        "rep" | "repe" | "repz" => Some(0xf3),
+        "repne" | "repnz" => Some(0xf2),
+        _ => None,
+    };
+    if let Some(prefix) = prefix {
+        asm.emit(&[prefix]);
+        let (mnemonic, operands) = split_mnemonic(operands);
+        return assemble_instruction(asm, &mnemonic.to_ascii_lowercase(), operands);
+    }
+
+    let operands = split_operands(operands)
+        .into_iter()
+        .map(|operand| parse_operand(asm, operand))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let fixed: &[u8] = match mnemonic {
+        "nop" => &[0x90],
+        "ud2" => &[0x0f, 0x0b],
+        "int3" => &[0xcc],
+        "syscall" => &[0x0f, 0x05],
+        "hlt" => &[0xf4],
+        "pause" => &[0xf3, 0x90],
+        "cpuid" => &[0x0f, 0xa2],
+        "rdtsc" => &[0x0f, 0x31],
+        "mfence" => &[0x0f, 0xae, 0xf0],
+        "lfence" => &[0x0f, 0xae, 0xe8],
+        "sfence" => &[0x0f, 0xae, 0xf8],
+        "leave" => &[0xc9],
+        "cdq" => &[0x99],
+        "cdqe" => &[0x48, 0x98],
+        "cqo" => &[0x48, 0x99],
+        "clc" => &[0xf8],
+        "stc" => &[0xf9],
+        "cld" => &[0xfc],
+        "std" => &[0xfd],
+        "endbr64" => &[0xf3, 0x0f, 0x1e, 0xfa],
+        "movsb" => &[0xa4],
+        "movsq" => &[0x48, 0xa5],
+        "stosb" => &[0xaa],
+        "stosq" => &[0x48, 0xab],
+        _ => &[],
+    };
+    if !fixed.is_empty() && operands.is_empty() {
+        asm.emit(fixed);
+        return Ok(());
+    }
+
+    if let Some(sse) = assemble_sse(asm, mnemonic, &operands) {
+        return sse;
+    }
+    check_integer_operands(&operands)?;
+
+    let alu = match mnemonic {
+        "add" => Some(0),
+        "or" => Some(1),
+        "adc" => Some(2),
+        "sbb" => Some(3),
+        "and" => Some(4),
+        "sub" => Some(5),
+        "xor" => Some(6),
+        "cmp" => Some(7),
+        _ => None,
+    };
+    if let Some(alu) = alu {
+        return match &operands[..] {
+            [dst @ (Operand::Reg(_) | Operand::Mem(_)), src @ Operand::Reg(src_reg)] => {
+                let size = operand_size(&[dst, src])?;
+                let opcode = alu * 8 + if size == 1 { 0 } else { 1 };
+                emit_modrm(asm, size, &[], &[opcode], RegField::Reg(*src_reg), dst, None)
+            }
+            [dst @ Operand::Reg(dst_reg), src @ Operand::Mem(_)] => {
+                let size = operand_size(&[dst, src])?;
+                let opcode = alu * 8 + if size == 1 { 2 } else { 3 };
+                emit_modrm(asm, size, &[], &[opcode], RegField::Reg(*dst_reg), src, None)
+            }
+            [dst @ (Operand::Reg(_) | Operand::Mem(_)), Operand::Imm(imm)] => {
+                let size = operand_size(&[dst])?;
+                let (opcode, imm_size) = if size == 1 {
+                    (0x80, 1)
+                } else if imm.as_constant().is_some_and(|imm| i8::try_from(imm).is_ok()) {
+                    (0x83, 1)
+                } else {
+                    (0x81, size.min(4))
+                };
+                let imm = sign_extended_imm(imm, size)?;
+                emit_modrm(
+                    asm,
+                    size,
+                    &[],
+                    &[opcode],
+                    RegField::Ext(alu),
+                    dst,
+                    Some((&imm, imm_size)),
+                )
+            }
+            _ => Err("invalid operands".to_owned()),
+        };
+    }
+
+    let unary = match mnemonic {
+        "inc" => Some((0xfe, 0)),
+        "dec" => Some((0xfe, 1)),
+        "not" => Some((0xf6, 2)),
+        "neg" => Some((0xf6, 3)),
+        "mul" => Some((0xf6, 4)),
+        "imul" if operands.len() == 1 => Some((0xf6, 5)),
+        "div" => Some((0xf6, 6)),
+        "idiv" => Some((0xf6, 7)),
+        _ => None,
+    };
+    if let Some((opcode, ext)) = unary {
+        let [operand @ (Operand::Reg(_) | Operand::Mem(_))] = &operands[..] else {
+            return Err("invalid operands".to_owned());
+        };
+        let size = operand_size(&[operand])?;
+        let opcode = if size == 1 { opcode } else { opcode + 1 };
+        return emit_modrm(asm, size, &[], &[opcode], RegField::Ext(ext), operand, None);
+    }
+
+    let shift = match mnemonic {
+        "rol" => Some(0),
+        "ror" => Some(1),
+        "rcl" => Some(2),
+        "rcr" => Some(3),
+        "shl" | "sal" => Some(4),
+        "shr" => Some(5),
+        "sar" => Some(7),
+        _ => None,
+    };
+    if let Some(ext) = shift {
+        let (dst, opcode, imm) = match &operands[..] {
+            [dst @ (Operand::Reg(_) | Operand::Mem(_))] => (dst, 0xd0, None),
+            [
+                dst @ (Operand::Reg(_) | Operand::Mem(_)),
+                Operand::Reg(Reg { num: 1, size: 1, .. }),
+            ] => (dst, 0xd2, None),
+            [dst @ (Operand::Reg(_) | Operand::Mem(_)), Operand::Imm(imm)] => {
+                (dst, 0xc0, Some((imm, 1)))
+            }
+            _ => return Err("invalid operands".to_owned()),
+        };
+        let size = operand_size(&[dst])?;
+        let opcode = if size == 1 { opcode } else { opcode + 1 };
+        return emit_modrm(asm, size, &[], &[opcode], RegField::Ext(ext), dst, imm);
+    }
+
+    if let Some(cond) = mnemonic.strip_prefix('j').and_then(condition_code) {
+        let [Operand::Imm(target)] = &operands[..] else {
+            return Err("expected branch target".to_owned());
+        };
+        emit_branch(asm, &[0x0f, 0x80 + cond], target);
+        return Ok(());
+    }
+    if let Some(cond) = mnemonic.strip_prefix("set").and_then(condition_code) {
+        let [dst @ (Operand::Reg(_) | Operand::Mem(_))] = &operands[..] else {
+            return Err("invalid operands".to_owned());
+        };
+        if operand_size(&[dst])? != 1 {
+            return Err("expected byte operand".to_owned());
+        }
+        return emit_modrm(asm, 1, &[], &[0x0f, 0x90 + cond], RegField::Ext(0), dst, None);
+    }
+    if let Some(cond) = mnemonic.strip_prefix("cmov").and_then(condition_code) {
+        let [dst @ Operand::Reg(dst_reg), src @ (Operand::Reg(_) | Operand::Mem(_))] =
+            &operands[..]
+        else {
+            return Err("invalid operands".to_owned());
+        };
+        let size = operand_size(&[dst, src])?;
+        return emit_modrm(
+            asm,
+            size,
+            &[],
+            &[0x0f, 0x40 + cond],
+            RegField::Reg(*dst_reg),
+            src,
+            None,
+        );
+    }
+
+    match (mnemonic, &operands[..]) {
+        ("mov", [dst @ (Operand::Reg(_) | Operand::Mem(_)), src @ Operand::Reg(src_reg)]) => {
+            let size = operand_size(&[dst, src])?;
+            let opcode = if size == 1 { 0x88 } else { 0x89 };
+            emit_modrm(asm, size, &[], &[opcode], RegField::Reg(*src_reg), dst, None)
+        }
+        ("mov", [dst @ Operand::Reg(dst_reg), src @ Operand::Mem(_)]) => {
+            let size = operand_size(&[dst, src])?;
+            let opcode = if size == 1 { 0x8a } else { 0x8b };
+            emit_modrm(asm, size, &[], &[opcode], RegField::Reg(*dst_reg), src, None)
+        }
+        ("mov", [Operand::Reg(dst), Operand::Imm(imm)]) => {
+            let fits_i32 = imm.as_constant().is_some_and(|imm| i32::try_from(imm).is_ok());
+            if dst.size == 8 && fits_i32 {
+                emit_modrm(asm, 8, &[], &[0xc7], RegField::Ext(0), &operands[0], Some((imm, 4)))
+            } else {
+                emit_opcode_reg(asm, dst.size, if dst.size == 1 { 0xb0 } else { 0xb8 }, *dst)?;
+                emit_imm(asm, imm, dst.size)
+            }
+        }
+        ("mov", [dst @ Operand::Mem(_), Operand::Imm(imm)]) => {
+            let size = operand_size(&[dst])?;
+            let imm = sign_extended_imm(imm, size)?;
+            let opcode = if size == 1 { 0xc6 } else { 0xc7 };
+            emit_modrm(asm, size, &[], &[opcode], RegField::Ext(0), dst, Some((&imm, size.min(4))))
+        }
+        (
+            "movzx" | "movsx",
+            [dst @ Operand::Reg(dst_reg), src @ (Operand::Reg(_) | Operand::Mem(_))],
+        ) => {
+            let opcode = match (mnemonic, src.size()) {
+                ("movzx", Some(1)) => 0xb6,
+                ("movzx", Some(2)) => 0xb7,
+                ("movsx", Some(1)) => 0xbe,
+                ("movsx", Some(2)) => 0xbf,
+                _ => return Err("expected byte or word source operand".to_owned()),
+            };
+            let size = operand_size(&[dst])?;
+            emit_modrm(asm, size, &[], &[0x0f, opcode], RegField::Reg(*dst_reg), src, None)
+        }
+        ("movsxd", [Operand::Reg(dst), src @ (Operand::Reg(_) | Operand::Mem(_))]) => {
+            if dst.size != 8 || src.size() != Some(4) {
+                return Err("invalid operand sizes".to_owned());
+            }
+            emit_modrm(asm, 8, &[], &[0x63], RegField::Reg(*dst), src, None)
+        }
+        ("lea", [Operand::Reg(dst), src @ Operand::Mem(_)]) => {
+            if dst.size == 1 {
+                return Err("invalid operand size".to_owned());
+            }
+            emit_modrm(asm, dst.size, &[], &[0x8d], RegField::Reg(*dst), src, None)
+        }
+        ("test", [dst @ (Operand::Reg(_) | Operand::Mem(_)), src @ Operand::Reg(src_reg)]) => {
+            let size = operand_size(&[dst, src])?;
+            let opcode = if size == 1 { 0x84 } else { 0x85 };
+            emit_modrm(asm, size, &[], &[opcode], RegField::Reg(*src_reg), dst, None)
+        }
+        ("test", [dst @ (Operand::Reg(_) | Operand::Mem(_)), Operand::Imm(imm)]) => {
+            let size = operand_size(&[dst])?;
+            let imm = sign_extended_imm(imm, size)?;
+            let opcode = if size == 1 { 0xf6 } else { 0xf7 };
+            emit_modrm(asm, size, &[], &[opcode], RegField::Ext(0), dst, Some((&imm, size.min(4))))
+        }
+        ("xchg", [dst @ (Operand::Reg(_) | Operand::Mem(_)), src @ Operand::Reg(src_reg)])
+        | ("xchg", [src @ Operand::Reg(src_reg), dst @ Operand::Mem(_)]) => {
+            let size = operand_size(&[dst, src])?;
+            let opcode = if size == 1 { 0x86 } else { 0x87 };
+            emit_modrm(asm, size, &[], &[opcode], RegField::Reg(*src_reg), dst, None)
+        }
+        ("imul", [dst @ Operand::Reg(dst_reg), src @ (Operand::Reg(_) | Operand::Mem(_))]) => {
+            let size = operand_size(&[dst, src])?;
+            emit_modrm(asm, size, &[], &[0x0f, 0xaf], RegField::Reg(*dst_reg), src, None)
+        }
+        (
+            "imul",
+            [
+                dst @ Operand::Reg(dst_reg),
+                src @ (Operand::Reg(_) | Operand::Mem(_)),
+                Operand::Imm(imm),
+            ],
+        ) => {
+            let size = operand_size(&[dst, src])?;
+            let (opcode, imm_size) =
+                if imm.as_constant().is_some_and(|imm| i8::try_from(imm).is_ok()) {
+                    (0x6b, 1)
+                } else {
+                    (0x69, size.min(4))
+                };
+            let imm = sign_extended_imm(imm, size)?;
+            emit_modrm(
+                asm,
+                size,
+                &[],
+                &[opcode],
+                RegField::Reg(*dst_reg),
+                src,
+                Some((&imm, imm_size)),
+            )
+        }
+        ("push", [Operand::Reg(reg)]) if reg.size == 8 => emit_opcode_reg(asm, 4, 0x50, *reg),
+        ("pop", [Operand::Reg(reg)]) if reg.size == 8 => emit_opcode_reg(asm, 4, 0x58, *reg),
+        ("push", [mem @ Operand::Mem(Mem { size: Some(8), .. })]) => {
+            emit_modrm(asm, 4, &[], &[0xff], RegField::Ext(6), mem, None)
+        }
+        ("pop", [mem @ Operand::Mem(Mem { size: Some(8), .. })]) => {
+            emit_modrm(asm, 4, &[], &[0x8f], RegField::Ext(0), mem, None)
+        }
+        ("push", [Operand::Imm(imm)]) => {
+            if imm.as_constant().is_some_and(|imm| i8::try_from(imm).is_ok()) {
+                asm.emit(&[0x6a]);
+                emit_imm(asm, imm, 1)
+            } else {
+                asm.emit(&[0x68]);
+                emit_imm(asm, &sign_extended_imm(imm, 8)?, 4)
+            }
+        }
+        ("call" | "jmp", [Operand::Imm(target)]) => {
+            emit_branch(asm, if mnemonic == "call" { &[0xe8] } else { &[0xe9] }, target);
+            Ok(())
+        }
+        ("call" | "jmp", [target @ (Operand::Reg(_) | Operand::Mem(_))]) => {
+            if target.size().is_some_and(|size| size != 8) {
+                return Err("expected 64-bit branch target".to_owned());
+            }
+            let ext = if mnemonic == "call" { 2 } else { 4 };
+            emit_modrm(asm, 4, &[], &[0xff], RegField::Ext(ext), target, None)
+        }
+        ("ret", []) => {
+            asm.emit(&[0xc3]);
+            Ok(())
+        }
+        ("ret", [Operand::Imm(imm)]) => {
+            asm.emit(&[0xc2]);
+            emit_imm(asm, imm, 2)
+        }
+        ("int", [Operand::Imm(imm)]) => {
+            asm.emit(&[0xcd]);
+            emit_imm(asm, imm, 1)
+        }
+        _ => Err(format!("unsupported instruction `{mnemonic}`")),
+    }
+}
+
+/// Check that a constant immediate fits the sign extended immediate of an instruction with the
+/// given operand size and convert it to the value stored in the instruction.
+fn sign_extended_imm(imm: &Expr, size: u8) -> Result<Expr, String> {
+    let Some(value) = imm.as_constant() else {
+        return Ok(imm.clone());
+    };
+    let value = match size {
+        8 if i32::try_from(value).is_err() => {
+            return Err(format!("immediate {value} doesn't fit in 32 bits"));
+        }
+        4 if (0x8000_0000..=0xffff_ffff).contains(&value) => value - (1 << 32),
+        2 if (0x8000..=0xffff).contains(&value) => value - (1 << 16),
+        1 if (0x80..=0xff).contains(&value) => value - (1 << 8),
+        _ => value,
+    };
+    Ok(Expr::constant(value))
+}
+
+fn assemble_sse(
+    asm: &mut Assembler,
+    mnemonic: &str,
+    operands: &[Operand],
+) -> Option<Result<(), String>> {
+    let (prefixes, load, store): (&[u8], _, _) = match mnemonic {
+        "movups" => (&[], 0x10, 0x11),
+        "movaps" => (&[], 0x28, 0x29),
+        "movdqu" => (&[0xf3], 0x6f, 0x7f),
+        "movdqa" => (&[0x66], 0x6f, 0x7f),
+        _ => return None,
+    };
+    let is_xmm = |operand: &Operand| matches!(operand, Operand::Reg(reg) if reg.size == 16);
+    let is_mem = |operand: &Operand| matches!(operand, Operand::Mem(mem) if mem.size.is_none_or(|size| size == 16));
+    Some(match operands {
+        [Operand::Reg(dst), src] if is_xmm(&operands[0]) && (is_xmm(src) || is_mem(src)) => {
+            emit_modrm(asm, 16, prefixes, &[0x0f, load], RegField::Reg(*dst), src, None)
+        }
+        [dst, Operand::Reg(src)] if is_mem(dst) && is_xmm(&operands[1]) => {
+            emit_modrm(asm, 16, prefixes, &[0x0f, store], RegField::Reg(*src), dst, None)
+        }
+        _ => Err("invalid operands".to_owned()),
+    })
+}
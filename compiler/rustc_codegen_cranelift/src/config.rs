@@ -57,6 +57,13 @@ pub struct BackendConfig {
     /// Defaults to true when the `CG_CLIF_DISABLE_INCR_CACHE` env var is set to 1 or false
     /// otherwise. Can be set using `-Cllvm-args=disable_incr_cache=...`.
     pub disable_incr_cache: bool,
+
+    /// Don't fall back to assembling `global_asm!` and inline asm with the LLVM backend in a child
+    /// rustc process when the integrated assembler doesn't support it and error out instead.
+    ///
+    /// Defaults to true when the `CG_CLIF_DISABLE_ASM_FALLBACK` env var is set to 1 or false
+    /// otherwise. Can be set using `-Cllvm-args=disable_asm_fallback=...`.
+    pub disable_asm_fallback: bool,
 }
 
 impl Default for BackendConfig {
@@ -74,6 +81,7 @@ fn default() -> Self {
             },
             enable_verifier: cfg!(debug_assertions) || bool_env_var("CG_CLIF_ENABLE_VERIFIER"),
             disable_incr_cache: bool_env_var("CG_CLIF_DISABLE_INCR_CACHE"),
+            disable_asm_fallback: bool_env_var("CG_CLIF_DISABLE_ASM_FALLBACK"),
         }
     }
 }
@@ -97,6 +105,9 @@ fn parse_bool(name: &str, value: &str) -> Result<bool, String> {
                     "mode" => config.codegen_mode = value.parse()?,
                     "enable_verifier" => config.enable_verifier = parse_bool(name, value)?,
                     "disable_incr_cache" => config.disable_incr_cache = parse_bool(name, value)?,
+                    "disable_asm_fallback" => {
+                        config.disable_asm_fallback = parse_bool(name, value)?
+                    }
                     _ => return Err(format!("Unknown option `{}`", name)),
                 }
             } else {
@@ -623,7 +623,7 @@ pub(crate) fn run_aot(
         }
     });
 
-    let global_asm_config = Arc::new(crate::global_asm::GlobalAsmConfig::new(tcx));
+    let global_asm_config = Arc::new(crate::global_asm::GlobalAsmConfig::new(tcx, &backend_config));
 
     let (todo_cgus, done_cgus) =
         cgus.into_iter().enumerate().partition::<Vec<_>, _>(|&(i, _)| match cgu_reuse[i] {
@@ -2,7 +2,7 @@
 //! standalone executable.
 
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::sync::Arc;
 
@@ -12,6 +12,7 @@
 use rustc_session::config::{OutputFilenames, OutputType};
 use rustc_target::asm::InlineAsmArch;
 
+use crate::BackendConfig;
 use crate::prelude::*;
 
 pub(crate) fn codegen_global_asm_item(tcx: TyCtxt<'_>, global_asm: &mut String, item_id: ItemId) {
@@ -109,20 +110,26 @@ pub(crate) fn codegen_global_asm_item(tcx: TyCtxt<'_>, global_asm: &mut String,
 #[derive(Debug)]
 pub(crate) struct GlobalAsmConfig {
     assembler: PathBuf,
+    triple: target_lexicon::Triple,
+    disable_asm_fallback: bool,
     target: String,
+    sysroot: PathBuf,
     pub(crate) output_filenames: Arc<OutputFilenames>,
 }
 
 impl GlobalAsmConfig {
-    pub(crate) fn new(tcx: TyCtxt<'_>) -> Self {
+    pub(crate) fn new(tcx: TyCtxt<'_>, backend_config: &BackendConfig) -> Self {
         GlobalAsmConfig {
             assembler: crate::toolchain::get_toolchain_binary(tcx.sess, "as"),
+            triple: crate::target_triple(tcx.sess),
+            disable_asm_fallback: backend_config.disable_asm_fallback,
             target: match &tcx.sess.opts.target_triple {
                 rustc_target::spec::TargetTriple::TargetTriple(triple) => triple.clone(),
                 rustc_target::spec::TargetTriple::TargetJson { path_for_rustdoc, .. } => {
                     path_for_rustdoc.to_str().unwrap().to_owned()
                 }
             },
+            sysroot: tcx.sess.sysroot.clone(),
             output_filenames: tcx.output_filenames(()).clone(),
         }
     }
@@ -164,55 +171,82 @@ pub(crate) fn compile_global_asm(
             return Err(format!("Failed to assemble `{}`", global_asm));
         }
     } else {
-        let mut child = Command::new(std::env::current_exe().unwrap())
-            // Avoid a warning about the jobserver fd not being passed
-            .env_remove("CARGO_MAKEFLAGS")
-            .arg("--target")
-            .arg(&config.target)
-            .arg("--crate-type")
-            .arg("staticlib")
-            .arg("--emit")
-            .arg("obj")
-            .arg("-o")
-            .arg(&global_asm_object_file)
-            .arg("-")
-            .arg("-Abad_asm_style")
-            .arg("-Zcodegen-backend=llvm")
-            .stdin(Stdio::piped())
-            .spawn()
-            .expect("Failed to spawn `as`.");
-        let mut stdin = child.stdin.take().unwrap();
-        stdin
-            .write_all(
-                br####"
-                #![feature(decl_macro, no_core, rustc_attrs)]
-                #![allow(internal_features)]
-                #![no_core]
-                #[rustc_builtin_macro]
-                #[rustc_macro_transparency = "semitransparent"]
-                macro global_asm() { /* compiler built-in */ }
-                global_asm!(r###"
-                "####,
-            )
-            .unwrap();
-        stdin.write_all(global_asm.as_bytes()).unwrap();
-        stdin
-            .write_all(
-                br####"
-                "###);
-                "####,
-            )
-            .unwrap();
-        std::mem::drop(stdin);
-        let status = child.wait().expect("Failed to wait for `as`.");
-        if !status.success() {
-            return Err(format!("Failed to assemble `{}`", global_asm));
+        // Assemble with the integrated assembler of cg_clif, which only supports a subset of the
+        // instructions and directives. Anything else is assembled with the LLVM backend instead.
+        match crate::assembler::assemble(&config.triple, &global_asm) {
+            Ok(object) => std::fs::write(&global_asm_object_file, object).map_err(|err| {
+                format!("Failed to write `{}`: {}", global_asm_object_file.display(), err)
+            })?,
+            Err(err) if config.disable_asm_fallback => {
+                return Err(format!("Failed to assemble `{}`: {}", global_asm, err));
+            }
+            Err(_) => assemble_with_llvm(config, &global_asm, &global_asm_object_file)?,
         }
     }
 
     Ok(Some(global_asm_object_file))
 }
 
+/// Assemble with the integrated assembler of the LLVM backend by running the current rustc on a
+/// `global_asm!` invocation.
+fn assemble_with_llvm(
+    config: &GlobalAsmConfig,
+    global_asm: &str,
+    object_file: &Path,
+) -> Result<(), String> {
+    // This has to be a separate process: the LLVM backend can't be loaded into a compiler
+    // session that already uses this backend.
+    let mut child = Command::new(std::env::current_exe().unwrap())
+        // Avoid a warning about the jobserver fd not being passed
+        .env_remove("CARGO_MAKEFLAGS")
+        // The LLVM backend is found through the sysroot, which the child can't always
+        // derive from its own location, for example in hermetic builds.
+        .arg("--sysroot")
+        .arg(&config.sysroot)
+        .arg("--target")
+        .arg(&config.target)
+        .arg("--crate-type")
+        .arg("staticlib")
+        .arg("--emit")
+        .arg("obj")
+        .arg("-o")
+        .arg(object_file)
+        .arg("-")
+        .arg("-Abad_asm_style")
+        .arg("-Zcodegen-backend=llvm")
+        .stdin(Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn `as`.");
+    let mut stdin = child.stdin.take().unwrap();
+    stdin
+        .write_all(
+            br####"
+            #![feature(decl_macro, no_core, rustc_attrs)]
+            #![allow(internal_features)]
+            #![no_core]
+            #[rustc_builtin_macro]
+            #[rustc_macro_transparency = "semitransparent"]
+            macro global_asm() { /* compiler built-in */ }
+            global_asm!(r###"
+            "####,
+        )
+        .unwrap();
+    stdin.write_all(global_asm.as_bytes()).unwrap();
+    stdin
+        .write_all(
+            br####"
+            "###);
+            "####,
+        )
+        .unwrap();
+    std::mem::drop(stdin);
+    let status = child.wait().expect("Failed to wait for `as`.");
+    if !status.success() {
+        return Err(format!("Failed to assemble `{}`", global_asm));
+    }
+    Ok(())
+}
+
 pub(crate) fn add_file_stem_postfix(mut path: PathBuf, postfix: &str) -> PathBuf {
     let mut new_filename = path.file_stem().unwrap().to_owned();
     new_filename.push(postfix);
@@ -121,6 +121,44 @@ pub(crate) fn codegen_aarch64_llvm_intrinsic_call<'tcx>(
             simd_reduce(fx, v, None, ret, &|fx, _ty, a, b| fx.bcx.ins().fadd(a, b));
         }
 
+        _ if intrinsic.starts_with("llvm.aarch64.neon.fmaxv.f") => {
+            intrinsic_args!(fx, args => (v); intrinsic);
+
+            simd_reduce(fx, v, None, ret, &|fx, _ty, a, b| fx.bcx.ins().fmax(a, b));
+        }
+
+        _ if intrinsic.starts_with("llvm.aarch64.neon.fminv.f") => {
+            intrinsic_args!(fx, args => (v); intrinsic);
+
+            simd_reduce(fx, v, None, ret, &|fx, _ty, a, b| fx.bcx.ins().fmin(a, b));
+        }
+
+        _ if intrinsic.starts_with("llvm.aarch64.neon.saddv.i")
+            || intrinsic.starts_with("llvm.aarch64.neon.uaddv.i")
+            || intrinsic.starts_with("llvm.aarch64.neon.saddlv.i")
+            || intrinsic.starts_with("llvm.aarch64.neon.uaddlv.i") =>
+        {
+            intrinsic_args!(fx, args => (v); intrinsic);
+
+            // The result may be wider than the lanes, e.g. `uaddv.i32.v16i8`.
+            let signed = intrinsic.starts_with("llvm.aarch64.neon.sadd");
+            let ret_ty = fx.clif_type(ret.layout().ty).unwrap();
+            let (lane_count, _lane_ty) = v.layout().ty.simd_size_and_type(fx.tcx);
+            let mut res = fx.bcx.ins().iconst(ret_ty, 0);
+            for lane_idx in 0..lane_count {
+                let lane = v.value_lane(fx, lane_idx).load_scalar(fx);
+                let lane = if fx.bcx.func.dfg.value_type(lane) == ret_ty {
+                    lane
+                } else if signed {
+                    fx.bcx.ins().sextend(ret_ty, lane)
+                } else {
+                    fx.bcx.ins().uextend(ret_ty, lane)
+                };
+                res = fx.bcx.ins().iadd(res, lane);
+            }
+            ret.write_cvalue(fx, CValue::by_val(res, ret.layout()));
+        }
+
         _ if intrinsic.starts_with("llvm.aarch64.neon.frintn.v") => {
             intrinsic_args!(fx, args => (v); intrinsic);
 
@@ -169,7 +169,10 @@ pub(crate) fn codegen_x86_llvm_intrinsic_call<'tcx>(
             }
         }
 
-        "llvm.x86.sse.max.ps" => {
+        "llvm.x86.sse.max.ps"
+        | "llvm.x86.sse2.max.pd"
+        | "llvm.x86.avx.max.ps.256"
+        | "llvm.x86.avx.max.pd.256" => {
             // https://www.intel.com/content/www/us/en/docs/intrinsics-guide/index.html#text=_mm_max_ps&ig_expand=4357
             intrinsic_args!(fx, args => (a, b); intrinsic);
 
@@ -182,7 +185,10 @@ pub(crate) fn codegen_x86_llvm_intrinsic_call<'tcx>(
             );
         }
 
-        "llvm.x86.sse.min.ps" => {
+        "llvm.x86.sse.min.ps"
+        | "llvm.x86.sse2.min.pd"
+        | "llvm.x86.avx.min.ps.256"
+        | "llvm.x86.avx.min.pd.256" => {
             // https://www.intel.com/content/www/us/en/docs/intrinsics-guide/index.html#text=_mm_min_ps&ig_expand=4489
             intrinsic_args!(fx, args => (a, b); intrinsic);
 
@@ -413,7 +419,12 @@ fn select4(
                 MemFlags::trusted(),
             );
         }
-        "llvm.x86.ssse3.pabs.b.128" | "llvm.x86.ssse3.pabs.w.128" | "llvm.x86.ssse3.pabs.d.128" => {
+        "llvm.x86.ssse3.pabs.b.128"
+        | "llvm.x86.ssse3.pabs.w.128"
+        | "llvm.x86.ssse3.pabs.d.128"
+        | "llvm.x86.avx2.pabs.b"
+        | "llvm.x86.avx2.pabs.w"
+        | "llvm.x86.avx2.pabs.d" => {
             intrinsic_args!(fx, args => (a); intrinsic);
 
             simd_for_each_lane(fx, a, ret, &|fx, _lane_ty, _res_lane_ty, lane| {
@@ -477,7 +488,10 @@ fn select4(
             let val = CValue::by_val_pair(cb_out, c, layout);
             ret.write_cvalue(fx, val);
         }
-        "llvm.x86.sse2.pavg.b" | "llvm.x86.sse2.pavg.w" => {
+        "llvm.x86.sse2.pavg.b"
+        | "llvm.x86.sse2.pavg.w"
+        | "llvm.x86.avx2.pavg.b"
+        | "llvm.x86.avx2.pavg.w" => {
             intrinsic_args!(fx, args => (a, b); intrinsic);
 
             // FIXME use vector instructions when possible
@@ -498,7 +512,11 @@ fn select4(
                 },
             );
         }
-        "llvm.x86.sse2.psra.w" => {
+        "llvm.x86.sse2.psra.w"
+        | "llvm.x86.sse2.psra.d"
+        | "llvm.x86.avx2.psra.w"
+        | "llvm.x86.avx2.psra.d" => {
+            // https://www.intel.com/content/www/us/en/docs/intrinsics-guide/index.html#text=_mm_sra_epi16&ig_expand=6051
             intrinsic_args!(fx, args => (a, count); intrinsic);
 
             let count_lane = count.force_stack(fx).0.load(fx, types::I64, MemFlags::trusted());
@@ -511,6 +529,252 @@ fn select4(
                 fx.bcx.ins().sshr(a_lane, saturated_count)
             });
         }
+        "llvm.x86.sse2.psll.w"
+        | "llvm.x86.sse2.psll.d"
+        | "llvm.x86.sse2.psll.q"
+        | "llvm.x86.avx2.psll.w"
+        | "llvm.x86.avx2.psll.d"
+        | "llvm.x86.avx2.psll.q"
+        | "llvm.x86.sse2.psrl.w"
+        | "llvm.x86.sse2.psrl.d"
+        | "llvm.x86.sse2.psrl.q"
+        | "llvm.x86.avx2.psrl.w"
+        | "llvm.x86.avx2.psrl.d"
+        | "llvm.x86.avx2.psrl.q" => {
+            // https://www.intel.com/content/www/us/en/docs/intrinsics-guide/index.html#text=_mm_sll_epi16&ig_expand=6025
+            // https://www.intel.com/content/www/us/en/docs/intrinsics-guide/index.html#text=_mm_srl_epi16&ig_expand=6100
+            intrinsic_args!(fx, args => (a, count); intrinsic);
+
+            let is_left = intrinsic.contains(".psll.");
+            let count_lane = count.force_stack(fx).0.load(fx, types::I64, MemFlags::trusted());
+            let lane_ty = fx.clif_type(a.layout().ty.simd_size_and_type(fx.tcx).1).unwrap();
+            // Unlike Cranelift, x86 doesn't mask the shift amount, but zeroes all lanes instead.
+            let out_of_range = fx.bcx.ins().icmp_imm(
+                IntCC::UnsignedGreaterThan,
+                count_lane,
+                i64::from(lane_ty.bits() - 1),
+            );
+            let zero = fx.bcx.ins().iconst(lane_ty, 0);
+
+            // FIXME use vector instructions when possible
+            simd_for_each_lane(fx, a, ret, &|fx, _lane_ty, _res_lane_ty, a_lane| {
+                let shifted = if is_left {
+                    fx.bcx.ins().ishl(a_lane, count_lane)
+                } else {
+                    fx.bcx.ins().ushr(a_lane, count_lane)
+                };
+                fx.bcx.ins().select(out_of_range, zero, shifted)
+            });
+        }
+        "llvm.x86.avx2.psllv.d"
+        | "llvm.x86.avx2.psllv.d.256"
+        | "llvm.x86.avx2.psllv.q"
+        | "llvm.x86.avx2.psllv.q.256"
+        | "llvm.x86.avx2.psrlv.d"
+        | "llvm.x86.avx2.psrlv.d.256"
+        | "llvm.x86.avx2.psrlv.q"
+        | "llvm.x86.avx2.psrlv.q.256"
+        | "llvm.x86.avx2.psrav.d"
+        | "llvm.x86.avx2.psrav.d.256" => {
+            // https://www.intel.com/content/www/us/en/docs/intrinsics-guide/index.html#text=_mm_sllv_epi32&ig_expand=6031
+            // https://www.intel.com/content/www/us/en/docs/intrinsics-guide/index.html#text=_mm_srlv_epi32&ig_expand=6106
+            // https://www.intel.com/content/www/us/en/docs/intrinsics-guide/index.html#text=_mm_srav_epi32&ig_expand=6071
+            intrinsic_args!(fx, args => (a, count); intrinsic);
+
+            // FIXME use vector instructions when possible
+            simd_pair_for_each_lane(
+                fx,
+                a,
+                count,
+                ret,
+                &|fx, _lane_ty, _res_lane_ty, a_lane, count_lane| {
+                    let lane_ty = fx.bcx.func.dfg.value_type(a_lane);
+                    let max_count = i64::from(lane_ty.bits() - 1);
+                    if intrinsic.contains(".psrav.") {
+                        // Out of range counts fill the lane with the sign bit.
+                        let max_count = fx.bcx.ins().iconst(lane_ty, max_count);
+                        let saturated_count = fx.bcx.ins().umin(count_lane, max_count);
+                        fx.bcx.ins().sshr(a_lane, saturated_count)
+                    } else {
+                        let shifted = if intrinsic.contains(".psllv.") {
+                            fx.bcx.ins().ishl(a_lane, count_lane)
+                        } else {
+                            fx.bcx.ins().ushr(a_lane, count_lane)
+                        };
+                        let out_of_range = fx.bcx.ins().icmp_imm(
+                            IntCC::UnsignedGreaterThan,
+                            count_lane,
+                            max_count,
+                        );
+                        let zero = fx.bcx.ins().iconst(lane_ty, 0);
+                        fx.bcx.ins().select(out_of_range, zero, shifted)
+                    }
+                },
+            );
+        }
+        "llvm.x86.ssse3.psign.b.128"
+        | "llvm.x86.ssse3.psign.w.128"
+        | "llvm.x86.ssse3.psign.d.128"
+        | "llvm.x86.avx2.psign.b"
+        | "llvm.x86.avx2.psign.w"
+        | "llvm.x86.avx2.psign.d" => {
+            // https://www.intel.com/content/www/us/en/docs/intrinsics-guide/index.html#text=_mm_sign_epi8&ig_expand=5999
+            intrinsic_args!(fx, args => (a, b); intrinsic);
+
+            simd_pair_for_each_lane(
+                fx,
+                a,
+                b,
+                ret,
+                &|fx, _lane_ty, _res_lane_ty, a_lane, b_lane| {
+                    let lane_ty = fx.bcx.func.dfg.value_type(a_lane);
+                    let zero = fx.bcx.ins().iconst(lane_ty, 0);
+                    let neg_a = fx.bcx.ins().ineg(a_lane);
+                    let b_is_neg = fx.bcx.ins().icmp_imm(IntCC::SignedLessThan, b_lane, 0);
+                    let b_is_zero = fx.bcx.ins().icmp_imm(IntCC::Equal, b_lane, 0);
+                    let res = fx.bcx.ins().select(b_is_neg, neg_a, a_lane);
+                    fx.bcx.ins().select(b_is_zero, zero, res)
+                },
+            );
+        }
+        "llvm.x86.ssse3.phadd.w.128"
+        | "llvm.x86.ssse3.phadd.d.128"
+        | "llvm.x86.ssse3.phadd.sw.128"
+        | "llvm.x86.ssse3.phsub.w.128"
+        | "llvm.x86.ssse3.phsub.d.128"
+        | "llvm.x86.ssse3.phsub.sw.128"
+        | "llvm.x86.avx2.phadd.w"
+        | "llvm.x86.avx2.phadd.d"
+        | "llvm.x86.avx2.phadd.sw"
+        | "llvm.x86.avx2.phsub.w"
+        | "llvm.x86.avx2.phsub.d"
+        | "llvm.x86.avx2.phsub.sw" => {
+            // https://www.intel.com/content/www/us/en/docs/intrinsics-guide/index.html#text=_mm_hadd_epi16&ig_expand=3667
+            // https://www.intel.com/content/www/us/en/docs/intrinsics-guide/index.html#text=_mm256_hadd_epi16&ig_expand=3669
+            intrinsic_args!(fx, args => (a, b); intrinsic);
+
+            assert_eq!(a.layout(), b.layout());
+            let layout = a.layout();
+
+            let (lane_count, lane_ty) = layout.ty.simd_size_and_type(fx.tcx);
+            let (ret_lane_count, ret_lane_ty) = ret.layout().ty.simd_size_and_type(fx.tcx);
+            assert_eq!(lane_ty, ret_lane_ty);
+            assert_eq!(lane_count, ret_lane_count);
+
+            let bin_op = if intrinsic.contains(".phadd.") { BinOp::Add } else { BinOp::Sub };
+            let saturating = intrinsic.contains(".sw");
+
+            // The 256-bit variants operate on each 128-bit half independently.
+            let lanes_per_half = 128 / fx.layout_of(lane_ty).size.bits();
+            let ret_lane_layout = fx.layout_of(lane_ty);
+            for out_lane_idx in 0..lane_count {
+                let half_start = out_lane_idx / lanes_per_half * lanes_per_half;
+                let idx_in_half = out_lane_idx % lanes_per_half;
+                let (src, pair_idx) = if idx_in_half < lanes_per_half / 2 {
+                    (a, idx_in_half)
+                } else {
+                    (b, idx_in_half - lanes_per_half / 2)
+                };
+
+                let lhs = src.value_lane(fx, half_start + pair_idx * 2);
+                let rhs = src.value_lane(fx, half_start + pair_idx * 2 + 1);
+
+                let res_lane = if saturating {
+                    crate::num::codegen_saturating_int_binop(fx, bin_op, lhs, rhs)
+                } else {
+                    let lhs = lhs.load_scalar(fx);
+                    let rhs = rhs.load_scalar(fx);
+                    let res = match bin_op {
+                        BinOp::Add => fx.bcx.ins().iadd(lhs, rhs),
+                        _ => fx.bcx.ins().isub(lhs, rhs),
+                    };
+                    CValue::by_val(res, ret_lane_layout)
+                };
+
+                ret.place_lane(fx, out_lane_idx).write_cvalue(fx, res_lane);
+            }
+        }
+        "llvm.x86.sse41.pblendvb"
+        | "llvm.x86.avx2.pblendvb"
+        | "llvm.x86.sse41.blendvps"
+        | "llvm.x86.sse41.blendvpd"
+        | "llvm.x86.avx.blendv.ps.256"
+        | "llvm.x86.avx.blendv.pd.256" => {
+            // https://www.intel.com/content/www/us/en/docs/intrinsics-guide/index.html#text=_mm_blendv_epi8&ig_expand=671
+            // https://www.intel.com/content/www/us/en/docs/intrinsics-guide/index.html#text=_mm_blendv_ps&ig_expand=673
+            intrinsic_args!(fx, args => (a, b, mask); intrinsic);
+
+            simd_trio_for_each_lane(
+                fx,
+                a,
+                b,
+                mask,
+                ret,
+                &|fx, _lane_ty, _res_lane_ty, a_lane, b_lane, mask_lane| {
+                    // Only the sign bit of each mask lane is used.
+                    let mask_ty = fx.bcx.func.dfg.value_type(mask_lane);
+                    let mask_lane = match mask_ty {
+                        types::F32 => codegen_bitcast(fx, types::I32, mask_lane),
+                        types::F64 => codegen_bitcast(fx, types::I64, mask_lane),
+                        _ => mask_lane,
+                    };
+                    let use_b = fx.bcx.ins().icmp_imm(IntCC::SignedLessThan, mask_lane, 0);
+                    fx.bcx.ins().select(use_b, b_lane, a_lane)
+                },
+            );
+        }
+        "llvm.x86.sse2.pmulh.w"
+        | "llvm.x86.sse2.pmulhu.w"
+        | "llvm.x86.avx2.pmulh.w"
+        | "llvm.x86.avx2.pmulhu.w" => {
+            // https://www.intel.com/content/www/us/en/docs/intrinsics-guide/index.html#text=_mm_mulhi_epi16&ig_expand=4755
+            // https://www.intel.com/content/www/us/en/docs/intrinsics-guide/index.html#text=_mm_mulhi_epu16&ig_expand=4756
+            intrinsic_args!(fx, args => (a, b); intrinsic);
+
+            // FIXME use vector instructions when possible
+            simd_pair_for_each_lane(
+                fx,
+                a,
+                b,
+                ret,
+                &|fx, _lane_ty, _res_lane_ty, a_lane, b_lane| {
+                    if intrinsic.contains(".pmulhu.") {
+                        fx.bcx.ins().umulhi(a_lane, b_lane)
+                    } else {
+                        fx.bcx.ins().smulhi(a_lane, b_lane)
+                    }
+                },
+            );
+        }
+        "llvm.x86.sse.movmsk.ps"
+        | "llvm.x86.sse2.movmsk.pd"
+        | "llvm.x86.avx.movmsk.ps.256"
+        | "llvm.x86.avx.movmsk.pd.256"
+        | "llvm.x86.sse2.pmovmskb.128"
+        | "llvm.x86.avx2.pmovmskb" => {
+            // https://www.intel.com/content/www/us/en/docs/intrinsics-guide/index.html#text=_mm_movemask_epi8&ig_expand=4873
+            // https://www.intel.com/content/www/us/en/docs/intrinsics-guide/index.html#text=_mm_movemask_ps&ig_expand=4875
+            intrinsic_args!(fx, args => (a); intrinsic);
+
+            let (lane_count, _lane_ty) = a.layout().ty.simd_size_and_type(fx.tcx);
+            assert_eq!(ret.layout().ty, fx.tcx.types.i32);
+
+            let mut res = fx.bcx.ins().iconst(types::I32, 0);
+            for lane_idx in 0..lane_count {
+                let lane = a.value_lane(fx, lane_idx).load_scalar(fx);
+                let lane = match fx.bcx.func.dfg.value_type(lane) {
+                    types::F32 => codegen_bitcast(fx, types::I32, lane),
+                    types::F64 => codegen_bitcast(fx, types::I64, lane),
+                    _ => lane,
+                };
+                let is_neg = fx.bcx.ins().icmp_imm(IntCC::SignedLessThan, lane, 0);
+                let bit = fx.bcx.ins().uextend(types::I32, is_neg);
+                let bit = fx.bcx.ins().ishl_imm(bit, lane_idx as i64);
+                res = fx.bcx.ins().bor(res, bit);
+            }
+
+            ret.write_cvalue(fx, CValue::by_val(res, fx.layout_of(fx.tcx.types.i32)));
+        }
         "llvm.x86.sse2.psad.bw" | "llvm.x86.avx2.psad.bw" => {
             // https://www.intel.com/content/www/us/en/docs/intrinsics-guide/index.html#text=_mm_sad_epu8&ig_expand=5770
             // https://www.intel.com/content/www/us/en/docs/intrinsics-guide/index.html#text=_mm256_sad_epu8&ig_expand=5771
@@ -623,7 +887,7 @@ fn select4(
             }
         }
 
-        "llvm.x86.ssse3.pmul.hr.sw.128" => {
+        "llvm.x86.ssse3.pmul.hr.sw.128" | "llvm.x86.avx2.pmul.hr.sw" => {
             // https://www.intel.com/content/www/us/en/docs/intrinsics-guide/index.html#text=_mm_mulhrs_epi16&ig_expand=4782
             intrinsic_args!(fx, args => (a, b); intrinsic);
 
@@ -55,6 +55,7 @@
 mod allocator;
 mod analyze;
 mod archive;
+mod assembler;
 mod base;
 mod cast;
 mod codegen_i128;
@@ -42,6 +42,8 @@ codegen_ssa_error_writing_def_file =
 
 codegen_ssa_expected_used_symbol = expected `used`, `used(compiler)` or `used(linker)`
 
+codegen_ssa_export_symbol_map_write_failure = failed to write export symbol map `{$path}`: {$error}
+
 codegen_ssa_extern_funcs_not_found = some `extern` functions couldn't be found; some native libraries may need to be installed or have their path specified
 
 codegen_ssa_extract_bundled_libs_archive_member = failed to get data from archive member '{$rlib}': {$error}
@@ -238,6 +240,17 @@ codegen_ssa_stripping_debug_info_failed = stripping debug info with `{$util}` fa
 
 codegen_ssa_symbol_file_write_failure = failed to write symbols file: {$error}
 
+codegen_ssa_symbol_version_not_exported = symbol `{$symbol}` was given version `{$version}` by `-Z symbol-versions`, but it is not exported
+
+codegen_ssa_symbol_versions_conflict = symbol `{$symbol}` is given more than one version in `{$path}`
+
+codegen_ssa_symbol_versions_malformed_line = malformed line {$line} in symbol versions file `{$path}`
+    .note = each line must contain a symbol name followed by a version tag made of ASCII letters, digits, `_` and `.`
+
+codegen_ssa_symbol_versions_read_failure = failed to read symbol versions file `{$path}`: {$error}
+
+codegen_ssa_symbol_versions_unsupported = `-Z symbol-versions` is not supported when linking for this target and has no effect
+
 codegen_ssa_target_feature_safe_trait = `#[target_feature(..)]` cannot be applied to safe trait method
     .label = cannot be applied to safe trait method
     .label_def = not an `unsafe` function
@@ -4,6 +4,7 @@
 use std::fs::{self, File};
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::{mem, str};
 
 use ar_archive_writer::{
     ArchiveKind, COFFShortExport, MachineTypes, NewArchiveMember, write_archive_to_stream,
@@ -19,7 +20,7 @@
 use tracing::trace;
 
 use super::metadata::search_for_section;
-use crate::common;
+use crate::{common, looks_like_rust_object_file};
 // Re-exporting for rustc_codegen_llvm::back::archive
 pub use crate::errors::{ArchiveBuildFailure, ExtractBundledLibsError, UnknownArchiveKind};
 use crate::errors::{
@@ -441,9 +442,16 @@ fn build_inner(self, output: &Path) -> io::Result<bool> {
             }
         };
 
+        let mut members = self.entries;
+        if self.sess.opts.unstable_opts.deterministic_archives {
+            normalize_rust_objects(&mut members);
+        }
+
         let mut entries = Vec::new();
 
-        for (entry_name, entry) in self.entries {
+        // Timestamps, owners and permissions are never taken from the build environment, so
+        // they don't need any further normalization for `-Zdeterministic-archives`.
+        for (entry_name, entry) in members {
             let data =
                 match entry {
                     ArchiveEntry::FromArchive { archive_index, file_range } => {
@@ -517,6 +525,43 @@ fn build_inner(self, output: &Path) -> io::Result<bool> {
     }
 }
 
+/// Sorts the Rust object files among `members` by name and renames them to
+/// `<prefix>.<index>.rcgu.o`, where `<prefix>` is the part of the original name
+/// before the first `.`, i.e. the crate name and `-C extra-filename`. This keeps
+/// the names recognizable by `looks_like_rust_object_file` and by the LTO filter in
+/// `add_static_crate`, while dropping the codegen unit names, which depend on how
+/// the crate was partitioned.
+///
+/// All other members keep their position, as `lib.rmeta` sometimes has to be the
+/// last member of an rlib (see `link_rlib`).
+fn normalize_rust_objects(members: &mut Vec<(Vec<u8>, ArchiveEntry)>) {
+    let is_rust_object =
+        |name: &[u8]| str::from_utf8(name).is_ok_and(|name| looks_like_rust_object_file(name));
+
+    let mut objects = Vec::new();
+    let mut layout = Vec::with_capacity(members.len());
+    for member in mem::take(members) {
+        if is_rust_object(&member.0) {
+            objects.push(member);
+            layout.push(None);
+        } else {
+            layout.push(Some(member));
+        }
+    }
+    objects.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut objects = objects.into_iter().enumerate();
+    for slot in layout {
+        members.push(slot.unwrap_or_else(|| {
+            let (index, (name, entry)) = objects.next().unwrap();
+            let prefix = name.split(|&b| b == b'.').next().unwrap();
+            let mut name = prefix.to_vec();
+            name.extend_from_slice(format!(".{index}.rcgu.o").as_bytes());
+            (name, entry)
+        }));
+    }
+}
+
 fn io_error_context(context: &str, err: io::Error) -> io::Error {
     io::Error::new(io::ErrorKind::Other, format!("{context}: {err}"))
 }
@@ -50,6 +50,7 @@
 use super::linker::{self, Linker};
 use super::metadata::{MetadataPosition, create_wrapper_file};
 use super::rpath::{self, RPathConfig};
+use super::symbol_export::SymbolVersions;
 use crate::{
     CodegenResults, CompiledModule, CrateInfo, NativeLib, common, errors,
     looks_like_rust_object_file,
@@ -175,6 +176,10 @@ pub fn link_binary(
         }
     }
 
+    if let Some(path) = &sess.opts.unstable_opts.export_symbol_map {
+        write_export_symbol_map(sess, codegen_results, path);
+    }
+
     // Remove the temporary object file and metadata if we aren't saving temps.
     sess.time("link_binary_remove_temps", || {
         // If the user requests that temporaries are saved, don't delete any.
@@ -232,6 +237,47 @@ pub fn link_binary(
     Ok(())
 }
 
+/// Writes the symbols exported from each linked crate type to the file passed to
+/// `-Z export-symbol-map`, as a JSON object mapping crate types to lists of symbols.
+fn write_export_symbol_map(sess: &Session, codegen_results: &CodegenResults, path: &Path) {
+    // Symbol versions are only reported when the linker actually applies them.
+    let versions = SymbolVersions::load(sess)
+        .filter(|_| linker::applies_symbol_versions(sess, linker_and_flavor(sess).1));
+
+    let mut map = serde_json::Map::new();
+    for &crate_type in &codegen_results.crate_info.crate_types {
+        match crate_type {
+            CrateType::Dylib | CrateType::Cdylib | CrateType::ProcMacro => {}
+            CrateType::Executable if sess.opts.unstable_opts.export_executable_symbols => {}
+            _ => continue,
+        }
+
+        let symbols = codegen_results.crate_info.exported_symbols[&crate_type]
+            .iter()
+            .map(|symbol| {
+                let demangled = rustc_demangle::try_demangle(symbol).ok().map(|d| format!("{d:#}"));
+                serde_json::json!({
+                    "symbol": symbol,
+                    "demangled": demangled,
+                    "version": versions.as_ref().map(|versions| versions.version_of(symbol)),
+                })
+            })
+            .collect();
+        map.insert(crate_type.to_string(), serde_json::Value::Array(symbols));
+    }
+
+    let result = File::create(path).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, &map)?;
+        writeln!(writer)?;
+        writer.flush()
+    });
+    if let Err(error) = result {
+        sess.dcx()
+            .emit_err(errors::ExportSymbolMapWriteFailure { path: path.to_path_buf(), error });
+    }
+}
+
 // Crate type is not passed when calculating the dylibs to include for LTO. In that case all
 // crate types must use the same dependency formats.
 pub fn each_linked_rlib(
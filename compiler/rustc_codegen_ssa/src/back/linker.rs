@@ -159,6 +159,29 @@ pub(crate) fn get_linker<'a>(
     }
 }
 
+/// Returns whether the versions given with `-Zsymbol-versions` are passed on to the linker of
+/// `flavor`. Only the version script written by `GccLinker` contains them, and it isn't used on
+/// Apple and Windows targets or when the target doesn't limit the symbols exported from dylibs.
+pub(crate) fn applies_symbol_versions(sess: &Session, flavor: LinkerFlavor) -> bool {
+    let is_gcc_linker = match flavor {
+        LinkerFlavor::Unix(Cc::No) => sess.target.os != "l4re" && sess.target.os != "aix",
+        LinkerFlavor::WasmLld(Cc::No) => false,
+        LinkerFlavor::Gnu(..)
+        | LinkerFlavor::Darwin(..)
+        | LinkerFlavor::WasmLld(..)
+        | LinkerFlavor::Unix(..) => true,
+        LinkerFlavor::Msvc(..)
+        | LinkerFlavor::EmCc
+        | LinkerFlavor::Bpf
+        | LinkerFlavor::Llbc
+        | LinkerFlavor::Ptx => false,
+    };
+    is_gcc_linker
+        && sess.target.limit_rdylib_exports
+        && !sess.target.is_like_osx
+        && !sess.target.is_like_windows
+}
+
 // Note: Ideally neither these helper function, nor the macro-generated inherent methods below
 // would exist, and these functions would live in `trait Linker`.
 // Unfortunately, adding these functions to `trait Linker` make it `dyn`-incompatible.
@@ -748,6 +771,15 @@ fn export_symbols(&mut self, tmpdir: &Path, crate_type: CrateType, symbols: &[St
         let is_windows = self.sess.target.is_like_windows;
         let path = tmpdir.join(if is_windows { "list.def" } else { "list" });
 
+        let versions = symbol_export::SymbolVersions::load(self.sess);
+        if let Some(versions) = &versions {
+            if self.sess.target.is_like_osx || is_windows {
+                self.sess.dcx().emit_warn(errors::SymbolVersionsUnsupported);
+            } else {
+                versions.check_exported(self.sess, symbols);
+            }
+        }
+
         debug!("EXPORTED SYMBOLS:");
 
         if self.sess.target.is_like_osx {
@@ -781,15 +813,40 @@ fn export_symbols(&mut self, tmpdir: &Path, crate_type: CrateType, symbols: &[St
             // Write an LD version script
             let res: io::Result<()> = try {
                 let mut f = File::create_buffered(&path)?;
-                writeln!(f, "{{")?;
-                if !symbols.is_empty() {
-                    writeln!(f, "  global:")?;
-                    for sym in symbols {
-                        debug!("    {sym};");
-                        writeln!(f, "    {sym};")?;
+                if let Some(versions) = &versions {
+                    // One node per version, each inheriting from the one before it. The first
+                    // node also hides everything that isn't exported.
+                    let mut previous = None;
+                    for version in versions.versions() {
+                        writeln!(f, "{version} {{")?;
+                        let mut global = symbols
+                            .iter()
+                            .filter(|sym| versions.version_of(sym) == version)
+                            .peekable();
+                        if global.peek().is_some() {
+                            writeln!(f, "  global:")?;
+                            for sym in global {
+                                debug!("    {sym}; ({version})");
+                                writeln!(f, "    {sym};")?;
+                            }
+                        }
+                        match previous {
+                            None => writeln!(f, "\n  local:\n    *;\n}};")?,
+                            Some(previous) => writeln!(f, "}} {previous};")?,
+                        }
+                        previous = Some(version);
+                    }
+                } else {
+                    writeln!(f, "{{")?;
+                    if !symbols.is_empty() {
+                        writeln!(f, "  global:")?;
+                        for sym in symbols {
+                            debug!("    {sym};");
+                            writeln!(f, "    {sym};")?;
+                        }
                     }
+                    writeln!(f, "\n  local:\n    *;\n}};")?;
                 }
-                writeln!(f, "\n  local:\n    *;\n}};")?;
             };
             if let Err(error) = res {
                 self.sess.dcx().emit_fatal(errors::VersionScriptWriteFailure { error });
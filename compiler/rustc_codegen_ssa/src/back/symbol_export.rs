@@ -1,6 +1,8 @@
 use std::collections::hash_map::Entry::*;
+use std::fs;
 
 use rustc_ast::expand::allocator::{ALLOCATOR_METHODS, NO_ALLOC_SHIM_IS_UNSTABLE};
+use rustc_data_structures::fx::{FxHashSet, FxIndexMap, FxIndexSet};
 use rustc_data_structures::unord::UnordMap;
 use rustc_hir::def::DefKind;
 use rustc_hir::def_id::{CrateNum, DefId, DefIdMap, LOCAL_CRATE, LocalDefId};
@@ -12,11 +14,13 @@
 use rustc_middle::query::LocalCrate;
 use rustc_middle::ty::{self, GenericArgKind, GenericArgsRef, Instance, SymbolName, TyCtxt};
 use rustc_middle::util::Providers;
+use rustc_session::Session;
 use rustc_session::config::{CrateType, OomStrategy};
 use rustc_target::spec::{SanitizerSet, TlsModel};
 use tracing::debug;
 
 use crate::base::allocator_kind_for_codegen;
+use crate::errors;
 
 fn threshold(tcx: TyCtxt<'_>) -> SymbolExportLevel {
     crates_export_threshold(tcx.crate_types())
@@ -677,3 +681,87 @@ fn wasm_import_module_map(tcx: TyCtxt<'_>, cnum: CrateNum) -> DefIdMap<String> {
 
     ret
 }
+
+/// The version tags requested with `-Z symbol-versions`.
+///
+/// The file lists one symbol and its version tag per line, separated by whitespace. Empty lines
+/// and lines starting with `#` are ignored. Versions are ordered by their first appearance in
+/// the file, and every version inherits from the one before it. Exported symbols that aren't
+/// listed get the first version.
+pub(crate) struct SymbolVersions {
+    versions: FxIndexSet<String>,
+    symbols: FxIndexMap<String, usize>,
+}
+
+impl SymbolVersions {
+    /// Reads the file passed to `-Z symbol-versions`, if any.
+    pub(crate) fn load(sess: &Session) -> Option<SymbolVersions> {
+        let path = sess.opts.unstable_opts.symbol_versions.as_ref()?;
+        let contents = fs::read_to_string(path).unwrap_or_else(|error| {
+            sess.dcx().emit_fatal(errors::SymbolVersionsReadFailure { path: path.clone(), error })
+        });
+
+        let is_valid_version = |version: &str| {
+            version.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        };
+        let is_valid_symbol = |symbol: &str| !symbol.contains([';', '{', '}', '"', '#']);
+
+        let mut versions = FxIndexSet::default();
+        let mut symbols = FxIndexMap::default();
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line.split_whitespace();
+            let (symbol, version) = match (fields.next(), fields.next(), fields.next()) {
+                (Some(symbol), Some(version), None)
+                    if is_valid_symbol(symbol) && is_valid_version(version) =>
+                {
+                    (symbol, version)
+                }
+                _ => sess.dcx().emit_fatal(errors::SymbolVersionsMalformedLine {
+                    path: path.clone(),
+                    line: index + 1,
+                }),
+            };
+
+            let (version, _) = versions.insert_full(version.to_string());
+            if let Some(&previous) = symbols.get(symbol)
+                && previous != version
+            {
+                sess.dcx().emit_fatal(errors::SymbolVersionsConflict {
+                    path: path.clone(),
+                    symbol: symbol.to_string(),
+                });
+            }
+            symbols.insert(symbol.to_string(), version);
+        }
+
+        if versions.is_empty() { None } else { Some(SymbolVersions { versions, symbols }) }
+    }
+
+    /// All version tags, each following the version it inherits from.
+    pub(crate) fn versions(&self) -> impl Iterator<Item = &str> {
+        self.versions.iter().map(|version| version.as_str())
+    }
+
+    /// The version tag of the exported symbol `symbol`.
+    pub(crate) fn version_of(&self, symbol: &str) -> &str {
+        let index = self.symbols.get(symbol).copied().unwrap_or(0);
+        &self.versions[index]
+    }
+
+    /// Warns about symbols that were given a version but aren't in `exported`.
+    pub(crate) fn check_exported(&self, sess: &Session, exported: &[String]) {
+        let exported: FxHashSet<&str> = exported.iter().map(|symbol| symbol.as_str()).collect();
+        let unknown = self.symbols.iter().filter(|(symbol, _)| !exported.contains(symbol.as_str()));
+        for (symbol, &version) in unknown {
+            sess.dcx().emit_warn(errors::SymbolVersionNotExported {
+                symbol: symbol.clone(),
+                version: self.versions[version].clone(),
+            });
+        }
+    }
+}
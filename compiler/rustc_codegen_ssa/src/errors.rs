@@ -105,6 +105,46 @@ pub(crate) struct SymbolFileWriteFailure {
     pub error: Error,
 }
 
+#[derive(Diagnostic)]
+#[diag(codegen_ssa_export_symbol_map_write_failure)]
+pub(crate) struct ExportSymbolMapWriteFailure {
+    pub path: PathBuf,
+    pub error: Error,
+}
+
+#[derive(Diagnostic)]
+#[diag(codegen_ssa_symbol_versions_read_failure)]
+pub(crate) struct SymbolVersionsReadFailure {
+    pub path: PathBuf,
+    pub error: Error,
+}
+
+#[derive(Diagnostic)]
+#[diag(codegen_ssa_symbol_versions_malformed_line)]
+#[note]
+pub(crate) struct SymbolVersionsMalformedLine {
+    pub path: PathBuf,
+    pub line: usize,
+}
+
+#[derive(Diagnostic)]
+#[diag(codegen_ssa_symbol_versions_conflict)]
+pub(crate) struct SymbolVersionsConflict {
+    pub path: PathBuf,
+    pub symbol: String,
+}
+
+#[derive(Diagnostic)]
+#[diag(codegen_ssa_symbol_version_not_exported)]
+pub(crate) struct SymbolVersionNotExported {
+    pub symbol: String,
+    pub version: String,
+}
+
+#[derive(Diagnostic)]
+#[diag(codegen_ssa_symbol_versions_unsupported)]
+pub(crate) struct SymbolVersionsUnsupported;
+
 #[derive(Diagnostic)]
 #[diag(codegen_ssa_ld64_unimplemented_modifier)]
 pub(crate) struct Ld64UnimplementedModifier;
@@ -50,16 +50,19 @@
 use rustc_lint::unerased_lint_store;
 use rustc_metadata::creader::MetadataLoader;
 use rustc_metadata::locator;
+use rustc_middle::lint::LintLevelSource;
+use rustc_middle::ty::TyCtxt;
 use rustc_parse::{new_parser_from_file, new_parser_from_source_str, unwrap_or_emit_fatal};
 use rustc_session::config::{
-    CG_OPTIONS, ErrorOutputType, Input, OutFileName, OutputType, UnstableOptions, Z_OPTIONS,
-    nightly_options,
+    CG_OPTIONS, ErrorOutputType, Input, OutFileName, OutputType, PrintKind, PrintRequest,
+    UnstableOptions, Z_OPTIONS, nightly_options,
 };
 use rustc_session::getopts::{self, Matches};
-use rustc_session::lint::{Lint, LintId};
+use rustc_session::lint::{Level, Lint, LintId};
 use rustc_session::output::collect_crate_types;
 use rustc_session::{EarlyDiagCtxt, Session, config, filesearch};
 use rustc_span::FileName;
+use rustc_span::def_id::CRATE_DEF_ID;
 use rustc_span::source_map::FileLoader;
 use rustc_target::json::ToJson;
 use rustc_target::spec::{Target, TargetTriple};
@@ -328,6 +331,7 @@ fn run_compiler(
         override_queries: None,
         make_codegen_backend,
         registry: diagnostics_registry(),
+        sarif_log: Some(default_early_dcx.sarif_log().clone()),
         using_internal_features,
         expanded_args: args,
     };
@@ -347,11 +351,15 @@ fn run_compiler(
         },
     };
 
-    drop(default_early_dcx);
+    // With `--error-format=sarif`, `default_early_dcx` adds to the log of the session, which is
+    // only written out once the last emitter adding to it is dropped. So it is kept alive until the
+    // compilation is done.
+    let default_early_dcx =
+        matches!(config.opts.error_format, ErrorOutputType::Sarif).then_some(default_early_dcx);
 
     callbacks.config(&mut config);
 
-    interface::run_compiler(config, |compiler| {
+    let result = interface::run_compiler(config, |compiler| {
         let sess = &compiler.sess;
         let codegen_backend = &*compiler.codegen_backend;
 
@@ -429,6 +437,17 @@ fn run_compiler(
             // Make sure name resolution and macro expansion is run.
             queries.global_ctxt()?.enter(|tcx| tcx.resolver_for_lowering());
 
+            let lint_level_prints: Vec<_> =
+                sess.opts.prints.iter().filter(|p| p.kind == PrintKind::LintLevels).collect();
+            if !lint_level_prints.is_empty() {
+                queries.global_ctxt()?.enter(|tcx| {
+                    for req in lint_level_prints {
+                        print_lint_levels(tcx, req);
+                    }
+                });
+                return early_exit();
+            }
+
             if callbacks.after_expansion(compiler, queries) == Compilation::Stop {
                 return early_exit();
             }
@@ -470,7 +489,9 @@ fn run_compiler(
         }
 
         Ok(())
-    })
+    });
+    drop(default_early_dcx);
+    result
 }
 
 // Extract output directory and file from matches.
@@ -702,7 +723,8 @@ fn print_crate_info(
 
     // NativeStaticLibs and LinkArgs are special - printed during linking
     // (empty iterator returns true)
-    if sess.opts.prints.iter().all(|p| p.kind == NativeStaticLibs || p.kind == LinkArgs) {
+    // LintLevels is printed after macro expansion, once crate attributes are known.
+    if sess.opts.prints.iter().all(|p| matches!(p.kind, NativeStaticLibs | LinkArgs | LintLevels)) {
         return Compilation::Continue;
     }
 
@@ -846,6 +868,17 @@ fn print_crate_info(
             // Any output here interferes with Cargo's parsing of other printed output
             NativeStaticLibs => {}
             LinkArgs => {}
+            // Printed after macro expansion, see `print_lint_levels`.
+            LintLevels => {}
+            IncrementalCache => {
+                let Some(incr_dir) = &sess.opts.incremental else {
+                    #[allow(rustc::diagnostic_outside_of_impl)]
+                    sess.dcx().fatal(
+                        "the incremental-cache print option requires `-C incremental` to be set",
+                    );
+                };
+                rustc_incremental::print_incremental_cache(sess, incr_dir, &mut crate_info);
+            }
             SplitDebuginfo => {
                 use rustc_target::spec::SplitDebuginfo::{Off, Packed, Unpacked};
 
@@ -871,7 +904,53 @@ fn print_crate_info(
 
         req.out.overwrite(&crate_info, sess);
     }
-    Compilation::Stop
+
+    if sess.opts.prints.iter().any(|p| p.kind == LintLevels) {
+        Compilation::Continue
+    } else {
+        Compilation::Stop
+    }
+}
+
+/// Prints the crate-level lint levels for `--print=lint-levels`.
+///
+/// Each registered lint (builtin or from a tool) is listed with its level at the crate root
+/// and where that level came from.
+fn print_lint_levels(tcx: TyCtxt<'_>, req: &PrintRequest) {
+    let sess = tcx.sess;
+    let crate_hir_id = tcx.local_def_id_to_hir_id(CRATE_DEF_ID);
+
+    let mut lints = unerased_lint_store(sess).get_lints().to_vec();
+    lints.sort_by_cached_key(|lint| lint.name_lower());
+
+    let mut lint_levels = String::new();
+    for lint in lints {
+        let name = lint.name_lower();
+        let (level, src) = tcx.lint_level_at_node(lint, crate_hir_id);
+        let source = match src {
+            LintLevelSource::Default => "default".to_owned(),
+            LintLevelSource::CommandLine(flag_val, orig_level) => {
+                let flag = orig_level.to_cmd_flag();
+                let flag_val = flag_val.as_str().replace('_', "-");
+                match orig_level {
+                    Level::ForceWarn(_) => format!("force-warn: {flag} {flag_val}"),
+                    _ => format!("command line: {flag} {flag_val}"),
+                }
+            }
+            LintLevelSource::Node { name: lint_name, span, .. } => {
+                let loc = sess.source_map().lookup_char_pos(span.lo());
+                format!(
+                    "crate attribute: {lint_name} at {}:{}:{}",
+                    sess.source_map().filename_for_diagnostics(&loc.file.name),
+                    loc.line,
+                    loc.col.to_usize() + 1,
+                )
+            }
+        };
+        writeln!(lint_levels, "{name}={} ({source})", level.as_str()).unwrap();
+    }
+
+    req.out.overwrite(&lint_levels, sess);
 }
 
 /// Prints version information
@@ -0,0 +1,483 @@
+//! A SARIF emitter for errors.
+//!
+//! This converts diagnostics into the results of a [SARIF 2.1.0] run. Since a SARIF log is a
+//! single JSON document, the results of a whole compilation session are buffered and written out
+//! as one log with one run once the last emitter of the session is dropped. As rustc compiles one
+//! crate per invocation, this yields one run per crate, which code scanning tools can ingest
+//! directly.
+//!
+//! Lints and error codes become the rules of the run, primary spans become the locations of a
+//! result, and structured suggestions become its fixes.
+//!
+//! [SARIF 2.1.0]: https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html
+
+use std::error::Report;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use derive_setters::Setters;
+use rustc_data_structures::fx::FxIndexMap;
+use rustc_data_structures::sync::{IntoDynSyncSend, Lrc};
+use rustc_error_messages::FluentArgs;
+use rustc_lint_defs::Applicability;
+use rustc_span::Span;
+use rustc_span::source_map::SourceMap;
+use serde::Serialize;
+
+use crate::diagnostic::IsLint;
+use crate::emitter::Emitter;
+use crate::registry::Registry;
+use crate::translation::{Translate, to_fluent_args};
+use crate::{CodeSuggestion, DiagInner, FluentBundle, LazyFallbackBundle, Level, Suggestions};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Setters)]
+pub struct SarifEmitter {
+    #[setters(skip)]
+    dst: IntoDynSyncSend<Box<dyn Write + Send>>,
+    registry: Option<Registry>,
+    #[setters(skip)]
+    sm: Lrc<SourceMap>,
+    fluent_bundle: Option<Lrc<FluentBundle>>,
+    #[setters(skip)]
+    fallback_bundle: LazyFallbackBundle,
+    /// Whether to write a log when no diagnostics were emitted at all. This is disabled for
+    /// emitters that only exist for a short time, e.g. before the session is created.
+    emit_empty_log: bool,
+    #[setters(skip)]
+    log: Arc<Mutex<Log>>,
+}
+
+/// The rules and results of a log that is written out once the last emitter adding to it is
+/// dropped.
+#[derive(Default)]
+struct Log {
+    /// The number of emitters that add to this log.
+    emitters: usize,
+    /// Whether any of the emitters wants the log to be written even if it has no results.
+    emit_empty: bool,
+    rules: FxIndexMap<String, ReportingDescriptor>,
+    results: Vec<SarifResult>,
+}
+
+/// A log that several emitters add to, see [`SarifEmitter::shared_log`].
+#[derive(Clone, Default)]
+pub struct SharedSarifLog(Arc<Mutex<Log>>);
+
+impl SarifEmitter {
+    pub fn new(
+        dst: Box<dyn Write + Send>,
+        sm: Lrc<SourceMap>,
+        fallback_bundle: LazyFallbackBundle,
+    ) -> SarifEmitter {
+        SarifEmitter {
+            dst: IntoDynSyncSend(dst),
+            registry: None,
+            sm,
+            fluent_bundle: None,
+            fallback_bundle,
+            emit_empty_log: true,
+            log: Arc::new(Mutex::new(Log { emitters: 1, ..Log::default() })),
+        }
+    }
+
+    /// Makes this emitter add its results to `log` instead of writing a log of its own.
+    ///
+    /// A session emits diagnostics through several emitters, starting with the ones used before
+    /// the session is created, and a SARIF log can't be appended to once it is written. So they
+    /// all share one log, which is written by whichever of them is dropped last.
+    pub fn shared_log(mut self, log: &SharedSarifLog) -> SarifEmitter {
+        log.0.lock().unwrap().emitters += 1;
+        self.log = Arc::clone(&log.0);
+        self
+    }
+
+    fn write_log(&mut self, log: &Log) -> io::Result<()> {
+        let log = SarifLog {
+            schema: "https://json.schemastore.org/sarif-2.1.0.json",
+            version: "2.1.0",
+            runs: [Run {
+                tool: Tool {
+                    driver: ToolComponent {
+                        name: "rustc",
+                        information_uri: "https://www.rust-lang.org/",
+                        rules: log.rules.values().collect(),
+                    },
+                },
+                column_kind: "unicodeCodePoints",
+                results: &log.results,
+            }],
+        };
+        serde_json::to_writer(&mut *self.dst, &log)?;
+        self.dst.write_all(b"\n")?;
+        self.dst.flush()
+    }
+
+    /// Returns the rule for a lint or an error code.
+    fn rule(&self, diag: &DiagInner) -> Option<ReportingDescriptor> {
+        if let Some(IsLint { name, .. }) = &diag.is_lint {
+            Some(ReportingDescriptor { id: name.clone(), help_uri: None, help: None })
+        } else if let Some(code) = diag.code {
+            let explanation = self
+                .registry
+                .as_ref()
+                .and_then(|registry| registry.try_find_description(code).ok());
+            Some(ReportingDescriptor {
+                id: code.to_string(),
+                help_uri: Some(format!("https://doc.rust-lang.org/error_codes/{code}.html")),
+                help: explanation.map(|explanation| Message::markdown(explanation.trim())),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn location(&self, span: Span, message: Option<String>) -> Option<Location> {
+        let (artifact_location, region) = self.region(span)?;
+        Some(Location {
+            physical_location: PhysicalLocation { artifact_location, region },
+            message: message.map(Message::text),
+        })
+    }
+
+    fn region(&self, span: Span) -> Option<(ArtifactLocation, Region)> {
+        if span.is_dummy() {
+            return None;
+        }
+        let start = self.sm.lookup_char_pos(span.lo());
+        let end = self.sm.lookup_char_pos(span.hi());
+        let file_name = self.sm.filename_for_diagnostics(&start.file.name).to_string();
+        let byte_start = start.file.original_relative_byte_pos(span.lo()).0;
+        let byte_end = start.file.original_relative_byte_pos(span.hi()).0;
+        let region = Region {
+            start_line: start.line,
+            start_column: start.col.0 + 1,
+            end_line: end.line,
+            end_column: end.col.0 + 1,
+            byte_offset: byte_start,
+            byte_length: byte_end - byte_start,
+        };
+        Some((ArtifactLocation { uri: file_name_to_uri(&file_name) }, region))
+    }
+
+    /// Turns each alternative of a suggestion into a fix.
+    fn fixes(&self, suggestion: &CodeSuggestion, args: &FluentArgs<'_>) -> Vec<Fix> {
+        let description =
+            self.translate_message(&suggestion.msg, args).map_err(Report::new).unwrap().to_string();
+        suggestion
+            .substitutions
+            .iter()
+            .filter_map(|substitution| {
+                let mut changes: FxIndexMap<String, ArtifactChange> = FxIndexMap::default();
+                for part in &substitution.parts {
+                    let (artifact_location, deleted_region) = self.region(part.span)?;
+                    changes
+                        .entry(artifact_location.uri.clone())
+                        .or_insert_with(|| ArtifactChange {
+                            artifact_location,
+                            replacements: vec![],
+                        })
+                        .replacements
+                        .push(Replacement {
+                            deleted_region,
+                            inserted_content: ArtifactContent { text: part.snippet.clone() },
+                        });
+                }
+                Some(Fix {
+                    description: Message::text(description.clone()),
+                    artifact_changes: changes.into_values().collect(),
+                    properties: FixProperties { applicability: suggestion.applicability },
+                })
+            })
+            .collect()
+    }
+}
+
+impl Translate for SarifEmitter {
+    fn fluent_bundle(&self) -> Option<&Lrc<FluentBundle>> {
+        self.fluent_bundle.as_ref()
+    }
+
+    fn fallback_fluent_bundle(&self) -> &FluentBundle {
+        &self.fallback_bundle
+    }
+}
+
+impl Emitter for SarifEmitter {
+    fn emit_diagnostic(&mut self, diag: DiagInner) {
+        let level = match diag.level {
+            Level::Bug | Level::Fatal | Level::Error | Level::DelayedBug => "error",
+            Level::ForceWarning(_) | Level::Warning => "warning",
+            Level::Note | Level::OnceNote | Level::Help | Level::OnceHelp => "note",
+            // Failure notes only summarize the other results, e.g. "aborting due to 2 previous
+            // errors", and allowed or expected lints are not reported at all.
+            Level::FailureNote | Level::Allow | Level::Expect(_) => return,
+        };
+        let args = to_fluent_args(diag.args.iter());
+
+        let mut message = self.translate_messages(&diag.messages, &args).into_owned();
+        let mut locations = vec![];
+        let mut related_locations = vec![];
+        for span_label in diag.span.span_labels() {
+            let label = span_label
+                .label
+                .as_ref()
+                .map(|label| self.translate_message(label, &args).unwrap().to_string());
+            if span_label.is_primary {
+                locations.extend(self.location(span_label.span, label));
+            } else if label.is_some() {
+                related_locations.extend(self.location(span_label.span, label));
+            }
+        }
+
+        // Notes and help messages of the diagnostic are attached to the location they refer to,
+        // or appended to the message if they don't have one.
+        for child in &diag.children {
+            let child_message = format!(
+                "{}: {}",
+                child.level.to_str(),
+                self.translate_messages(&child.messages, &args)
+            );
+            match child.span.primary_span().and_then(|span| self.location(span, None)) {
+                Some(mut location) => {
+                    location.message = Some(Message::text(child_message));
+                    related_locations.push(location);
+                }
+                None => {
+                    message.push('\n');
+                    message.push_str(&child_message);
+                }
+            }
+        }
+
+        let suggestions: &[CodeSuggestion] = match &diag.suggestions {
+            Suggestions::Enabled(suggestions) => suggestions,
+            Suggestions::Sealed(suggestions) => suggestions,
+            Suggestions::Disabled => &[],
+        };
+        let fixes =
+            suggestions.iter().flat_map(|suggestion| self.fixes(suggestion, &args)).collect();
+
+        let rule = self.rule(&diag);
+        let mut log = self.log.lock().unwrap();
+        let rule_id = rule.as_ref().map(|rule| rule.id.clone());
+        let rule_index = rule.map(|rule| {
+            let entry = log.rules.entry(rule.id.clone());
+            let index = entry.index();
+            entry.or_insert(rule);
+            index
+        });
+        log.results.push(SarifResult {
+            rule_id,
+            rule_index,
+            level,
+            message: Message::text(message),
+            locations,
+            related_locations,
+            fixes,
+        });
+    }
+
+    fn source_map(&self) -> Option<&Lrc<SourceMap>> {
+        Some(&self.sm)
+    }
+
+    fn should_show_explain(&self) -> bool {
+        // Explanations are linked from the rules instead.
+        false
+    }
+}
+
+impl Drop for SarifEmitter {
+    fn drop(&mut self) {
+        let shared_log = Arc::clone(&self.log);
+        let mut log = shared_log.lock().unwrap();
+        log.emitters -= 1;
+        log.emit_empty |= self.emit_empty_log;
+        if log.emitters > 0 {
+            return;
+        }
+        if log.results.is_empty() && !log.emit_empty {
+            return;
+        }
+        if let Err(e) = self.write_log(&log) {
+            // Don't turn a panic that is already unwinding into an abort.
+            if !std::thread::panicking() {
+                panic!("failed to print diagnostics: {e:?}");
+            }
+        }
+    }
+}
+
+/// Turns a file name as shown in diagnostics into a URI reference.
+fn file_name_to_uri(file_name: &str) -> String {
+    let path = file_name.replace('\\', "/");
+    let mut uri = String::with_capacity(path.len());
+    if Path::new(file_name).is_absolute() {
+        uri.push_str("file://");
+        if !path.starts_with('/') {
+            uri.push('/');
+        }
+    }
+    for c in path.chars() {
+        match c {
+            '%' => uri.push_str("%25"),
+            ' ' => uri.push_str("%20"),
+            '#' => uri.push_str("%23"),
+            '?' => uri.push_str("%3F"),
+            c => uri.push(c),
+        }
+    }
+    uri
+}
+
+// The following data types are provided just for serialisation. They only cover the parts of
+// the SARIF object model that are actually emitted.
+
+#[derive(Serialize)]
+struct SarifLog<'a> {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: [Run<'a>; 1],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Run<'a> {
+    tool: Tool<'a>,
+    /// Columns are counted in characters, like in the JSON output.
+    column_kind: &'static str,
+    results: &'a [SarifResult],
+}
+
+#[derive(Serialize)]
+struct Tool<'a> {
+    driver: ToolComponent<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolComponent<'a> {
+    name: &'static str,
+    information_uri: &'static str,
+    rules: Vec<&'a ReportingDescriptor>,
+}
+
+/// A rule, which is either a lint or an error code.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportingDescriptor {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    help_uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    help: Option<Message>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule_index: Option<usize>,
+    /// "error", "warning" or "note".
+    level: &'static str,
+    message: Message,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    locations: Vec<Location>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    related_locations: Vec<Location>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fixes: Vec<Fix>,
+}
+
+#[derive(Serialize)]
+struct Message {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    markdown: Option<String>,
+}
+
+impl Message {
+    fn text(text: impl Into<String>) -> Message {
+        Message { text: Some(text.into()), markdown: None }
+    }
+
+    fn markdown(markdown: &str) -> Message {
+        // Consumers that can't render markdown fall back to `text`, which is required.
+        Message { text: Some(markdown.to_owned()), markdown: Some(markdown.to_owned()) }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    physical_location: PhysicalLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<Message>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    region: Region,
+}
+
+#[derive(Serialize)]
+struct ArtifactLocation {
+    uri: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    /// 1-based.
+    start_line: usize,
+    end_line: usize,
+    /// 1-based, character offset.
+    start_column: usize,
+    end_column: usize,
+    /// 0-based byte offset into the original source file.
+    byte_offset: u32,
+    byte_length: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Fix {
+    description: Message,
+    artifact_changes: Vec<ArtifactChange>,
+    properties: FixProperties,
+}
+
+#[derive(Serialize)]
+struct FixProperties {
+    /// How confident rustc is that applying the fix is correct.
+    applicability: Applicability,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactChange {
+    artifact_location: ArtifactLocation,
+    replacements: Vec<Replacement>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Replacement {
+    deleted_region: Region,
+    inserted_content: ArtifactContent,
+}
+
+#[derive(Serialize)]
+struct ArtifactContent {
+    text: String,
+}
@@ -0,0 +1,173 @@
+use std::str;
+use std::sync::{Arc, Mutex};
+
+use rustc_span::BytePos;
+use rustc_span::source_map::FilePathMapping;
+use serde_json::Value;
+
+use super::*;
+use crate::DiagCtxt;
+use crate::codes::E0308;
+
+struct Shared<T> {
+    data: Arc<Mutex<T>>,
+}
+
+impl<T: Write> Write for Shared<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.data.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.data.lock().unwrap().flush()
+    }
+}
+
+/// Runs `f` with a `DiagCtxt` that has a `SarifEmitter`, and returns the log it wrote.
+fn sarif_log(code: &str, f: impl FnOnce(&DiagCtxt)) -> Value {
+    rustc_span::create_default_session_globals_then(|| {
+        let sm = Lrc::new(SourceMap::new(FilePathMapping::empty()));
+        sm.new_source_file(Path::new("src/main.rs").to_owned().into(), code.to_owned());
+        let fallback_bundle =
+            crate::fallback_fluent_bundle(vec![crate::DEFAULT_LOCALE_RESOURCE], false);
+
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let emitter =
+            SarifEmitter::new(Box::new(Shared { data: output.clone() }), sm, fallback_bundle);
+        let dcx = DiagCtxt::new(Box::new(emitter));
+        f(&dcx);
+        drop(dcx);
+
+        let bytes = output.lock().unwrap();
+        let log = str::from_utf8(&bytes).unwrap();
+        assert_eq!(log.lines().count(), 1);
+        serde_json::from_str(log).unwrap()
+    })
+}
+
+#[test]
+fn empty_run() {
+    let log = sarif_log("fn main() {}", |_| {});
+    assert_eq!(log["version"], "2.1.0");
+    assert_eq!(log["runs"].as_array().unwrap().len(), 1);
+    assert_eq!(log["runs"][0]["tool"]["driver"]["name"], "rustc");
+    assert_eq!(log["runs"][0]["results"], Value::Array(vec![]));
+}
+
+#[test]
+fn error_with_code_and_suggestion() {
+    let log = sarif_log("fn main() {\n    let x: f32 = 1;\n}\n", |dcx| {
+        let span = Span::with_root_ctxt(BytePos(29), BytePos(30));
+        dcx.handle()
+            .struct_span_err(span, "mismatched types")
+            .with_code(E0308)
+            .with_span_suggestion(
+                span,
+                "use a float literal",
+                "1.0",
+                Applicability::MachineApplicable,
+            )
+            .emit();
+    });
+
+    let run = &log["runs"][0];
+    assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "E0308");
+
+    let result = &run["results"][0];
+    assert_eq!(result["ruleId"], "E0308");
+    assert_eq!(result["ruleIndex"], 0);
+    assert_eq!(result["level"], "error");
+    assert_eq!(result["message"]["text"], "mismatched types");
+
+    let location = &result["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "src/main.rs");
+    assert_eq!(location["region"]["startLine"], 2);
+    assert_eq!(location["region"]["startColumn"], 18);
+    assert_eq!(location["region"]["endColumn"], 19);
+
+    let fix = &result["fixes"][0];
+    assert_eq!(fix["description"]["text"], "use a float literal");
+    assert_eq!(fix["properties"]["applicability"], "MachineApplicable");
+    let replacement = &fix["artifactChanges"][0]["replacements"][0];
+    assert_eq!(replacement["deletedRegion"]["byteOffset"], 29);
+    assert_eq!(replacement["deletedRegion"]["byteLength"], 1);
+    assert_eq!(replacement["insertedContent"]["text"], "1.0");
+}
+
+#[test]
+fn session_emitters_share_one_log() {
+    rustc_span::create_default_session_globals_then(|| {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let log = SharedSarifLog::default();
+        let new_dcx = |emit_empty_log| {
+            let sm = Lrc::new(SourceMap::new(FilePathMapping::empty()));
+            let fallback_bundle =
+                crate::fallback_fluent_bundle(vec![crate::DEFAULT_LOCALE_RESOURCE], false);
+            let emitter =
+                SarifEmitter::new(Box::new(Shared { data: output.clone() }), sm, fallback_bundle)
+                    .emit_empty_log(emit_empty_log)
+                    .shared_log(&log);
+            DiagCtxt::new(Box::new(emitter))
+        };
+
+        let early_dcx = new_dcx(false);
+        early_dcx.handle().warn("early warning");
+        let dcx = new_dcx(true);
+        drop(early_dcx);
+        assert!(output.lock().unwrap().is_empty());
+
+        dcx.handle().warn("late warning");
+        drop(dcx);
+
+        let bytes = output.lock().unwrap();
+        let log = str::from_utf8(&bytes).unwrap();
+        assert_eq!(log.lines().count(), 1);
+        let log: Value = serde_json::from_str(log).unwrap();
+        let results = log["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["message"]["text"], "early warning");
+        assert_eq!(results[1]["message"]["text"], "late warning");
+    })
+}
+
+#[test]
+fn separate_logs_stay_separate() {
+    rustc_span::create_default_session_globals_then(|| {
+        let new_dcx = |output: &Arc<Mutex<Vec<u8>>>, log: &SharedSarifLog| {
+            let sm = Lrc::new(SourceMap::new(FilePathMapping::empty()));
+            let fallback_bundle =
+                crate::fallback_fluent_bundle(vec![crate::DEFAULT_LOCALE_RESOURCE], false);
+            let emitter =
+                SarifEmitter::new(Box::new(Shared { data: output.clone() }), sm, fallback_bundle)
+                    .shared_log(log);
+            DiagCtxt::new(Box::new(emitter))
+        };
+
+        // Two sessions running at the same time, like two rustdoc doctests.
+        let outputs = [Arc::new(Mutex::new(Vec::new())), Arc::new(Mutex::new(Vec::new()))];
+        let logs = [SharedSarifLog::default(), SharedSarifLog::default()];
+        let first = new_dcx(&outputs[0], &logs[0]);
+        let second = new_dcx(&outputs[1], &logs[1]);
+        first.handle().warn("first");
+        second.handle().warn("second");
+        drop(first);
+        assert!(outputs[1].lock().unwrap().is_empty());
+        drop(second);
+
+        for (output, message) in outputs.iter().zip(["first", "second"]) {
+            let bytes = output.lock().unwrap();
+            let log: Value = serde_json::from_str(str::from_utf8(&bytes).unwrap()).unwrap();
+            let results = log["runs"][0]["results"].as_array().unwrap();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0]["message"]["text"], message);
+        }
+    })
+}
+
+#[test]
+fn uris() {
+    assert_eq!(file_name_to_uri("src/my file.rs"), "src/my%20file.rs");
+    assert_eq!(file_name_to_uri("src\\lib.rs"), "src/lib.rs");
+    #[cfg(unix)]
+    assert_eq!(file_name_to_uri("/home/user/lib.rs"), "file:///home/user/lib.rs");
+}
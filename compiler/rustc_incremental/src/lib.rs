@@ -16,8 +16,8 @@
 
 pub use persist::{
     LoadResult, copy_cgu_workproduct_to_incr_comp_cache_dir, finalize_session_directory,
-    in_incr_comp_dir, in_incr_comp_dir_sess, load_query_result_cache, save_dep_graph,
-    save_work_product_index, setup_dep_graph,
+    in_incr_comp_dir, in_incr_comp_dir_sess, load_query_result_cache, print_incremental_cache,
+    save_dep_graph, save_work_product_index, setup_dep_graph,
 };
 
 rustc_fluent_macro::fluent_messages! { "../messages.ftl" }
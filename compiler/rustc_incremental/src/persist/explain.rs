@@ -0,0 +1,81 @@
+//! Code to write the report requested with `-Zincremental-explain`, which
+//! explains why the queries that were re-executed in this session could not
+//! be reused from the previous one.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use rustc_middle::dep_graph::{DepNode, ReexecutionRoot};
+use rustc_middle::ty::TyCtxt;
+use tracing::debug;
+
+use crate::errors;
+
+#[derive(serde::Serialize)]
+struct Report {
+    /// Whether a dep-graph from a previous session was loaded at all. If not,
+    /// every query was executed and `reexecuted` is empty.
+    previous_session_loaded: bool,
+    reexecuted: Vec<ReexecutedNode>,
+}
+
+#[derive(serde::Serialize)]
+struct ReexecutedNode {
+    #[serde(flatten)]
+    node: Node,
+    /// The first red dependency of `node`, followed by its first red
+    /// dependency, and so on.
+    chain: Vec<Node>,
+    /// Why the last node of `chain` is red.
+    cause: &'static str,
+}
+
+#[derive(serde::Serialize)]
+struct Node {
+    kind: String,
+    node: String,
+}
+
+impl Node {
+    fn new(node: &DepNode) -> Node {
+        Node { kind: format!("{:?}", node.kind), node: format!("{node:?}") }
+    }
+}
+
+/// Writes the `-Zincremental-explain` report to `path`.
+pub(crate) fn write_reexecution_report(tcx: TyCtxt<'_>, path: &Path) {
+    debug!("write_reexecution_report({})", path.display());
+
+    let Some(causes) = tcx.dep_graph.reexecution_causes(tcx) else {
+        return;
+    };
+    let report = Report {
+        previous_session_loaded: tcx.dep_graph.has_previous_session(),
+        reexecuted: causes
+            .iter()
+            .map(|cause| ReexecutedNode {
+                node: Node::new(&cause.node),
+                chain: cause.chain.iter().map(Node::new).collect(),
+                cause: match cause.root {
+                    ReexecutionRoot::InputChanged => "input-changed",
+                    ReexecutionRoot::ResultChanged => "result-changed",
+                    ReexecutionRoot::Removed => "removed",
+                },
+            })
+            .collect(),
+    };
+
+    let result = File::create(path).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, &report)?;
+        writer.flush()
+    });
+    if let Err(err) = result {
+        tcx.dcx().emit_err(errors::WriteNew {
+            name: "incremental explanation",
+            path: path.to_path_buf(),
+            err,
+        });
+    }
+}
@@ -102,6 +102,18 @@
 //! It might be a good idea though to try and detect whether we are on an
 //! unsupported file system and emit a warning in that case. This is not yet
 //! implemented.
+//!
+//! ## Size limit
+//!
+//! The garbage collection described above only ever looks at the crate that
+//! is being compiled, so an incremental compilation directory shared by many
+//! crates (like the one Cargo uses) keeps growing as long as new crates are
+//! added to it. With `-Zincremental-cache-limit=<bytes>`, each garbage
+//! collection additionally deletes the oldest finalized session directories
+//! of all crates in the incremental compilation directory until their total
+//! size is within the limit. Session directories that are locked by another
+//! process, as well as the current session, are never deleted for this, so
+//! the limit can be exceeded temporarily.
 
 use std::fs as std_fs;
 use std::io::{self, ErrorKind};
@@ -130,7 +142,7 @@
 const LOCK_FILE_EXT: &str = ".lock";
 const DEP_GRAPH_FILENAME: &str = "dep-graph.bin";
 const STAGING_DEP_GRAPH_FILENAME: &str = "dep-graph.part.bin";
-const WORK_PRODUCTS_FILENAME: &str = "work-products.bin";
+pub(super) const WORK_PRODUCTS_FILENAME: &str = "work-products.bin";
 const QUERY_CACHE_FILENAME: &str = "query-cache.bin";
 
 // We encode integers using the following base, so they are shorter than decimal
@@ -844,9 +856,129 @@ pub(crate) fn garbage_collect_session_directories(sess: &Session) -> io::Result<
         true
     });
 
+    if let Some(limit) = sess.opts.unstable_opts.incremental_cache_limit {
+        prune_incr_comp_dir_to_size(sess, limit);
+    }
+
     Ok(())
 }
 
+/// A session directory found by [`list_session_directories`].
+pub(super) struct SessionDirInfo {
+    /// The name of the crate directory the session belongs to.
+    pub crate_dir_name: String,
+    /// The name of the session directory itself.
+    pub name: String,
+    pub path: PathBuf,
+    pub finalized: bool,
+    /// The total size of the files in the session directory, in bytes. Files
+    /// that are hard-linked into several session directories are counted once
+    /// for each of them.
+    pub size: u64,
+}
+
+/// Lists the session directories of all crates in the incremental compilation
+/// directory `incr_dir`, sorted by crate and session directory name.
+pub(super) fn list_session_directories(incr_dir: &Path) -> Vec<SessionDirInfo> {
+    let mut sessions = vec![];
+
+    let Ok(crate_dirs) = incr_dir.read_dir() else {
+        return sessions;
+    };
+    for crate_dir in crate_dirs {
+        // Ignore any errors, as well as anything that isn't a directory.
+        let Ok(crate_dir) = crate_dir else { continue };
+        let Ok(crate_dir_name) = crate_dir.file_name().into_string() else { continue };
+        let Ok(session_dirs) = crate_dir.path().read_dir() else { continue };
+
+        for session_dir in session_dirs {
+            let Ok(session_dir) = session_dir else { continue };
+            let Ok(name) = session_dir.file_name().into_string() else { continue };
+            if !is_session_directory(&name) || !session_dir.path().is_dir() {
+                continue;
+            }
+
+            let path = session_dir.path();
+            sessions.push(SessionDirInfo {
+                crate_dir_name: crate_dir_name.clone(),
+                finalized: is_finalized(&name),
+                size: dir_size(&path),
+                name,
+                path,
+            });
+        }
+    }
+
+    sessions.sort_by(|a, b| (&a.crate_dir_name, &a.name).cmp(&(&b.crate_dir_name, &b.name)));
+    sessions
+}
+
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = path.read_dir() else {
+        return 0;
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Deletes the oldest finalized session directories in the incremental
+/// compilation directory until all sessions together take up at most `limit`
+/// bytes. See the "Size limit" section of the module documentation.
+fn prune_incr_comp_dir_to_size(sess: &Session, limit: u64) {
+    let incr_dir = sess.opts.incremental.as_ref().unwrap();
+    // Session directory paths are canonicalized, so compare them by name.
+    let current_session_name = sess.incr_comp_session_dir().file_name().unwrap().to_owned();
+
+    let sessions = list_session_directories(incr_dir);
+    let mut total_size: u64 = sessions.iter().map(|session| session.size).sum();
+    debug!("prune_incr_comp_dir_to_size() - {total_size} bytes in use, limit is {limit} bytes");
+
+    let mut candidates: Vec<_> = sessions
+        .into_iter()
+        .filter(|session| session.finalized && current_session_name != *session.name)
+        .filter_map(|session| {
+            let timestamp = extract_timestamp_from_session_dir(&session.name).ok()?;
+            Some((timestamp, session))
+        })
+        .collect();
+    candidates.sort_by_key(|&(timestamp, _)| timestamp);
+
+    for (_, session) in candidates {
+        if total_size <= limit {
+            break;
+        }
+
+        let lock_file_path = lock_file_path(&session.path);
+        let Ok(lock) = flock::Lock::new(
+            &lock_file_path,
+            false, // don't wait
+            false, // don't create the lock-file
+            true,  // get an exclusive lock
+        ) else {
+            debug!("prune_incr_comp_dir_to_size() - not deleting, still in use");
+            continue;
+        };
+
+        debug!("prune_incr_comp_dir_to_size() - deleting `{}`", session.path.display());
+        if let Err(err) = safe_remove_dir_all(&session.path) {
+            sess.dcx().emit_warn(errors::FinalizedGcFailed { path: &session.path, err });
+        } else {
+            delete_session_dir_lock_file(sess, &lock_file_path);
+            total_size -= session.size;
+        }
+
+        // Let's make it explicit that the file lock is released at this point,
+        // or rather, that we held on to it until here
+        drop(lock);
+    }
+}
+
 fn delete_old(sess: &Session, path: &Path) {
     debug!("garbage_collect_session_directories() - deleting `{}`", path.display());
 
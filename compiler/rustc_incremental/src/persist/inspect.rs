@@ -0,0 +1,61 @@
+//! Code to describe the contents of an incremental compilation directory, for
+//! `--print=incremental-cache`.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use rustc_serialize::Decodable;
+use rustc_serialize::opaque::MemDecoder;
+use rustc_session::Session;
+
+use super::data::SerializedWorkProduct;
+use super::file_format;
+use super::fs::{WORK_PRODUCTS_FILENAME, in_incr_comp_dir, list_session_directories};
+
+/// Writes one line for every session directory in the incremental compilation
+/// directory `incr_dir` to `out`, each followed by an indented line for every
+/// work product of the session, and finally a line with the totals.
+pub fn print_incremental_cache(sess: &Session, incr_dir: &Path, out: &mut String) {
+    let sessions = list_session_directories(incr_dir);
+
+    let mut total_size = 0;
+    for session in &sessions {
+        total_size += session.size;
+
+        let state = if session.finalized { "finalized" } else { "working" };
+        let work_products = read_work_products(sess, &session.path);
+        let count = match &work_products {
+            Some(work_products) => work_products.len().to_string(),
+            None => "unknown".to_string(),
+        };
+        writeln!(
+            out,
+            "{}/{} {state} size={} work-products={count}",
+            session.crate_dir_name, session.name, session.size,
+        )
+        .unwrap();
+
+        let mut work_products = work_products.unwrap_or_default();
+        work_products.sort_by(|a, b| a.work_product.cgu_name.cmp(&b.work_product.cgu_name));
+        for SerializedWorkProduct { work_product, .. } in &work_products {
+            write!(out, "    {}", work_product.cgu_name).unwrap();
+            for (kind, path) in work_product.saved_files.to_sorted_stable_ord() {
+                write!(out, " {kind}={path}").unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+    }
+
+    writeln!(out, "total sessions={} size={total_size}", sessions.len()).unwrap();
+}
+
+/// Returns the work products recorded in a session directory, or `None` if
+/// there is no readable work product index, e.g. because the session is still
+/// in progress or was created by a different compiler.
+fn read_work_products(sess: &Session, session_dir: &Path) -> Option<Vec<SerializedWorkProduct>> {
+    let path = in_incr_comp_dir(session_dir, WORK_PRODUCTS_FILENAME);
+    let (data, start_pos) =
+        file_format::read_file(&path, false, sess.is_nightly_build(), sess.cfg_version).ok()??;
+    let mut decoder = MemDecoder::new(&data[..], start_pos).ok()?;
+    Some(Decodable::decode(&mut decoder))
+}
@@ -14,7 +14,7 @@
 
 use super::data::*;
 use super::fs::*;
-use super::{dirty_clean, file_format, work_product};
+use super::{dirty_clean, explain, file_format, work_product};
 use crate::assert_dep_graph::assert_dep_graph;
 use crate::errors;
 
@@ -48,6 +48,12 @@ pub fn save_dep_graph(tcx: TyCtxt<'_>) {
             tcx.dep_graph.print_incremental_info()
         }
 
+        if let Some(path) = &sess.opts.unstable_opts.incremental_explain {
+            sess.time("incr_comp_write_explanation", || {
+                explain::write_reexecution_report(tcx, path)
+            });
+        }
+
         join(
             move || {
                 sess.time("incr_comp_persist_dep_graph", || {
@@ -179,5 +185,6 @@ pub(crate) fn build_dep_graph(
         encoder,
         sess.opts.unstable_opts.query_dep_graph,
         sess.opts.unstable_opts.incremental_info,
+        sess.opts.unstable_opts.incremental_explain.is_some(),
     ))
 }
@@ -9,6 +9,7 @@
 use rustc_data_structures::sync::Lrc;
 use rustc_data_structures::{defer, jobserver};
 use rustc_errors::registry::Registry;
+use rustc_errors::sarif::SharedSarifLog;
 use rustc_errors::{DiagCtxtHandle, ErrorGuaranteed};
 use rustc_lint::LintStore;
 use rustc_middle::ty;
@@ -344,6 +345,11 @@ pub struct Config {
     /// Registry of diagnostics codes.
     pub registry: Registry,
 
+    /// The log that `--error-format=sarif` adds to. The driver passes the one of the
+    /// [`EarlyDiagCtxt`] it used before, so that the session writes a single log. A new log is
+    /// used if this is `None`.
+    pub sarif_log: Option<SharedSarifLog>,
+
     /// The inner atomic value is set to true when a feature marked as `internal` is
     /// enabled. Makes it so that "please report a bug" is hidden, as ICEs with
     /// internal features are wontfix, and they are usually the cause of the ICEs.
@@ -379,7 +385,8 @@ pub fn run_compiler<R: Send>(config: Config, f: impl FnOnce(&Compiler) -> R + Se
     rustc_data_structures::sync::set_dyn_thread_safe_mode(config.opts.unstable_opts.threads > 1);
 
     // Check jobserver before run_in_thread_pool_with_globals, which call jobserver::acquire_thread
-    let early_dcx = EarlyDiagCtxt::new(config.opts.error_format);
+    let sarif_log = config.sarif_log.unwrap_or_default();
+    let early_dcx = EarlyDiagCtxt::with_sarif_log(config.opts.error_format, sarif_log.clone());
     initialize_checked_jobserver(&early_dcx);
 
     crate::callbacks::setup_callbacks();
@@ -398,7 +405,7 @@ pub fn run_compiler<R: Send>(config: Config, f: impl FnOnce(&Compiler) -> R + Se
         |current_gcx| {
             // The previous `early_dcx` can't be reused here because it doesn't
             // impl `Send`. Creating a new one is fine.
-            let early_dcx = EarlyDiagCtxt::new(config.opts.error_format);
+            let early_dcx = EarlyDiagCtxt::with_sarif_log(config.opts.error_format, sarif_log);
 
             let codegen_backend = match config.make_codegen_backend {
                 None => util::get_codegen_backend(
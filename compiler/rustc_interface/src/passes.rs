@@ -450,6 +450,16 @@ fn write_out_deps(tcx: TyCtxt<'_>, outputs: &OutputFilenames, out_filenames: &[P
             files.push(normalize_path(profile_sample.as_path().to_path_buf()));
         }
 
+        // The coverage filter decides which functions get instrumented
+        if let Some(coverage_filter) = sess.coverage_filter_path() {
+            files.push(normalize_path(coverage_filter.to_path_buf()));
+        }
+
+        // The version tags of exported symbols end up in the linker version script
+        if let Some(ref symbol_versions) = sess.opts.unstable_opts.symbol_versions {
+            files.push(normalize_path(symbol_versions.clone()));
+        }
+
         // Debugger visualizer files
         for debugger_visualizer in tcx.debugger_visualizers(LOCAL_CRATE) {
             files.push(normalize_path(debugger_visualizer.path.clone().unwrap()));
@@ -14,7 +14,7 @@
 use rustc_middle::ty::{GlobalCtxt, TyCtxt};
 use rustc_serialize::opaque::FileEncodeResult;
 use rustc_session::Session;
-use rustc_session::config::{self, OutputFilenames, OutputType};
+use rustc_session::config::{self, OutputFilenames, OutputType, PrintTypeSizesFormat};
 
 use crate::errors::FailedWritingFile;
 use crate::interface::{Compiler, Result};
@@ -131,7 +131,15 @@ pub fn codegen_and_build_linker(
         // This must run after monomorphization so that all generic types
         // have been instantiated.
         if tcx.sess.opts.unstable_opts.print_type_sizes {
-            tcx.sess.code_stats.print_type_sizes();
+            match tcx.sess.opts.unstable_opts.print_type_sizes_format {
+                PrintTypeSizesFormat::Text => tcx.sess.code_stats.print_type_sizes(),
+                PrintTypeSizesFormat::Json => {
+                    let path = tcx.output_filenames(()).with_extension("type_sizes.json");
+                    if let Err(error) = tcx.sess.code_stats.write_type_sizes_json(&path) {
+                        tcx.dcx().emit_fatal(FailedWritingFile { path: &path, error });
+                    }
+                }
+            }
         }
 
         if tcx.sess.opts.unstable_opts.print_vtable_sizes {
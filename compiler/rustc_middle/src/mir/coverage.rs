@@ -332,3 +332,33 @@ pub struct MCDCDecisionSpan {
     pub end_markers: Vec<BlockMarkerId>,
     pub decision_depth: u16,
 }
+
+/// Rules read from the file passed to `-Zcoverage-options=filter=<path>`, which
+/// select the functions that the `InstrumentCoverage` MIR pass will instrument.
+///
+/// Rules are checked in reverse order, so the last rule that matches a function
+/// decides whether it is instrumented. Functions that match no rule are instrumented.
+#[derive(Clone, Debug, Default, HashStable)]
+pub struct CoverageFilter {
+    pub rules: Vec<CoverageFilterRule>,
+}
+
+/// A single `include <target>:<glob>` or `exclude <target>:<glob>` line of a
+/// coverage filter file.
+#[derive(Clone, Debug, HashStable)]
+pub struct CoverageFilterRule {
+    /// True for `include` rules, false for `exclude` rules.
+    pub include: bool,
+    pub target: CoverageFilterTarget,
+    pub pattern: String,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, HashStable)]
+pub enum CoverageFilterTarget {
+    /// `fn:<glob>`: Matches the def-path of the function, as printed in
+    /// diagnostics (e.g. `generated::parse` or `<Foo as Clone>::clone`).
+    DefPath,
+    /// `file:<glob>`: Matches the path of the source file that contains the
+    /// function, as it was passed to the compiler (e.g. `src/vendor/zlib.rs`).
+    File,
+}
@@ -578,6 +578,16 @@
         arena_cache
     }
 
+    /// The rules of the `-Zcoverage-options=filter=<path>` file, if one was given.
+    ///
+    /// The file is read outside of the query system, so this is re-run in every
+    /// session to notice changes to its contents.
+    query coverage_filter(_: ()) -> &'tcx Option<mir::coverage::CoverageFilter> {
+        eval_always
+        arena_cache
+        desc { "reading the coverage filter file" }
+    }
+
     /// The `DefId` is the `DefId` of the containing MIR body. Promoteds do not have their own
     /// `DefId`. This function returns all promoteds in the specified body. The body references
     /// promoteds by the `DefId` and the `mir::Promoted` index. This is necessary, because
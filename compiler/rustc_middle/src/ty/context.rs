@@ -1297,6 +1297,10 @@ pub struct GlobalCtxt<'tcx> {
     /// Stores memory for globals (statics/consts).
     pub(crate) alloc_map: Lock<interpret::AllocMap<'tcx>>,
 
+    /// Bodies that `instance_mir` returns instead of the `optimized_mir` of an item,
+    /// see [`TyCtxt::replace_optimized_mir`].
+    pub(crate) replaced_optimized_mir: Lock<FxHashMap<DefId, &'tcx Body<'tcx>>>,
+
     current_gcx: CurrentGcx,
 }
 
@@ -1527,6 +1531,7 @@ pub fn create_global_ctxt(
             canonical_param_env_cache: Default::default(),
             data_layout,
             alloc_map: Lock::new(interpret::AllocMap::new()),
+            replaced_optimized_mir: Default::default(),
             current_gcx,
         }
     }
@@ -42,6 +42,7 @@
     extension,
 };
 use rustc_query_system::ich::StableHashingContext;
+use rustc_query_system::query::QueryCache;
 use rustc_serialize::{Decodable, Encodable};
 use rustc_session::lint::LintBuffer;
 pub use rustc_session::lint::RegisteredTools;
@@ -1753,7 +1754,10 @@ pub fn instance_mir(self, instance: ty::InstanceKind<'tcx>) -> &'tcx Body<'tcx>
                     | DefKind::InlineConst => self.mir_for_ctfe(def),
                     // If the caller wants `mir_for_ctfe` of a function they should not be using
                     // `instance_mir`, so we'll assume const fn also wants the optimized version.
-                    _ => self.optimized_mir(def),
+                    _ => match self.replaced_optimized_mir.lock().get(&def) {
+                        Some(&body) => body,
+                        None => self.optimized_mir(def),
+                    },
                 }
             }
             ty::InstanceKind::VTableShim(..)
@@ -1771,6 +1775,33 @@ pub fn instance_mir(self, instance: ty::InstanceKind<'tcx>) -> &'tcx Body<'tcx>
         }
     }
 
+    /// Makes `instance_mir` return `body` instead of the `optimized_mir` of `def_id` for the
+    /// rest of this session. This is used by tools that transform optimized MIR before codegen,
+    /// e.g. through stable MIR. The replacement is expected to have been validated already.
+    ///
+    /// Must only be called if [`TyCtxt::check_optimized_mir_replaceable`] succeeds.
+    pub fn replace_optimized_mir(self, def_id: DefId, body: Body<'tcx>) {
+        if let Err(reason) = self.check_optimized_mir_replaceable() {
+            bug!("cannot replace the optimized MIR of {def_id:?}: {reason}");
+        }
+        let body = self.arena.alloc(body);
+        self.replaced_optimized_mir.lock().insert(def_id, body);
+    }
+
+    /// Checks that [`TyCtxt::replace_optimized_mir`] can still be used. Nothing that was
+    /// computed from the original body is recomputed, and replacements are not tracked by the
+    /// dependency graph, so bodies can't be replaced under incremental compilation or once
+    /// codegen has started.
+    pub fn check_optimized_mir_replaceable(self) -> Result<(), &'static str> {
+        if self.sess.opts.incremental.is_some() {
+            return Err("bodies cannot be replaced under incremental compilation");
+        }
+        if self.query_system.caches.collect_and_partition_mono_items.lookup(&()).is_some() {
+            return Err("bodies cannot be replaced once codegen has started");
+        }
+        Ok(())
+    }
+
     // FIXME(@lcnr): Remove this function.
     pub fn get_attrs_unchecked(self, did: DefId) -> &'tcx [ast::Attribute] {
         if let Some(did) = did.as_local() {
@@ -2335,7 +2335,11 @@ pub(crate) fn lower_let_expr(
         emit_storage_live: EmitStorageLive,
     ) -> BlockAnd<()> {
         let expr_span = self.thir[expr_id].span;
+        // The scrutinee is not a condition of any enclosing let-chain, so give any
+        // boolean expressions inside it their own MC/DC decision depth.
+        self.mcdc_increment_depth_if_enabled();
         let scrutinee = unpack!(block = self.lower_scrutinee(block, expr_id, expr_span));
+        self.mcdc_decrement_depth_if_enabled();
         let built_tree = self.lower_match_tree(
             block,
             expr_span,
@@ -2445,6 +2449,10 @@ fn bind_and_guard_matched_candidate(
 
             let mut guard_span = rustc_span::DUMMY_SP;
 
+            // A guard is a decision of its own, even if the `match` is being lowered
+            // while the conditions of an enclosing decision are still being visited
+            // (e.g. `let x = a && matches!(y, Some(z) if z > 0 && b);`).
+            self.mcdc_increment_depth_if_enabled();
             let (post_guard_block, otherwise_post_guard_block) =
                 self.in_if_then_scope(match_scope, guard_span, |this| {
                     guard_span = this.thir[guard].span;
@@ -2456,6 +2464,7 @@ fn bind_and_guard_matched_candidate(
                         DeclareLetBindings::No, // For guards, `let` bindings are declared separately
                     )
                 });
+            self.mcdc_decrement_depth_if_enabled();
 
             let source_info = self.source_info(guard_span);
             let guard_end = self.source_info(tcx.sess.source_map().end_point(guard_span));
@@ -9,6 +9,11 @@ mir_transform_const_mut_borrow = taking a mutable reference to a `const` item
     .note2 = the mutable reference will refer to this temporary, not the original `const` item
     .note3 = mutable reference created due to call to this method
 
+mir_transform_coverage_filter_invalid_rule = invalid rule on line {$line} of coverage filter file `{$path}`
+    .help = rules have the form `include fn:<glob>`, `exclude fn:<glob>`, `include file:<glob>` or `exclude file:<glob>`
+
+mir_transform_coverage_filter_unreadable = failed to read coverage filter file `{$path}`: {$error}
+
 mir_transform_ffi_unwind_call = call to {$foreign ->
     [true] foreign function
     *[false] function pointer
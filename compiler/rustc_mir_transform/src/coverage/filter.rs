@@ -0,0 +1,83 @@
+//! Parsing and matching of the rules in a `-Zcoverage-options=filter=<path>` file.
+//!
+//! Each non-blank line that isn't a `#` comment is a rule of the form
+//! `include <target>:<glob>` or `exclude <target>:<glob>`, where `<target>` is
+//! `fn` (matched against the function's def-path) or `file` (matched against
+//! the path of its source file).
+
+use rustc_middle::mir::coverage::{CoverageFilter, CoverageFilterRule, CoverageFilterTarget};
+
+/// Parses the contents of a coverage filter file.
+///
+/// On failure, returns the 1-based number of the first line that is not a valid rule.
+pub(super) fn parse_coverage_filter(contents: &str) -> Result<CoverageFilter, usize> {
+    let mut rules = vec![];
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        rules.push(parse_rule(line).ok_or(index + 1)?);
+    }
+    Ok(CoverageFilter { rules })
+}
+
+fn parse_rule(line: &str) -> Option<CoverageFilterRule> {
+    let (action, rest) = line.split_once(char::is_whitespace)?;
+    let include = match action {
+        "include" => true,
+        "exclude" => false,
+        _ => return None,
+    };
+    let (target, pattern) = rest.trim_start().split_once(':')?;
+    let target = match target {
+        "fn" => CoverageFilterTarget::DefPath,
+        "file" => CoverageFilterTarget::File,
+        _ => return None,
+    };
+    if pattern.is_empty() {
+        return None;
+    }
+    Some(CoverageFilterRule { include, target, pattern: pattern.to_owned() })
+}
+
+/// Returns true if the filter allows instrumenting a function with the given
+/// def-path, defined in the given source file.
+pub(super) fn filter_includes(filter: &CoverageFilter, def_path: &str, file: &str) -> bool {
+    filter
+        .rules
+        .iter()
+        .rev()
+        .find(|rule| match rule.target {
+            CoverageFilterTarget::DefPath => glob_matches(&rule.pattern, def_path, "::"),
+            CoverageFilterTarget::File => glob_matches(&rule.pattern, file, "/"),
+        })
+        .is_none_or(|rule| rule.include)
+}
+
+/// Matches `text` against a glob `pattern`, in which `**` matches any sequence
+/// of characters, `*` matches any sequence that doesn't contain `separator`,
+/// and `?` matches any single character.
+pub(super) fn glob_matches(pattern: &str, text: &str, separator: &str) -> bool {
+    if let Some(rest) = pattern.strip_prefix("**") {
+        return (0..=text.len())
+            .filter(|&i| text.is_char_boundary(i))
+            .any(|i| glob_matches(rest, &text[i..], separator));
+    }
+    if let Some(rest) = pattern.strip_prefix('*') {
+        let end = text.find(separator).unwrap_or(text.len());
+        return (0..=end)
+            .filter(|&i| text.is_char_boundary(i))
+            .any(|i| glob_matches(rest, &text[i..], separator));
+    }
+
+    let mut pattern_chars = pattern.chars();
+    let mut text_chars = text.chars();
+    match (pattern_chars.next(), text_chars.next()) {
+        (None, None) => true,
+        (Some(p), Some(t)) if p == '?' || p == t => {
+            glob_matches(pattern_chars.as_str(), text_chars.as_str(), separator)
+        }
+        _ => false,
+    }
+}
@@ -243,19 +243,24 @@ pub(super) fn extract_mcdc_mappings(
     let bcb_from_marker =
         |marker: BlockMarkerId| basic_coverage_blocks.bcb_from_bb(block_markers[marker]?);
 
-    let check_branch_bcb =
-        |raw_span: Span, true_marker: BlockMarkerId, false_marker: BlockMarkerId| {
-            // For now, ignore any branch span that was introduced by
-            // expansion. This makes things like assert macros less noisy.
-            if !raw_span.ctxt().outer_expn_data().is_root() {
-                return None;
-            }
-            let span = unexpand_into_body_span(raw_span, body_span)?;
+    let check_branch_bcb = |raw_span: Span,
+                            true_marker: BlockMarkerId,
+                            false_marker: BlockMarkerId,
+                            in_decision: bool| {
+        // For now, ignore any standalone branch span that was introduced by
+        // expansion. This makes things like assert macros less noisy.
+        // Conditions of a decision must be kept even if they come from an
+        // expansion (e.g. `matches!(..) && b`), because the decision expects
+        // a branch for every one of its conditions.
+        if !in_decision && !raw_span.ctxt().outer_expn_data().is_root() {
+            return None;
+        }
+        let span = unexpand_into_body_span(raw_span, body_span)?;
 
-            let true_bcb = bcb_from_marker(true_marker)?;
-            let false_bcb = bcb_from_marker(false_marker)?;
-            Some((span, true_bcb, false_bcb))
-        };
+        let true_bcb = bcb_from_marker(true_marker)?;
+        let false_bcb = bcb_from_marker(false_marker)?;
+        Some((span, true_bcb, false_bcb))
+    };
 
     mcdc_branches.extend(coverage_info_hi.mcdc_branch_spans.iter().filter_map(
         |&mir::coverage::MCDCBranchSpan {
@@ -266,7 +271,7 @@ pub(super) fn extract_mcdc_mappings(
              decision_depth,
          }| {
             let (span, true_bcb, false_bcb) =
-                check_branch_bcb(raw_span, true_marker, false_marker)?;
+                check_branch_bcb(raw_span, true_marker, false_marker, condition_info.is_some())?;
             Some(MCDCBranch { span, true_bcb, false_bcb, condition_info, decision_depth })
         },
     ));
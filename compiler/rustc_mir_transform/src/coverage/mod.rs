@@ -1,6 +1,7 @@
 pub(super) mod query;
 
 mod counters;
+mod filter;
 mod graph;
 mod mappings;
 mod spans;
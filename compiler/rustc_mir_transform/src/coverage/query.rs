@@ -1,20 +1,25 @@
 use rustc_data_structures::captures::Captures;
 use rustc_middle::middle::codegen_fn_attrs::CodegenFnAttrFlags;
-use rustc_middle::mir::coverage::{CounterId, CoverageKind};
+use rustc_middle::mir::coverage::{CounterId, CoverageFilter, CoverageKind};
 use rustc_middle::mir::{Body, CoverageIdsInfo, Statement, StatementKind};
 use rustc_middle::query::TyCtxtAt;
+use rustc_middle::ty::print::with_no_trimmed_paths;
 use rustc_middle::ty::{self, TyCtxt};
 use rustc_middle::util::Providers;
 use rustc_span::def_id::LocalDefId;
 use rustc_span::sym;
 use tracing::trace;
 
+use crate::coverage::filter::{filter_includes, parse_coverage_filter};
+use crate::errors;
+
 /// Registers query/hook implementations related to coverage.
 pub(crate) fn provide(providers: &mut Providers) {
     providers.hooks.is_eligible_for_coverage =
         |TyCtxtAt { tcx, .. }, def_id| is_eligible_for_coverage(tcx, def_id);
     providers.queries.coverage_attr_on = coverage_attr_on;
     providers.queries.coverage_ids_info = coverage_ids_info;
+    providers.queries.coverage_filter = coverage_filter;
 }
 
 /// Hook implementation for [`TyCtxt::is_eligible_for_coverage`].
@@ -51,6 +56,16 @@ fn is_eligible_for_coverage(tcx: TyCtxt<'_>, def_id: LocalDefId) -> bool {
         return false;
     }
 
+    if let Some(filter) = tcx.coverage_filter(()) {
+        let def_path = with_no_trimmed_paths!(tcx.def_path_str(def_id));
+        let file = tcx.sess.source_map().span_to_filename(tcx.def_span(def_id));
+        let file = file.prefer_local().to_string().replace('\\', "/");
+        if !filter_includes(filter, &def_path, &file) {
+            trace!("InstrumentCoverage skipped for {def_id:?} (excluded by coverage filter)");
+            return false;
+        }
+    }
+
     true
 }
 
@@ -78,6 +93,27 @@ fn coverage_attr_on(tcx: TyCtxt<'_>, def_id: LocalDefId) -> bool {
     }
 }
 
+/// Query implementation for `coverage_filter`.
+fn coverage_filter(tcx: TyCtxt<'_>, (): ()) -> Option<CoverageFilter> {
+    let path = tcx.sess.coverage_filter_path()?;
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        // A missing file has already been reported when the session was created.
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(error) => {
+            tcx.dcx().emit_err(errors::CoverageFilterUnreadable { path, error });
+            return None;
+        }
+    };
+    match parse_coverage_filter(&contents) {
+        Ok(filter) => Some(filter),
+        Err(line) => {
+            tcx.dcx().emit_err(errors::CoverageFilterInvalidRule { path, line });
+            None
+        }
+    }
+}
+
 /// Query implementation for `coverage_ids_info`.
 fn coverage_ids_info<'tcx>(
     tcx: TyCtxt<'tcx>,
@@ -546,3 +546,39 @@ fn test_covgraph_switchint_loop_then_inner_loop_else_break() {
     assert_successors(&basic_coverage_blocks, bcb(5), &[bcb(1)]);
     assert_successors(&basic_coverage_blocks, bcb(6), &[bcb(4)]);
 }
+
+#[test]
+fn test_coverage_filter_glob() {
+    use super::filter::glob_matches;
+
+    assert!(glob_matches("generated::*", "generated::parse", "::"));
+    assert!(!glob_matches("generated::*", "generated::inner::parse", "::"));
+    assert!(glob_matches("generated::**", "generated::inner::parse", "::"));
+    assert!(glob_matches("*::tests::*", "lexer::tests::helper", "::"));
+    assert!(glob_matches("src/vendor/**", "src/vendor/zlib/inflate.rs", "/"));
+    assert!(!glob_matches("src/*.rs", "src/vendor/zlib.rs", "/"));
+    assert!(glob_matches("src/lib.r?", "src/lib.rs", "/"));
+}
+
+#[test]
+fn test_coverage_filter_rules() {
+    use super::filter::{filter_includes, parse_coverage_filter};
+
+    let filter = parse_coverage_filter(
+        "# Skip vendored code, but keep the parts we patched.\n\
+         exclude file:src/vendor/**\n\
+         include fn:vendor::patched::*\n\
+         \n\
+         exclude fn:**::tests::*\n",
+    )
+    .unwrap();
+    assert_eq!(filter.rules.len(), 3);
+    assert!(filter_includes(&filter, "main", "src/main.rs"));
+    assert!(!filter_includes(&filter, "vendor::zlib::inflate", "src/vendor/zlib.rs"));
+    assert!(filter_includes(&filter, "vendor::patched::inflate", "src/vendor/patched.rs"));
+    assert!(!filter_includes(&filter, "lexer::tests::helper", "src/lexer.rs"));
+
+    assert_eq!(parse_coverage_filter("include fn:a\nskip fn:b\n").unwrap_err(), 2);
+    assert_eq!(parse_coverage_filter("exclude module:a\n").unwrap_err(), 1);
+    assert_eq!(parse_coverage_filter("exclude fn:\n").unwrap_err(), 1);
+}
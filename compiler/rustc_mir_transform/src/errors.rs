@@ -1,3 +1,6 @@
+use std::io;
+use std::path::Path;
+
 use rustc_errors::codes::*;
 use rustc_errors::{Diag, LintDiagnostic};
 use rustc_macros::{Diagnostic, LintDiagnostic, Subdiagnostic};
@@ -38,6 +41,21 @@ pub(crate) struct UnalignedPackedRef {
     pub span: Span,
 }
 
+#[derive(Diagnostic)]
+#[diag(mir_transform_coverage_filter_unreadable)]
+pub(crate) struct CoverageFilterUnreadable<'a> {
+    pub path: &'a Path,
+    pub error: io::Error,
+}
+
+#[derive(Diagnostic)]
+#[diag(mir_transform_coverage_filter_invalid_rule)]
+#[help]
+pub(crate) struct CoverageFilterInvalidRule<'a> {
+    pub path: &'a Path,
+    pub line: usize,
+}
+
 pub(crate) struct AssertLint<P> {
     pub span: Span,
     pub assert_kind: AssertKind<P>,
@@ -27,8 +27,8 @@
 use rustc_index::IndexVec;
 use rustc_middle::mir::{
     AnalysisPhase, Body, CallSource, ClearCrossCrate, ConstOperand, ConstQualifs, LocalDecl,
-    MirPhase, Operand, Place, ProjectionElem, Promoted, RuntimePhase, Rvalue, START_BLOCK,
-    SourceInfo, Statement, StatementKind, TerminatorKind,
+    Location, MirPhase, Operand, Place, ProjectionElem, Promoted, RuntimePhase, Rvalue,
+    START_BLOCK, SourceInfo, Statement, StatementKind, TerminatorKind,
 };
 use rustc_middle::ty::{self, TyCtxt, TypeVisitableExt};
 use rustc_middle::util::Providers;
@@ -687,6 +687,35 @@ fn inner_optimized_mir(tcx: TyCtxt<'_>, did: LocalDefId) -> Body<'_> {
     body
 }
 
+/// Checks that optimized MIR can still be replaced, see
+/// [`TyCtxt::check_optimized_mir_replaceable`]. Bodies also can't be replaced when the MIR
+/// inliner runs, as the original body might already have been inlined into other bodies.
+pub fn check_optimized_mir_replaceable(tcx: TyCtxt<'_>) -> Result<(), &'static str> {
+    tcx.check_optimized_mir_replaceable()?;
+    if pm::should_run_pass(tcx, &inline::Inline) {
+        return Err("bodies cannot be replaced when MIR inlining is enabled, see `-Zinline-mir`");
+    }
+    Ok(())
+}
+
+/// Prepares a body that was built outside of the MIR pipeline to be used as optimized MIR, e.g.
+/// by [`TyCtxt::replace_optimized_mir`].
+///
+/// The body is validated against the invariants of optimized MIR. All violations are returned
+/// and, if there are any, the body must not be used.
+pub fn prepare_replaced_optimized_mir<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    body: &mut Body<'tcx>,
+) -> Vec<(Location, String)> {
+    body.phase = MirPhase::Runtime(RuntimePhase::Optimized);
+    let failures = validate::validate_body(tcx, body.phase, body);
+    if failures.is_empty() {
+        RequiredConstsVisitor::compute_required_consts(body);
+        mentioned_items::MentionedItems.run_pass(tcx, body);
+    }
+    failures
+}
+
 /// Fetch all the promoteds of an item and prepare their MIR bodies to be ready for
 /// constant evaluation once all generic parameters become known.
 fn promoted_mir(tcx: TyCtxt<'_>, def: LocalDefId) -> &IndexVec<Promoted, Body<'_>> {
@@ -1,5 +1,7 @@
 //! Validates the MIR to ensure that invariants are upheld.
 
+use std::cell::RefCell;
+
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_hir::LangItem;
 use rustc_index::IndexVec;
@@ -48,61 +50,87 @@ fn run_pass(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) {
         if matches!(body.source.instance, InstanceKind::Intrinsic(..) | InstanceKind::Virtual(..)) {
             return;
         }
-        let def_id = body.source.def_id();
-        let mir_phase = self.mir_phase;
-        let param_env = match mir_phase.reveal() {
-            Reveal::UserFacing => tcx.param_env(def_id),
-            Reveal::All => tcx.param_env_reveal_all_normalized(def_id),
-        };
-
-        let can_unwind = if mir_phase <= MirPhase::Runtime(RuntimePhase::Initial) {
-            // In this case `AbortUnwindingCalls` haven't yet been executed.
-            true
-        } else if !tcx.def_kind(def_id).is_fn_like() {
-            true
-        } else {
-            let body_ty = tcx.type_of(def_id).skip_binder();
-            let body_abi = match body_ty.kind() {
-                ty::FnDef(..) => body_ty.fn_sig(tcx).abi(),
-                ty::Closure(..) => Abi::RustCall,
-                ty::CoroutineClosure(..) => Abi::RustCall,
-                ty::Coroutine(..) => Abi::Rust,
-                // No need to do MIR validation on error bodies
-                ty::Error(_) => return,
-                _ => {
-                    span_bug!(body.span, "unexpected body ty: {:?} phase {:?}", body_ty, mir_phase)
-                }
-            };
+        validate(tcx, &self.when, self.mir_phase, body, None);
+    }
+}
 
-            ty::layout::fn_can_unwind(tcx, Some(def_id), body_abi)
-        };
+/// Checks a body that was built outside of the MIR pipeline against the invariants of the MIR
+/// dialect of `mir_phase`. Unlike the [`Validator`] pass, which panics on the first violation,
+/// this returns all of them.
+///
+/// The body must only refer to locals, basic blocks and source scopes that exist.
+pub(super) fn validate_body<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    mir_phase: MirPhase,
+    body: &Body<'tcx>,
+) -> Vec<(Location, String)> {
+    let failures = RefCell::new(Vec::new());
+    validate(tcx, "validating a replaced body", mir_phase, body, Some(&failures));
+    failures.into_inner()
+}
 
-        let mut cfg_checker = CfgChecker {
-            when: &self.when,
-            body,
-            tcx,
-            mir_phase,
-            unwind_edge_count: 0,
-            reachable_blocks: traversal::reachable_as_bitset(body),
-            value_cache: FxHashSet::default(),
-            can_unwind,
+/// Collects the violations in `failures` if it is set, and panics on the first one otherwise.
+fn validate<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    when: &str,
+    mir_phase: MirPhase,
+    body: &Body<'tcx>,
+    failures: Option<&RefCell<Vec<(Location, String)>>>,
+) {
+    let def_id = body.source.def_id();
+    let param_env = match mir_phase.reveal() {
+        Reveal::UserFacing => tcx.param_env(def_id),
+        Reveal::All => tcx.param_env_reveal_all_normalized(def_id),
+    };
+
+    let can_unwind = if mir_phase <= MirPhase::Runtime(RuntimePhase::Initial) {
+        // In this case `AbortUnwindingCalls` haven't yet been executed.
+        true
+    } else if !tcx.def_kind(def_id).is_fn_like() {
+        true
+    } else {
+        let body_ty = tcx.type_of(def_id).skip_binder();
+        let body_abi = match body_ty.kind() {
+            ty::FnDef(..) => body_ty.fn_sig(tcx).abi(),
+            ty::Closure(..) => Abi::RustCall,
+            ty::CoroutineClosure(..) => Abi::RustCall,
+            ty::Coroutine(..) => Abi::Rust,
+            // No need to do MIR validation on error bodies
+            ty::Error(_) => return,
+            _ => {
+                span_bug!(body.span, "unexpected body ty: {:?} phase {:?}", body_ty, mir_phase)
+            }
         };
-        cfg_checker.visit_body(body);
-        cfg_checker.check_cleanup_control_flow();
 
-        // Also run the TypeChecker.
-        for (location, msg) in validate_types(tcx, self.mir_phase, param_env, body, body) {
-            cfg_checker.fail(location, msg);
-        }
+        ty::layout::fn_can_unwind(tcx, Some(def_id), body_abi)
+    };
+
+    let mut cfg_checker = CfgChecker {
+        when,
+        body,
+        tcx,
+        mir_phase,
+        unwind_edge_count: 0,
+        reachable_blocks: traversal::reachable_as_bitset(body),
+        value_cache: FxHashSet::default(),
+        can_unwind,
+        failures,
+    };
+    cfg_checker.visit_body(body);
+    cfg_checker.check_cleanup_control_flow();
+
+    // Also run the TypeChecker.
+    for (location, msg) in validate_types(tcx, mir_phase, param_env, body, body) {
+        cfg_checker.fail(location, msg);
+    }
 
-        if let MirPhase::Runtime(_) = body.phase {
-            if let ty::InstanceKind::Item(_) = body.source.instance {
-                if body.has_free_regions() {
-                    cfg_checker.fail(
-                        Location::START,
-                        format!("Free regions in optimized {} MIR", body.phase.name()),
-                    );
-                }
+    if let MirPhase::Runtime(_) = body.phase {
+        if let ty::InstanceKind::Item(_) = body.source.instance {
+            if body.has_free_regions() {
+                cfg_checker.fail(
+                    Location::START,
+                    format!("Free regions in optimized {} MIR", body.phase.name()),
+                );
             }
         }
     }
@@ -119,11 +147,17 @@ struct CfgChecker<'a, 'tcx> {
     // If `false`, then the MIR must not contain `UnwindAction::Continue` or
     // `TerminatorKind::Resume`.
     can_unwind: bool,
+    // If set, violations are collected here instead of panicking.
+    failures: Option<&'a RefCell<Vec<(Location, String)>>>,
 }
 
 impl<'a, 'tcx> CfgChecker<'a, 'tcx> {
     #[track_caller]
     fn fail(&self, location: Location, msg: impl AsRef<str>) {
+        if let Some(failures) = self.failures {
+            failures.borrow_mut().push((location, msg.as_ref().to_owned()));
+            return;
+        }
         // We might see broken MIR when other errors have already occurred.
         assert!(
             self.tcx.dcx().has_errors().is_some(),
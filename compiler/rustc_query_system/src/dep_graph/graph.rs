@@ -106,6 +106,40 @@ pub(crate) struct DepGraphData<D: Deps> {
     /// a particular query result was decoded from disk
     /// (not just marked green)
     debug_loaded_from_disk: Lock<FxHashSet<DepNode>>,
+
+    /// Only used with `-Zincremental-explain`. Maps every node from the
+    /// previous session that could not be marked green to the first of its
+    /// dependencies that prevented it, see [`DepGraph::reexecution_causes`].
+    red_dependencies: Option<Lock<FxHashMap<SerializedDepNodeIndex, SerializedDepNodeIndex>>>,
+}
+
+/// Why a node from the previous compilation session was re-executed, as
+/// recorded with `-Zincremental-explain`.
+#[derive(Debug)]
+pub struct ReexecutionCause {
+    /// The node that was re-executed.
+    pub node: DepNode,
+    /// The chain of dependencies that made `node` re-execute: each one is the
+    /// first red dependency of the one before it, starting with the first red
+    /// dependency of `node`. The last one is red for a reason of its own, see
+    /// `root`.
+    pub chain: Vec<DepNode>,
+    pub root: ReexecutionRoot,
+}
+
+/// Why the last node of a [`ReexecutionCause::chain`] is red.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReexecutionRoot {
+    /// The node is an input of the dep-graph, like the contents of a source
+    /// file or the hash of an upstream crate, and it has changed.
+    InputChanged,
+    /// The node was re-executed and produced a different result, without any
+    /// of its dependencies being red. This happens for example for nodes that
+    /// depend on untracked state.
+    ResultChanged,
+    /// The node could not be recomputed because its key no longer exists, for
+    /// example because the item it refers to was removed.
+    Removed,
 }
 
 pub fn hash_result<R>(hcx: &mut StableHashingContext<'_>, result: &R) -> Fingerprint
@@ -125,6 +159,7 @@ pub fn new(
         encoder: FileEncoder,
         record_graph: bool,
         record_stats: bool,
+        record_red_dependencies: bool,
     ) -> DepGraph<D> {
         let prev_graph_node_count = prev_graph.node_count();
 
@@ -179,6 +214,7 @@ pub fn new(
                 previous: prev_graph,
                 colors,
                 debug_loaded_from_disk: Default::default(),
+                red_dependencies: record_red_dependencies.then(Default::default),
             })),
             virtual_dep_node_index: Lrc::new(AtomicU32::new(0)),
         }
@@ -670,6 +706,12 @@ pub fn previous_work_products(&self) -> &WorkProductMap {
         &self.data.as_ref().unwrap().previous_work_products
     }
 
+    /// Returns `true` if a non-empty dep-graph from a previous session was
+    /// loaded, i.e. if there were any results that could have been reused.
+    pub fn has_previous_session(&self) -> bool {
+        self.data.as_ref().is_some_and(|data| data.previous.node_count() > 0)
+    }
+
     pub fn debug_was_loaded_from_disk(&self, dep_node: DepNode) -> bool {
         self.data.as_ref().unwrap().debug_loaded_from_disk.lock().contains(&dep_node)
     }
@@ -764,6 +806,7 @@ fn try_mark_parent_green<Qcx: QueryContext<Deps = D>>(
                 // mark the DepNode as green and also don't need to bother
                 // with checking any of the other dependencies.
                 debug!("dependency {dep_dep_node:?} was immediately red");
+                self.record_red_dependency(frame, parent_dep_node_index);
                 return None;
             }
             None => {}
@@ -791,6 +834,7 @@ fn try_mark_parent_green<Qcx: QueryContext<Deps = D>>(
         if !qcx.dep_context().try_force_from_dep_node(*dep_dep_node, frame) {
             // The DepNode could not be forced.
             debug!("dependency {dep_dep_node:?} could not be forced");
+            self.record_red_dependency(frame, parent_dep_node_index);
             return None;
         }
 
@@ -803,6 +847,7 @@ fn try_mark_parent_green<Qcx: QueryContext<Deps = D>>(
             }
             Some(DepNodeColor::Red) => {
                 debug!("dependency {dep_dep_node:?} was red after forcing",);
+                self.record_red_dependency(frame, parent_dep_node_index);
                 return None;
             }
             None => {}
@@ -826,6 +871,19 @@ fn try_mark_parent_green<Qcx: QueryContext<Deps = D>>(
         return None;
     }
 
+    /// Records that `red_dependency` kept the node of the innermost frame of
+    /// `frame` from being marked green, unless another dependency was found
+    /// to do so first.
+    fn record_red_dependency(
+        &self,
+        frame: Option<&MarkFrame<'_>>,
+        red_dependency: SerializedDepNodeIndex,
+    ) {
+        if let (Some(red_dependencies), Some(frame)) = (&self.red_dependencies, frame) {
+            red_dependencies.lock().entry(frame.index).or_insert(red_dependency);
+        }
+    }
+
     /// Try to mark a dep-node which existed in the previous compilation session as green.
     #[instrument(skip(self, qcx, prev_dep_node_index, frame), level = "debug")]
     fn try_mark_previous_green<Qcx: QueryContext<Deps = D>>(
@@ -960,6 +1018,53 @@ pub fn exec_cache_promotions<Tcx: DepContext>(&self, tcx: Tcx) {
         }
     }
 
+    /// Returns, for every node from the previous session that was re-executed
+    /// because it could not be marked green, the chain of red dependencies
+    /// that caused it. Returns `None` unless `-Zincremental-explain` is set.
+    pub fn reexecution_causes<Tcx: DepContext>(&self, tcx: Tcx) -> Option<Vec<ReexecutionCause>> {
+        let data = self.data.as_ref()?;
+        let red_dependencies = data.red_dependencies.as_ref()?.lock();
+
+        let mut causes: Vec<_> = red_dependencies
+            .iter()
+            // Nodes that could not be marked green but were not needed
+            // afterwards were never re-executed, so they don't have a color.
+            .filter(|&(&prev_index, _)| data.colors.get(prev_index).is_some())
+            .map(|(&prev_index, &first_red_dependency)| {
+                let mut chain = vec![first_red_dependency];
+                while let Some(&next) = red_dependencies.get(chain.last().unwrap()) {
+                    chain.push(next);
+                }
+
+                let root_index = *chain.last().unwrap();
+                let root_node = data.previous.index_to_node(root_index);
+                let root = if data.colors.get(root_index).is_none() {
+                    ReexecutionRoot::Removed
+                } else if tcx.is_eval_always(root_node.kind) {
+                    ReexecutionRoot::InputChanged
+                } else {
+                    ReexecutionRoot::ResultChanged
+                };
+
+                (prev_index, chain, root)
+            })
+            .collect();
+        // Sort by the index in the previous graph to not depend on the order in
+        // which nodes were marked.
+        causes.sort_by_key(|&(prev_index, _, _)| prev_index);
+
+        Some(
+            causes
+                .into_iter()
+                .map(|(prev_index, chain, root)| ReexecutionCause {
+                    node: data.previous.index_to_node(prev_index),
+                    chain: chain.into_iter().map(|i| data.previous.index_to_node(i)).collect(),
+                    root,
+                })
+                .collect(),
+        )
+    }
+
     pub fn print_incremental_info(&self) {
         if let Some(data) = &self.data {
             data.current.encoder.print_incremental_info(
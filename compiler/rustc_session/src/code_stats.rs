@@ -1,4 +1,7 @@
 use std::cmp;
+use std::fs::File;
+use std::io;
+use std::path::Path;
 
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_data_structures::sync::Lock;
@@ -60,14 +63,51 @@ pub enum DataTypeKind {
     Coroutine,
 }
 
+impl DataTypeKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            DataTypeKind::Struct => "struct",
+            DataTypeKind::Union => "union",
+            DataTypeKind::Enum => "enum",
+            DataTypeKind::Closure => "closure",
+            DataTypeKind::Coroutine => "coroutine",
+        }
+    }
+}
+
+/// The largest niche of a type, i.e. the invalid values of one of its scalars
+/// that enclosing enums can use to store their discriminant.
+///
+/// The `u128`s are serialized as decimal strings, as JSON numbers beyond 2^53
+/// lose precision in most parsers.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, serde::Serialize)]
+pub struct NicheInfo {
+    pub offset: u64,
+    pub size: u64,
+    #[serde(serialize_with = "serialize_u128_as_string")]
+    pub valid_range_start: u128,
+    #[serde(serialize_with = "serialize_u128_as_string")]
+    pub valid_range_end: u128,
+    /// Number of values outside of the valid range.
+    #[serde(serialize_with = "serialize_u128_as_string")]
+    pub available: u128,
+}
+
+fn serialize_u128_as_string<S: serde::Serializer>(n: &u128, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_str(n)
+}
+
 #[derive(PartialEq, Eq, Hash, Debug)]
 pub struct TypeSizeInfo {
     pub kind: DataTypeKind,
     pub type_description: String,
+    /// Path of the item that defines this type.
+    pub def_path: String,
     pub align: u64,
     pub overall_size: u64,
     pub packed: bool,
     pub opt_discr_size: Option<u64>,
+    pub niche: Option<NicheInfo>,
     pub variants: Vec<VariantInfo>,
 }
 
@@ -101,10 +141,12 @@ pub fn record_type_size<S: ToString>(
         &self,
         kind: DataTypeKind,
         type_desc: S,
+        def_path: String,
         align: Align,
         overall_size: Size,
         packed: bool,
         opt_discr_size: Option<Size>,
+        niche: Option<NicheInfo>,
         mut variants: Vec<VariantInfo>,
     ) {
         // Sort variants so the largest ones are shown first. A stable sort is
@@ -118,10 +160,12 @@ pub fn record_type_size<S: ToString>(
         let info = TypeSizeInfo {
             kind,
             type_description: type_desc.to_string(),
+            def_path,
             align: align.bytes(),
             overall_size: overall_size.bytes(),
             packed,
             opt_discr_size: opt_discr_size.map(|s| s.bytes()),
+            niche,
             variants,
         };
         self.type_sizes.borrow_mut().insert(info);
@@ -239,6 +283,113 @@ pub fn print_type_sizes(&self) {
         }
     }
 
+    /// Writes the recorded type layouts as a JSON array to `path`, in the same order
+    /// as `print_type_sizes` prints them.
+    pub fn write_type_sizes_json(&self, path: &Path) -> io::Result<()> {
+        #[derive(serde::Serialize)]
+        struct TypeJson<'a> {
+            kind: &'static str,
+            type_description: &'a str,
+            def_path: &'a str,
+            size: u64,
+            align: u64,
+            packed: bool,
+            discriminant_size: Option<u64>,
+            niche: Option<NicheInfo>,
+            end_padding: u64,
+            variants: Vec<VariantJson>,
+        }
+
+        #[derive(serde::Serialize)]
+        struct VariantJson {
+            name: Option<String>,
+            size_kind: &'static str,
+            size: u64,
+            align: u64,
+            fields: Vec<FieldJson>,
+        }
+
+        #[derive(serde::Serialize)]
+        struct FieldJson {
+            kind: String,
+            name: String,
+            type_name: Option<String>,
+            offset: u64,
+            size: u64,
+            align: u64,
+            /// Padding between the end of the previous field (or the discriminant)
+            /// and this field.
+            padding_before: u64,
+        }
+
+        let type_sizes = self.type_sizes.borrow();
+        // We will soon sort, so the initial order does not matter.
+        #[allow(rustc::potential_query_instability)]
+        let mut sorted: Vec<_> = type_sizes.iter().collect();
+        sorted.sort_by_key(|info| (cmp::Reverse(info.overall_size), &info.type_description));
+
+        let types: Vec<_> = sorted
+            .into_iter()
+            .map(|info| {
+                let discr_size = info.opt_discr_size.unwrap_or(0);
+                let mut max_variant_size = discr_size;
+                let variants = info
+                    .variants
+                    .iter()
+                    .map(|variant| {
+                        max_variant_size = cmp::max(max_variant_size, variant.size);
+                        let mut min_offset = discr_size;
+                        // Same field order as `print_type_sizes`, see there.
+                        let mut fields = variant.fields.clone();
+                        fields.sort_by_key(|f| (f.offset, f.size));
+                        let fields = fields
+                            .into_iter()
+                            .map(|field| {
+                                let padding_before = field.offset.saturating_sub(min_offset);
+                                min_offset = field.offset + field.size;
+                                FieldJson {
+                                    kind: field.kind.to_string(),
+                                    name: field.name.to_string(),
+                                    type_name: field.type_name.map(|t| t.to_string()),
+                                    offset: field.offset,
+                                    size: field.size,
+                                    align: field.align,
+                                    padding_before,
+                                }
+                            })
+                            .collect();
+                        VariantJson {
+                            name: variant.name.map(|n| n.to_string()),
+                            size_kind: match variant.kind {
+                                SizeKind::Exact => "exact",
+                                SizeKind::Min => "min",
+                            },
+                            size: variant.size,
+                            align: variant.align,
+                            fields,
+                        }
+                    })
+                    .collect();
+                TypeJson {
+                    kind: info.kind.as_str(),
+                    type_description: &info.type_description,
+                    def_path: &info.def_path,
+                    size: info.overall_size,
+                    align: info.align,
+                    packed: info.packed,
+                    discriminant_size: info.opt_discr_size,
+                    niche: info.niche,
+                    end_padding: info.overall_size.saturating_sub(max_variant_size),
+                    variants,
+                }
+            })
+            .collect();
+
+        let file = File::create_buffered(path)?;
+        serde_json::to_writer_pretty(file, &types)?;
+        Ok(())
+    }
+
     pub fn print_vtable_sizes(&self, crate_name: Symbol) {
         // We will soon sort, so the initial order does not matter.
         #[allow(rustc::potential_query_instability)]
@@ -147,7 +147,7 @@ pub enum InstrumentCoverage {
 }
 
 /// Individual flag values controlled by `-Z coverage-options`.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
 pub struct CoverageOptions {
     pub level: CoverageLevel,
 
@@ -158,6 +158,11 @@ pub struct CoverageOptions {
     /// For internal debugging only. If other code changes would make it hard
     /// to keep supporting this flag, remove it.
     pub no_mir_spans: bool,
+
+    /// `-Z coverage-options=filter=<path>`: Read include/exclude rules from
+    /// the given file, to select which functions are instrumented for coverage
+    /// by matching their def-paths and source files against glob patterns.
+    pub filter: Option<PathBuf>,
 }
 
 /// Controls whether branch coverage or MC/DC coverage is enabled.
@@ -631,6 +636,8 @@ pub enum ErrorOutputType {
         json_rendered: HumanReadableErrorType,
         color_config: ColorConfig,
     },
+    /// A SARIF log with a single run, for code scanning tools.
+    Sarif,
 }
 
 impl Default for ErrorOutputType {
@@ -832,6 +839,8 @@ pub enum PrintKind {
     LinkArgs,
     SplitDebuginfo,
     DeploymentTarget,
+    LintLevels,
+    IncrementalCache,
 }
 
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
@@ -1512,7 +1521,8 @@ pub fn rustc_short_optgroups() -> Vec<RustcOptGroup> {
             "[crate-name|file-names|sysroot|target-libdir|cfg|check-cfg|calling-conventions|\
              target-list|target-cpus|target-features|relocation-models|code-models|\
              tls-models|target-spec-json|all-target-specs-json|native-static-libs|\
-             stack-protector-strategies|link-args|deployment-target]",
+             stack-protector-strategies|link-args|lint-levels|incremental-cache|\
+             deployment-target]",
         ),
         opt::flagmulti_s("g", "", "Equivalent to -C debuginfo=2"),
         opt::flagmulti_s("O", "", "Equivalent to -C opt-level=2"),
@@ -1571,7 +1581,7 @@ pub fn rustc_optgroups() -> Vec<RustcOptGroup> {
             "",
             "error-format",
             "How errors and other messages are produced",
-            "human|json|short",
+            "human|json|short|sarif",
         ),
         opt::multi_s("", "json", "Configure the JSON output of the compiler", "CONFIG"),
         opt::opt_s(
@@ -1751,14 +1761,15 @@ pub fn parse_error_format(
                 ErrorOutputType::Json { pretty: true, json_rendered, color_config: json_color }
             }
             Some("short") => ErrorOutputType::HumanReadable(HumanReadableErrorType::Short, color),
+            Some("sarif") => ErrorOutputType::Sarif,
             Some(arg) => {
                 early_dcx.abort_if_error_and_set_error_format(ErrorOutputType::HumanReadable(
                     HumanReadableErrorType::Default,
                     color,
                 ));
                 early_dcx.early_fatal(format!(
-                    "argument for `--error-format` must be `human`, `json` or \
-                     `short` (instead was `{arg}`)"
+                    "argument for `--error-format` must be `human`, `json`, `short` or \
+                     `sarif` (instead was `{arg}`)"
                 ))
             }
         }
@@ -1822,6 +1833,9 @@ fn check_error_format_stability(
         {
             early_dcx.early_fatal("`--error-format=human-annotate-rs` is unstable");
         }
+        if let ErrorOutputType::Sarif = error_format {
+            early_dcx.early_fatal("`--error-format=sarif` is unstable");
+        }
     }
 }
 
@@ -1936,7 +1950,9 @@ fn collect_print_requests(
         ("crate-name", PrintKind::CrateName),
         ("deployment-target", PrintKind::DeploymentTarget),
         ("file-names", PrintKind::FileNames),
+        ("incremental-cache", PrintKind::IncrementalCache),
         ("link-args", PrintKind::LinkArgs),
+        ("lint-levels", PrintKind::LintLevels),
         ("native-static-libs", PrintKind::NativeStaticLibs),
         ("relocation-models", PrintKind::RelocationModels),
         ("split-debuginfo", PrintKind::SplitDebuginfo),
@@ -1991,6 +2007,26 @@ fn collect_print_requests(
                     );
                 }
             }
+            Some((_, PrintKind::LintLevels)) => {
+                if unstable_opts.unstable_options {
+                    PrintKind::LintLevels
+                } else {
+                    early_dcx.early_fatal(
+                        "the `-Z unstable-options` flag must also be passed to \
+                         enable the lint-levels print option",
+                    );
+                }
+            }
+            Some((_, PrintKind::IncrementalCache)) => {
+                if unstable_opts.unstable_options {
+                    PrintKind::IncrementalCache
+                } else {
+                    early_dcx.early_fatal(
+                        "the `-Z unstable-options` flag must also be passed to \
+                         enable the incremental-cache print option",
+                    );
+                }
+            }
             Some(&(_, print_kind)) => print_kind,
             None => {
                 let prints =
@@ -2465,6 +2501,10 @@ pub fn build_session_options(early_dcx: &mut EarlyDiagCtxt, matches: &getopts::M
 
     let incremental = cg.incremental.as_ref().map(PathBuf::from);
 
+    if unstable_opts.incremental_explain.is_some() && incremental.is_none() {
+        early_dcx.early_warn("-Z incremental-explain has no effect without -C incremental");
+    }
+
     let assert_incr_state = parse_assert_incr_state(early_dcx, &unstable_opts.assert_incr_state);
 
     if unstable_opts.profile && incremental.is_some() {
@@ -2751,12 +2791,14 @@ fn parse_pretty(early_dcx: &EarlyDiagCtxt, unstable_opts: &UnstableOptions) -> O
         "thir-flat" => ThirFlat,
         "mir" => Mir,
         "stable-mir" => StableMir,
+        "stable-mir-json" => StableMirJson,
         "mir-cfg" => MirCFG,
         name => early_dcx.early_fatal(format!(
             "argument to `unpretty` must be one of `normal`, `identified`, \
                             `expanded`, `expanded,identified`, `expanded,hygiene`, \
                             `ast-tree`, `ast-tree,expanded`, `hir`, `hir,identified`, \
-                            `hir,typed`, `hir-tree`, `thir-tree`, `thir-flat`, `mir`, `stable-mir`, or \
+                            `hir,typed`, `hir-tree`, `thir-tree`, `thir-flat`, `mir`, `stable-mir`, \
+                            `stable-mir-json`, or \
                             `mir-cfg`; got {name}"
         )),
     };
@@ -2935,6 +2977,8 @@ pub enum PpMode {
     MirCFG,
     /// `-Zunpretty=stable-mir`
     StableMir,
+    /// `-Zunpretty=stable-mir-json`
+    StableMirJson,
 }
 
 impl PpMode {
@@ -2952,7 +2996,8 @@ pub fn needs_ast_map(&self) -> bool {
             | ThirFlat
             | Mir
             | MirCFG
-            | StableMir => true,
+            | StableMir
+            | StableMirJson => true,
         }
     }
     pub fn needs_hir(&self) -> bool {
@@ -2960,13 +3005,18 @@ pub fn needs_hir(&self) -> bool {
         match *self {
             Source(_) | AstTree | AstTreeExpanded => false,
 
-            Hir(_) | HirTree | ThirTree | ThirFlat | Mir | MirCFG | StableMir => true,
+            Hir(_) | HirTree | ThirTree | ThirFlat | Mir | MirCFG | StableMir | StableMirJson => {
+                true
+            }
         }
     }
 
     pub fn needs_analysis(&self) -> bool {
         use PpMode::*;
-        matches!(*self, Hir(PpHirMode::Typed) | Mir | StableMir | MirCFG | ThirTree | ThirFlat)
+        matches!(
+            *self,
+            Hir(PpHirMode::Typed) | Mir | StableMir | StableMirJson | MirCFG | ThirTree | ThirFlat
+        )
     }
 }
 
@@ -3290,6 +3340,15 @@ pub fn extension(self) -> &'static str {
     }
 }
 
+/// Which format to use for `-Z print-type-sizes`
+#[derive(Clone, Copy, PartialEq, Hash, Debug)]
+pub enum PrintTypeSizesFormat {
+    /// Print human-readable text to stdout
+    Text,
+    /// Write structured JSON to `<crate>.type_sizes.json` in the output directory
+    Json,
+}
+
 /// `-Z patchable-function-entry` representation - how many nops to put before and after function
 /// entry.
 #[derive(Clone, Copy, PartialEq, Hash, Debug, Default)]
@@ -94,6 +94,12 @@ pub(crate) struct ProfileSampleUseFileDoesNotExist<'a> {
     pub(crate) path: &'a std::path::Path,
 }
 
+#[derive(Diagnostic)]
+#[diag(session_coverage_filter_file_does_not_exist)]
+pub(crate) struct CoverageFilterFileDoesNotExist<'a> {
+    pub(crate) path: &'a std::path::Path,
+}
+
 #[derive(Diagnostic)]
 #[diag(session_target_requires_unwind_tables)]
 pub(crate) struct TargetRequiresUnwindTables;
@@ -1,5 +1,6 @@
 // tidy-alphabetical-start
 #![allow(internal_features)]
+#![feature(file_buffered)]
 #![feature(iter_intersperse)]
 #![feature(let_chains)]
 #![feature(map_many_mut)]
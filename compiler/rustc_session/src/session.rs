@@ -19,6 +19,7 @@
 use rustc_errors::emitter::{DynEmitter, HumanEmitter, HumanReadableErrorType, stderr_destination};
 use rustc_errors::json::JsonEmitter;
 use rustc_errors::registry::Registry;
+use rustc_errors::sarif::{SarifEmitter, SharedSarifLog};
 use rustc_errors::{
     Diag, DiagCtxt, DiagCtxtHandle, DiagMessage, Diagnostic, ErrorGuaranteed, FatalAbort,
     FluentBundle, LazyFallbackBundle, TerminalUrl, fallback_fluent_bundle,
@@ -35,7 +36,7 @@
 };
 
 use crate::code_stats::CodeStats;
-pub use crate::code_stats::{DataTypeKind, FieldInfo, FieldKind, SizeKind, VariantInfo};
+pub use crate::code_stats::{DataTypeKind, FieldInfo, FieldKind, NicheInfo, SizeKind, VariantInfo};
 use crate::config::{
     self, CoverageLevel, CrateType, DebugInfo, ErrorOutputType, FunctionReturn, Input,
     InstrumentCoverage, OptLevel, OutFileName, OutputType, RemapPathScopeComponents,
@@ -367,6 +368,11 @@ pub fn coverage_no_mir_spans(&self) -> bool {
         self.opts.unstable_opts.coverage_options.no_mir_spans
     }
 
+    /// The file passed to `-Zcoverage-options=filter=<path>`, if any.
+    pub fn coverage_filter_path(&self) -> Option<&Path> {
+        self.opts.unstable_opts.coverage_options.filter.as_deref()
+    }
+
     pub fn is_sanitizer_cfi_enabled(&self) -> bool {
         self.opts.unstable_opts.sanitizer.contains(SanitizerSet::CFI)
     }
@@ -922,6 +928,7 @@ fn default_emitter(
     source_map: Lrc<SourceMap>,
     bundle: Option<Lrc<FluentBundle>>,
     fallback_bundle: LazyFallbackBundle,
+    sarif_log: &SharedSarifLog,
 ) -> Box<DynEmitter> {
     let macro_backtrace = sopts.unstable_opts.macro_backtrace;
     let track_diagnostics = sopts.unstable_opts.track_diagnostics;
@@ -987,6 +994,16 @@ fn default_emitter(
             .track_diagnostics(track_diagnostics)
             .terminal_url(terminal_url),
         ),
+        config::ErrorOutputType::Sarif => Box::new(
+            SarifEmitter::new(
+                Box::new(io::BufWriter::new(io::stderr())),
+                source_map,
+                fallback_bundle,
+            )
+            .registry(Some(registry))
+            .fluent_bundle(bundle)
+            .shared_log(sarif_log),
+        ),
     }
 }
 
@@ -1032,7 +1049,14 @@ pub fn build_session(
         sopts.unstable_opts.translate_directionality_markers,
     );
     let source_map = rustc_span::source_map::get_source_map().unwrap();
-    let emitter = default_emitter(&sopts, registry, source_map.clone(), bundle, fallback_bundle);
+    let emitter = default_emitter(
+        &sopts,
+        registry,
+        source_map.clone(),
+        bundle,
+        fallback_bundle,
+        &early_dcx.sarif_log,
+    );
 
     let mut dcx =
         DiagCtxt::new(emitter).with_flags(sopts.unstable_opts.dcx_flags(can_emit_warnings));
@@ -1168,6 +1192,13 @@ fn validate_commandline_args_with_session_available(sess: &Session) {
         }
     }
 
+    // Likewise, a missing coverage filter should not silently instrument everything.
+    if let Some(path) = sess.coverage_filter_path() {
+        if !path.exists() {
+            sess.dcx().emit_err(errors::CoverageFilterFileDoesNotExist { path });
+        }
+    }
+
     // Unwind tables cannot be disabled if the target requires them.
     if let Some(include_uwtables) = sess.opts.cg.force_unwind_tables {
         if sess.target.requires_uwtable && !include_uwtables {
@@ -1383,12 +1414,25 @@ enum IncrCompSession {
 /// A wrapper around an [`DiagCtxt`] that is used for early error emissions.
 pub struct EarlyDiagCtxt {
     dcx: DiagCtxt,
+    /// The log that `--error-format=sarif` adds to, which is shared with the session.
+    sarif_log: SharedSarifLog,
 }
 
 impl EarlyDiagCtxt {
     pub fn new(output: ErrorOutputType) -> Self {
-        let emitter = mk_emitter(output);
-        Self { dcx: DiagCtxt::new(emitter) }
+        Self::with_sarif_log(output, SharedSarifLog::default())
+    }
+
+    /// Creates a context that adds to `sarif_log` with `--error-format=sarif`, so that its
+    /// diagnostics end up in the same log as the ones of another context, see
+    /// [`EarlyDiagCtxt::sarif_log`].
+    pub fn with_sarif_log(output: ErrorOutputType, sarif_log: SharedSarifLog) -> Self {
+        let emitter = mk_emitter(output, &sarif_log);
+        Self { dcx: DiagCtxt::new(emitter), sarif_log }
+    }
+
+    pub fn sarif_log(&self) -> &SharedSarifLog {
+        &self.sarif_log
     }
 
     /// Swap out the underlying dcx once we acquire the user's preference on error emission
@@ -1397,7 +1441,7 @@ pub fn new(output: ErrorOutputType) -> Self {
     pub fn abort_if_error_and_set_error_format(&mut self, output: ErrorOutputType) {
         self.dcx.handle().abort_if_errors();
 
-        let emitter = mk_emitter(output);
+        let emitter = mk_emitter(output, &self.sarif_log);
         self.dcx = DiagCtxt::new(emitter);
     }
 
@@ -1445,7 +1489,7 @@ pub fn early_struct_warn(&self, msg: impl Into<DiagMessage>) -> Diag<'_, ()> {
     }
 }
 
-fn mk_emitter(output: ErrorOutputType) -> Box<DynEmitter> {
+fn mk_emitter(output: ErrorOutputType, sarif_log: &SharedSarifLog) -> Box<DynEmitter> {
     // FIXME(#100717): early errors aren't translated at the moment, so this is fine, but it will
     // need to reference every crate that might emit an early error for translation to work.
     let fallback_bundle =
@@ -1468,6 +1512,15 @@ fn mk_emitter(output: ErrorOutputType) -> Box<DynEmitter> {
                 color_config,
             ))
         }
+        config::ErrorOutputType::Sarif => Box::new(
+            SarifEmitter::new(
+                Box::new(io::BufWriter::new(io::stderr())),
+                Lrc::new(SourceMap::new(FilePathMapping::empty())),
+                fallback_bundle,
+            )
+            .emit_empty_log(false)
+            .shared_log(sarif_log),
+        ),
     };
     emitter
 }
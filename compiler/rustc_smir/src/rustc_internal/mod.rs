@@ -13,6 +13,9 @@
 use rustc_middle::mir::interpret::AllocId;
 use rustc_middle::ty;
 use rustc_middle::ty::TyCtxt;
+use rustc_middle::util::Providers;
+use rustc_session::Session;
+use rustc_session::parse::ParseSess;
 use rustc_span::Span;
 use rustc_span::def_id::{CrateNum, DefId};
 use scoped_tls::scoped_thread_local;
@@ -20,6 +23,7 @@
 use stable_mir::abi::Layout;
 use stable_mir::ty::IndexedVal;
 
+use crate::rustc_smir::borrowck;
 use crate::rustc_smir::context::TablesWrapper;
 use crate::rustc_smir::{Stable, Tables};
 
@@ -219,10 +223,31 @@ pub fn run<F, T>(tcx: TyCtxt<'_>, f: F) -> Result<T, Error>
         ty_consts: IndexMap::default(),
         mir_consts: IndexMap::default(),
         layouts: IndexMap::default(),
+        borrowck_facts: borrowck::take_recorded_facts(tcx),
     }));
     stable_mir::compiler_interface::run(&tables, || init(&tables, f))
 }
 
+/// Wraps the `override_queries` and `psess_created` callbacks of the compiler configuration,
+/// so that the compiler records the borrow-checker facts of the local bodies selected by
+/// `filter`. Used by [run_driver!].
+#[doc(hidden)]
+pub fn record_borrowck_facts(
+    filter: fn(&str) -> bool,
+    override_queries: Option<fn(&Session, &mut Providers)>,
+    psess_created: Option<Box<dyn FnOnce(&mut ParseSess) + Send>>,
+) -> (fn(&Session, &mut Providers), Box<dyn FnOnce(&mut ParseSess) + Send>) {
+    // The parse session is created on the thread running the compiler, before the queries
+    // are overridden.
+    let psess_created: Box<dyn FnOnce(&mut ParseSess) + Send> = Box::new(move |psess| {
+        borrowck::start_recording(filter, override_queries);
+        if let Some(psess_created) = psess_created {
+            psess_created(psess);
+        }
+    });
+    (borrowck::override_queries, psess_created)
+}
+
 /// Instantiate and run the compiler with the provided arguments and callback.
 ///
 /// The callback will be invoked after the compiler ran all its analyses, but before code generation.
@@ -269,13 +294,26 @@ pub fn run<F, T>(tcx: TyCtxt<'_>, f: F) -> Result<T, Error>
 /// #   assert_eq!(result, Err(CompilerError::Skipped))
 /// # }
 /// ```
+///
+/// Either format can be followed by `borrowck_facts = filter`, where `filter` is a
+/// `fn(&str) -> bool`. The borrow-checker facts of the local bodies whose name, as returned by
+/// [`stable_mir::CrateDef::name`], is accepted by `filter` are then recorded while the compiler
+/// borrow-checks them, and can be retrieved with [`stable_mir::CrateItem::borrowck_facts`].
+/// Facts are not recorded for bodies borrow-checked on another thread by the parallel
+/// frontend, nor for bodies whose borrow-checking result was loaded from the incremental cache.
 #[macro_export]
 macro_rules! run {
+    ($args:expr, $callback_fn:ident, borrowck_facts = $filter:expr) => {
+        run_driver!($args, || $callback_fn(), Some($filter))
+    };
+    ($args:expr, $callback:expr, borrowck_facts = $filter:expr) => {
+        run_driver!($args, $callback, Some($filter))
+    };
     ($args:expr, $callback_fn:ident) => {
-        run_driver!($args, || $callback_fn())
+        run_driver!($args, || $callback_fn(), None)
     };
     ($args:expr, $callback:expr) => {
-        run_driver!($args, $callback)
+        run_driver!($args, $callback, None)
     };
 }
 
@@ -285,11 +323,17 @@ macro_rules! run {
 /// which can be used to invoke internal APIs.
 #[macro_export]
 macro_rules! run_with_tcx {
+    ($args:expr, $callback_fn:ident, borrowck_facts = $filter:expr) => {
+        run_driver!($args, |tcx| $callback_fn(tcx), Some($filter), with_tcx)
+    };
+    ($args:expr, $callback:expr, borrowck_facts = $filter:expr) => {
+        run_driver!($args, $callback, Some($filter), with_tcx)
+    };
     ($args:expr, $callback_fn:ident) => {
-        run_driver!($args, |tcx| $callback_fn(tcx), with_tcx)
+        run_driver!($args, |tcx| $callback_fn(tcx), None, with_tcx)
     };
     ($args:expr, $callback:expr) => {
-        run_driver!($args, $callback, with_tcx)
+        run_driver!($args, $callback, None, with_tcx)
     };
 }
 
@@ -307,11 +351,12 @@ macro_rules! optional {
 /// This macro implements the instantiation of a StableMIR driver, and it will invoke
 /// the given callback after the compiler analyses.
 ///
-/// The third argument determines whether the callback requires `tcx` as an argument.
+/// The third argument is the filter of the bodies whose borrow-checker facts are recorded,
+/// if any. The fourth argument determines whether the callback requires `tcx` as an argument.
 #[macro_export]
 #[doc(hidden)]
 macro_rules! run_driver {
-    ($args:expr, $callback:expr $(, $with_tcx:ident)?) => {{
+    ($args:expr, $callback:expr, $borrowck_facts:expr $(, $with_tcx:ident)?) => {{
         use rustc_driver::{Callbacks, Compilation, RunCompiler};
         use rustc_interface::{interface, Queries};
         use stable_mir::CompilerError;
@@ -325,6 +370,7 @@ pub struct StableMir<B = (), C = (), F = fn($(optional!($with_tcx TyCtxt))?) ->
         {
             args: Vec<String>,
             callback: Option<F>,
+            borrowck_facts: Option<fn(&str) -> bool>,
             result: Option<ControlFlow<B, C>>,
         }
 
@@ -335,8 +381,12 @@ impl<B, C, F> StableMir<B, C, F>
             F: FnOnce($(optional!($with_tcx TyCtxt))?) -> ControlFlow<B, C> + Send,
         {
             /// Creates a new `StableMir` instance, with given test_function and arguments.
-            pub fn new(args: Vec<String>, callback: F) -> Self {
-                StableMir { args, callback: Some(callback), result: None }
+            pub fn new(
+                args: Vec<String>,
+                callback: F,
+                borrowck_facts: Option<fn(&str) -> bool>,
+            ) -> Self {
+                StableMir { args, callback: Some(callback), borrowck_facts, result: None }
             }
 
             /// Runs the compiler against given target and tests it with `test_function`
@@ -368,6 +418,18 @@ impl<B, C, F> Callbacks for StableMir<B, C, F>
             C: Send,
             F: FnOnce($(optional!($with_tcx TyCtxt))?) -> ControlFlow<B, C> + Send,
         {
+            fn config(&mut self, config: &mut interface::Config) {
+                if let Some(filter) = self.borrowck_facts {
+                    let (override_queries, psess_created) = rustc_internal::record_borrowck_facts(
+                        filter,
+                        config.override_queries.take(),
+                        config.psess_created.take(),
+                    );
+                    config.override_queries = Some(override_queries);
+                    config.psess_created = Some(psess_created);
+                }
+            }
+
             /// Called after analysis. Return value instructs the compiler whether to
             /// continue the compilation afterwards (defaults to `Compilation::Continue`)
             fn after_analysis<'tcx>(
@@ -393,7 +455,7 @@ fn after_analysis<'tcx>(
             }
         }
 
-        StableMir::new($args, $callback).run()
+        StableMir::new($args, $callback, $borrowck_facts).run()
     }};
 }
 
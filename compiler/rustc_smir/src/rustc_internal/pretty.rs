@@ -1,8 +1,17 @@
 use std::io;
+use std::ops::ControlFlow;
 
+use rustc_data_structures::fx::{FxIndexMap, FxIndexSet};
 use rustc_middle::ty::TyCtxt;
+use serde::Serialize;
+use stable_mir::mir::mono::{Instance, MonoItem};
+use stable_mir::mir::visit::Location;
+use stable_mir::mir::{Body, MirVisitor};
+use stable_mir::ty::{LineInfo, RigidTy, Span, Ty, TyKind};
+use stable_mir::visitor::{Visitable, Visitor};
+use stable_mir::{CrateDef, CrateItem, DefId, Filename, ItemKind, Symbol};
 
-use super::run;
+use super::{run, stable};
 
 pub fn write_smir_pretty<'tcx, W: io::Write>(tcx: TyCtxt<'tcx>, w: &mut W) -> io::Result<()> {
     writeln!(
@@ -19,3 +28,147 @@ pub fn write_smir_pretty<'tcx, W: io::Write>(tcx: TyCtxt<'tcx>, w: &mut W) -> io
     });
     Ok(())
 }
+
+/// Write the stable MIR of all local items and of all mono items as JSON.
+///
+/// The mono items are the ones codegen would produce for this crate, so they include
+/// instantiations of upstream generic items, shims and drop glue, i.e. every body reachable
+/// from the crate. Types, spans and definitions are serialized as indices. The `types`, `spans`
+/// and `defs` tables of the output resolve every index reachable from the bodies, so the output
+/// can be analyzed without a running compiler.
+pub fn write_smir_json<'tcx, W: io::Write>(tcx: TyCtxt<'tcx>, w: &mut W) -> io::Result<()> {
+    let krate = run(tcx, || {
+        let mut collector = Collector::default();
+        let items = stable_mir::all_local_items()
+            .into_iter()
+            .map(|item| {
+                let body = item.body();
+                collector.visit_body(&body);
+                collector.add_def(item.0, item.name());
+                collector.add_span(item.span());
+                ItemJson { item, name: item.name(), kind: item.kind(), body }
+            })
+            .collect();
+        let (_, units) = tcx.collect_and_partition_mono_items(());
+        // Inlined items are part of every codegen unit that uses them.
+        let mono_items: FxIndexSet<_> = units.iter().flat_map(|unit| unit.items().keys()).collect();
+        let mono_items = mono_items
+            .into_iter()
+            .map(|item| {
+                let item = stable(*item);
+                let instance = match &item {
+                    MonoItem::Fn(instance) => Some(*instance),
+                    MonoItem::Static(def) => Some(Instance::from(*def)),
+                    MonoItem::GlobalAsm(_) => None,
+                };
+                let name = instance.map(|instance| instance.name());
+                let body = instance.and_then(|instance| instance.body());
+                if let Some(body) = &body {
+                    collector.visit_body(body);
+                }
+                MonoItemJson { item, name, body }
+            })
+            .collect();
+        CrateJson {
+            name: stable_mir::local_crate().name,
+            items,
+            mono_items,
+            types: collector.types.into_iter().collect(),
+            spans: collector.spans.into_iter().collect(),
+            defs: collector.defs.into_iter().collect(),
+        }
+    })
+    .map_err(|err| io::Error::other(err.to_string()))?;
+    serde_json::to_writer(w, &krate)?;
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct CrateJson {
+    name: Symbol,
+    items: Vec<ItemJson>,
+    mono_items: Vec<MonoItemJson>,
+    types: Vec<(Ty, TyKind)>,
+    spans: Vec<(Span, SpanJson)>,
+    defs: Vec<(DefId, Symbol)>,
+}
+
+#[derive(Serialize)]
+struct ItemJson {
+    item: CrateItem,
+    name: Symbol,
+    kind: ItemKind,
+    body: Body,
+}
+
+#[derive(Serialize)]
+struct MonoItemJson {
+    item: MonoItem,
+    /// The name of the instance, with its generic arguments.
+    name: Option<Symbol>,
+    body: Option<Body>,
+}
+
+#[derive(Serialize)]
+struct SpanJson {
+    file: Filename,
+    lines: LineInfo,
+}
+
+/// Collects the types, spans and definitions referenced by the exported bodies.
+#[derive(Default)]
+struct Collector {
+    types: FxIndexMap<Ty, TyKind>,
+    spans: FxIndexMap<Span, SpanJson>,
+    defs: FxIndexMap<DefId, Symbol>,
+}
+
+impl Collector {
+    fn add_def(&mut self, def_id: DefId, name: Symbol) {
+        self.defs.entry(def_id).or_insert(name);
+    }
+
+    fn add_span(&mut self, span: Span) {
+        self.spans
+            .entry(span)
+            .or_insert_with(|| SpanJson { file: span.get_filename(), lines: span.get_lines() });
+    }
+}
+
+impl MirVisitor for Collector {
+    fn visit_ty(&mut self, ty: &Ty, _location: Location) {
+        let _ = ty.visit(&mut TyCollector(self));
+    }
+
+    fn visit_span(&mut self, span: &Span) {
+        self.add_span(*span);
+    }
+}
+
+/// Walks a type and everything nested in it.
+struct TyCollector<'a>(&'a mut Collector);
+
+impl Visitor for TyCollector<'_> {
+    type Break = ();
+
+    fn visit_ty(&mut self, ty: &Ty) -> ControlFlow<()> {
+        if self.0.types.contains_key(ty) {
+            return ControlFlow::Continue(());
+        }
+        let kind = ty.kind();
+        let def_id = match kind.rigid() {
+            Some(RigidTy::Adt(def, _)) => Some(def.def_id()),
+            Some(RigidTy::Foreign(def)) => Some(def.def_id()),
+            Some(RigidTy::FnDef(def, _)) => Some(def.def_id()),
+            Some(RigidTy::Closure(def, _)) => Some(def.def_id()),
+            Some(RigidTy::Coroutine(def, ..)) => Some(def.def_id()),
+            Some(RigidTy::CoroutineWitness(def, _)) => Some(def.def_id()),
+            _ => None,
+        };
+        if let Some(def_id) = def_id {
+            self.0.add_def(def_id, def_id.name());
+        }
+        self.0.types.insert(*ty, kind);
+        ty.super_visit(self)
+    }
+}
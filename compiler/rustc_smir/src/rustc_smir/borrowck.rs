@@ -0,0 +1,255 @@
+//! Recording and conversion of borrow-checker facts.
+//!
+//! The body that was borrow-checked is stolen before the StableMIR callback runs, so the
+//! facts must be captured while borrow-checking. When borrow-checker facts are requested,
+//! the StableMIR driver overrides the `mir_borrowck` query, which records the facts of the
+//! requested bodies on the thread running the compiler. [`crate::rustc_internal::run`] then
+//! moves them into the [`Tables`], which drop them once the callback returns.
+
+use std::cell::RefCell;
+use std::mem::{self, ManuallyDrop};
+
+use rustc_borrowck::borrow_set::TwoPhaseActivation;
+use rustc_borrowck::consumers::{
+    self, BodyWithBorrowckFacts, ConsumerOptions, RichLocation,
+    calculate_borrows_out_of_scope_at_location,
+};
+use rustc_data_structures::fx::FxHashMap;
+use rustc_hir::def_id::LocalDefId;
+use rustc_index::Idx;
+use rustc_middle::query::queries::mir_borrowck::ProvidedValue;
+use rustc_middle::ty::TyCtxt;
+use rustc_middle::ty::print::with_no_trimmed_paths;
+use rustc_middle::util::Providers;
+use rustc_mir_dataflow::move_paths::MoveData;
+use rustc_session::Session;
+use stable_mir::Error;
+use stable_mir::mir::borrowck::{
+    BorrowckFacts, Loan, MovePath, OutlivesConstraint, Point, PointKind, PoloniusFacts,
+};
+
+use crate::rustc_smir::{Stable, Tables};
+
+/// The state of the recording on the thread running the compiler. Query providers are plain
+/// function pointers, so this is how they get to it.
+struct Recorder {
+    /// Selects the bodies whose facts are recorded, by the name returned by
+    /// [`stable_mir::CrateDef::name`].
+    filter: fn(&str) -> bool,
+    /// The `override_queries` the compiler was configured with before facts were requested.
+    override_queries: Option<fn(&Session, &mut Providers)>,
+    /// The `mir_borrowck` provider that was replaced by [`mir_borrowck`].
+    mir_borrowck: Option<fn(TyCtxt<'_>, LocalDefId) -> ProvidedValue<'_>>,
+    /// The recorded facts, with the lifetime of the type context erased. Facts that are never
+    /// taken are leaked, as the type context is gone by the time the thread exits.
+    facts: FxHashMap<LocalDefId, ManuallyDrop<BodyWithBorrowckFacts<'static>>>,
+}
+
+thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+/// Starts recording the facts of the bodies selected by `filter` on this thread. Must be
+/// called on the thread that creates the type context, before it is created.
+pub(crate) fn start_recording(
+    filter: fn(&str) -> bool,
+    override_queries: Option<fn(&Session, &mut Providers)>,
+) {
+    RECORDER.set(Some(Recorder {
+        filter,
+        override_queries,
+        mir_borrowck: None,
+        facts: FxHashMap::default(),
+    }));
+}
+
+pub(crate) fn override_queries(session: &Session, providers: &mut Providers) {
+    let Some(override_queries) = RECORDER
+        .with_borrow(|recorder| recorder.as_ref().map(|recorder| recorder.override_queries))
+    else {
+        return;
+    };
+    if let Some(override_queries) = override_queries {
+        override_queries(session, providers);
+    }
+    let replaced = mem::replace(&mut providers.mir_borrowck, mir_borrowck);
+    RECORDER.with_borrow_mut(|recorder| recorder.as_mut().unwrap().mir_borrowck = Some(replaced));
+}
+
+fn mir_borrowck<'tcx>(tcx: TyCtxt<'tcx>, def: LocalDefId) -> ProvidedValue<'tcx> {
+    // Bodies borrow-checked on other threads of the parallel frontend aren't recorded.
+    let (filter, replaced) = RECORDER
+        .with_borrow(|recorder| {
+            recorder.as_ref().map(|recorder| (recorder.filter, recorder.mir_borrowck.unwrap()))
+        })
+        .unzip();
+    if !filter.is_some_and(|filter| filter(&with_no_trimmed_paths!(tcx.def_path_str(def)))) {
+        return match replaced {
+            Some(replaced) => replaced(tcx, def),
+            None => {
+                let mut providers = Providers::default();
+                rustc_borrowck::provide(&mut providers);
+                (providers.mir_borrowck)(tcx, def)
+            }
+        };
+    }
+    let (result, facts) =
+        consumers::mir_borrowck_with_facts(tcx, def, ConsumerOptions::PoloniusInputFacts);
+    if let Some(facts) = facts {
+        // SAFETY: The facts only get back out of the recorder through `take_recorded_facts`,
+        // which restores the lifetime of the type context they were recorded in.
+        let facts = unsafe { mem::transmute::<_, BodyWithBorrowckFacts<'static>>(*facts) };
+        RECORDER.with_borrow_mut(|recorder| {
+            recorder.as_mut().unwrap().facts.insert(def, ManuallyDrop::new(facts))
+        });
+    }
+    result
+}
+
+/// Takes the facts recorded on this thread so far.
+pub(crate) fn take_recorded_facts<'tcx>(
+    _tcx: TyCtxt<'tcx>,
+) -> FxHashMap<LocalDefId, BodyWithBorrowckFacts<'tcx>> {
+    let facts = RECORDER.with_borrow_mut(|recorder| {
+        recorder.as_mut().map(|recorder| mem::take(&mut recorder.facts)).unwrap_or_default()
+    });
+    facts
+        .into_iter()
+        .map(|(def, facts)| {
+            // SAFETY: A thread only runs a single compilation, so the facts were recorded in
+            // the type context of `_tcx`.
+            let facts = ManuallyDrop::into_inner(facts);
+            (def, unsafe { mem::transmute::<_, BodyWithBorrowckFacts<'tcx>>(facts) })
+        })
+        .collect()
+}
+
+pub(crate) fn borrowck_facts<'tcx>(
+    tables: &mut Tables<'tcx>,
+    def: LocalDefId,
+) -> Result<BorrowckFacts, Error> {
+    let tcx = tables.tcx;
+    let Some(facts) = tables.borrowck_facts.remove(&def) else {
+        return Err(Error::new(format!(
+            "No borrow-checker facts were recorded for `{}`",
+            tcx.def_path_str(def.to_def_id())
+        )));
+    };
+    let converted = convert_facts(tables, def, &facts);
+    tables.borrowck_facts.insert(def, facts);
+    Ok(converted)
+}
+
+fn convert_facts<'tcx>(
+    tables: &mut Tables<'tcx>,
+    def: LocalDefId,
+    facts: &BodyWithBorrowckFacts<'tcx>,
+) -> BorrowckFacts {
+    let tcx = tables.tcx;
+    let body = &facts.body;
+    // Both are always computed with `ConsumerOptions::PoloniusInputFacts`.
+    let location_table = facts.location_table.as_ref().unwrap();
+    let input = facts.input_facts.as_deref().unwrap();
+
+    let points = location_table
+        .all_points()
+        .map(|point| {
+            let (kind, location) = match location_table.to_location(point) {
+                RichLocation::Start(location) => (PointKind::Start, location),
+                RichLocation::Mid(location) => (PointKind::Mid, location),
+            };
+            Point { block: location.block.index(), statement_index: location.statement_index, kind }
+        })
+        .collect();
+
+    let loans = facts
+        .borrow_set
+        .location_map
+        .values()
+        .map(|borrow| Loan {
+            kind: borrow.kind.stable(tables),
+            region: borrow.region.index(),
+            borrowed_place: borrow.borrowed_place.stable(tables),
+            assigned_place: borrow.assigned_place.stable(tables),
+            reserve_point: location_table.mid_index(borrow.reserve_location).index(),
+            activation_point: match borrow.activation_location {
+                TwoPhaseActivation::ActivatedAt(location) => {
+                    Some(location_table.start_index(location).index())
+                }
+                TwoPhaseActivation::NotTwoPhase | TwoPhaseActivation::NotActivated => None,
+            },
+        })
+        .collect();
+
+    let move_data = MoveData::gather_moves(body, tcx, tcx.param_env(def), |_| true);
+    let move_paths = move_data
+        .move_paths
+        .iter()
+        .map(|path| MovePath {
+            place: path.place.stable(tables),
+            parent: path.parent.map(|parent| parent.index()),
+        })
+        .collect();
+
+    let outlives_constraints = facts
+        .region_inference_context
+        .outlives_constraints()
+        .map(|constraint| OutlivesConstraint {
+            sup: constraint.sup.index(),
+            sub: constraint.sub.index(),
+            span: constraint.span.stable(tables),
+        })
+        .collect();
+
+    let mut loans_out_of_scope_at: Vec<_> = calculate_borrows_out_of_scope_at_location(
+        body,
+        &facts.region_inference_context,
+        &facts.borrow_set,
+    )
+    .into_iter()
+    .flat_map(|(location, loans)| {
+        let point = location_table.start_index(location).index();
+        loans.into_iter().map(move |loan| (loan.index(), point))
+    })
+    .collect();
+    loans_out_of_scope_at.sort_unstable();
+
+    let input_facts = PoloniusFacts {
+        loan_issued_at: triples(&input.loan_issued_at),
+        universal_region: input.universal_region.iter().map(|region| region.index()).collect(),
+        cfg_edge: pairs(&input.cfg_edge),
+        loan_killed_at: pairs(&input.loan_killed_at),
+        subset_base: triples(&input.subset_base),
+        loan_invalidated_at: pairs(&input.loan_invalidated_at),
+        var_used_at: pairs(&input.var_used_at),
+        var_defined_at: pairs(&input.var_defined_at),
+        var_dropped_at: pairs(&input.var_dropped_at),
+        use_of_var_derefs_origin: pairs(&input.use_of_var_derefs_origin),
+        drop_of_var_derefs_origin: pairs(&input.drop_of_var_derefs_origin),
+        child_path: pairs(&input.child_path),
+        path_is_var: pairs(&input.path_is_var),
+        path_assigned_at_base: pairs(&input.path_assigned_at_base),
+        path_moved_at_base: pairs(&input.path_moved_at_base),
+        path_accessed_at_base: pairs(&input.path_accessed_at_base),
+        known_placeholder_subset: pairs(&input.known_placeholder_subset),
+        placeholder: pairs(&input.placeholder),
+    };
+
+    BorrowckFacts {
+        body: body.stable(tables),
+        points,
+        loans,
+        move_paths,
+        outlives_constraints,
+        loans_out_of_scope_at,
+        input_facts,
+    }
+}
+
+fn pairs<A: Idx, B: Idx>(facts: &[(A, B)]) -> Vec<(usize, usize)> {
+    facts.iter().map(|&(a, b)| (a.index(), b.index())).collect()
+}
+
+fn triples<A: Idx, B: Idx, C: Idx>(facts: &[(A, B, C)]) -> Vec<(usize, usize, usize)> {
+    facts.iter().map(|&(a, b, c)| (a.index(), b.index(), c.index())).collect()
+}
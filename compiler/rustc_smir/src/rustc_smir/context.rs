@@ -22,6 +22,7 @@
 use stable_mir::abi::{FnAbi, Layout, LayoutShape};
 use stable_mir::compiler_interface::Context;
 use stable_mir::mir::alloc::GlobalAlloc;
+use stable_mir::mir::borrowck::BorrowckFacts;
 use stable_mir::mir::mono::{InstanceDef, StaticDef};
 use stable_mir::mir::{BinOp, Body, Place, UnOp};
 use stable_mir::target::{MachineInfo, MachineSize};
@@ -34,7 +35,7 @@
 
 use crate::rustc_internal::RustcInternal;
 use crate::rustc_smir::builder::BodyBuilder;
-use crate::rustc_smir::{Stable, Tables, alloc, new_item_kind, smir_crate};
+use crate::rustc_smir::{Stable, Tables, alloc, borrowck, new_item_kind, replace_body, smir_crate};
 
 impl<'tcx> Context for TablesWrapper<'tcx> {
     fn target_info(&self) -> MachineInfo {
@@ -71,6 +72,24 @@ fn has_body(&self, def: DefId) -> bool {
         tables.item_has_body(def_id)
     }
 
+    fn borrowck_facts(&self, item: DefId) -> Result<BorrowckFacts, Error> {
+        let mut tables = self.0.borrow_mut();
+        let def_id = tables[item];
+        let Some(local_def_id) = def_id.as_local() else {
+            return Err(Error::new(format!(
+                "Borrow-checker facts are only available for local items, found `{}`",
+                tables.tcx.def_path_str(def_id)
+            )));
+        };
+        borrowck::borrowck_facts(&mut *tables, local_def_id)
+    }
+
+    fn replace_body(&self, item: DefId, body: &Body) -> Result<(), Error> {
+        let mut tables = self.0.borrow_mut();
+        let def_id = tables[item];
+        replace_body::replace_body(&mut *tables, def_id, body)
+    }
+
     fn foreign_modules(&self, crate_num: CrateNum) -> Vec<stable_mir::ty::ForeignModuleDef> {
         let mut tables = self.0.borrow_mut();
         let tcx = tables.tcx;
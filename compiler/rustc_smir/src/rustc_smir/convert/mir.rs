@@ -670,10 +670,27 @@ fn stable(&self, tables: &mut Tables<'_>) -> Self::T {
                 destination: targets.first().map(|d| d.as_usize()),
                 unwind: unwind.stable(tables),
             },
-            mir::TerminatorKind::Yield { .. }
-            | mir::TerminatorKind::CoroutineDrop
-            | mir::TerminatorKind::FalseEdge { .. }
-            | mir::TerminatorKind::FalseUnwind { .. } => unreachable!(),
+            mir::TerminatorKind::FalseEdge { real_target, imaginary_target } => {
+                TerminatorKind::FalseEdge {
+                    real_target: real_target.as_usize(),
+                    imaginary_target: imaginary_target.as_usize(),
+                }
+            }
+            mir::TerminatorKind::FalseUnwind { real_target, unwind } => {
+                TerminatorKind::FalseUnwind {
+                    real_target: real_target.as_usize(),
+                    unwind: unwind.stable(tables),
+                }
+            }
+            mir::TerminatorKind::Yield { value, resume, resume_arg, drop } => {
+                TerminatorKind::Yield {
+                    value: value.stable(tables),
+                    resume: resume.as_usize(),
+                    resume_arg: resume_arg.stable(tables),
+                    drop: drop.map(|drop| drop.as_usize()),
+                }
+            }
+            mir::TerminatorKind::CoroutineDrop => TerminatorKind::CoroutineDrop,
         }
     }
 }
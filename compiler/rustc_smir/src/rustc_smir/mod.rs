@@ -9,11 +9,13 @@
 
 use std::ops::RangeInclusive;
 
+use rustc_borrowck::consumers::BodyWithBorrowckFacts;
+use rustc_data_structures::fx::FxHashMap;
 use rustc_hir::def::DefKind;
 use rustc_middle::mir;
 use rustc_middle::mir::interpret::AllocId;
 use rustc_middle::ty::{self, Instance, Ty, TyCtxt};
-use rustc_span::def_id::{CrateNum, DefId, LOCAL_CRATE};
+use rustc_span::def_id::{CrateNum, DefId, LOCAL_CRATE, LocalDefId};
 use stable_mir::abi::Layout;
 use stable_mir::mir::mono::InstanceDef;
 use stable_mir::ty::{MirConstId, Span, TyConstId};
@@ -23,9 +25,11 @@
 use crate::rustc_internal::IndexMap;
 
 mod alloc;
+pub(crate) mod borrowck;
 mod builder;
 pub(crate) mod context;
 mod convert;
+pub(crate) mod replace_body;
 
 pub struct Tables<'tcx> {
     pub(crate) tcx: TyCtxt<'tcx>,
@@ -37,6 +41,8 @@ pub struct Tables<'tcx> {
     pub(crate) ty_consts: IndexMap<ty::Const<'tcx>, TyConstId>,
     pub(crate) mir_consts: IndexMap<mir::Const<'tcx>, MirConstId>,
     pub(crate) layouts: IndexMap<rustc_target::abi::Layout<'tcx>, Layout>,
+    /// Recorded by the `mir_borrowck` override, see [`borrowck`].
+    pub(crate) borrowck_facts: FxHashMap<LocalDefId, BodyWithBorrowckFacts<'tcx>>,
 }
 
 impl<'tcx> Tables<'tcx> {
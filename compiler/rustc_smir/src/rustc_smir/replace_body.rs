@@ -0,0 +1,363 @@
+//! Replacement of the optimized MIR of local items.
+//!
+//! A replacement body is converted back to rustc MIR, validated, and then registered with
+//! `TyCtxt::replace_optimized_mir`, so that `TyCtxt::instance_mir` returns it in place of
+//! `optimized_mir` for the rest of the compilation session.
+
+use rustc_hir::def::DefKind;
+use rustc_index::IndexVec;
+use rustc_middle::mir;
+use rustc_middle::mir::visit::{PlaceContext, Visitor};
+use rustc_span::def_id::DefId;
+use rustc_span::source_map::Spanned;
+use stable_mir::Error;
+use stable_mir::mir::{
+    Body, Rvalue, Statement, StatementKind, Terminator, TerminatorKind, UnwindAction, VarDebugInfo,
+    VarDebugInfoContents,
+};
+
+use crate::rustc_internal::RustcInternal;
+use crate::rustc_smir::Tables;
+
+pub(crate) fn replace_body<'tcx>(
+    tables: &mut Tables<'tcx>,
+    def_id: DefId,
+    body: &Body,
+) -> Result<(), Error> {
+    let tcx = tables.tcx;
+    let is_fn_like =
+        matches!(tcx.def_kind(def_id), DefKind::Fn | DefKind::AssocFn | DefKind::Closure);
+    if !def_id.is_local()
+        || !is_fn_like
+        || tcx.is_coroutine(def_id)
+        || !tcx.is_mir_available(def_id)
+    {
+        return Err(Error::new(format!(
+            "Only the body of local functions, methods and closures can be replaced, found `{}`",
+            tcx.def_path_str(def_id)
+        )));
+    }
+
+    if let Err(reason) = rustc_mir_transform::check_optimized_mir_replaceable(tcx) {
+        return Err(Error::new(format!(
+            "The body of `{}` cannot be replaced: {reason}",
+            tcx.def_path_str(def_id)
+        )));
+    }
+
+    let original = tcx.optimized_mir(def_id);
+    let mut new_body = internal_body(tables, original, body)?;
+    // The validator expects all indices to be in bounds.
+    let mut checker = IndexChecker { body: &new_body, failure: None };
+    checker.visit_body(&new_body);
+    if let Some(failure) = checker.failure {
+        return Err(Error::new(format!(
+            "Invalid body for `{}`:\n{failure}",
+            tcx.def_path_str(def_id)
+        )));
+    }
+    let failures = rustc_mir_transform::prepare_replaced_optimized_mir(tcx, &mut new_body);
+    if !failures.is_empty() {
+        let failures: Vec<_> =
+            failures.into_iter().map(|(location, msg)| format!("at {location:?}: {msg}")).collect();
+        return Err(Error::new(format!(
+            "Invalid body for `{}`:\n{}",
+            tcx.def_path_str(def_id),
+            failures.join("\n")
+        )));
+    }
+
+    tcx.replace_optimized_mir(def_id, new_body);
+    Ok(())
+}
+
+/// Converts `body` back to rustc MIR.
+///
+/// Stable MIR does not keep source scopes, so the scopes of the `original` body are reused.
+/// Statements and terminators are all put in the outermost scope.
+fn internal_body<'tcx>(
+    tables: &mut Tables<'tcx>,
+    original: &mir::Body<'tcx>,
+    body: &Body,
+) -> Result<mir::Body<'tcx>, Error> {
+    let tcx = tables.tcx;
+    let mut basic_blocks = IndexVec::with_capacity(body.blocks.len());
+    for (block, is_cleanup) in body.blocks.iter().zip(cleanup_blocks(body)) {
+        let statements = block
+            .statements
+            .iter()
+            .map(|statement| internal_statement(tables, statement))
+            .collect::<Result<_, _>>()?;
+        let terminator = internal_terminator(tables, &block.terminator)?;
+        basic_blocks.push(mir::BasicBlockData {
+            statements,
+            terminator: Some(terminator),
+            is_cleanup,
+        });
+    }
+
+    let local_decls = body
+        .locals()
+        .iter()
+        .map(|decl| {
+            let mut local_decl =
+                mir::LocalDecl::new(decl.ty.internal(tables, tcx), decl.span.internal(tables, tcx));
+            local_decl.mutability = decl.mutability.internal(tables, tcx);
+            local_decl
+        })
+        .collect();
+    let var_debug_info =
+        body.var_debug_info.iter().map(|info| internal_var_debug_info(tables, info)).collect();
+
+    let mut new_body = mir::Body::new(
+        original.source,
+        basic_blocks,
+        original.source_scopes.clone(),
+        local_decls,
+        IndexVec::new(),
+        body.arg_locals().len(),
+        var_debug_info,
+        body.span.internal(tables, tcx),
+        None,
+        None,
+    );
+    new_body.spread_arg = body.spread_arg().map(mir::Local::from_usize);
+    Ok(new_body)
+}
+
+/// Finds the first local, basic block or source scope a body refers to that doesn't exist.
+struct IndexChecker<'a, 'tcx> {
+    body: &'a mir::Body<'tcx>,
+    failure: Option<String>,
+}
+
+impl<'tcx> Visitor<'tcx> for IndexChecker<'_, 'tcx> {
+    fn visit_body(&mut self, body: &mir::Body<'tcx>) {
+        if let Some(local) = body.spread_arg {
+            if body.local_decls.get(local).is_none() {
+                self.failure = Some(format!("invalid spread argument {local:?}"));
+            }
+        }
+        self.super_body(body);
+    }
+
+    fn visit_local(&mut self, local: mir::Local, _context: PlaceContext, location: mir::Location) {
+        if self.body.local_decls.get(local).is_none() {
+            self.failure.get_or_insert_with(|| format!("at {location:?}: invalid local {local:?}"));
+        }
+    }
+
+    fn visit_source_scope(&mut self, scope: mir::SourceScope) {
+        if self.body.source_scopes.get(scope).is_none() {
+            self.failure.get_or_insert_with(|| format!("invalid source scope {scope:?}"));
+        }
+    }
+
+    fn visit_terminator(&mut self, terminator: &mir::Terminator<'tcx>, location: mir::Location) {
+        for target in terminator.successors() {
+            if self.body.basic_blocks.get(target).is_none() {
+                self.failure.get_or_insert_with(|| {
+                    format!("at {location:?}: invalid basic block {target:?}")
+                });
+            }
+        }
+        self.super_terminator(terminator, location);
+    }
+}
+
+/// Stable MIR does not record which blocks are cleanup blocks. These are the blocks that
+/// are reached by unwinding.
+fn cleanup_blocks(body: &Body) -> Vec<bool> {
+    let mut is_cleanup = vec![false; body.blocks.len()];
+    let mut worklist: Vec<_> = body
+        .blocks
+        .iter()
+        .filter_map(|block| match block.terminator.kind.unwind() {
+            Some(UnwindAction::Cleanup(target)) => Some(*target),
+            _ => None,
+        })
+        .collect();
+    while let Some(bb) = worklist.pop() {
+        // Jumps to invalid blocks are reported by the validator.
+        let Some(cleanup) = is_cleanup.get_mut(bb) else { continue };
+        if !*cleanup {
+            *cleanup = true;
+            worklist.extend(body.blocks[bb].terminator.successors());
+        }
+    }
+    is_cleanup
+}
+
+fn internal_statement<'tcx>(
+    tables: &mut Tables<'tcx>,
+    statement: &Statement,
+) -> Result<mir::Statement<'tcx>, Error> {
+    let tcx = tables.tcx;
+    let kind = match &statement.kind {
+        StatementKind::Assign(place, rvalue) => {
+            let rvalue = match rvalue {
+                // Only some operators have a checked form, which the infallible conversion
+                // of `Rvalue` can't report.
+                Rvalue::CheckedBinaryOp(bin_op, lhs, rhs) => {
+                    let Some(op) = bin_op.internal(tables, tcx).wrapping_to_overflowing() else {
+                        return Err(Error::new(format!(
+                            "`{bin_op:?}` cannot be used in `Rvalue::CheckedBinaryOp`"
+                        )));
+                    };
+                    mir::Rvalue::BinaryOp(
+                        op,
+                        Box::new((lhs.internal(tables, tcx), rhs.internal(tables, tcx))),
+                    )
+                }
+                rvalue => rvalue.internal(tables, tcx),
+            };
+            mir::StatementKind::Assign(Box::new((place.internal(tables, tcx), rvalue)))
+        }
+        StatementKind::SetDiscriminant { place, variant_index } => {
+            mir::StatementKind::SetDiscriminant {
+                place: Box::new(place.internal(tables, tcx)),
+                variant_index: variant_index.internal(tables, tcx),
+            }
+        }
+        StatementKind::Deinit(place) => {
+            mir::StatementKind::Deinit(Box::new(place.internal(tables, tcx)))
+        }
+        StatementKind::StorageLive(local) => {
+            mir::StatementKind::StorageLive(mir::Local::from_usize(*local))
+        }
+        StatementKind::StorageDead(local) => {
+            mir::StatementKind::StorageDead(mir::Local::from_usize(*local))
+        }
+        StatementKind::Retag(kind, place) => mir::StatementKind::Retag(
+            kind.internal(tables, tcx),
+            Box::new(place.internal(tables, tcx)),
+        ),
+        StatementKind::PlaceMention(place) => {
+            mir::StatementKind::PlaceMention(Box::new(place.internal(tables, tcx)))
+        }
+        StatementKind::Intrinsic(intrinsic) => {
+            mir::StatementKind::Intrinsic(Box::new(intrinsic.internal(tables, tcx)))
+        }
+        StatementKind::ConstEvalCounter => mir::StatementKind::ConstEvalCounter,
+        StatementKind::Nop => mir::StatementKind::Nop,
+        // These contain information that is not kept in stable MIR.
+        StatementKind::FakeRead(..)
+        | StatementKind::AscribeUserType { .. }
+        | StatementKind::Coverage(_) => {
+            return Err(Error::new(format!(
+                "Statement `{:?}` cannot be converted back to rustc MIR",
+                statement.kind
+            )));
+        }
+    };
+    Ok(mir::Statement {
+        source_info: mir::SourceInfo::outermost(statement.span.internal(tables, tcx)),
+        kind,
+    })
+}
+
+fn internal_terminator<'tcx>(
+    tables: &mut Tables<'tcx>,
+    terminator: &Terminator,
+) -> Result<mir::Terminator<'tcx>, Error> {
+    let tcx = tables.tcx;
+    let span = terminator.span.internal(tables, tcx);
+    let kind = match &terminator.kind {
+        TerminatorKind::Goto { target } => {
+            mir::TerminatorKind::Goto { target: mir::BasicBlock::from_usize(*target) }
+        }
+        TerminatorKind::SwitchInt { discr, targets } => mir::TerminatorKind::SwitchInt {
+            discr: discr.internal(tables, tcx),
+            targets: targets.internal(tables, tcx),
+        },
+        TerminatorKind::Resume => mir::TerminatorKind::UnwindResume,
+        // The reason is not kept in stable MIR. It only affects the panic message.
+        TerminatorKind::Abort => {
+            mir::TerminatorKind::UnwindTerminate(mir::UnwindTerminateReason::InCleanup)
+        }
+        TerminatorKind::Return => mir::TerminatorKind::Return,
+        TerminatorKind::Unreachable => mir::TerminatorKind::Unreachable,
+        TerminatorKind::Drop { place, target, unwind } => mir::TerminatorKind::Drop {
+            place: place.internal(tables, tcx),
+            target: mir::BasicBlock::from_usize(*target),
+            unwind: unwind.internal(tables, tcx),
+            replace: false,
+        },
+        TerminatorKind::Call { func, args, destination, target, unwind } => {
+            mir::TerminatorKind::Call {
+                func: func.internal(tables, tcx),
+                args: args
+                    .iter()
+                    .map(|arg| Spanned { node: arg.internal(tables, tcx), span })
+                    .collect(),
+                destination: destination.internal(tables, tcx),
+                target: target.map(mir::BasicBlock::from_usize),
+                unwind: unwind.internal(tables, tcx),
+                call_source: mir::CallSource::Normal,
+                fn_span: span,
+            }
+        }
+        TerminatorKind::Assert { cond, expected, msg, target, unwind } => {
+            mir::TerminatorKind::Assert {
+                cond: cond.internal(tables, tcx),
+                expected: *expected,
+                msg: Box::new(msg.internal(tables, tcx)),
+                target: mir::BasicBlock::from_usize(*target),
+                unwind: unwind.internal(tables, tcx),
+            }
+        }
+        TerminatorKind::FalseEdge { real_target, imaginary_target } => {
+            mir::TerminatorKind::FalseEdge {
+                real_target: mir::BasicBlock::from_usize(*real_target),
+                imaginary_target: mir::BasicBlock::from_usize(*imaginary_target),
+            }
+        }
+        TerminatorKind::FalseUnwind { real_target, unwind } => mir::TerminatorKind::FalseUnwind {
+            real_target: mir::BasicBlock::from_usize(*real_target),
+            unwind: unwind.internal(tables, tcx),
+        },
+        TerminatorKind::Yield { value, resume, resume_arg, drop } => mir::TerminatorKind::Yield {
+            value: value.internal(tables, tcx),
+            resume: mir::BasicBlock::from_usize(*resume),
+            resume_arg: resume_arg.internal(tables, tcx),
+            drop: drop.map(mir::BasicBlock::from_usize),
+        },
+        TerminatorKind::CoroutineDrop => mir::TerminatorKind::CoroutineDrop,
+        // The template and operands are only kept in a textual form.
+        TerminatorKind::InlineAsm { .. } => {
+            return Err(Error::new(
+                "Inline assembly cannot be converted back to rustc MIR".to_string(),
+            ));
+        }
+    };
+    Ok(mir::Terminator { source_info: mir::SourceInfo::outermost(span), kind })
+}
+
+fn internal_var_debug_info<'tcx>(
+    tables: &mut Tables<'tcx>,
+    info: &VarDebugInfo,
+) -> mir::VarDebugInfo<'tcx> {
+    let tcx = tables.tcx;
+    mir::VarDebugInfo {
+        name: rustc_span::Symbol::intern(&info.name),
+        source_info: mir::SourceInfo {
+            span: info.source_info.span.internal(tables, tcx),
+            scope: mir::SourceScope::from_u32(info.source_info.scope),
+        },
+        composite: info.composite.as_ref().map(|fragment| {
+            Box::new(mir::VarDebugInfoFragment {
+                ty: fragment.ty.internal(tables, tcx),
+                projection: fragment.projection.internal(tables, tcx),
+            })
+        }),
+        value: match &info.value {
+            VarDebugInfoContents::Place(place) => {
+                mir::VarDebugInfoContents::Place(place.internal(tables, tcx))
+            }
+            VarDebugInfoContents::Const(constant) => {
+                mir::VarDebugInfoContents::Const(constant.internal(tables, tcx))
+            }
+        },
+        argument_index: info.argument_index,
+    }
+}
@@ -8,6 +8,7 @@
 use crate::abi::{FnAbi, Layout, LayoutShape};
 use crate::crate_def::Attribute;
 use crate::mir::alloc::{AllocId, GlobalAlloc};
+use crate::mir::borrowck::BorrowckFacts;
 use crate::mir::mono::{Instance, InstanceDef, StaticDef};
 use crate::mir::{BinOp, Body, Place, UnOp};
 use crate::target::MachineInfo;
@@ -33,6 +34,10 @@ pub trait Context {
     fn mir_body(&self, item: DefId) -> mir::Body;
     /// Check whether the body of a function is available.
     fn has_body(&self, item: DefId) -> bool;
+    /// Retrieve the borrow-checker facts of a local function body.
+    fn borrowck_facts(&self, item: DefId) -> Result<BorrowckFacts, Error>;
+    /// Replace the body of a local function that is used for code generation.
+    fn replace_body(&self, item: DefId, body: &Body) -> Result<(), Error>;
     fn foreign_modules(&self, crate_num: CrateNum) -> Vec<ForeignModuleDef>;
     fn foreign_module(&self, mod_def: ForeignModuleDef) -> ForeignModule;
     fn foreign_items(&self, mod_def: ForeignModuleDef) -> Vec<ForeignDef>;
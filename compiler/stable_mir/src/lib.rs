@@ -20,7 +20,8 @@
 use std::fmt::Debug;
 use std::{fmt, io};
 
-use serde::Serialize;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 use crate::compiler_interface::with;
 pub use crate::crate_def::{CrateDef, CrateDefType, DefId};
@@ -74,7 +75,8 @@ fn to_index(&self) -> usize {
 pub type ImplTraitDecls = Vec<ImplDef>;
 
 /// Holds information about a crate.
-#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Crate {
     pub id: CrateNum,
     pub name: Symbol,
@@ -98,7 +100,8 @@ pub fn trait_impls(&self) -> ImplTraitDecls {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash, Serialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ItemKind {
     Fn,
     Static,
@@ -106,7 +109,8 @@ pub enum ItemKind {
     Ctor(CtorKind),
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash, Serialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum CtorKind {
     Const,
     Fn,
@@ -116,7 +120,7 @@ pub enum CtorKind {
 
 crate_def_with_ty! {
     /// Holds information about an item in a crate.
-    #[derive(Serialize)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub CrateItem;
 }
 
@@ -128,6 +132,26 @@ pub fn body(&self) -> mir::Body {
         with(|cx| cx.mir_body(self.0))
     }
 
+    /// Return the borrow-checker facts of this item's body.
+    ///
+    /// Facts are only recorded for local items, and only if they were requested when starting
+    /// the compiler with `run!` or `run_with_tcx!`.
+    pub fn borrowck_facts(&self) -> Result<mir::borrowck::BorrowckFacts, Error> {
+        with(|cx| cx.borrowck_facts(self.0))
+    }
+
+    /// Replace the body of this item, which will be used for code generation instead of
+    /// the body returned by [`CrateItem::body`]. The body is validated first, and an error
+    /// is returned if it is invalid or cannot be represented in the compiler.
+    ///
+    /// Only local functions, methods and closures can be replaced, and the replacement is
+    /// not exported in the crate metadata. Bodies cannot be replaced under incremental
+    /// compilation, when MIR inlining is enabled (see `-Zinline-mir=no`), or once code
+    /// generation has started.
+    pub fn replace_body(&self, body: &mir::Body) -> Result<(), Error> {
+        with(|cx| cx.replace_body(self.0, body))
+    }
+
     pub fn span(&self) -> Span {
         with(|cx| cx.span_of_an_item(self.0))
     }
@@ -189,7 +213,8 @@ pub fn all_trait_impls() -> ImplTraitDecls {
 }
 
 /// A type that provides internal information but that can still be used for debug purpose.
-#[derive(Clone, PartialEq, Eq, Hash, Serialize)]
+#[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Opaque(String);
 
 impl std::fmt::Display for Opaque {
@@ -0,0 +1,119 @@
+//! Borrow-checker facts of a function body.
+//!
+//! These facts follow the input format of [Polonius](https://github.com/rust-lang/polonius),
+//! together with the loans, move paths and region inference results they refer to.
+//!
+//! All facts refer to [`BorrowckFacts::body`], which is the body that was borrow-checked.
+//! It is taken before any optimization and generally differs from [`crate::CrateItem::body`].
+//! Its regions are [`RegionKind::ReVar`](crate::ty::RegionKind::ReVar).
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::mir::{BasicBlockIdx, Body, BorrowKind, Local, Place};
+use crate::ty::Span;
+
+/// A region inference variable.
+pub type RegionVid = usize;
+/// An index into [`BorrowckFacts::points`].
+pub type PointIdx = usize;
+/// An index into [`BorrowckFacts::loans`].
+pub type LoanIdx = usize;
+/// An index into [`BorrowckFacts::move_paths`].
+pub type MovePathIdx = usize;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BorrowckFacts {
+    /// The body these facts refer to.
+    pub body: Body,
+    /// All points of the control-flow graph of `body`.
+    pub points: Vec<Point>,
+    /// All borrows that occur in `body`.
+    pub loans: Vec<Loan>,
+    /// All move paths of `body`.
+    pub move_paths: Vec<MovePath>,
+    /// The outlives constraints that were used to infer the region variables.
+    pub outlives_constraints: Vec<OutlivesConstraint>,
+    /// The points at which each loan goes out of scope, as computed by region inference.
+    pub loans_out_of_scope_at: Vec<(LoanIdx, PointIdx)>,
+    /// The Polonius input facts of `body`.
+    pub input_facts: PoloniusFacts,
+}
+
+/// A point in the control-flow graph.
+///
+/// Each statement and terminator has two points: one right before it starts
+/// and one at which it takes effect.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Point {
+    pub block: BasicBlockIdx,
+    pub statement_index: usize,
+    pub kind: PointKind,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PointKind {
+    Start,
+    Mid,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Loan {
+    pub kind: BorrowKind,
+    /// The region of the resulting reference.
+    pub region: RegionVid,
+    pub borrowed_place: Place,
+    /// The place the reference is assigned to.
+    pub assigned_place: Place,
+    /// The mid point of the statement creating the borrow.
+    pub reserve_point: PointIdx,
+    /// For two-phase borrows, the start point of the statement activating the borrow.
+    pub activation_point: Option<PointIdx>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MovePath {
+    pub place: Place,
+    pub parent: Option<MovePathIdx>,
+}
+
+/// A constraint `sup: sub` between two region variables.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OutlivesConstraint {
+    pub sup: RegionVid,
+    pub sub: RegionVid,
+    pub span: Span,
+}
+
+/// The Polonius input relations.
+///
+/// See the [Polonius book](https://rust-lang.github.io/polonius/rules/relations.html)
+/// for the meaning of each relation.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PoloniusFacts {
+    pub loan_issued_at: Vec<(RegionVid, LoanIdx, PointIdx)>,
+    pub universal_region: Vec<RegionVid>,
+    pub cfg_edge: Vec<(PointIdx, PointIdx)>,
+    pub loan_killed_at: Vec<(LoanIdx, PointIdx)>,
+    pub subset_base: Vec<(RegionVid, RegionVid, PointIdx)>,
+    pub loan_invalidated_at: Vec<(PointIdx, LoanIdx)>,
+    pub var_used_at: Vec<(Local, PointIdx)>,
+    pub var_defined_at: Vec<(Local, PointIdx)>,
+    pub var_dropped_at: Vec<(Local, PointIdx)>,
+    pub use_of_var_derefs_origin: Vec<(Local, RegionVid)>,
+    pub drop_of_var_derefs_origin: Vec<(Local, RegionVid)>,
+    pub child_path: Vec<(MovePathIdx, MovePathIdx)>,
+    pub path_is_var: Vec<(MovePathIdx, Local)>,
+    pub path_assigned_at_base: Vec<(MovePathIdx, PointIdx)>,
+    pub path_moved_at_base: Vec<(MovePathIdx, PointIdx)>,
+    pub path_accessed_at_base: Vec<(MovePathIdx, PointIdx)>,
+    pub known_placeholder_subset: Vec<(RegionVid, RegionVid)>,
+    pub placeholder: Vec<(RegionVid, LoanIdx)>,
+}
@@ -1,7 +1,8 @@
 use std::fmt::{Debug, Formatter};
 use std::io;
 
-use serde::Serialize;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 use crate::abi::FnAbi;
 use crate::crate_def::CrateDef;
@@ -9,14 +10,16 @@
 use crate::ty::{Allocation, ClosureDef, ClosureKind, FnDef, GenericArgs, IndexedVal, Ty};
 use crate::{CrateItem, DefId, Error, ItemKind, Opaque, Symbol, with};
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum MonoItem {
     Fn(Instance),
     Static(StaticDef),
     GlobalAsm(Opaque),
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Serialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Instance {
     /// The type of instance.
     pub kind: InstanceKind,
@@ -25,7 +28,8 @@ pub struct Instance {
     pub def: InstanceDef,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum InstanceKind {
     /// A user defined item.
     Item,
@@ -53,6 +57,21 @@ pub fn body(&self) -> Option<Body> {
         with(|context| context.instance_body(self.def))
     }
 
+    /// Replace the body of this instance for code generation.
+    ///
+    /// Only instances of items that do not require monomorphization can be replaced, since
+    /// the replacement applies to the item. See [`CrateItem::replace_body`].
+    pub fn replace_body(&self, body: &Body) -> Result<(), Error> {
+        let item = CrateItem::try_from(*self)?;
+        if item.requires_monomorphization() {
+            return Err(Error::new(format!(
+                "Cannot replace the body of generic instance `{}`",
+                self.name()
+            )));
+        }
+        item.replace_body(body)
+    }
+
     /// Check whether this instance has a body available.
     ///
     /// For intrinsics with fallback body, this will return `true`. It is up to the user to decide
@@ -243,7 +262,8 @@ fn from(value: StaticDef) -> Self {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct InstanceDef(usize);
 
 impl CrateDef for InstanceDef {
@@ -254,7 +274,7 @@ fn def_id(&self) -> DefId {
 
 crate_def! {
     /// Holds information about a static variable definition.
-    #[derive(Serialize)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub StaticDef;
 }
 
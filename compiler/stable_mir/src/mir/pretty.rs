@@ -183,13 +183,19 @@ fn pretty_terminator_head<W: Write>(writer: &mut W, terminator: &TerminatorKind)
             write!(writer, ")")
         }
         InlineAsm { .. } => write!(writer, "{INDENT}InlineAsm"),
+        FalseEdge { .. } => write!(writer, "{INDENT}falseEdge"),
+        FalseUnwind { .. } => write!(writer, "{INDENT}falseUnwind"),
+        Yield { value, resume: _, resume_arg, drop: _ } => {
+            write!(writer, "{INDENT}{:?} = yield({})", resume_arg, pretty_operand(value))
+        }
+        CoroutineDrop => write!(writer, "{INDENT}coroutine_drop"),
     }
 }
 
 fn pretty_successor_labels(terminator: &TerminatorKind) -> Vec<String> {
     use self::TerminatorKind::*;
     match terminator {
-        Resume | Abort | Return | Unreachable => vec![],
+        Resume | Abort | Return | Unreachable | CoroutineDrop => vec![],
         Goto { .. } => vec!["".to_string()],
         SwitchInt { targets, .. } => targets
             .branches()
@@ -210,6 +216,13 @@ fn pretty_successor_labels(terminator: &TerminatorKind) -> Vec<String> {
         Assert { unwind: _, .. } => vec!["success".into()],
         InlineAsm { destination: Some(_), .. } => vec!["goto".into(), "unwind".into()],
         InlineAsm { destination: None, .. } => vec!["unwind".into()],
+        FalseEdge { .. } => vec!["real".into(), "imaginary".into()],
+        FalseUnwind { unwind: UnwindAction::Cleanup(_), .. } => {
+            vec!["real".into(), "unwind".into()]
+        }
+        FalseUnwind { unwind: _, .. } => vec!["real".into()],
+        Yield { drop: Some(_), .. } => vec!["resume".into(), "drop".into()],
+        Yield { drop: None, .. } => vec!["resume".into()],
     }
 }
 
@@ -246,7 +246,14 @@ fn super_terminator(&mut self, term: &Terminator, location: Location) {
             TerminatorKind::Goto { .. }
             | TerminatorKind::Resume
             | TerminatorKind::Abort
-            | TerminatorKind::Unreachable => {}
+            | TerminatorKind::Unreachable
+            | TerminatorKind::FalseEdge { .. }
+            | TerminatorKind::FalseUnwind { .. }
+            | TerminatorKind::CoroutineDrop => {}
+            TerminatorKind::Yield { value, resume: _, resume_arg, drop: _ } => {
+                self.visit_operand(value, location);
+                self.visit_place(resume_arg, PlaceContext::MUTATING, location);
+            }
             TerminatorKind::Assert { cond, expected: _, msg, target: _, unwind: _ } => {
                 self.visit_operand(cond, location);
                 self.visit_assert_msg(msg, location);
@@ -693,6 +693,11 @@ pub fn temp_dir() -> PathBuf {
 /// If the executable is renamed while it is running, platforms may return the
 /// path at the time it was loaded instead of the new path.
 ///
+/// On WASI, the `RUST_WASI_CURRENT_EXE` environment variable is returned if
+/// the host set it. Otherwise `argv[0]` is looked up relative to the current
+/// directory and then to each preopened directory, and an error is returned
+/// if it doesn't name a file visible to the program.
+///
 /// # Errors
 ///
 /// Acquiring the path of the current executable is a platform-specific operation
@@ -188,6 +188,13 @@ fn drop(&mut self) {
                 #[cfg(unix)]
                 crate::sys::fs::debug_assert_fd_is_open(self.fd);
 
+                // Numbers that std handed out for preview 2 resources are
+                // unknown to wasi-libc.
+                #[cfg(all(target_os = "wasi", target_env = "p2"))]
+                if crate::sys::fd::close_reserved(self.fd) {
+                    return;
+                }
+
                 let _ = libc::close(self.fd);
             }
             #[cfg(target_os = "hermit")]
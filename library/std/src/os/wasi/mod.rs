@@ -36,7 +36,11 @@
 pub mod ffi;
 pub mod fs;
 pub mod io;
+// Preview 2 sockets are `wasi:sockets` resources, so `sock_accept` isn't
+// available for them either.
+#[cfg(not(target_env = "p2"))]
 pub mod net;
+pub mod preopens;
 
 /// A prelude for conveniently writing platform-specific code.
 ///
@@ -0,0 +1,86 @@
+//! Directories preopened by the host.
+//!
+//! A WASI program can only access the parts of the host's filesystem that the
+//! host explicitly made available to it before it started. Each of these
+//! preopened directories is visible to the program under a path chosen by the
+//! host, and `std::fs` resolves paths against them.
+
+#![unstable(feature = "wasi_ext", issue = "71213")]
+
+#[cfg(not(target_env = "p2"))]
+use crate::os::wasi::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use crate::path::{Path, PathBuf};
+use crate::{fmt, sys};
+
+/// A directory preopened by the host.
+///
+/// Returned by [`preopened_dirs`].
+pub struct PreopenedDir {
+    path: PathBuf,
+    #[cfg(not(target_env = "p2"))]
+    fd: RawFd,
+}
+
+impl PreopenedDir {
+    /// Returns the path under which the host made this directory available.
+    ///
+    /// This is usually, but not necessarily, an absolute path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl fmt::Debug for PreopenedDir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("PreopenedDir");
+        debug.field("path", &self.path);
+        #[cfg(not(target_env = "p2"))]
+        debug.field("fd", &self.fd);
+        debug.finish()
+    }
+}
+
+#[cfg(not(target_env = "p2"))]
+impl AsFd for PreopenedDir {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        // SAFETY: preopened descriptors stay open for the lifetime of the
+        // program unless something explicitly closes them.
+        unsafe { BorrowedFd::borrow_raw(self.fd) }
+    }
+}
+
+#[cfg(not(target_env = "p2"))]
+impl AsRawFd for PreopenedDir {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+/// Returns the directories preopened by the host, in the order the host
+/// provided them.
+///
+/// # Examples
+///
+/// ```no_run
+/// #![feature(wasi_ext)]
+/// use std::os::wasi::preopens::preopened_dirs;
+///
+/// for dir in preopened_dirs() {
+///     println!("{}", dir.path().display());
+/// }
+/// ```
+pub fn preopened_dirs() -> Vec<PreopenedDir> {
+    #[cfg(not(target_env = "p2"))]
+    {
+        sys::fs::preopens().into_iter().map(|(fd, path)| PreopenedDir { path, fd }).collect()
+    }
+    #[cfg(target_env = "p2")]
+    {
+        sys::fs::preopens()
+            .iter()
+            .map(|preopen| PreopenedDir { path: preopen.path.clone() })
+            .collect()
+    }
+}
@@ -2,6 +2,7 @@
 use crate::pipe::{PipeReader, PipeWriter};
 use crate::process::Stdio;
 pub use crate::sys::pipe::AnonPipe;
+use crate::sys_common::FromInner;
 
 #[inline]
 pub fn pipe() -> io::Result<(AnonPipe, AnonPipe)> {
@@ -11,13 +12,13 @@ pub fn pipe() -> io::Result<(AnonPipe, AnonPipe)> {
 #[unstable(feature = "anonymous_pipe", issue = "127154")]
 impl From<PipeReader> for Stdio {
     fn from(pipe: PipeReader) -> Self {
-        pipe.0.diverge()
+        Stdio::from_inner(pipe.0.into())
     }
 }
 
 #[unstable(feature = "anonymous_pipe", issue = "127154")]
 impl From<PipeWriter> for Stdio {
     fn from(pipe: PipeWriter) -> Self {
-        pipe.0.diverge()
+        Stdio::from_inner(pipe.0.into())
     }
 }
@@ -1,5 +1,8 @@
 #![forbid(unsafe_op_in_unsafe_fn)]
 
+#[cfg(test)]
+mod tests;
+
 use super::fd::WasiFd;
 use crate::ffi::{CStr, OsStr, OsString};
 use crate::io::{self, BorrowedCursor, IoSlice, IoSliceMut, SeekFrom};
@@ -743,6 +746,59 @@ pub fn __wasilibc_find_relpath(
     })
 }
 
+/// How many descriptors in a row may turn out not to be preopened directories
+/// before [`preopens`] stops looking for more.
+///
+/// The host hands out preopens as a contiguous range of descriptors starting
+/// at 3, but by the time we look the program may have closed some of them or
+/// been given other descriptors in between.
+const MAX_PREOPEN_GAP: u32 = 64;
+
+/// Returns the directories preopened by the host, along with the paths they
+/// were preopened under.
+///
+/// Like wasi-libc, this probes descriptors counting up from 3, but rather
+/// than stopping at the first invalid one it only gives up after
+/// [`MAX_PREOPEN_GAP`] descriptors in a row that are not preopened
+/// directories.
+pub(crate) fn preopens() -> Vec<(RawFd, PathBuf)> {
+    scan_preopens(|fd| {
+        let prestat = unsafe { wasi::fd_prestat_get(fd) }?;
+        if prestat.tag != wasi::PREOPENTYPE_DIR.raw() {
+            return Ok(None);
+        }
+        let len = unsafe { prestat.u.dir.pr_name_len };
+        let mut name = vec![0; len];
+        unsafe { wasi::fd_prestat_dir_name(fd, name.as_mut_ptr(), len) }?;
+        Ok(Some(name))
+    })
+}
+
+/// The scan behind [`preopens`], with `dir_name` returning the raw name of
+/// the directory preopened as `fd`, if any.
+fn scan_preopens(
+    mut dir_name: impl FnMut(wasi::Fd) -> Result<Option<Vec<u8>>, wasi::Errno>,
+) -> Vec<(RawFd, PathBuf)> {
+    let mut preopens = Vec::new();
+    let mut gap = 0;
+    let mut fd = 3;
+    while gap < MAX_PREOPEN_GAP {
+        match dir_name(fd) {
+            Ok(Some(mut name)) => {
+                // Some hosts include the terminating NUL in the length.
+                if let Some(nul) = name.iter().position(|&b| b == 0) {
+                    name.truncate(nul);
+                }
+                preopens.push((fd as RawFd, PathBuf::from(OsString::from_vec(name))));
+                gap = 0;
+            }
+            Ok(None) | Err(_) => gap += 1,
+        }
+        fd += 1;
+    }
+    preopens
+}
+
 pub fn osstr2str(f: &OsStr) -> io::Result<&str> {
     f.to_str()
         .ok_or_else(|| io::const_io_error!(io::ErrorKind::Uncategorized, "input must be utf-8"))
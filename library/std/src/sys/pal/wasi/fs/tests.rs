@@ -0,0 +1,30 @@
+use super::{MAX_PREOPEN_GAP, scan_preopens};
+use crate::path::PathBuf;
+
+#[test]
+fn scan_preopens_skips_gaps() {
+    // 5 was closed by the program, 6 is a regular file and 8 is preopened
+    // with a NUL-terminated name.
+    let preopens = scan_preopens(|fd| match fd {
+        3 => Ok(Some(b"/".to_vec())),
+        4 => Ok(Some(b"/tmp".to_vec())),
+        6 => Err(wasi::ERRNO_NOTSUP),
+        8 => Ok(Some(b"data\0".to_vec())),
+        _ => Err(wasi::ERRNO_BADF),
+    });
+    assert_eq!(
+        preopens,
+        [(3, PathBuf::from("/")), (4, PathBuf::from("/tmp")), (8, PathBuf::from("data"))]
+    );
+}
+
+#[test]
+fn scan_preopens_is_bounded() {
+    let mut probed = 0;
+    let preopens = scan_preopens(|fd| {
+        probed += 1;
+        if fd == 3 + MAX_PREOPEN_GAP { Ok(Some(b"/unreachable".to_vec())) } else { Ok(None) }
+    });
+    assert!(preopens.is_empty());
+    assert_eq!(probed, MAX_PREOPEN_GAP);
+}
@@ -6,14 +6,67 @@
 use crate::io::{self, BorrowedCursor, IoSlice, IoSliceMut};
 use crate::net::{Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr};
 use crate::os::wasi::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, RawFd};
+use crate::sync::atomic::{AtomicU64, Ordering};
 use crate::sys::unsupported;
 use crate::sys_common::{AsInner, FromInner, IntoInner};
 use crate::time::Duration;
 
+#[cfg(test)]
+mod tests;
+
 pub struct Socket(WasiFd);
 
 pub struct TcpStream {
     inner: Socket,
+    // WASI has no socket options for timeouts, so they are emulated with
+    // `poll_oneoff`. Stored as nanoseconds, with 0 meaning no timeout.
+    read_timeout: AtomicU64,
+    write_timeout: AtomicU64,
+}
+
+fn set_timeout(timeout: &AtomicU64, dur: Option<Duration>) -> io::Result<()> {
+    let nanos = match dur {
+        Some(dur) if dur.is_zero() => {
+            return Err(io::const_io_error!(
+                io::ErrorKind::InvalidInput,
+                "cannot set a 0 duration timeout",
+            ));
+        }
+        Some(dur) => u64::try_from(dur.as_nanos()).unwrap_or(u64::MAX),
+        None => 0,
+    };
+    timeout.store(nanos, Ordering::Relaxed);
+    Ok(())
+}
+
+fn get_timeout(timeout: &AtomicU64) -> Option<Duration> {
+    match timeout.load(Ordering::Relaxed) {
+        0 => None,
+        nanos => Some(Duration::from_nanos(nanos)),
+    }
+}
+
+/// Asks the host whether `socket` is in non-blocking mode. The flag isn't
+/// cached, as it can also be changed through the raw descriptor.
+fn is_nonblocking(socket: &Socket) -> io::Result<bool> {
+    let fdstat = unsafe { wasi::fd_fdstat_get(socket.as_raw_fd() as wasi::Fd).map_err(err2io)? };
+    Ok(fdstat.fs_flags & wasi::FDFLAGS_NONBLOCK != 0)
+}
+
+fn set_nonblocking(socket: &Socket, state: bool) -> io::Result<()> {
+    let fd = socket.as_raw_fd() as wasi::Fd;
+    let fdstat = unsafe { wasi::fd_fdstat_get(fd).map_err(err2io)? };
+
+    let mut flags = fdstat.fs_flags;
+
+    if state {
+        flags |= wasi::FDFLAGS_NONBLOCK;
+    } else {
+        flags &= !wasi::FDFLAGS_NONBLOCK;
+    }
+
+    unsafe { wasi::fd_fdstat_set_flags(fd, flags).map_err(err2io)? };
+    Ok(())
 }
 
 impl AsInner<WasiFd> for Socket {
@@ -60,7 +113,69 @@ unsafe fn from_raw_fd(raw_fd: RawFd) -> Self {
     }
 }
 
+impl Socket {
+    /// Waits for the socket to become readable (or writable if `write` is
+    /// set), failing with `TimedOut` once `timeout` has elapsed.
+    fn wait(&self, write: bool, timeout: Duration) -> io::Result<()> {
+        const READY: wasi::Userdata = 0;
+        const TIMEOUT: wasi::Userdata = 1;
+
+        let fd = self.as_raw_fd() as wasi::Fd;
+        let fd = wasi::SubscriptionFdReadwrite { file_descriptor: fd };
+        let ready = if write {
+            wasi::SubscriptionU {
+                tag: wasi::EVENTTYPE_FD_WRITE.raw(),
+                u: wasi::SubscriptionUU { fd_write: fd },
+            }
+        } else {
+            wasi::SubscriptionU {
+                tag: wasi::EVENTTYPE_FD_READ.raw(),
+                u: wasi::SubscriptionUU { fd_read: fd },
+            }
+        };
+        let clock = wasi::SubscriptionClock {
+            id: wasi::CLOCKID_MONOTONIC,
+            timeout: u64::try_from(timeout.as_nanos()).unwrap_or(u64::MAX),
+            precision: 0,
+            flags: 0,
+        };
+        let subscriptions = [
+            wasi::Subscription { userdata: READY, u: ready },
+            wasi::Subscription {
+                userdata: TIMEOUT,
+                u: wasi::SubscriptionU {
+                    tag: wasi::EVENTTYPE_CLOCK.raw(),
+                    u: wasi::SubscriptionUU { clock },
+                },
+            },
+        ];
+        let mut events: [wasi::Event; 2] = unsafe { crate::mem::zeroed() };
+        let n = unsafe {
+            wasi::poll_oneoff(subscriptions.as_ptr(), events.as_mut_ptr(), subscriptions.len())
+                .map_err(err2io)?
+        };
+        // An error on the socket itself also counts as ready; the operation
+        // that follows will report it.
+        if events[..n].iter().any(|event| event.userdata == READY) {
+            Ok(())
+        } else {
+            Err(err2io(wasi::ERRNO_TIMEDOUT))
+        }
+    }
+}
+
 impl TcpStream {
+    /// Waits for the socket to become ready if a timeout is set.
+    ///
+    /// Non-blocking sockets return right away, so that the following
+    /// operation reports `WouldBlock` as usual.
+    fn wait(&self, write: bool, timeout: &AtomicU64) -> io::Result<()> {
+        match get_timeout(timeout) {
+            Some(timeout) if !is_nonblocking(self.socket())? => self.socket().wait(write, timeout),
+            _ => Ok(()),
+        }
+    }
+
     pub fn connect(_: io::Result<&SocketAddr>) -> io::Result<TcpStream> {
         unsupported()
     }
@@ -69,24 +184,29 @@ pub fn connect_timeout(_: &SocketAddr, _: Duration) -> io::Result<TcpStream> {
         unsupported()
     }
 
-    pub fn set_read_timeout(&self, _: Option<Duration>) -> io::Result<()> {
-        unsupported()
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        set_timeout(&self.read_timeout, timeout)
     }
 
-    pub fn set_write_timeout(&self, _: Option<Duration>) -> io::Result<()> {
-        unsupported()
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        set_timeout(&self.write_timeout, timeout)
     }
 
     pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
-        unsupported()
+        Ok(get_timeout(&self.read_timeout))
     }
 
     pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
-        unsupported()
+        Ok(get_timeout(&self.write_timeout))
     }
 
-    pub fn peek(&self, _: &mut [u8]) -> io::Result<usize> {
-        unsupported()
+    pub fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.wait(false, &self.read_timeout)?;
+        let (n, _) = self
+            .socket()
+            .as_inner()
+            .sock_recv(&mut [IoSliceMut::new(buf)], wasi::RIFLAGS_RECV_PEEK)?;
+        Ok(n)
     }
 
     pub fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
@@ -94,10 +214,12 @@ pub fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
     }
 
     pub fn read_buf(&self, buf: BorrowedCursor<'_>) -> io::Result<()> {
+        self.wait(false, &self.read_timeout)?;
         self.socket().as_inner().read_buf(buf)
     }
 
     pub fn read_vectored(&self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        self.wait(false, &self.read_timeout)?;
         self.socket().as_inner().read(bufs)
     }
 
@@ -110,6 +232,7 @@ pub fn write(&self, buf: &[u8]) -> io::Result<usize> {
     }
 
     pub fn write_vectored(&self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.wait(true, &self.write_timeout)?;
         self.socket().as_inner().write(bufs)
     }
 
@@ -164,26 +287,12 @@ pub fn ttl(&self) -> io::Result<u32> {
     }
 
     pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        // WASI has no equivalent of `SO_ERROR`.
         unsupported()
     }
 
     pub fn set_nonblocking(&self, state: bool) -> io::Result<()> {
-        let fdstat = unsafe {
-            wasi::fd_fdstat_get(self.socket().as_inner().as_raw_fd() as wasi::Fd).map_err(err2io)?
-        };
-
-        let mut flags = fdstat.fs_flags;
-
-        if state {
-            flags |= wasi::FDFLAGS_NONBLOCK;
-        } else {
-            flags &= !wasi::FDFLAGS_NONBLOCK;
-        }
-
-        unsafe {
-            wasi::fd_fdstat_set_flags(self.socket().as_inner().as_raw_fd() as wasi::Fd, flags)
-                .map_err(err2io)
-        }
+        set_nonblocking(self.socket(), state)
     }
 
     #[inline]
@@ -198,7 +307,11 @@ pub fn into_socket(self) -> Socket {
 
 impl FromInner<Socket> for TcpStream {
     fn from_inner(socket: Socket) -> TcpStream {
-        TcpStream { inner: socket }
+        TcpStream {
+            inner: socket,
+            read_timeout: AtomicU64::new(0),
+            write_timeout: AtomicU64::new(0),
+        }
     }
 }
 
@@ -222,12 +335,25 @@ pub fn socket_addr(&self) -> io::Result<SocketAddr> {
     }
 
     pub fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        // Not every host honors `FDFLAGS_NONBLOCK` for `sock_accept`, so make
+        // sure a connection is pending before accepting it.
+        if is_nonblocking(self.socket())? {
+            self.socket().wait(false, Duration::ZERO).map_err(|e| {
+                if e.kind() == io::ErrorKind::TimedOut {
+                    io::ErrorKind::WouldBlock.into()
+                } else {
+                    e
+                }
+            })?;
+        }
+
         let fd = unsafe {
             wasi::sock_accept(self.as_inner().as_inner().as_raw_fd() as _, 0).map_err(err2io)?
         };
 
+        let stream = TcpStream::from_inner(unsafe { Socket::from_raw_fd(fd as _) });
         Ok((
-            TcpStream::from_inner(unsafe { Socket::from_raw_fd(fd as _) }),
+            stream,
             // WASI has no concept of SocketAddr yet
             // return an unspecified IPv4Addr
             SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0),
@@ -259,22 +385,7 @@ pub fn take_error(&self) -> io::Result<Option<io::Error>> {
     }
 
     pub fn set_nonblocking(&self, state: bool) -> io::Result<()> {
-        let fdstat = unsafe {
-            wasi::fd_fdstat_get(self.socket().as_inner().as_raw_fd() as wasi::Fd).map_err(err2io)?
-        };
-
-        let mut flags = fdstat.fs_flags;
-
-        if state {
-            flags |= wasi::FDFLAGS_NONBLOCK;
-        } else {
-            flags &= !wasi::FDFLAGS_NONBLOCK;
-        }
-
-        unsafe {
-            wasi::fd_fdstat_set_flags(self.socket().as_inner().as_raw_fd() as wasi::Fd, flags)
-                .map_err(err2io)
-        }
+        set_nonblocking(self.socket(), state)
     }
 
     #[inline]
@@ -0,0 +1,88 @@
+use super::{Socket, get_timeout, set_timeout};
+use crate::io::ErrorKind;
+use crate::mem::ManuallyDrop;
+use crate::net::{TcpListener, TcpStream};
+use crate::os::wasi::io::{FromRawFd, RawFd};
+use crate::sync::atomic::AtomicU64;
+use crate::time::Duration;
+
+/// Finds a listening socket that the host preopened, like the one of
+/// `wasmtime run --tcplisten 127.0.0.1:0`. WASI can't create sockets, so
+/// tests that need one pass trivially without it.
+///
+/// The socket is shared by the tests and must not be closed, nor left in
+/// non-blocking mode.
+fn preopened_socket() -> Option<RawFd> {
+    (3..64)
+        .find(|&fd| {
+            unsafe { wasi::fd_fdstat_get(fd) }
+                .is_ok_and(|stat| stat.fs_filetype == wasi::FILETYPE_SOCKET_STREAM)
+        })
+        .map(|fd| fd as RawFd)
+}
+
+fn set_raw_nonblocking(fd: RawFd, state: bool) {
+    let fd = fd as wasi::Fd;
+    let mut flags = unsafe { wasi::fd_fdstat_get(fd).unwrap() }.fs_flags;
+    if state {
+        flags |= wasi::FDFLAGS_NONBLOCK;
+    } else {
+        flags &= !wasi::FDFLAGS_NONBLOCK;
+    }
+    unsafe { wasi::fd_fdstat_set_flags(fd, flags).unwrap() };
+}
+
+#[test]
+fn timeouts() {
+    let timeout = AtomicU64::new(0);
+    assert_eq!(get_timeout(&timeout), None);
+    set_timeout(&timeout, Some(Duration::from_millis(1500))).unwrap();
+    assert_eq!(get_timeout(&timeout), Some(Duration::from_millis(1500)));
+    let err = set_timeout(&timeout, Some(Duration::ZERO)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert_eq!(get_timeout(&timeout), Some(Duration::from_millis(1500)));
+    set_timeout(&timeout, None).unwrap();
+    assert_eq!(get_timeout(&timeout), None);
+}
+
+#[test]
+fn wait_times_out() {
+    let Some(fd) = preopened_socket() else { return };
+    // Nobody connects, so the listener never becomes readable.
+    let socket = ManuallyDrop::new(unsafe { Socket::from_raw_fd(fd) });
+    let err = socket.wait(false, Duration::from_millis(10)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TimedOut);
+}
+
+#[test]
+fn peek_times_out() {
+    let Some(fd) = preopened_socket() else { return };
+    let stream = ManuallyDrop::new(unsafe { TcpStream::from_raw_fd(fd) });
+    stream.set_read_timeout(Some(Duration::from_millis(10))).unwrap();
+    let err = stream.peek(&mut [0; 1]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TimedOut);
+}
+
+#[test]
+fn nonblocking_accept() {
+    let Some(fd) = preopened_socket() else { return };
+    let listener = ManuallyDrop::new(unsafe { TcpListener::from_raw_fd(fd) });
+
+    listener.set_nonblocking(true).unwrap();
+    assert_eq!(listener.accept().unwrap_err().kind(), ErrorKind::WouldBlock);
+    listener.set_nonblocking(false).unwrap();
+
+    // Changes made through the raw descriptor are seen as well.
+    set_raw_nonblocking(fd, true);
+    assert_eq!(listener.accept().unwrap_err().kind(), ErrorKind::WouldBlock);
+    set_raw_nonblocking(fd, false);
+}
+
+#[test]
+fn take_error_is_unsupported() {
+    let Some(fd) = preopened_socket() else { return };
+    let listener = ManuallyDrop::new(unsafe { TcpListener::from_raw_fd(fd) });
+    assert_eq!(listener.take_error().unwrap_err().kind(), ErrorKind::Unsupported);
+    let stream = ManuallyDrop::new(unsafe { TcpStream::from_raw_fd(fd) });
+    assert_eq!(stream.take_error().unwrap_err().kind(), ErrorKind::Unsupported);
+}
@@ -7,9 +7,9 @@
 use crate::marker::PhantomData;
 use crate::ops::Drop;
 use crate::os::wasi::prelude::*;
+use crate::os::wasi::preopens::preopened_dirs;
 use crate::path::{self, PathBuf};
 use crate::sys::common::small_c_string::{run_path_with_cstr, run_with_cstr};
-use crate::sys::unsupported;
 use crate::{fmt, io, str, vec};
 
 // Add a few symbols not in upstream `libc` just yet.
@@ -138,7 +138,34 @@ fn description(&self) -> &str {
 }
 
 pub fn current_exe() -> io::Result<PathBuf> {
-    unsupported()
+    // A WebAssembly module isn't necessarily a file the guest can see, so
+    // hosts can say where it is. Otherwise look for `argv[0]`, relative to the
+    // working directory and then to each preopened directory.
+    if let Some(exe) = crate::env::var_os("RUST_WASI_CURRENT_EXE") {
+        return Ok(PathBuf::from(exe));
+    }
+    let argv0 = match crate::env::args_os().next() {
+        Some(argv0) if !argv0.is_empty() => PathBuf::from(argv0),
+        _ => return Err(io::const_io_error!(io::ErrorKind::NotFound, "no program name available")),
+    };
+    let mut candidates = Vec::new();
+    if argv0.is_absolute() {
+        candidates.push(argv0);
+    } else {
+        candidates.push(getcwd()?.join(&argv0));
+        candidates.extend(
+            preopened_dirs()
+                .iter()
+                .filter(|dir| dir.path().is_absolute())
+                .map(|dir| dir.path().join(&argv0)),
+        );
+    }
+    candidates.into_iter().find(|path| crate::fs::metadata(path).is_ok_and(|m| m.is_file())).ok_or(
+        io::const_io_error!(
+            io::ErrorKind::NotFound,
+            "the program name doesn't refer to a file accessible to this program",
+        ),
+    )
 }
 
 pub struct Env {
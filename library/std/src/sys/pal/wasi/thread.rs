@@ -9,6 +9,7 @@
 cfg_if::cfg_if! {
     if #[cfg(target_feature = "atomics")] {
         use crate::cmp;
+        use crate::env;
         use crate::ptr;
         use crate::sys::os;
         // Add a few symbols not in upstream `libc` just yet.
@@ -132,7 +133,9 @@ pub fn yield_now() {
     }
 
     pub fn set_name(_name: &CStr) {
-        // nope
+        // wasi-libc has no `pthread_setname_np` and WASI has no way to tell
+        // the host about thread names. The name is still tracked by
+        // `thread::Thread`, so `Thread::name` and panic messages include it.
     }
 
     pub fn sleep(dur: Duration) {
@@ -187,5 +190,20 @@ pub fn join(self) {
 }
 
 pub fn available_parallelism() -> io::Result<NonZero<usize>> {
-    unsupported()
+    cfg_if::cfg_if! {
+        if #[cfg(target_feature = "atomics")] {
+            // wasi-threads has no way to ask how many threads can actually run
+            // in parallel, so let the host say so through the environment.
+            // wasi-libc's `sysconf(_SC_NPROCESSORS_ONLN)` is hard-wired to 1,
+            // so it isn't asked.
+            match env::var_os("RUST_WASI_AVAILABLE_PARALLELISM")
+                .and_then(|s| s.to_str().and_then(|s| s.parse().ok()))
+            {
+                Some(count) => Ok(count),
+                None => unsupported(),
+            }
+        } else {
+            unsupported()
+        }
+    }
 }
@@ -0,0 +1,121 @@
+//! Conversions from the error types of WASI preview 2 interfaces to
+//! `io::Error`.
+//!
+//! Each preview 2 interface defines its own `error-code` enumeration. These
+//! are translated to the equivalent `errno` value used by wasi-libc so that
+//! `io::Error::raw_os_error`, `decode_error_kind` and `error_string` behave
+//! the same way for errors coming from either source.
+
+#![forbid(unsafe_op_in_unsafe_fn)]
+
+use wasip2::filesystem::types::{ErrorCode, filesystem_error_code};
+use wasip2::io::streams::StreamError;
+use wasip2::sockets::network;
+
+use crate::io;
+
+fn errno2io(errno: wasi::Errno) -> io::Error {
+    io::Error::from_raw_os_error(errno.raw().into())
+}
+
+pub(crate) fn filesystem_err2io(code: ErrorCode) -> io::Error {
+    errno2io(match code {
+        ErrorCode::Access => wasi::ERRNO_ACCES,
+        ErrorCode::WouldBlock => wasi::ERRNO_AGAIN,
+        ErrorCode::Already => wasi::ERRNO_ALREADY,
+        ErrorCode::BadDescriptor => wasi::ERRNO_BADF,
+        ErrorCode::Busy => wasi::ERRNO_BUSY,
+        ErrorCode::Deadlock => wasi::ERRNO_DEADLK,
+        ErrorCode::Quota => wasi::ERRNO_DQUOT,
+        ErrorCode::Exist => wasi::ERRNO_EXIST,
+        ErrorCode::FileTooLarge => wasi::ERRNO_FBIG,
+        ErrorCode::IllegalByteSequence => wasi::ERRNO_ILSEQ,
+        ErrorCode::InProgress => wasi::ERRNO_INPROGRESS,
+        ErrorCode::Interrupted => wasi::ERRNO_INTR,
+        ErrorCode::Invalid => wasi::ERRNO_INVAL,
+        ErrorCode::Io => wasi::ERRNO_IO,
+        ErrorCode::IsDirectory => wasi::ERRNO_ISDIR,
+        ErrorCode::Loop => wasi::ERRNO_LOOP,
+        ErrorCode::TooManyLinks => wasi::ERRNO_MLINK,
+        ErrorCode::MessageSize => wasi::ERRNO_MSGSIZE,
+        ErrorCode::NameTooLong => wasi::ERRNO_NAMETOOLONG,
+        ErrorCode::NoDevice => wasi::ERRNO_NODEV,
+        ErrorCode::NoEntry => wasi::ERRNO_NOENT,
+        ErrorCode::NoLock => wasi::ERRNO_NOLCK,
+        ErrorCode::InsufficientMemory => wasi::ERRNO_NOMEM,
+        ErrorCode::InsufficientSpace => wasi::ERRNO_NOSPC,
+        ErrorCode::NotDirectory => wasi::ERRNO_NOTDIR,
+        ErrorCode::NotEmpty => wasi::ERRNO_NOTEMPTY,
+        ErrorCode::NotRecoverable => wasi::ERRNO_NOTRECOVERABLE,
+        ErrorCode::Unsupported => wasi::ERRNO_NOTSUP,
+        ErrorCode::NoTty => wasi::ERRNO_NOTTY,
+        ErrorCode::NoSuchDevice => wasi::ERRNO_NXIO,
+        ErrorCode::Overflow => wasi::ERRNO_OVERFLOW,
+        ErrorCode::NotPermitted => wasi::ERRNO_PERM,
+        ErrorCode::Pipe => wasi::ERRNO_PIPE,
+        ErrorCode::ReadOnly => wasi::ERRNO_ROFS,
+        ErrorCode::InvalidSeek => wasi::ERRNO_SPIPE,
+        ErrorCode::TextFileBusy => wasi::ERRNO_TXTBSY,
+        ErrorCode::CrossDevice => wasi::ERRNO_XDEV,
+    })
+}
+
+/// Converts the failure of a `wasi:io/streams` operation.
+///
+/// A closed stream is reported as a broken pipe. The error resource attached
+/// to a failed operation is opaque, so it is only downcast to a filesystem
+/// error code; anything else keeps its debug description.
+pub(crate) fn stream_err2io(err: StreamError) -> io::Error {
+    match err {
+        StreamError::Closed => errno2io(wasi::ERRNO_PIPE),
+        StreamError::LastOperationFailed(err) => match filesystem_error_code(&err) {
+            Some(code) => filesystem_err2io(code),
+            None => io::Error::new(io::ErrorKind::Uncategorized, err.to_debug_string()),
+        },
+    }
+}
+
+pub(crate) fn sockets_err2io(code: network::ErrorCode) -> io::Error {
+    use network::ErrorCode;
+
+    errno2io(match code {
+        ErrorCode::Unknown => wasi::ERRNO_IO,
+        ErrorCode::AccessDenied => wasi::ERRNO_ACCES,
+        ErrorCode::NotSupported => wasi::ERRNO_NOTSUP,
+        ErrorCode::InvalidArgument => wasi::ERRNO_INVAL,
+        ErrorCode::OutOfMemory => wasi::ERRNO_NOMEM,
+        ErrorCode::Timeout => wasi::ERRNO_TIMEDOUT,
+        ErrorCode::ConcurrencyConflict => wasi::ERRNO_ALREADY,
+        ErrorCode::NotInProgress => wasi::ERRNO_INVAL,
+        ErrorCode::WouldBlock => wasi::ERRNO_AGAIN,
+        ErrorCode::InvalidState => wasi::ERRNO_INVAL,
+        ErrorCode::NewSocketLimit => wasi::ERRNO_MFILE,
+        ErrorCode::AddressNotBindable => wasi::ERRNO_ADDRNOTAVAIL,
+        ErrorCode::AddressInUse => wasi::ERRNO_ADDRINUSE,
+        ErrorCode::RemoteUnreachable => wasi::ERRNO_HOSTUNREACH,
+        ErrorCode::ConnectionRefused => wasi::ERRNO_CONNREFUSED,
+        ErrorCode::ConnectionReset => wasi::ERRNO_CONNRESET,
+        ErrorCode::ConnectionAborted => wasi::ERRNO_CONNABORTED,
+        ErrorCode::DatagramTooLarge => wasi::ERRNO_MSGSIZE,
+        // Resolver failures have no errno equivalent; wasi-libc reports them
+        // through `getaddrinfo`'s own error codes instead.
+        ErrorCode::NameUnresolvable => {
+            return io::const_io_error!(
+                io::ErrorKind::Uncategorized,
+                "failed to lookup address information: name does not resolve",
+            );
+        }
+        ErrorCode::TemporaryResolverFailure => {
+            return io::const_io_error!(
+                io::ErrorKind::Uncategorized,
+                "failed to lookup address information: temporary failure in name resolution",
+            );
+        }
+        ErrorCode::PermanentResolverFailure => {
+            return io::const_io_error!(
+                io::ErrorKind::Uncategorized,
+                "failed to lookup address information: non-recoverable failure in name resolution",
+            );
+        }
+    })
+}
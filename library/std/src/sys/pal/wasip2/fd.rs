@@ -0,0 +1,633 @@
+//! File descriptors for preview 2 resources.
+//!
+//! Files and sockets in preview 2 are component-model resources, but the
+//! stable `AsFd`/`AsRawFd` family of traits requires them to have a file
+//! descriptor. std hands out numbers for the resources it creates from its
+//! own table, starting far above the numbers used by wasi-libc and the
+//! preview 1 adapter, which always pick the lowest free one.
+//!
+//! Such a number isn't usable with the preview 1 functions of the adapter,
+//! but it keeps the identity of the resource across `IntoRawFd` and
+//! `FromRawFd`: converting into a raw descriptor parks the resource in a table
+//! under its number, and converting back picks it up again. Descriptors that
+//! std didn't create itself, such as files opened by C code through wasi-libc,
+//! are real descriptors of the adapter and are accessed through its preview 1
+//! functions instead.
+
+#![forbid(unsafe_op_in_unsafe_fn)]
+#![allow(dead_code)]
+
+use wasip2::filesystem::types::{
+    Advice, Descriptor, DescriptorStat, DescriptorType, NewTimestamp, PathFlags,
+};
+
+use super::err2io;
+use super::error::filesystem_err2io;
+use crate::any::Any;
+use crate::collections::{BTreeMap, BTreeSet};
+use crate::io::{self, BorrowedCursor, IoSlice, IoSliceMut, SeekFrom};
+use crate::mem::ManuallyDrop;
+use crate::os::wasi::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
+use crate::sync::Mutex;
+use crate::sys::time::SystemTime;
+use crate::sys::unsupported;
+use crate::sys_common::{FromInner, IntoInner};
+
+#[path = "../wasi/fd.rs"]
+mod adapter;
+
+pub(crate) use self::adapter::WasiFd as AdapterFd;
+
+/// The first number std hands out for its own descriptors.
+const FIRST_RESERVED_FD: RawFd = 1 << 30;
+
+/// A descriptor number reserved from std's table for a preview 2 resource.
+///
+/// Dropping it releases the number again.
+#[derive(Debug)]
+pub(crate) struct ReservedFd(RawFd);
+
+impl ReservedFd {
+    pub(crate) fn new() -> io::Result<ReservedFd> {
+        let mut table = TABLE.lock().unwrap();
+        if let Some(fd) = table.free.pop_first() {
+            return Ok(ReservedFd(fd));
+        }
+        let fd = table.next;
+        table.next = fd
+            .checked_add(1)
+            .ok_or_else(|| io::Error::from_raw_os_error(wasi::ERRNO_NFILE.raw().into()))?;
+        Ok(ReservedFd(fd))
+    }
+
+    /// Parks `resource` under this number and turns the number into an
+    /// owned descriptor, see [`ReservedFd::unpark`].
+    pub(crate) fn park<T: Send + 'static>(self, resource: T) -> OwnedFd {
+        let fd = ManuallyDrop::new(self).0;
+        TABLE.lock().unwrap().parked.insert(fd, Box::new(resource));
+        // SAFETY: the number is reserved until it is picked up again by
+        // `unpark`, or closed through the `OwnedFd`.
+        unsafe { OwnedFd::from_raw_fd(fd) }
+    }
+
+    /// Picks up the resource parked under `fd` by [`ReservedFd::park`].
+    ///
+    /// Gives `fd` back if nothing of type `T` was parked under it, which is
+    /// the case for descriptors that std didn't create.
+    pub(crate) fn unpark<T: Send + 'static>(fd: OwnedFd) -> Result<(ReservedFd, T), OwnedFd> {
+        let mut table = TABLE.lock().unwrap();
+        let raw = fd.as_raw_fd();
+        match table.parked.remove(&raw).map(|resource| resource.downcast::<T>()) {
+            Some(Ok(resource)) => Ok((ReservedFd(fd.into_raw_fd()), *resource)),
+            Some(Err(resource)) => {
+                table.parked.insert(raw, resource);
+                Err(fd)
+            }
+            None => Err(fd),
+        }
+    }
+}
+
+impl Drop for ReservedFd {
+    fn drop(&mut self) {
+        TABLE.lock().unwrap().free.insert(self.0);
+    }
+}
+
+/// Closes `fd` if it is one of std's own numbers, dropping the resource
+/// parked under it.
+///
+/// Called when an `OwnedFd` is dropped. Returns `false` for descriptors of
+/// wasi-libc and the adapter, which have to be closed through them instead.
+pub(crate) fn close_reserved(fd: RawFd) -> bool {
+    if fd < FIRST_RESERVED_FD {
+        return false;
+    }
+    let resource = {
+        let mut table = TABLE.lock().unwrap();
+        if fd >= table.next || table.free.contains(&fd) {
+            return false;
+        }
+        table.free.insert(fd);
+        table.parked.remove(&fd)
+    };
+    // Dropped outside of the lock, as dropping a resource may close others.
+    drop(resource);
+    true
+}
+
+impl AsFd for ReservedFd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        // SAFETY: the number stays reserved for as long as `self` is alive.
+        unsafe { BorrowedFd::borrow_raw(self.0) }
+    }
+}
+
+impl AsRawFd for ReservedFd {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.0
+    }
+}
+
+/// std's own descriptor numbers.
+struct FdTable {
+    /// The lowest number that was never handed out.
+    next: RawFd,
+    /// Numbers below `next` that were released again.
+    free: BTreeSet<RawFd>,
+    /// Resources that were turned into raw descriptors, keyed by their number.
+    parked: BTreeMap<RawFd, Box<dyn Any + Send>>,
+}
+
+static TABLE: Mutex<FdTable> =
+    Mutex::new(FdTable { next: FIRST_RESERVED_FD, free: BTreeSet::new(), parked: BTreeMap::new() });
+
+/// A file or directory.
+pub struct WasiFd {
+    inner: Inner,
+}
+
+enum Inner {
+    /// Opened by std through `wasi:filesystem`.
+    Descriptor(DescriptorFd),
+    /// Opened by someone else through the adapter.
+    Adapter(AdapterFd),
+}
+
+struct DescriptorFd {
+    descriptor: Descriptor,
+    // Preview 2 descriptors have no implicit file cursor: reads and writes
+    // always name an offset. The lock is held across each read, write and
+    // seek so that concurrent calls don't use or advance the same offset.
+    position: Mutex<u64>,
+    append: bool,
+    // Declared last so that the number is released after the resource.
+    fd: ReservedFd,
+}
+
+/// The state of a `DescriptorFd` while it is parked.
+struct Parked {
+    descriptor: Descriptor,
+    position: u64,
+    append: bool,
+}
+
+/// The largest number of bytes `blocking-write-and-flush` accepts at once.
+const MAX_APPEND: usize = 4096;
+
+impl WasiFd {
+    pub fn from_descriptor(descriptor: Descriptor, append: bool) -> io::Result<WasiFd> {
+        let fd = ReservedFd::new()?;
+        let inner = DescriptorFd { descriptor, position: Mutex::new(0), append, fd };
+        Ok(WasiFd { inner: Inner::Descriptor(inner) })
+    }
+
+    /// Returns the `wasi:filesystem` descriptor, unless this is a descriptor
+    /// of the adapter.
+    pub fn descriptor(&self) -> Option<&Descriptor> {
+        match &self.inner {
+            Inner::Descriptor(inner) => Some(&inner.descriptor),
+            Inner::Adapter(_) => None,
+        }
+    }
+
+    fn adapter(&self) -> io::Result<&AdapterFd> {
+        match &self.inner {
+            Inner::Descriptor(_) => unsupported(),
+            Inner::Adapter(fd) => Ok(fd),
+        }
+    }
+
+    pub fn datasync(&self) -> io::Result<()> {
+        match &self.inner {
+            Inner::Descriptor(inner) => inner.descriptor.sync_data().map_err(filesystem_err2io),
+            Inner::Adapter(fd) => fd.datasync(),
+        }
+    }
+
+    pub fn pread(&self, bufs: &mut [IoSliceMut<'_>], offset: u64) -> io::Result<usize> {
+        match &self.inner {
+            Inner::Descriptor(inner) => {
+                let buf = bufs.iter_mut().find(|b| !b.is_empty()).map_or(&mut [][..], |b| &mut **b);
+                inner.pread(buf, offset)
+            }
+            Inner::Adapter(fd) => fd.pread(bufs, offset),
+        }
+    }
+
+    pub fn pwrite(&self, bufs: &[IoSlice<'_>], offset: u64) -> io::Result<usize> {
+        match &self.inner {
+            Inner::Descriptor(inner) => {
+                let buf = bufs.iter().find(|b| !b.is_empty()).map_or(&[][..], |b| &**b);
+                inner.pwrite(buf, offset)
+            }
+            Inner::Adapter(fd) => fd.pwrite(bufs, offset),
+        }
+    }
+
+    pub fn read(&self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        match &self.inner {
+            Inner::Descriptor(inner) => {
+                crate::io::default_read_vectored(|buf| inner.read(buf), bufs)
+            }
+            Inner::Adapter(fd) => fd.read(bufs),
+        }
+    }
+
+    pub fn read_buf(&self, cursor: BorrowedCursor<'_>) -> io::Result<()> {
+        match &self.inner {
+            Inner::Descriptor(inner) => crate::io::default_read_buf(|buf| inner.read(buf), cursor),
+            Inner::Adapter(fd) => fd.read_buf(cursor),
+        }
+    }
+
+    pub fn write(&self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        match &self.inner {
+            Inner::Descriptor(inner) => {
+                crate::io::default_write_vectored(|buf| inner.write(buf), bufs)
+            }
+            Inner::Adapter(fd) => fd.write(bufs),
+        }
+    }
+
+    pub fn seek(&self, pos: SeekFrom) -> io::Result<u64> {
+        let inner = match &self.inner {
+            Inner::Descriptor(inner) => inner,
+            Inner::Adapter(fd) => return fd.seek(pos),
+        };
+        let mut position = inner.position.lock().unwrap();
+        let new = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => position.checked_add_signed(offset),
+            SeekFrom::End(offset) => {
+                let stat = inner.descriptor.stat().map_err(filesystem_err2io)?;
+                stat.size.checked_add_signed(offset)
+            }
+        };
+        let new = new.ok_or_else(|| {
+            io::const_io_error!(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        *position = new;
+        Ok(new)
+    }
+
+    pub fn tell(&self) -> io::Result<u64> {
+        match &self.inner {
+            Inner::Descriptor(inner) => Ok(*inner.position.lock().unwrap()),
+            Inner::Adapter(fd) => fd.tell(),
+        }
+    }
+
+    pub fn set_flags(&self, flags: wasi::Fdflags) -> io::Result<()> {
+        // Preview 2 descriptors can't change their flags after being opened.
+        self.adapter()?.set_flags(flags)
+    }
+
+    pub fn set_rights(&self, base: wasi::Rights, inheriting: wasi::Rights) -> io::Result<()> {
+        // Preview 2 has no rights.
+        self.adapter()?.set_rights(base, inheriting)
+    }
+
+    pub fn sync(&self) -> io::Result<()> {
+        match &self.inner {
+            Inner::Descriptor(inner) => inner.descriptor.sync().map_err(filesystem_err2io),
+            Inner::Adapter(fd) => fd.sync(),
+        }
+    }
+
+    pub(crate) fn advise(&self, offset: u64, len: u64, advice: wasi::Advice) -> io::Result<()> {
+        let inner = match &self.inner {
+            Inner::Descriptor(inner) => inner,
+            Inner::Adapter(fd) => return fd.advise(offset, len, advice),
+        };
+        let advice = match advice {
+            wasi::ADVICE_SEQUENTIAL => Advice::Sequential,
+            wasi::ADVICE_RANDOM => Advice::Random,
+            wasi::ADVICE_WILLNEED => Advice::WillNeed,
+            wasi::ADVICE_DONTNEED => Advice::DontNeed,
+            wasi::ADVICE_NOREUSE => Advice::NoReuse,
+            _ => Advice::Normal,
+        };
+        inner.descriptor.advise(offset, len, advice).map_err(filesystem_err2io)
+    }
+
+    pub fn allocate(&self, offset: u64, len: u64) -> io::Result<()> {
+        // `wasi:filesystem` removed `fd_allocate`.
+        self.adapter()?.allocate(offset, len)
+    }
+
+    pub fn create_directory(&self, path: &str) -> io::Result<()> {
+        match &self.inner {
+            Inner::Descriptor(inner) => {
+                inner.descriptor.create_directory_at(path).map_err(filesystem_err2io)
+            }
+            Inner::Adapter(fd) => fd.create_directory(path),
+        }
+    }
+
+    pub fn link(
+        &self,
+        old_flags: wasi::Lookupflags,
+        old_path: &str,
+        new_fd: &WasiFd,
+        new_path: &str,
+    ) -> io::Result<()> {
+        match (&self.inner, &new_fd.inner) {
+            (Inner::Descriptor(old), Inner::Descriptor(new)) => old
+                .descriptor
+                .link_at(path_flags(old_flags), old_path, &new.descriptor, new_path)
+                .map_err(filesystem_err2io),
+            (Inner::Adapter(old), Inner::Adapter(new)) => {
+                old.link(old_flags, old_path, new, new_path)
+            }
+            _ => Err(cross_device()),
+        }
+    }
+
+    pub fn rename(&self, old_path: &str, new_fd: &WasiFd, new_path: &str) -> io::Result<()> {
+        match (&self.inner, &new_fd.inner) {
+            (Inner::Descriptor(old), Inner::Descriptor(new)) => old
+                .descriptor
+                .rename_at(old_path, &new.descriptor, new_path)
+                .map_err(filesystem_err2io),
+            (Inner::Adapter(old), Inner::Adapter(new)) => old.rename(old_path, new, new_path),
+            _ => Err(cross_device()),
+        }
+    }
+
+    pub(crate) fn filestat_get(&self) -> io::Result<wasi::Filestat> {
+        let inner = match &self.inner {
+            Inner::Descriptor(inner) => inner,
+            Inner::Adapter(fd) => return fd.filestat_get(),
+        };
+        let stat = inner.descriptor.stat().map_err(filesystem_err2io)?;
+        let hash = inner.descriptor.metadata_hash().map_err(filesystem_err2io)?;
+        Ok(filestat(stat, hash.lower))
+    }
+
+    pub fn filestat_set_times(
+        &self,
+        atim: wasi::Timestamp,
+        mtim: wasi::Timestamp,
+        fstflags: wasi::Fstflags,
+    ) -> io::Result<()> {
+        match &self.inner {
+            Inner::Descriptor(inner) => inner
+                .descriptor
+                .set_times(
+                    new_timestamp(atim, fstflags & wasi::FSTFLAGS_ATIM != 0),
+                    new_timestamp(mtim, fstflags & wasi::FSTFLAGS_MTIM != 0),
+                )
+                .map_err(filesystem_err2io),
+            Inner::Adapter(fd) => fd.filestat_set_times(atim, mtim, fstflags),
+        }
+    }
+
+    pub fn filestat_set_size(&self, size: u64) -> io::Result<()> {
+        match &self.inner {
+            Inner::Descriptor(inner) => inner.descriptor.set_size(size).map_err(filesystem_err2io),
+            Inner::Adapter(fd) => fd.filestat_set_size(size),
+        }
+    }
+
+    pub(crate) fn path_filestat_get(
+        &self,
+        flags: wasi::Lookupflags,
+        path: &str,
+    ) -> io::Result<wasi::Filestat> {
+        match &self.inner {
+            Inner::Descriptor(inner) => {
+                path_filestat_get(&inner.descriptor, path_flags(flags), path)
+            }
+            Inner::Adapter(fd) => fd.path_filestat_get(flags, path),
+        }
+    }
+
+    pub fn path_filestat_set_times(
+        &self,
+        flags: wasi::Lookupflags,
+        path: &str,
+        atim: wasi::Timestamp,
+        mtim: wasi::Timestamp,
+        fstflags: wasi::Fstflags,
+    ) -> io::Result<()> {
+        match &self.inner {
+            Inner::Descriptor(inner) => inner
+                .descriptor
+                .set_times_at(
+                    path_flags(flags),
+                    path,
+                    new_timestamp(atim, fstflags & wasi::FSTFLAGS_ATIM != 0),
+                    new_timestamp(mtim, fstflags & wasi::FSTFLAGS_MTIM != 0),
+                )
+                .map_err(filesystem_err2io),
+            Inner::Adapter(fd) => fd.path_filestat_set_times(flags, path, atim, mtim, fstflags),
+        }
+    }
+
+    pub fn symlink(&self, old_path: &str, new_path: &str) -> io::Result<()> {
+        match &self.inner {
+            Inner::Descriptor(inner) => {
+                inner.descriptor.symlink_at(old_path, new_path).map_err(filesystem_err2io)
+            }
+            Inner::Adapter(fd) => fd.symlink(old_path, new_path),
+        }
+    }
+
+    pub fn unlink_file(&self, path: &str) -> io::Result<()> {
+        match &self.inner {
+            Inner::Descriptor(inner) => {
+                inner.descriptor.unlink_file_at(path).map_err(filesystem_err2io)
+            }
+            Inner::Adapter(fd) => fd.unlink_file(path),
+        }
+    }
+
+    pub fn remove_directory(&self, path: &str) -> io::Result<()> {
+        match &self.inner {
+            Inner::Descriptor(inner) => {
+                inner.descriptor.remove_directory_at(path).map_err(filesystem_err2io)
+            }
+            Inner::Adapter(fd) => fd.remove_directory(path),
+        }
+    }
+
+    pub fn append(&self) -> bool {
+        matches!(&self.inner, Inner::Descriptor(inner) if inner.append)
+    }
+
+    pub fn position(&self) -> Option<u64> {
+        match &self.inner {
+            Inner::Descriptor(inner) => Some(*inner.position.lock().unwrap()),
+            Inner::Adapter(_) => None,
+        }
+    }
+}
+
+impl DescriptorFd {
+    fn pread(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let len = u64::try_from(buf.len()).unwrap_or(u64::MAX);
+        let (data, _eof) = self.descriptor.read(len, offset).map_err(filesystem_err2io)?;
+        buf[..data.len()].copy_from_slice(&data);
+        Ok(data.len())
+    }
+
+    fn pwrite(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        let written = self.descriptor.write(buf, offset).map_err(filesystem_err2io)?;
+        Ok(written as usize)
+    }
+
+    fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut position = self.position.lock().unwrap();
+        let n = self.pread(buf, *position)?;
+        *position += n as u64;
+        Ok(n)
+    }
+
+    fn write(&self, buf: &[u8]) -> io::Result<usize> {
+        if self.append {
+            // Appending writes always land at the current end of the file, so
+            // the tracked position is irrelevant for them.
+            let buf = &buf[..buf.len().min(MAX_APPEND)];
+            let stream = self.descriptor.append_via_stream().map_err(filesystem_err2io)?;
+            stream.blocking_write_and_flush(buf).map_err(super::error::stream_err2io)?;
+            return Ok(buf.len());
+        }
+        let mut position = self.position.lock().unwrap();
+        let n = self.pwrite(buf, *position)?;
+        *position += n as u64;
+        Ok(n)
+    }
+}
+
+fn cross_device() -> io::Error {
+    io::Error::from_raw_os_error(wasi::ERRNO_XDEV.raw().into())
+}
+
+pub(crate) fn path_flags(flags: wasi::Lookupflags) -> PathFlags {
+    if flags & wasi::LOOKUPFLAGS_SYMLINK_FOLLOW != 0 {
+        PathFlags::SYMLINK_FOLLOW
+    } else {
+        PathFlags::empty()
+    }
+}
+
+fn new_timestamp(ts: wasi::Timestamp, set: bool) -> NewTimestamp {
+    if set {
+        NewTimestamp::Timestamp(SystemTime::from_wasi_timestamp(ts).to_wasi_datetime())
+    } else {
+        NewTimestamp::NoChange
+    }
+}
+
+pub(crate) fn path_filestat_get(
+    dir: &Descriptor,
+    flags: PathFlags,
+    path: &str,
+) -> io::Result<wasi::Filestat> {
+    let stat = dir.stat_at(flags, path).map_err(filesystem_err2io)?;
+    let hash = dir.metadata_hash_at(flags, path).map_err(filesystem_err2io)?;
+    Ok(filestat(stat, hash.lower))
+}
+
+/// Converts the result of `stat` to its preview 1 equivalent.
+///
+/// Preview 2 has no inode numbers. Like wasi-libc, the metadata hash of the
+/// file stands in for them. Timestamps the host doesn't provide are reported
+/// as zero.
+fn filestat(stat: DescriptorStat, ino: u64) -> wasi::Filestat {
+    let timestamp = |time: Option<wasip2::clocks::wall_clock::Datetime>| {
+        time.and_then(|time| SystemTime::from_wasi_datetime(time).to_wasi_timestamp()).unwrap_or(0)
+    };
+    wasi::Filestat {
+        dev: 0,
+        ino,
+        filetype: filetype(stat.type_),
+        nlink: stat.link_count,
+        size: stat.size,
+        atim: timestamp(stat.data_access_timestamp),
+        mtim: timestamp(stat.data_modification_timestamp),
+        ctim: timestamp(stat.status_change_timestamp),
+    }
+}
+
+pub(crate) fn filetype(type_: DescriptorType) -> wasi::Filetype {
+    match type_ {
+        DescriptorType::BlockDevice => wasi::FILETYPE_BLOCK_DEVICE,
+        DescriptorType::CharacterDevice => wasi::FILETYPE_CHARACTER_DEVICE,
+        DescriptorType::Directory => wasi::FILETYPE_DIRECTORY,
+        DescriptorType::SymbolicLink => wasi::FILETYPE_SYMBOLIC_LINK,
+        DescriptorType::RegularFile => wasi::FILETYPE_REGULAR_FILE,
+        DescriptorType::Socket => wasi::FILETYPE_SOCKET_STREAM,
+        DescriptorType::Fifo | DescriptorType::Unknown => wasi::FILETYPE_UNKNOWN,
+    }
+}
+
+impl IntoInner<OwnedFd> for WasiFd {
+    fn into_inner(self) -> OwnedFd {
+        match self.inner {
+            Inner::Descriptor(inner) => {
+                let parked = Parked {
+                    descriptor: inner.descriptor,
+                    position: inner.position.into_inner().unwrap(),
+                    append: inner.append,
+                };
+                inner.fd.park(parked)
+            }
+            Inner::Adapter(fd) => fd.into_inner(),
+        }
+    }
+}
+
+impl FromInner<OwnedFd> for WasiFd {
+    fn from_inner(owned_fd: OwnedFd) -> Self {
+        let inner = match ReservedFd::unpark::<Parked>(owned_fd) {
+            Ok((fd, parked)) => Inner::Descriptor(DescriptorFd {
+                descriptor: parked.descriptor,
+                position: Mutex::new(parked.position),
+                append: parked.append,
+                fd,
+            }),
+            Err(owned_fd) => Inner::Adapter(AdapterFd::from_inner(owned_fd)),
+        };
+        WasiFd { inner }
+    }
+}
+
+impl AsFd for WasiFd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        match &self.inner {
+            Inner::Descriptor(inner) => inner.fd.as_fd(),
+            Inner::Adapter(fd) => fd.as_fd(),
+        }
+    }
+}
+
+impl AsRawFd for WasiFd {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.as_fd().as_raw_fd()
+    }
+}
+
+impl IntoRawFd for WasiFd {
+    fn into_raw_fd(self) -> RawFd {
+        self.into_inner().into_raw_fd()
+    }
+}
+
+impl FromRawFd for WasiFd {
+    unsafe fn from_raw_fd(raw_fd: RawFd) -> Self {
+        unsafe { Self::from_inner(OwnedFd::from_raw_fd(raw_fd)) }
+    }
+}
+
+impl crate::fmt::Debug for WasiFd {
+    fn fmt(&self, f: &mut crate::fmt::Formatter<'_>) -> crate::fmt::Result {
+        f.debug_struct("WasiFd").field("fd", &self.as_raw_fd()).finish()
+    }
+}
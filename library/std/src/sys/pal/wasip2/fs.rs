@@ -0,0 +1,695 @@
+//! Filesystem support built directly on `wasi:filesystem`.
+//!
+//! Files opened here are backed by a `wasi:filesystem` descriptor, see
+//! `super::fd` for how they still get a file descriptor number.
+
+#![forbid(unsafe_op_in_unsafe_fn)]
+
+use wasip2::filesystem::preopens;
+use wasip2::filesystem::types::{
+    Descriptor, DescriptorFlags, DirectoryEntryStream, OpenFlags, PathFlags,
+};
+
+use super::error::filesystem_err2io;
+use super::fd::{self, WasiFd};
+use crate::ffi::{OsStr, OsString};
+use crate::fmt;
+use crate::io::{self, BorrowedCursor, IoSlice, IoSliceMut, SeekFrom};
+use crate::os::wasi::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, RawFd};
+use crate::path::{Component, Path, PathBuf};
+use crate::sync::{Arc, OnceLock};
+use crate::sys::time::SystemTime;
+use crate::sys::unsupported;
+pub use crate::sys_common::fs::{exists, remove_dir_all};
+use crate::sys_common::{AsInner, FromInner, IntoInner};
+
+#[cfg(test)]
+mod tests;
+
+pub struct File {
+    fd: WasiFd,
+}
+
+#[derive(Clone)]
+pub struct FileAttr {
+    meta: wasi::Filestat,
+}
+
+pub struct ReadDir {
+    inner: Arc<ReadDirInner>,
+    stream: Option<DirectoryEntryStream>,
+}
+
+struct ReadDirInner {
+    root: PathBuf,
+    dir: File,
+}
+
+pub struct DirEntry {
+    name: String,
+    bits: wasi::Filetype,
+    inner: Arc<ReadDirInner>,
+}
+
+#[derive(Clone, Debug)]
+pub struct OpenOptions {
+    read: bool,
+    write: bool,
+    append: bool,
+    truncate: bool,
+    create: bool,
+    create_new: bool,
+    directory: bool,
+    dirflags: wasi::Lookupflags,
+    flags: DescriptorFlags,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FilePermissions {
+    readonly: bool,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FileTimes {
+    accessed: Option<SystemTime>,
+    modified: Option<SystemTime>,
+}
+
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
+pub struct FileType {
+    bits: wasi::Filetype,
+}
+
+#[derive(Debug)]
+pub struct DirBuilder {}
+
+impl FileAttr {
+    pub fn size(&self) -> u64 {
+        self.meta.size
+    }
+
+    pub fn perm(&self) -> FilePermissions {
+        // not currently implemented in wasi yet
+        FilePermissions { readonly: false }
+    }
+
+    pub fn file_type(&self) -> FileType {
+        FileType { bits: self.meta.filetype }
+    }
+
+    pub fn modified(&self) -> io::Result<SystemTime> {
+        Ok(SystemTime::from_wasi_timestamp(self.meta.mtim))
+    }
+
+    pub fn accessed(&self) -> io::Result<SystemTime> {
+        Ok(SystemTime::from_wasi_timestamp(self.meta.atim))
+    }
+
+    pub fn created(&self) -> io::Result<SystemTime> {
+        Ok(SystemTime::from_wasi_timestamp(self.meta.ctim))
+    }
+
+    pub(crate) fn as_wasi(&self) -> &wasi::Filestat {
+        &self.meta
+    }
+}
+
+impl FilePermissions {
+    pub fn readonly(&self) -> bool {
+        self.readonly
+    }
+
+    pub fn set_readonly(&mut self, readonly: bool) {
+        self.readonly = readonly;
+    }
+}
+
+impl FileTimes {
+    pub fn set_accessed(&mut self, t: SystemTime) {
+        self.accessed = Some(t);
+    }
+
+    pub fn set_modified(&mut self, t: SystemTime) {
+        self.modified = Some(t);
+    }
+}
+
+impl FileType {
+    pub fn is_dir(&self) -> bool {
+        self.bits == wasi::FILETYPE_DIRECTORY
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.bits == wasi::FILETYPE_REGULAR_FILE
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.bits == wasi::FILETYPE_SYMBOLIC_LINK
+    }
+
+    pub(crate) fn bits(&self) -> wasi::Filetype {
+        self.bits
+    }
+}
+
+impl ReadDir {
+    fn new(dir: File, root: PathBuf) -> io::Result<ReadDir> {
+        let stream = dir.descriptor()?.read_directory().map_err(filesystem_err2io)?;
+        Ok(ReadDir { stream: Some(stream), inner: Arc::new(ReadDirInner { dir, root }) })
+    }
+}
+
+impl fmt::Debug for ReadDir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReadDir").finish_non_exhaustive()
+    }
+}
+
+impl Iterator for ReadDir {
+    type Item = io::Result<DirEntry>;
+
+    fn next(&mut self) -> Option<io::Result<DirEntry>> {
+        let stream = self.stream.as_ref()?;
+        match stream.read_directory_entry() {
+            Ok(Some(entry)) => Some(Ok(DirEntry {
+                name: entry.name,
+                bits: fd::filetype(entry.type_),
+                inner: self.inner.clone(),
+            })),
+            Ok(None) => {
+                self.stream = None;
+                None
+            }
+            Err(e) => {
+                self.stream = None;
+                Some(Err(filesystem_err2io(e)))
+            }
+        }
+    }
+}
+
+impl DirEntry {
+    pub fn path(&self) -> PathBuf {
+        self.inner.root.join(&self.name)
+    }
+
+    pub fn file_name(&self) -> OsString {
+        OsString::from(self.name.clone())
+    }
+
+    pub fn metadata(&self) -> io::Result<FileAttr> {
+        metadata_at(self.inner.dir.descriptor()?, PathFlags::empty(), &self.name)
+    }
+
+    pub fn file_type(&self) -> io::Result<FileType> {
+        Ok(FileType { bits: self.bits })
+    }
+
+    pub fn ino(&self) -> wasi::Inode {
+        // Directory entries don't carry the metadata hash that stands in for
+        // the inode number, so it has to be looked up separately.
+        self.metadata().map_or(0, |meta| meta.meta.ino)
+    }
+}
+
+impl OpenOptions {
+    pub fn new() -> OpenOptions {
+        OpenOptions {
+            read: false,
+            write: false,
+            append: false,
+            truncate: false,
+            create: false,
+            create_new: false,
+            directory: false,
+            dirflags: wasi::LOOKUPFLAGS_SYMLINK_FOLLOW,
+            flags: DescriptorFlags::empty(),
+        }
+    }
+
+    pub fn read(&mut self, read: bool) {
+        self.read = read;
+    }
+
+    pub fn write(&mut self, write: bool) {
+        self.write = write;
+    }
+
+    pub fn append(&mut self, append: bool) {
+        self.append = append;
+    }
+
+    pub fn truncate(&mut self, truncate: bool) {
+        self.truncate = truncate;
+    }
+
+    pub fn create(&mut self, create: bool) {
+        self.create = create;
+    }
+
+    pub fn create_new(&mut self, create_new: bool) {
+        self.create_new = create_new;
+    }
+
+    pub fn directory(&mut self, directory: bool) {
+        self.directory = directory;
+    }
+
+    pub fn lookup_flags(&mut self, flags: wasi::Lookupflags) {
+        self.dirflags = flags;
+    }
+
+    pub fn dsync(&mut self, set: bool) {
+        self.flags.set(DescriptorFlags::DATA_INTEGRITY_SYNC, set);
+    }
+
+    pub fn nonblock(&mut self, _set: bool) {
+        // Preview 2 descriptors are never non-blocking.
+    }
+
+    pub fn rsync(&mut self, set: bool) {
+        self.flags.set(DescriptorFlags::REQUESTED_WRITE_SYNC, set);
+    }
+
+    pub fn sync(&mut self, set: bool) {
+        self.flags.set(DescriptorFlags::FILE_INTEGRITY_SYNC, set);
+    }
+
+    pub fn fs_rights_base(&mut self, _rights: wasi::Rights) {
+        // Preview 2 has no rights beyond reading and writing.
+    }
+
+    pub fn fs_rights_inheriting(&mut self, _rights: wasi::Rights) {
+        // Preview 2 has no rights beyond reading and writing.
+    }
+
+    fn open_flags(&self) -> OpenFlags {
+        let mut flags = OpenFlags::empty();
+        if self.create || self.create_new {
+            flags = flags | OpenFlags::CREATE;
+        }
+        if self.create_new {
+            flags = flags | OpenFlags::EXCLUSIVE;
+        }
+        if self.truncate {
+            flags = flags | OpenFlags::TRUNCATE;
+        }
+        if self.directory {
+            flags = flags | OpenFlags::DIRECTORY;
+        }
+        flags
+    }
+
+    fn descriptor_flags(&self) -> DescriptorFlags {
+        let mut flags = self.flags;
+        if self.read {
+            flags = flags | DescriptorFlags::READ;
+        }
+        if self.write || self.append {
+            flags = flags | DescriptorFlags::WRITE;
+        }
+        flags
+    }
+}
+
+impl File {
+    pub fn open(path: &Path, opts: &OpenOptions) -> io::Result<File> {
+        let (dir, file) = open_parent(path)?;
+        open_at(dir, &file, opts)
+    }
+
+    pub fn open_at(&self, path: &Path, opts: &OpenOptions) -> io::Result<File> {
+        open_at(self.descriptor()?, path, opts)
+    }
+
+    /// Returns the `wasi:filesystem` descriptor of this file, or an error if
+    /// it wasn't opened by std.
+    pub(crate) fn descriptor(&self) -> io::Result<&Descriptor> {
+        self.fd.descriptor().ok_or_else(|| {
+            io::const_io_error!(
+                io::ErrorKind::Unsupported,
+                "operation not supported on file descriptors not opened by std",
+            )
+        })
+    }
+
+    pub fn file_attr(&self) -> io::Result<FileAttr> {
+        self.fd.filestat_get().map(|meta| FileAttr { meta })
+    }
+
+    pub fn metadata_at(&self, flags: wasi::Lookupflags, path: &Path) -> io::Result<FileAttr> {
+        let meta = self.fd.path_filestat_get(flags, osstr2str(path.as_ref())?)?;
+        Ok(FileAttr { meta })
+    }
+
+    pub fn fsync(&self) -> io::Result<()> {
+        self.fd.sync()
+    }
+
+    pub fn datasync(&self) -> io::Result<()> {
+        self.fd.datasync()
+    }
+
+    pub fn truncate(&self, size: u64) -> io::Result<()> {
+        self.fd.filestat_set_size(size)
+    }
+
+    pub fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_vectored(&mut [IoSliceMut::new(buf)])
+    }
+
+    pub fn read_vectored(&self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        self.fd.read(bufs)
+    }
+
+    #[inline]
+    pub fn is_read_vectored(&self) -> bool {
+        false
+    }
+
+    pub fn read_buf(&self, cursor: BorrowedCursor<'_>) -> io::Result<()> {
+        self.fd.read_buf(cursor)
+    }
+
+    pub fn write(&self, buf: &[u8]) -> io::Result<usize> {
+        self.write_vectored(&[IoSlice::new(buf)])
+    }
+
+    pub fn write_vectored(&self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.fd.write(bufs)
+    }
+
+    #[inline]
+    pub fn is_write_vectored(&self) -> bool {
+        false
+    }
+
+    pub fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    pub fn seek(&self, pos: SeekFrom) -> io::Result<u64> {
+        self.fd.seek(pos)
+    }
+
+    pub fn duplicate(&self) -> io::Result<File> {
+        // https://github.com/CraneStation/wasmtime/blob/master/docs/WASI-rationale.md#why-no-dup
+        unsupported()
+    }
+
+    pub fn set_permissions(&self, _perm: FilePermissions) -> io::Result<()> {
+        // Permissions haven't been fully figured out in wasi yet, so this is
+        // likely temporary
+        unsupported()
+    }
+
+    pub fn set_times(&self, times: FileTimes) -> io::Result<()> {
+        let to_timestamp = |time: Option<SystemTime>| match time {
+            Some(time) if let Some(ts) = time.to_wasi_timestamp() => Ok(ts),
+            Some(_) => Err(io::const_io_error!(
+                io::ErrorKind::InvalidInput,
+                "timestamp is too large to set as a file time"
+            )),
+            None => Ok(0),
+        };
+        self.fd.filestat_set_times(
+            to_timestamp(times.accessed)?,
+            to_timestamp(times.modified)?,
+            times.accessed.map_or(0, |_| wasi::FSTFLAGS_ATIM)
+                | times.modified.map_or(0, |_| wasi::FSTFLAGS_MTIM),
+        )
+    }
+
+    pub fn read_link(&self, file: &Path) -> io::Result<PathBuf> {
+        readlink_at(self.descriptor()?, file)
+    }
+}
+
+impl AsInner<WasiFd> for File {
+    #[inline]
+    fn as_inner(&self) -> &WasiFd {
+        &self.fd
+    }
+}
+
+impl IntoInner<WasiFd> for File {
+    fn into_inner(self) -> WasiFd {
+        self.fd
+    }
+}
+
+impl FromInner<WasiFd> for File {
+    fn from_inner(fd: WasiFd) -> File {
+        File { fd }
+    }
+}
+
+impl AsFd for File {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl AsRawFd for File {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl IntoRawFd for File {
+    fn into_raw_fd(self) -> RawFd {
+        self.fd.into_raw_fd()
+    }
+}
+
+impl FromRawFd for File {
+    unsafe fn from_raw_fd(raw_fd: RawFd) -> Self {
+        unsafe { Self { fd: FromRawFd::from_raw_fd(raw_fd) } }
+    }
+}
+
+impl DirBuilder {
+    pub fn new() -> DirBuilder {
+        DirBuilder {}
+    }
+
+    pub fn mkdir(&self, p: &Path) -> io::Result<()> {
+        let (dir, file) = open_parent(p)?;
+        dir.create_directory_at(osstr2str(file.as_ref())?).map_err(filesystem_err2io)
+    }
+}
+
+impl fmt::Debug for File {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("File");
+        debug.field("fd", &self.as_raw_fd());
+        if let Some(position) = self.fd.position() {
+            debug.field("position", &position).field("append", &self.fd.append());
+        }
+        debug.finish()
+    }
+}
+
+pub fn readdir(p: &Path) -> io::Result<ReadDir> {
+    let mut opts = OpenOptions::new();
+    opts.directory = true;
+    opts.read(true);
+    let dir = File::open(p, &opts)?;
+    ReadDir::new(dir, p.to_path_buf())
+}
+
+pub fn unlink(p: &Path) -> io::Result<()> {
+    let (dir, file) = open_parent(p)?;
+    dir.unlink_file_at(osstr2str(file.as_ref())?).map_err(filesystem_err2io)
+}
+
+pub fn rename(old: &Path, new: &Path) -> io::Result<()> {
+    let (old, old_file) = open_parent(old)?;
+    let (new, new_file) = open_parent(new)?;
+    old.rename_at(osstr2str(old_file.as_ref())?, new, osstr2str(new_file.as_ref())?)
+        .map_err(filesystem_err2io)
+}
+
+pub fn set_perm(_p: &Path, _perm: FilePermissions) -> io::Result<()> {
+    // Permissions haven't been fully figured out in wasi yet, so this is
+    // likely temporary
+    unsupported()
+}
+
+pub fn rmdir(p: &Path) -> io::Result<()> {
+    let (dir, file) = open_parent(p)?;
+    dir.remove_directory_at(osstr2str(file.as_ref())?).map_err(filesystem_err2io)
+}
+
+pub fn readlink(p: &Path) -> io::Result<PathBuf> {
+    let (dir, file) = open_parent(p)?;
+    readlink_at(dir, &file)
+}
+
+fn readlink_at(dir: &Descriptor, file: &Path) -> io::Result<PathBuf> {
+    let target = dir.readlink_at(osstr2str(file.as_ref())?).map_err(filesystem_err2io)?;
+    Ok(PathBuf::from(target))
+}
+
+pub fn symlink(original: &Path, link: &Path) -> io::Result<()> {
+    let (link, link_file) = open_parent(link)?;
+    link.symlink_at(osstr2str(original.as_ref())?, osstr2str(link_file.as_ref())?)
+        .map_err(filesystem_err2io)
+}
+
+pub fn link(original: &Path, link: &Path) -> io::Result<()> {
+    let (original, original_file) = open_parent(original)?;
+    let (link, link_file) = open_parent(link)?;
+    // Pass no path flags, meaning don't follow symlinks.
+    original
+        .link_at(
+            PathFlags::empty(),
+            osstr2str(original_file.as_ref())?,
+            link,
+            osstr2str(link_file.as_ref())?,
+        )
+        .map_err(filesystem_err2io)
+}
+
+pub fn stat(p: &Path) -> io::Result<FileAttr> {
+    let (dir, file) = open_parent(p)?;
+    metadata_at(dir, PathFlags::SYMLINK_FOLLOW, osstr2str(file.as_ref())?)
+}
+
+pub fn lstat(p: &Path) -> io::Result<FileAttr> {
+    let (dir, file) = open_parent(p)?;
+    metadata_at(dir, PathFlags::empty(), osstr2str(file.as_ref())?)
+}
+
+fn metadata_at(dir: &Descriptor, flags: PathFlags, path: &str) -> io::Result<FileAttr> {
+    fd::path_filestat_get(dir, flags, path).map(|meta| FileAttr { meta })
+}
+
+pub fn canonicalize(_p: &Path) -> io::Result<PathBuf> {
+    // This seems to not be in wasi's API yet, and we may need to end up
+    // emulating it ourselves. For now just return an error.
+    unsupported()
+}
+
+fn open_at(dir: &Descriptor, path: &Path, opts: &OpenOptions) -> io::Result<File> {
+    let descriptor = dir
+        .open_at(
+            fd::path_flags(opts.dirflags),
+            osstr2str(path.as_ref())?,
+            opts.open_flags(),
+            opts.descriptor_flags(),
+        )
+        .map_err(filesystem_err2io)?;
+    Ok(File { fd: WasiFd::from_descriptor(descriptor, opts.append)? })
+}
+
+/// A directory the host made available to the component, along with the
+/// absolute guest path it is mounted at.
+pub(crate) struct Preopen {
+    pub(crate) dir: Descriptor,
+    pub(crate) path: PathBuf,
+}
+
+/// Returns the directories preopened by the host.
+///
+/// `wasi:filesystem/preopens` hands out a new set of handles every time it is
+/// called, so the list is fetched once and kept for the lifetime of the
+/// component.
+pub(crate) fn preopens() -> &'static [Preopen] {
+    static PREOPENS: OnceLock<Vec<Preopen>> = OnceLock::new();
+    PREOPENS.get_or_init(|| {
+        preopens::get_directories()
+            .into_iter()
+            .map(|(dir, path)| Preopen { dir, path: PathBuf::from(path) })
+            .collect()
+    })
+}
+
+/// Attempts to open a bare path `p`.
+///
+/// As with preview 1, all filesystem access happens relative to a directory
+/// handle. Relative paths are first made absolute using the current working
+/// directory, and the result is then matched component-wise against the
+/// guest paths of the preopened directories, picking the longest match. A
+/// preopen named `.` is treated as the root directory.
+///
+/// If successful, the preopened directory and the path relative to it are
+/// returned.
+fn open_parent(p: &Path) -> io::Result<(&'static Descriptor, PathBuf)> {
+    let absolute;
+    let path = if p.is_absolute() {
+        p
+    } else {
+        absolute = super::os::getcwd()?.join(p);
+        &absolute
+    };
+    let preopens = preopens();
+    let Some((index, relative)) = find_preopen(path, preopens.iter().map(|p| &*p.path)) else {
+        let msg = format!(
+            "failed to find a pre-opened file descriptor \
+             through which {:?} could be opened",
+            p
+        );
+        return Err(io::Error::new(io::ErrorKind::Uncategorized, msg));
+    };
+    Ok((&preopens[index].dir, relative))
+}
+
+/// Returns the index of the preopen that `path` lies in, along with the path
+/// relative to it.
+fn find_preopen<'a>(
+    path: &Path,
+    preopens: impl Iterator<Item = &'a Path>,
+) -> Option<(usize, PathBuf)> {
+    let components = normalized_components(path);
+    let mut best: Option<(usize, usize)> = None;
+    for (index, preopen) in preopens.enumerate() {
+        let prefix = normalized_components(preopen);
+        if components.starts_with(&prefix) && best.map_or(true, |(_, len)| prefix.len() > len) {
+            best = Some((index, prefix.len()));
+        }
+    }
+    let (index, len) = best?;
+    let relative: PathBuf = components[len..].iter().collect();
+    if relative.as_os_str().is_empty() {
+        Some((index, PathBuf::from(".")))
+    } else {
+        Some((index, relative))
+    }
+}
+
+/// Returns the components of the absolute `path` that are meaningful for
+/// matching it against a preopen.
+///
+/// `.` components are dropped, and `..` components remove the component
+/// before them, staying at the root like they do on the host. Without this,
+/// a path like `/a/../b` would be matched against a preopen at `/a` and then
+/// rejected by the host for leaving it, even if `/b` is preopened too.
+fn normalized_components(path: &Path) -> Vec<Component<'_>> {
+    let mut components = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(_) => components.push(component),
+            Component::ParentDir => {
+                components.pop();
+            }
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+        }
+    }
+    components
+}
+
+pub fn osstr2str(f: &OsStr) -> io::Result<&str> {
+    f.to_str()
+        .ok_or_else(|| io::const_io_error!(io::ErrorKind::Uncategorized, "input must be utf-8"))
+}
+
+pub fn copy(from: &Path, to: &Path) -> io::Result<u64> {
+    use crate::fs::File;
+
+    let mut reader = File::open(from)?;
+    let mut writer = File::create(to)?;
+
+    io::copy(&mut reader, &mut writer)
+}
@@ -0,0 +1,81 @@
+use super::find_preopen;
+use crate::fs;
+use crate::io::prelude::*;
+use crate::os::wasi::fs::FileExt;
+use crate::os::wasi::io::{AsRawFd, FromRawFd, IntoRawFd, OwnedFd};
+use crate::path::{Path, PathBuf};
+use crate::sys_common::io::test::tmpdir;
+
+fn find(path: &str, preopens: &[&str]) -> Option<(usize, PathBuf)> {
+    find_preopen(Path::new(path), preopens.iter().map(Path::new))
+}
+
+#[test]
+fn longest_preopen_wins() {
+    let preopens = ["/", "/tmp", "/tmp/a"];
+    assert_eq!(find("/tmp/a/b", &preopens), Some((2, PathBuf::from("b"))));
+    assert_eq!(find("/tmp/ab", &preopens), Some((1, PathBuf::from("ab"))));
+    assert_eq!(find("/etc", &preopens), Some((0, PathBuf::from("etc"))));
+    assert_eq!(find("/tmp", &preopens), Some((1, PathBuf::from("."))));
+}
+
+#[test]
+fn current_dir_preopen_is_the_root() {
+    assert_eq!(find("/a/b", &["."]), Some((0, PathBuf::from("a/b"))));
+    assert_eq!(find("/a/b", &["./a"]), Some((0, PathBuf::from("b"))));
+}
+
+#[test]
+fn parent_dir_components_are_resolved() {
+    let preopens = ["/a", "/b"];
+    assert_eq!(find("/a/../b/c", &preopens), Some((1, PathBuf::from("c"))));
+    assert_eq!(find("/a/./x/../y", &preopens), Some((0, PathBuf::from("y"))));
+    // `..` at the root stays at the root.
+    assert_eq!(find("/../../b", &preopens), Some((1, PathBuf::from("."))));
+    // Leaving every preopen is not possible.
+    assert_eq!(find("/a/../c", &preopens), None);
+}
+
+#[test]
+fn raw_fd_round_trip() {
+    let tmpdir = tmpdir();
+    let path = tmpdir.join("file");
+    let mut file = fs::File::create(&path).unwrap();
+    file.write_all(b"hello").unwrap();
+
+    // Files get distinct descriptor numbers, and keep them across conversions.
+    let other = fs::File::create(tmpdir.join("other")).unwrap();
+    assert_ne!(file.as_raw_fd(), other.as_raw_fd());
+    let fd = file.into_raw_fd();
+    let mut file = unsafe { fs::File::from_raw_fd(fd) };
+    assert_eq!(file.as_raw_fd(), fd);
+
+    // The file position survives the round trip.
+    file.write_all(b" world").unwrap();
+    let file = fs::File::from(OwnedFd::from(file));
+    assert_eq!(file.tell().unwrap(), 11);
+    drop(file);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "hello world");
+}
+
+#[test]
+fn file_ext() {
+    let tmpdir = tmpdir();
+    let dir = fs::File::open(tmpdir.path()).unwrap();
+    dir.create_directory("sub").unwrap();
+    assert!(dir.metadata_at(0, "sub").unwrap().is_dir());
+    dir.remove_directory("sub").unwrap();
+
+    let file = fs::File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .open(tmpdir.join("file"))
+        .unwrap();
+    assert_eq!(file.write_at(b"abcdef", 2).unwrap(), 6);
+    let mut buf = [0; 4];
+    assert_eq!(file.read_at(&mut buf, 4).unwrap(), 4);
+    assert_eq!(&buf, b"cdef");
+    // Positional I/O leaves the file position alone.
+    assert_eq!(file.tell().unwrap(), 0);
+}
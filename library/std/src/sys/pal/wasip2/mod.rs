@@ -3,36 +3,38 @@
 //! This is the next evolution of the original wasi target, and is intended to
 //! replace that target over time.
 //!
-//! To begin with, this target mirrors the wasi target 1 to 1, but over
-//! time this will change significantly.
+//! Command line arguments, clocks, the filesystem, networking, random data and
+//! stdio are implemented directly on top of the preview 2 component-model
+//! interfaces (`wasi:cli`, `wasi:clocks`, `wasi:filesystem`, `wasi:sockets`,
+//! `wasi:random` and `wasi:io`). Anything else that isn't provided by
+//! wasi-libc still shares its implementation with the preview 1 target for
+//! now.
 
-#[path = "../wasi/args.rs"]
 pub mod args;
 #[path = "../wasi/env.rs"]
 pub mod env;
-#[path = "../wasi/fd.rs"]
 pub mod fd;
-#[path = "../wasi/fs.rs"]
 pub mod fs;
 #[allow(unused)]
 #[path = "../wasm/atomics/futex.rs"]
 pub mod futex;
 #[path = "../wasi/io.rs"]
 pub mod io;
-
-#[path = "../wasi/net.rs"]
 pub mod net;
 #[path = "../wasi/os.rs"]
 pub mod os;
+#[cfg(feature = "wasi_process")]
+pub mod pipe;
+#[cfg(not(feature = "wasi_process"))]
 #[path = "../unsupported/pipe.rs"]
 pub mod pipe;
+#[cfg(feature = "wasi_process")]
+pub mod process;
+#[cfg(not(feature = "wasi_process"))]
 #[path = "../unsupported/process.rs"]
 pub mod process;
-#[path = "../wasi/stdio.rs"]
 pub mod stdio;
-#[path = "../wasi/thread.rs"]
 pub mod thread;
-#[path = "../wasi/time.rs"]
 pub mod time;
 
 #[path = "../unsupported/common.rs"]
@@ -42,6 +44,8 @@
 
 pub use common::*;
 
+mod error;
+#[allow(unused)]
 #[path = "../wasi/helpers.rs"]
 mod helpers;
 
@@ -0,0 +1,998 @@
+//! Networking on top of `wasi:sockets`.
+//!
+//! Sockets in preview 2 are resources whose operations never block; instead
+//! they report `would-block` and hand out a `pollable` to wait on. Blocking
+//! behaviour, timeouts and `set_nonblocking` are therefore all implemented here
+//! by deciding whether (and for how long) to wait on that pollable.
+//!
+//! Each socket also reserves a descriptor number so that it can implement the
+//! `AsFd` family of traits, see [`super::fd`].
+
+#![forbid(unsafe_op_in_unsafe_fn)]
+
+use wasip2::clocks::monotonic_clock;
+use wasip2::io::poll::{self, Pollable};
+use wasip2::io::streams::{InputStream, OutputStream, StreamError};
+use wasip2::sockets::network::{
+    ErrorCode, IpAddress, IpAddressFamily, IpSocketAddress, Ipv4SocketAddress, Ipv6SocketAddress,
+};
+use wasip2::sockets::tcp::{ShutdownType, TcpSocket};
+use wasip2::sockets::udp::{IncomingDatagramStream, OutgoingDatagram, OutgoingDatagramStream};
+use wasip2::sockets::{
+    instance_network, ip_name_lookup, tcp_create_socket, udp, udp_create_socket,
+};
+
+use super::error::{sockets_err2io, stream_err2io};
+use super::fd::{AdapterFd, ReservedFd};
+use crate::io::{self, BorrowedCursor, IoSlice, IoSliceMut};
+use crate::net::{Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, SocketAddrV4, SocketAddrV6};
+use crate::os::wasi::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use crate::sync::Mutex;
+use crate::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use crate::sys::unsupported;
+use crate::sys_common::{FromInner, IntoInner};
+use crate::time::Duration;
+use crate::{fmt, slice, vec};
+
+#[cfg(test)]
+mod tests;
+
+fn family(addr: &SocketAddr) -> IpAddressFamily {
+    match addr {
+        SocketAddr::V4(_) => IpAddressFamily::Ipv4,
+        SocketAddr::V6(_) => IpAddressFamily::Ipv6,
+    }
+}
+
+fn to_wasi_addr(addr: &SocketAddr) -> IpSocketAddress {
+    match addr {
+        SocketAddr::V4(addr) => {
+            let [a, b, c, d] = addr.ip().octets();
+            IpSocketAddress::Ipv4(Ipv4SocketAddress { port: addr.port(), address: (a, b, c, d) })
+        }
+        SocketAddr::V6(addr) => {
+            let [a, b, c, d, e, f, g, h] = addr.ip().segments();
+            IpSocketAddress::Ipv6(Ipv6SocketAddress {
+                port: addr.port(),
+                flow_info: addr.flowinfo(),
+                address: (a, b, c, d, e, f, g, h),
+                scope_id: addr.scope_id(),
+            })
+        }
+    }
+}
+
+fn from_wasi_addr(addr: IpSocketAddress) -> SocketAddr {
+    match addr {
+        IpSocketAddress::Ipv4(addr) => {
+            let (a, b, c, d) = addr.address;
+            SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(a, b, c, d), addr.port))
+        }
+        IpSocketAddress::Ipv6(addr) => {
+            let (a, b, c, d, e, f, g, h) = addr.address;
+            SocketAddr::V6(SocketAddrV6::new(
+                Ipv6Addr::new(a, b, c, d, e, f, g, h),
+                addr.port,
+                addr.flow_info,
+                addr.scope_id,
+            ))
+        }
+    }
+}
+
+fn from_wasi_ip(ip: IpAddress, port: u16) -> SocketAddr {
+    match ip {
+        IpAddress::Ipv4((a, b, c, d)) => {
+            SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(a, b, c, d), port))
+        }
+        IpAddress::Ipv6((a, b, c, d, e, f, g, h)) => {
+            SocketAddr::V6(SocketAddrV6::new(Ipv6Addr::new(a, b, c, d, e, f, g, h), port, 0, 0))
+        }
+    }
+}
+
+/// Drives a `start-*`/`finish-*` style operation to completion, waiting on
+/// `subscribe` for as long as it reports `would-block`.
+fn finish<T>(
+    subscribe: impl Fn() -> Pollable,
+    mut op: impl FnMut() -> Result<T, ErrorCode>,
+) -> io::Result<T> {
+    loop {
+        match op() {
+            Err(ErrorCode::WouldBlock) => subscribe().block(),
+            result => return result.map_err(sockets_err2io),
+        }
+    }
+}
+
+/// Blocks until `pollable` is ready, giving up once `timeout` has elapsed.
+fn wait(pollable: &Pollable, timeout: Option<Duration>) -> io::Result<()> {
+    match timeout {
+        Some(timeout) => wait_until(pollable, &timer(timeout)),
+        None => {
+            pollable.block();
+            Ok(())
+        }
+    }
+}
+
+/// Returns a pollable that becomes ready once `timeout` has elapsed.
+fn timer(timeout: Duration) -> Pollable {
+    monotonic_clock::subscribe_duration(u64::try_from(timeout.as_nanos()).unwrap_or(u64::MAX))
+}
+
+/// Blocks until `pollable` is ready, giving up once `timer` is.
+fn wait_until(pollable: &Pollable, timer: &Pollable) -> io::Result<()> {
+    if poll::poll(&[pollable, timer]).contains(&0) {
+        Ok(())
+    } else {
+        Err(io::const_io_error!(io::ErrorKind::TimedOut, "operation timed out"))
+    }
+}
+
+fn hop_limit(ttl: u32) -> io::Result<u8> {
+    u8::try_from(ttl)
+        .map_err(|_| io::const_io_error!(io::ErrorKind::InvalidInput, "ttl must be at most 255"))
+}
+
+/// A read or write timeout, stored as nanoseconds with zero meaning "none".
+struct Timeout(AtomicU64);
+
+impl Timeout {
+    const fn new() -> Timeout {
+        Timeout(AtomicU64::new(0))
+    }
+
+    fn get(&self) -> Option<Duration> {
+        match self.0.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => Some(Duration::from_nanos(nanos)),
+        }
+    }
+
+    fn set(&self, dur: Option<Duration>) -> io::Result<()> {
+        let nanos = match dur {
+            Some(dur) if dur.is_zero() => {
+                return Err(io::const_io_error!(
+                    io::ErrorKind::InvalidInput,
+                    "cannot set a 0 duration timeout",
+                ));
+            }
+            Some(dur) => u64::try_from(dur.as_nanos()).unwrap_or(u64::MAX),
+            None => 0,
+        };
+        self.0.store(nanos, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Shared blocking state of a socket: how long to wait in either direction,
+/// and whether to wait at all.
+struct Blocking {
+    read_timeout: Timeout,
+    write_timeout: Timeout,
+    nonblocking: AtomicBool,
+}
+
+impl Blocking {
+    const fn new() -> Blocking {
+        Blocking {
+            read_timeout: Timeout::new(),
+            write_timeout: Timeout::new(),
+            nonblocking: AtomicBool::new(false),
+        }
+    }
+
+    fn wait_read(&self, pollable: Pollable) -> io::Result<()> {
+        self.wait(pollable, &self.read_timeout)
+    }
+
+    fn wait_write(&self, pollable: Pollable) -> io::Result<()> {
+        self.wait(pollable, &self.write_timeout)
+    }
+
+    fn wait(&self, pollable: Pollable, timeout: &Timeout) -> io::Result<()> {
+        if self.nonblocking.load(Ordering::Relaxed) {
+            return Err(sockets_err2io(ErrorCode::WouldBlock));
+        }
+        wait(&pollable, timeout.get())
+    }
+}
+
+/// The descriptor number of a socket, see [`super::fd`].
+pub struct Socket(SocketFd);
+
+enum SocketFd {
+    /// Reserved by std for a `wasi:sockets` resource.
+    Reserved(ReservedFd),
+    /// Created by someone else, or a number whose resource is parked.
+    Adapter(AdapterFd),
+}
+
+impl Socket {
+    fn new() -> io::Result<Socket> {
+        ReservedFd::new().map(|fd| Socket(SocketFd::Reserved(fd)))
+    }
+
+    /// Parks `resource` under this number, see [`ReservedFd::park`].
+    fn park<T: Send + 'static>(self, resource: T) -> Socket {
+        match self.0 {
+            SocketFd::Reserved(fd) => {
+                Socket(SocketFd::Adapter(AdapterFd::from_inner(fd.park(resource))))
+            }
+            SocketFd::Adapter(fd) => Socket(SocketFd::Adapter(fd)),
+        }
+    }
+
+    /// Picks up the resource parked under this number, if there is one.
+    fn unpark<T: Send + 'static>(self) -> (Option<T>, Socket) {
+        let fd = match self.0 {
+            SocketFd::Reserved(fd) => return (None, Socket(SocketFd::Reserved(fd))),
+            SocketFd::Adapter(fd) => fd,
+        };
+        match ReservedFd::unpark::<T>(fd.into_inner()) {
+            Ok((fd, resource)) => (Some(resource), Socket(SocketFd::Reserved(fd))),
+            Err(fd) => (None, Socket(SocketFd::Adapter(AdapterFd::from_inner(fd)))),
+        }
+    }
+}
+
+impl AsFd for Socket {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        match &self.0 {
+            SocketFd::Reserved(fd) => fd.as_fd(),
+            SocketFd::Adapter(fd) => fd.as_fd(),
+        }
+    }
+}
+
+impl AsRawFd for Socket {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.as_fd().as_raw_fd()
+    }
+}
+
+impl IntoInner<AdapterFd> for Socket {
+    fn into_inner(self) -> AdapterFd {
+        match self.0 {
+            // Every socket std creates parks its resource before giving the
+            // number away, so there is nothing to keep here.
+            SocketFd::Reserved(fd) => AdapterFd::from_inner(fd.park(())),
+            SocketFd::Adapter(fd) => fd,
+        }
+    }
+}
+
+impl FromInner<AdapterFd> for Socket {
+    fn from_inner(fd: AdapterFd) -> Socket {
+        Socket(SocketFd::Adapter(fd))
+    }
+}
+
+impl fmt::Debug for Socket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Socket").field("fd", &self.as_raw_fd()).finish()
+    }
+}
+
+fn not_std_socket<T>() -> io::Result<T> {
+    Err(io::const_io_error!(
+        io::ErrorKind::Unsupported,
+        "operation not supported on sockets that weren't created by std",
+    ))
+}
+
+pub struct TcpStream {
+    // Declared before `fd` so that the resources are gone before their
+    // number is released. `None` for sockets that std didn't create.
+    inner: Option<TcpStreamInner>,
+    fd: Socket,
+}
+
+struct TcpStreamInner {
+    // The streams are children of `socket` and have to be dropped first.
+    input: InputStream,
+    output: OutputStream,
+    socket: TcpSocket,
+    // Bytes returned by `peek` that haven't been consumed by `read` yet.
+    peeked: Mutex<Vec<u8>>,
+    blocking: Blocking,
+}
+
+impl TcpStream {
+    fn new(socket: TcpSocket, input: InputStream, output: OutputStream) -> io::Result<TcpStream> {
+        let fd = Socket::new()?;
+        let inner = TcpStreamInner {
+            input,
+            output,
+            socket,
+            peeked: Mutex::new(Vec::new()),
+            blocking: Blocking::new(),
+        };
+        Ok(TcpStream { inner: Some(inner), fd })
+    }
+
+    fn inner(&self) -> io::Result<&TcpStreamInner> {
+        self.inner.as_ref().map_or_else(not_std_socket, Ok)
+    }
+
+    fn start_connect(addr: &SocketAddr) -> io::Result<TcpSocket> {
+        let socket = tcp_create_socket::create_tcp_socket(family(addr)).map_err(sockets_err2io)?;
+        let network = instance_network::instance_network();
+        socket.start_connect(&network, to_wasi_addr(addr)).map_err(sockets_err2io)?;
+        Ok(socket)
+    }
+
+    pub fn connect(addr: io::Result<&SocketAddr>) -> io::Result<TcpStream> {
+        let socket = TcpStream::start_connect(addr?)?;
+        let (input, output) = finish(|| socket.subscribe(), || socket.finish_connect())?;
+        TcpStream::new(socket, input, output)
+    }
+
+    pub fn connect_timeout(addr: &SocketAddr, timeout: Duration) -> io::Result<TcpStream> {
+        if timeout.is_zero() {
+            return Err(io::const_io_error!(
+                io::ErrorKind::InvalidInput,
+                "cannot set a 0 duration timeout",
+            ));
+        }
+        let socket = TcpStream::start_connect(addr)?;
+        // The timeout covers the whole connection attempt, not each wakeup.
+        let deadline = timer(timeout);
+        loop {
+            match socket.finish_connect() {
+                Ok((input, output)) => return TcpStream::new(socket, input, output),
+                Err(ErrorCode::WouldBlock) => wait_until(&socket.subscribe(), &deadline)?,
+                Err(e) => return Err(sockets_err2io(e)),
+            }
+        }
+    }
+
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.inner()?.blocking.read_timeout.set(timeout)
+    }
+
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.inner()?.blocking.write_timeout.set(timeout)
+    }
+
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        Ok(self.inner()?.blocking.read_timeout.get())
+    }
+
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        Ok(self.inner()?.blocking.write_timeout.get())
+    }
+
+    pub fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        let inner = self.inner()?;
+        let mut peeked = inner.peeked.lock().unwrap();
+        if peeked.is_empty() {
+            *peeked = inner.recv(buf.len())?;
+        }
+        let n = buf.len().min(peeked.len());
+        buf[..n].copy_from_slice(&peeked[..n]);
+        Ok(n)
+    }
+
+    pub fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        let inner = self.inner()?;
+        let mut peeked = inner.peeked.lock().unwrap();
+        let data = if peeked.is_empty() { inner.recv(buf.len())? } else { peeked.split_off(0) };
+        let n = buf.len().min(data.len());
+        buf[..n].copy_from_slice(&data[..n]);
+        // Keep anything that didn't fit for the next read.
+        peeked.extend_from_slice(&data[n..]);
+        Ok(n)
+    }
+
+    pub fn read_buf(&self, cursor: BorrowedCursor<'_>) -> io::Result<()> {
+        crate::io::default_read_buf(|buf| self.read(buf), cursor)
+    }
+
+    pub fn read_vectored(&self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        crate::io::default_read_vectored(|buf| self.read(buf), bufs)
+    }
+
+    pub fn is_read_vectored(&self) -> bool {
+        false
+    }
+
+    pub fn write(&self, buf: &[u8]) -> io::Result<usize> {
+        let inner = self.inner()?;
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            match inner.output.check_write() {
+                Ok(0) => inner.blocking.wait_write(inner.output.subscribe())?,
+                Ok(permit) => {
+                    let n = buf.len().min(usize::try_from(permit).unwrap_or(usize::MAX));
+                    inner.output.write(&buf[..n]).map_err(stream_err2io)?;
+                    // Start sending right away rather than waiting for the
+                    // host to decide the buffer is full enough.
+                    inner.output.flush().map_err(stream_err2io)?;
+                    return Ok(n);
+                }
+                Err(e) => return Err(stream_err2io(e)),
+            }
+        }
+    }
+
+    pub fn write_vectored(&self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        crate::io::default_write_vectored(|buf| self.write(buf), bufs)
+    }
+
+    pub fn is_write_vectored(&self) -> bool {
+        false
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.inner()?.socket.remote_address().map(from_wasi_addr).map_err(sockets_err2io)
+    }
+
+    pub fn socket_addr(&self) -> io::Result<SocketAddr> {
+        self.inner()?.socket.local_address().map(from_wasi_addr).map_err(sockets_err2io)
+    }
+
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        let how = match how {
+            Shutdown::Read => ShutdownType::Receive,
+            Shutdown::Write => ShutdownType::Send,
+            Shutdown::Both => ShutdownType::Both,
+        };
+        self.inner()?.socket.shutdown(how).map_err(sockets_err2io)
+    }
+
+    pub fn duplicate(&self) -> io::Result<TcpStream> {
+        unsupported()
+    }
+
+    pub fn set_linger(&self, _: Option<Duration>) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub fn linger(&self) -> io::Result<Option<Duration>> {
+        unsupported()
+    }
+
+    pub fn set_nodelay(&self, _: bool) -> io::Result<()> {
+        // `wasi:sockets` has no equivalent of `TCP_NODELAY`.
+        unsupported()
+    }
+
+    pub fn nodelay(&self) -> io::Result<bool> {
+        unsupported()
+    }
+
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        self.inner()?.socket.set_hop_limit(hop_limit(ttl)?).map_err(sockets_err2io)
+    }
+
+    pub fn ttl(&self) -> io::Result<u32> {
+        self.inner()?.socket.hop_limit().map(u32::from).map_err(sockets_err2io)
+    }
+
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        // Errors are reported by the operation that ran into them; there is
+        // no pending error state to retrieve.
+        Ok(None)
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.inner()?.blocking.nonblocking.store(nonblocking, Ordering::Relaxed);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn socket(&self) -> &Socket {
+        &self.fd
+    }
+
+    pub fn into_socket(self) -> Socket {
+        match self.inner {
+            Some(inner) => self.fd.park(inner),
+            None => self.fd,
+        }
+    }
+}
+
+impl TcpStreamInner {
+    /// Reads up to `len` bytes from the input stream, waiting for data if
+    /// none is available yet. An empty result means end of stream.
+    fn recv(&self, len: usize) -> io::Result<Vec<u8>> {
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+        loop {
+            match self.input.read(len as u64) {
+                Ok(data) if data.is_empty() => self.blocking.wait_read(self.input.subscribe())?,
+                Ok(data) => return Ok(data),
+                Err(StreamError::Closed) => return Ok(Vec::new()),
+                Err(e) => return Err(stream_err2io(e)),
+            }
+        }
+    }
+}
+
+impl FromInner<Socket> for TcpStream {
+    fn from_inner(socket: Socket) -> TcpStream {
+        let (inner, fd) = socket.unpark();
+        TcpStream { inner, fd }
+    }
+}
+
+impl fmt::Debug for TcpStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut res = f.debug_struct("TcpStream");
+
+        if let Ok(addr) = self.socket_addr() {
+            res.field("addr", &addr);
+        }
+
+        if let Ok(peer) = self.peer_addr() {
+            res.field("peer", &peer);
+        }
+
+        res.field("fd", &self.fd.as_raw_fd()).finish()
+    }
+}
+
+pub struct TcpListener {
+    // `None` for sockets that std didn't create, see `TcpStream`.
+    inner: Option<TcpListenerInner>,
+    fd: Socket,
+}
+
+struct TcpListenerInner {
+    socket: TcpSocket,
+    nonblocking: AtomicBool,
+}
+
+impl TcpListener {
+    fn inner(&self) -> io::Result<&TcpListenerInner> {
+        self.inner.as_ref().map_or_else(not_std_socket, Ok)
+    }
+
+    pub fn bind(addr: io::Result<&SocketAddr>) -> io::Result<TcpListener> {
+        let addr = addr?;
+        let socket = tcp_create_socket::create_tcp_socket(family(addr)).map_err(sockets_err2io)?;
+        let network = instance_network::instance_network();
+        socket.start_bind(&network, to_wasi_addr(addr)).map_err(sockets_err2io)?;
+        finish(|| socket.subscribe(), || socket.finish_bind())?;
+        socket.start_listen().map_err(sockets_err2io)?;
+        finish(|| socket.subscribe(), || socket.finish_listen())?;
+        let fd = Socket::new()?;
+        let inner = TcpListenerInner { socket, nonblocking: AtomicBool::new(false) };
+        Ok(TcpListener { inner: Some(inner), fd })
+    }
+
+    pub fn socket_addr(&self) -> io::Result<SocketAddr> {
+        self.inner()?.socket.local_address().map(from_wasi_addr).map_err(sockets_err2io)
+    }
+
+    pub fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        let inner = self.inner()?;
+        let (socket, input, output) = loop {
+            match inner.socket.accept() {
+                Err(ErrorCode::WouldBlock) if !inner.nonblocking.load(Ordering::Relaxed) => {
+                    inner.socket.subscribe().block()
+                }
+                result => break result.map_err(sockets_err2io)?,
+            }
+        };
+        let addr = socket.remote_address().map(from_wasi_addr).map_err(sockets_err2io)?;
+        Ok((TcpStream::new(socket, input, output)?, addr))
+    }
+
+    pub fn duplicate(&self) -> io::Result<TcpListener> {
+        unsupported()
+    }
+
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        self.inner()?.socket.set_hop_limit(hop_limit(ttl)?).map_err(sockets_err2io)
+    }
+
+    pub fn ttl(&self) -> io::Result<u32> {
+        self.inner()?.socket.hop_limit().map(u32::from).map_err(sockets_err2io)
+    }
+
+    pub fn set_only_v6(&self, _: bool) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub fn only_v6(&self) -> io::Result<bool> {
+        unsupported()
+    }
+
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        Ok(None)
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.inner()?.nonblocking.store(nonblocking, Ordering::Relaxed);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn socket(&self) -> &Socket {
+        &self.fd
+    }
+
+    pub fn into_socket(self) -> Socket {
+        match self.inner {
+            Some(inner) => self.fd.park(inner),
+            None => self.fd,
+        }
+    }
+}
+
+impl FromInner<Socket> for TcpListener {
+    fn from_inner(socket: Socket) -> TcpListener {
+        let (inner, fd) = socket.unpark();
+        TcpListener { inner, fd }
+    }
+}
+
+impl fmt::Debug for TcpListener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut res = f.debug_struct("TcpListener");
+
+        if let Ok(addr) = self.socket_addr() {
+            res.field("addr", &addr);
+        }
+
+        res.field("fd", &self.fd.as_raw_fd()).finish()
+    }
+}
+
+struct Datagrams {
+    incoming: IncomingDatagramStream,
+    outgoing: OutgoingDatagramStream,
+    // A datagram returned by `peek` that hasn't been received yet.
+    peeked: Option<(Vec<u8>, SocketAddr)>,
+}
+
+pub struct UdpSocket {
+    // `None` for sockets that std didn't create, see `TcpStream`.
+    inner: Option<UdpSocketInner>,
+    fd: Socket,
+}
+
+struct UdpSocketInner {
+    // The streams are children of `socket` and have to be dropped first.
+    // `connect` has to drop the current streams before it can create new
+    // ones, which leaves this empty if creating them fails.
+    datagrams: Mutex<Option<Datagrams>>,
+    socket: udp::UdpSocket,
+    blocking: Blocking,
+}
+
+impl UdpSocket {
+    fn inner(&self) -> io::Result<&UdpSocketInner> {
+        self.inner.as_ref().map_or_else(not_std_socket, Ok)
+    }
+
+    pub fn bind(addr: io::Result<&SocketAddr>) -> io::Result<UdpSocket> {
+        let addr = addr?;
+        let socket = udp_create_socket::create_udp_socket(family(addr)).map_err(sockets_err2io)?;
+        let network = instance_network::instance_network();
+        socket.start_bind(&network, to_wasi_addr(addr)).map_err(sockets_err2io)?;
+        finish(|| socket.subscribe(), || socket.finish_bind())?;
+        let (incoming, outgoing) = socket.stream(None).map_err(sockets_err2io)?;
+        let fd = Socket::new()?;
+        let inner = UdpSocketInner {
+            datagrams: Mutex::new(Some(Datagrams { incoming, outgoing, peeked: None })),
+            socket,
+            blocking: Blocking::new(),
+        };
+        Ok(UdpSocket { inner: Some(inner), fd })
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        match self.inner()?.socket.remote_address() {
+            Ok(addr) => Ok(from_wasi_addr(addr)),
+            Err(ErrorCode::InvalidState) => {
+                Err(io::Error::from_raw_os_error(wasi::ERRNO_NOTCONN.raw().into()))
+            }
+            Err(e) => Err(sockets_err2io(e)),
+        }
+    }
+
+    pub fn socket_addr(&self) -> io::Result<SocketAddr> {
+        self.inner()?.socket.local_address().map(from_wasi_addr).map_err(sockets_err2io)
+    }
+
+    /// Copies the next datagram into `buf`, discarding whatever doesn't fit,
+    /// and leaves it queued if `peek` is set.
+    fn recv_inner(&self, buf: &mut [u8], peek: bool) -> io::Result<(usize, SocketAddr)> {
+        let inner = self.inner()?;
+        let mut datagrams = inner.datagrams.lock().unwrap();
+        let Some(datagrams) = &mut *datagrams else {
+            return Err(sockets_err2io(ErrorCode::InvalidState));
+        };
+        let (data, addr) = match datagrams.peeked.take() {
+            Some(datagram) => datagram,
+            None => loop {
+                let received = datagrams.incoming.receive(1).map_err(sockets_err2io)?;
+                match received.into_iter().next() {
+                    Some(datagram) => {
+                        break (datagram.data, from_wasi_addr(datagram.remote_address));
+                    }
+                    None => inner.blocking.wait_read(datagrams.incoming.subscribe())?,
+                }
+            },
+        };
+        let n = buf.len().min(data.len());
+        buf[..n].copy_from_slice(&data[..n]);
+        if peek {
+            datagrams.peeked = Some((data, addr));
+        }
+        Ok((n, addr))
+    }
+
+    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.recv_inner(buf, false)
+    }
+
+    pub fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.recv_inner(buf, true)
+    }
+
+    fn send_inner(&self, buf: &[u8], addr: Option<&SocketAddr>) -> io::Result<usize> {
+        let inner = self.inner()?;
+        let datagrams = inner.datagrams.lock().unwrap();
+        let Some(datagrams) = &*datagrams else {
+            return Err(sockets_err2io(ErrorCode::InvalidState));
+        };
+        let datagram =
+            OutgoingDatagram { data: buf.to_vec(), remote_address: addr.map(to_wasi_addr) };
+        loop {
+            match datagrams.outgoing.check_send().map_err(sockets_err2io)? {
+                0 => inner.blocking.wait_write(datagrams.outgoing.subscribe())?,
+                _ => {
+                    let sent = datagrams
+                        .outgoing
+                        .send(slice::from_ref(&datagram))
+                        .map_err(sockets_err2io)?;
+                    if sent == 1 {
+                        return Ok(buf.len());
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn send_to(&self, buf: &[u8], addr: &SocketAddr) -> io::Result<usize> {
+        self.send_inner(buf, Some(addr))
+    }
+
+    pub fn duplicate(&self) -> io::Result<UdpSocket> {
+        unsupported()
+    }
+
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.inner()?.blocking.read_timeout.set(timeout)
+    }
+
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.inner()?.blocking.write_timeout.set(timeout)
+    }
+
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        Ok(self.inner()?.blocking.read_timeout.get())
+    }
+
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        Ok(self.inner()?.blocking.write_timeout.get())
+    }
+
+    pub fn set_broadcast(&self, _: bool) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub fn broadcast(&self) -> io::Result<bool> {
+        unsupported()
+    }
+
+    pub fn set_multicast_loop_v4(&self, _: bool) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub fn multicast_loop_v4(&self) -> io::Result<bool> {
+        unsupported()
+    }
+
+    pub fn set_multicast_ttl_v4(&self, _: u32) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub fn multicast_ttl_v4(&self) -> io::Result<u32> {
+        unsupported()
+    }
+
+    pub fn set_multicast_loop_v6(&self, _: bool) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub fn multicast_loop_v6(&self) -> io::Result<bool> {
+        unsupported()
+    }
+
+    pub fn join_multicast_v4(&self, _: &Ipv4Addr, _: &Ipv4Addr) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub fn join_multicast_v6(&self, _: &Ipv6Addr, _: u32) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub fn leave_multicast_v4(&self, _: &Ipv4Addr, _: &Ipv4Addr) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub fn leave_multicast_v6(&self, _: &Ipv6Addr, _: u32) -> io::Result<()> {
+        unsupported()
+    }
+
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        self.inner()?.socket.set_unicast_hop_limit(hop_limit(ttl)?).map_err(sockets_err2io)
+    }
+
+    pub fn ttl(&self) -> io::Result<u32> {
+        self.inner()?.socket.unicast_hop_limit().map(u32::from).map_err(sockets_err2io)
+    }
+
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        Ok(None)
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.inner()?.blocking.nonblocking.store(nonblocking, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.recv_from(buf).map(|(n, _)| n)
+    }
+
+    pub fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.peek_from(buf).map(|(n, _)| n)
+    }
+
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        // Without a destination the outgoing stream only accepts datagrams
+        // if it was created by `connect`.
+        self.peer_addr()?;
+        self.send_inner(buf, None)
+    }
+
+    pub fn connect(&self, addr: io::Result<&SocketAddr>) -> io::Result<()> {
+        let inner = self.inner()?;
+        let addr = to_wasi_addr(addr?);
+        let mut datagrams = inner.datagrams.lock().unwrap();
+        *datagrams = None;
+        let (incoming, outgoing) = inner.socket.stream(Some(addr)).map_err(sockets_err2io)?;
+        *datagrams = Some(Datagrams { incoming, outgoing, peeked: None });
+        Ok(())
+    }
+
+    #[inline]
+    pub fn socket(&self) -> &Socket {
+        &self.fd
+    }
+
+    pub fn into_socket(self) -> Socket {
+        match self.inner {
+            Some(inner) => self.fd.park(inner),
+            None => self.fd,
+        }
+    }
+}
+
+impl FromInner<Socket> for UdpSocket {
+    fn from_inner(socket: Socket) -> UdpSocket {
+        let (inner, fd) = socket.unpark();
+        UdpSocket { inner, fd }
+    }
+}
+
+impl fmt::Debug for UdpSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut res = f.debug_struct("UdpSocket");
+
+        if let Ok(addr) = self.socket_addr() {
+            res.field("addr", &addr);
+        }
+
+        res.field("fd", &self.fd.as_raw_fd()).finish()
+    }
+}
+
+pub struct LookupHost {
+    addrs: vec::IntoIter<SocketAddr>,
+    port: u16,
+}
+
+impl LookupHost {
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl Iterator for LookupHost {
+    type Item = SocketAddr;
+    fn next(&mut self) -> Option<SocketAddr> {
+        self.addrs.next()
+    }
+}
+
+impl<'a> TryFrom<&'a str> for LookupHost {
+    type Error = io::Error;
+
+    fn try_from(s: &str) -> io::Result<LookupHost> {
+        macro_rules! try_opt {
+            ($e:expr, $msg:expr) => {
+                match $e {
+                    Some(r) => r,
+                    None => return Err(io::const_io_error!(io::ErrorKind::InvalidInput, $msg)),
+                }
+            };
+        }
+
+        // split the string by ':' and convert the second part to u16
+        let (host, port_str) = try_opt!(s.rsplit_once(':'), "invalid socket address");
+        let port: u16 = try_opt!(port_str.parse().ok(), "invalid port value");
+        (host, port).try_into()
+    }
+}
+
+impl<'a> TryFrom<(&'a str, u16)> for LookupHost {
+    type Error = io::Error;
+
+    fn try_from((host, port): (&'a str, u16)) -> io::Result<LookupHost> {
+        let network = instance_network::instance_network();
+        let stream = ip_name_lookup::resolve_addresses(&network, host).map_err(sockets_err2io)?;
+        let mut addrs = Vec::new();
+        while let Some(ip) = finish(|| stream.subscribe(), || stream.resolve_next_address())? {
+            addrs.push(from_wasi_ip(ip, port));
+        }
+        Ok(LookupHost { addrs: addrs.into_iter(), port })
+    }
+}
+
+#[allow(nonstandard_style)]
+pub mod netc {
+    pub const AF_INET: u8 = 0;
+    pub const AF_INET6: u8 = 1;
+    pub type sa_family_t = u8;
+
+    #[derive(Copy, Clone)]
+    pub struct in_addr {
+        pub s_addr: u32,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct sockaddr_in {
+        #[allow(dead_code)]
+        pub sin_family: sa_family_t,
+        pub sin_port: u16,
+        pub sin_addr: in_addr,
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct in6_addr {
+        pub s6_addr: [u8; 16],
+    }
+
+    #[derive(Copy, Clone)]
+    pub struct sockaddr_in6 {
+        #[allow(dead_code)]
+        pub sin6_family: sa_family_t,
+        pub sin6_port: u16,
+        pub sin6_addr: in6_addr,
+        pub sin6_flowinfo: u32,
+        pub sin6_scope_id: u32,
+    }
+}
@@ -0,0 +1,79 @@
+use crate::io::prelude::*;
+use crate::io::ErrorKind;
+use crate::net::test::next_test_ip4;
+use crate::net::{TcpListener, TcpStream, UdpSocket};
+use crate::os::wasi::io::{AsRawFd, FromRawFd, IntoRawFd};
+use crate::time::{Duration, Instant};
+
+#[test]
+fn connect_drop_reconnect() {
+    let addr = next_test_ip4();
+    let listener = TcpListener::bind(addr).unwrap();
+
+    // Dropping a connected stream has to release its streams before the
+    // socket, or the host refuses to drop the socket.
+    for _ in 0..3 {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        let (mut accepted, _) = listener.accept().unwrap();
+        stream.write_all(b"ping").unwrap();
+        let mut buf = [0; 4];
+        accepted.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ping");
+    }
+}
+
+#[test]
+fn tcp_raw_fd_round_trip() {
+    let addr = next_test_ip4();
+    let listener = TcpListener::bind(addr).unwrap();
+    let stream = TcpStream::connect(addr).unwrap();
+    let (accepted, _) = listener.accept().unwrap();
+
+    let fd = stream.as_raw_fd();
+    assert_eq!(stream.into_raw_fd(), fd);
+    let mut stream = unsafe { TcpStream::from_raw_fd(fd) };
+    assert_eq!(stream.peer_addr().unwrap(), addr);
+
+    let listener = unsafe { TcpListener::from_raw_fd(listener.into_raw_fd()) };
+    assert_eq!(listener.local_addr().unwrap(), addr);
+
+    stream.write_all(b"pong").unwrap();
+    let mut accepted = unsafe { TcpStream::from_raw_fd(accepted.into_raw_fd()) };
+    let mut buf = [0; 4];
+    accepted.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"pong");
+}
+
+#[test]
+fn udp_raw_fd_round_trip() {
+    let addr = next_test_ip4();
+    let socket = UdpSocket::bind(addr).unwrap();
+    let socket = unsafe { UdpSocket::from_raw_fd(socket.into_raw_fd()) };
+    assert_eq!(socket.local_addr().unwrap(), addr);
+    socket.send_to(b"x", addr).unwrap();
+    let mut buf = [0; 1];
+    assert_eq!(socket.recv_from(&mut buf).unwrap(), (1, addr));
+}
+
+#[test]
+fn set_nodelay_is_unsupported() {
+    let addr = next_test_ip4();
+    let _listener = TcpListener::bind(addr).unwrap();
+    let stream = TcpStream::connect(addr).unwrap();
+    assert_eq!(stream.set_nodelay(true).unwrap_err().kind(), ErrorKind::Unsupported);
+    assert_eq!(stream.nodelay().unwrap_err().kind(), ErrorKind::Unsupported);
+}
+
+#[test]
+fn connect_timeout_is_a_deadline() {
+    // An address from TEST-NET-1, which nothing should answer.
+    let addr = "192.0.2.1:80".parse().unwrap();
+    let timeout = Duration::from_millis(200);
+    let start = Instant::now();
+    match TcpStream::connect_timeout(&addr, timeout) {
+        // Hosts without a route fail right away instead.
+        Ok(_) => panic!("connected to {addr}"),
+        Err(e) if e.kind() == ErrorKind::TimedOut => assert!(start.elapsed() < timeout * 5),
+        Err(_) => {}
+    }
+}
@@ -0,0 +1,144 @@
+//! Pipes to child processes, which are `wasi:io` streams handed out by the
+//! `rust:process/spawn` host interface described in `process.wit`.
+
+#![forbid(unsafe_op_in_unsafe_fn)]
+
+use wasip2::io::poll;
+use wasip2::io::streams::{InputStream, OutputStream, StreamError};
+
+use super::error::stream_err2io;
+use crate::fmt;
+use crate::io::{self, BorrowedCursor, IoSlice, IoSliceMut};
+use crate::sys::unsupported;
+use crate::sys_common::io::DEFAULT_BUF_SIZE;
+use crate::sys_common::{FromInner, IntoInner};
+
+/// The stream behind one end of a pipe.
+pub enum PipeStream {
+    /// The end the guest reads from.
+    Read(InputStream),
+    /// The end the guest writes to.
+    Write(OutputStream),
+}
+
+pub struct AnonPipe {
+    stream: PipeStream,
+}
+
+impl fmt::Debug for AnonPipe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stream = match &self.stream {
+            PipeStream::Read(input) => input.handle(),
+            PipeStream::Write(output) => output.handle(),
+        };
+        f.debug_struct("AnonPipe").field("stream", &stream).finish()
+    }
+}
+
+/// The error for using a pipe in the wrong direction, like a read from the
+/// write end of a pipe on Unix.
+fn wrong_end() -> io::Error {
+    io::Error::from_raw_os_error(wasi::ERRNO_BADF.raw().into())
+}
+
+impl AnonPipe {
+    pub fn try_clone(&self) -> io::Result<Self> {
+        unsupported()
+    }
+
+    pub fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        let PipeStream::Read(input) = &self.stream else {
+            return Err(wrong_end());
+        };
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        match input.blocking_read(buf.len() as u64) {
+            Ok(data) => {
+                buf[..data.len()].copy_from_slice(&data);
+                Ok(data.len())
+            }
+            Err(StreamError::Closed) => Ok(0),
+            Err(e) => Err(stream_err2io(e)),
+        }
+    }
+
+    pub fn read_buf(&self, cursor: BorrowedCursor<'_>) -> io::Result<()> {
+        io::default_read_buf(|buf| self.read(buf), cursor)
+    }
+
+    pub fn read_vectored(&self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        io::default_read_vectored(|buf| self.read(buf), bufs)
+    }
+
+    pub fn is_read_vectored(&self) -> bool {
+        false
+    }
+
+    pub fn read_to_end(&self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        let start = buf.len();
+        let mut chunk = [0; DEFAULT_BUF_SIZE];
+        loop {
+            match self.read(&mut chunk) {
+                Ok(0) => return Ok(buf.len() - start),
+                Ok(n) => buf.extend_from_slice(&chunk[..n]),
+                Err(e) if e.is_interrupted() => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    pub fn write(&self, buf: &[u8]) -> io::Result<usize> {
+        let PipeStream::Write(output) = &self.stream else {
+            return Err(wrong_end());
+        };
+        // `blocking-write-and-flush` takes at most 4096 bytes at a time.
+        let n = buf.len().min(4096);
+        output.blocking_write_and_flush(&buf[..n]).map_err(stream_err2io)?;
+        Ok(n)
+    }
+
+    pub fn write_vectored(&self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        io::default_write_vectored(|buf| self.write(buf), bufs)
+    }
+
+    pub fn is_write_vectored(&self) -> bool {
+        false
+    }
+}
+
+impl FromInner<PipeStream> for AnonPipe {
+    fn from_inner(stream: PipeStream) -> AnonPipe {
+        AnonPipe { stream }
+    }
+}
+
+impl IntoInner<PipeStream> for AnonPipe {
+    fn into_inner(self) -> PipeStream {
+        self.stream
+    }
+}
+
+pub fn read2(p1: AnonPipe, v1: &mut Vec<u8>, p2: AnonPipe, v2: &mut Vec<u8>) -> io::Result<()> {
+    let (PipeStream::Read(s1), PipeStream::Read(s2)) = (&p1.stream, &p2.stream) else {
+        return Err(wrong_end());
+    };
+    // Reading one pipe to the end before starting on the other could
+    // deadlock if the child fills up the second one in the meantime, so
+    // always read from whichever pipe has something to offer.
+    let mut chunk = [0; DEFAULT_BUF_SIZE];
+    loop {
+        let ready = poll::poll(&[&s1.subscribe(), &s2.subscribe()]);
+        let (pipe, buf, other, other_buf) = if ready.contains(&0) {
+            (&p1, &mut *v1, &p2, &mut *v2)
+        } else {
+            (&p2, &mut *v2, &p1, &mut *v1)
+        };
+        match pipe.read(&mut chunk) {
+            Ok(0) => return other.read_to_end(other_buf).map(drop),
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+            Err(e) if e.is_interrupted() => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
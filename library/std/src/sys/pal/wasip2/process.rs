@@ -0,0 +1,672 @@
+//! Child processes through the `rust:process/spawn` host interface.
+//!
+//! WASI has no notion of processes, so this is only built with the
+//! `wasi_process` feature of the standard library, for embedders that provide
+//! the interface defined in `process.wit` next to this file. The host only
+//! looks up and runs the program. Everything else is decided by the guest:
+//! the child gets the guest's view of the environment, a working directory set
+//! with `Command::current_dir` is opened here and handed to the host as a
+//! `wasi:filesystem` descriptor, and pipes are `wasi:io` streams.
+
+#![forbid(unsafe_op_in_unsafe_fn)]
+
+use wasip2::filesystem::types::ErrorCode;
+use wasip2::io::poll::Pollable;
+use wasip2::io::streams::{InputStream, OutputStream};
+
+use super::error::filesystem_err2io;
+use super::pipe::{AnonPipe, PipeStream};
+pub use crate::ffi::OsString as EnvKey;
+use crate::ffi::{OsStr, OsString};
+use crate::num::NonZero;
+use crate::path::Path;
+use crate::sys::fs::{File, OpenOptions};
+use crate::sys_common::process::{CommandEnv, CommandEnvs};
+use crate::sys_common::{FromInner, IntoInner};
+use crate::{fmt, io};
+
+#[cfg(test)]
+mod tests;
+
+/// Hand-written bindings to `process.wit`, following the canonical ABI.
+mod host {
+    use crate::mem::MaybeUninit;
+
+    pub const STDIO_INHERIT: u32 = 0;
+    pub const STDIO_NULL: u32 = 1;
+    pub const STDIO_PIPE: u32 = 2;
+    pub const STDIO_PARENT_STDOUT: u32 = 3;
+    pub const STDIO_PARENT_STDERR: u32 = 4;
+    pub const STDIO_INPUT: u32 = 5;
+    pub const STDIO_OUTPUT: u32 = 6;
+
+    /// Space for the results that the canonical ABI returns through memory.
+    /// The largest one is `result<option<exit-status>, error-code>`.
+    #[repr(C, align(4))]
+    pub struct RetArea([MaybeUninit<u8>; 16]);
+
+    impl RetArea {
+        pub fn new() -> RetArea {
+            RetArea([MaybeUninit::uninit(); 16])
+        }
+
+        /// Stands in for the host writing `bytes`.
+        #[cfg(test)]
+        pub fn from_bytes(bytes: [u8; 16]) -> RetArea {
+            RetArea(bytes.map(MaybeUninit::new))
+        }
+
+        /// Reads the byte at `offset`, which the host must have written.
+        pub unsafe fn u8(&self, offset: usize) -> u8 {
+            unsafe { self.0[offset].assume_init() }
+        }
+
+        /// Reads the aligned word at `offset`, which the host must have written.
+        pub unsafe fn u32(&self, offset: usize) -> u32 {
+            unsafe { self.0.as_ptr().add(offset).cast::<u32>().read() }
+        }
+    }
+
+    #[link(wasm_import_module = "rust:process/spawn@0.1.0")]
+    extern "C" {
+        #[link_name = "[constructor]command"]
+        pub fn command_new(program: *const u8, program_len: usize) -> u32;
+        #[link_name = "[method]command.arg"]
+        pub fn command_arg(command: u32, arg: *const u8, arg_len: usize);
+        #[link_name = "[method]command.env"]
+        pub fn command_env(
+            command: u32,
+            key: *const u8,
+            key_len: usize,
+            value: *const u8,
+            value_len: usize,
+        );
+        #[link_name = "[method]command.current-dir"]
+        pub fn command_current_dir(command: u32, dir: u32);
+        #[link_name = "[method]command.stdin"]
+        pub fn command_stdin(command: u32, kind: u32, stream: u32);
+        #[link_name = "[method]command.stdout"]
+        pub fn command_stdout(command: u32, kind: u32, stream: u32);
+        #[link_name = "[method]command.stderr"]
+        pub fn command_stderr(command: u32, kind: u32, stream: u32);
+        #[link_name = "[method]command.spawn"]
+        pub fn command_spawn(command: u32, ret: *mut RetArea);
+        #[link_name = "[resource-drop]command"]
+        pub fn command_drop(command: u32);
+
+        #[link_name = "[method]child.id"]
+        pub fn child_id(child: u32) -> u32;
+        #[link_name = "[method]child.take-stdin"]
+        pub fn child_take_stdin(child: u32, ret: *mut RetArea);
+        #[link_name = "[method]child.take-stdout"]
+        pub fn child_take_stdout(child: u32, ret: *mut RetArea);
+        #[link_name = "[method]child.take-stderr"]
+        pub fn child_take_stderr(child: u32, ret: *mut RetArea);
+        #[link_name = "[method]child.kill"]
+        pub fn child_kill(child: u32, ret: *mut RetArea);
+        #[link_name = "[method]child.subscribe"]
+        pub fn child_subscribe(child: u32) -> u32;
+        #[link_name = "[method]child.try-wait"]
+        pub fn child_try_wait(child: u32, ret: *mut RetArea);
+        #[link_name = "[resource-drop]child"]
+        pub fn child_drop(child: u32);
+    }
+}
+
+fn host_err2io(code: u8) -> io::Error {
+    // SAFETY: the component model only lets valid `error-code` cases through.
+    filesystem_err2io(unsafe { ErrorCode::_lift(code) })
+}
+
+/// Lifts the `result<child, error-code>` of `command.spawn`.
+unsafe fn lift_spawn(ret: &host::RetArea) -> io::Result<u32> {
+    unsafe {
+        match ret.u8(0) {
+            0 => Ok(ret.u32(4)),
+            _ => Err(host_err2io(ret.u8(4))),
+        }
+    }
+}
+
+/// Lifts the `result<_, error-code>` of `child.kill`.
+unsafe fn lift_kill(ret: &host::RetArea) -> io::Result<()> {
+    unsafe {
+        match ret.u8(0) {
+            0 => Ok(()),
+            _ => Err(host_err2io(ret.u8(1))),
+        }
+    }
+}
+
+/// Lifts the `result<option<exit-status>, error-code>` of `child.try-wait`.
+unsafe fn lift_try_wait(ret: &host::RetArea) -> io::Result<Option<ExitStatus>> {
+    unsafe {
+        match (ret.u8(0), ret.u8(4), ret.u8(8)) {
+            (0, 0, _) => Ok(None),
+            (0, _, 0) => Ok(Some(ExitStatus::Exited(ret.u32(12) as i32))),
+            (0, _, _) => Ok(Some(ExitStatus::Terminated(ret.u32(12) as i32))),
+            _ => Err(host_err2io(ret.u8(4))),
+        }
+    }
+}
+
+/// Lifts the `option<stream>` of the `child.take-*` functions.
+unsafe fn lift_stream(ret: &host::RetArea) -> Option<u32> {
+    unsafe {
+        match ret.u8(0) {
+            0 => None,
+            _ => Some(ret.u32(4)),
+        }
+    }
+}
+
+/// Converts `s` to a string that can be passed to the host, rejecting
+/// interior NULs like every other platform does.
+fn host_str(s: &OsStr) -> io::Result<&str> {
+    let s = s.to_str().ok_or_else(|| {
+        io::const_io_error!(io::ErrorKind::InvalidInput, "input must be utf-8")
+    })?;
+    if s.contains('\0') {
+        return Err(io::const_io_error!(
+            io::ErrorKind::InvalidInput,
+            "nul byte found in provided data",
+        ));
+    }
+    Ok(s)
+}
+
+/// An owned `command` resource.
+struct HostCommand(u32);
+
+impl HostCommand {
+    fn new(program: &str) -> HostCommand {
+        HostCommand(unsafe { host::command_new(program.as_ptr(), program.len()) })
+    }
+
+    fn arg(&self, arg: &str) {
+        unsafe { host::command_arg(self.0, arg.as_ptr(), arg.len()) }
+    }
+
+    fn env(&self, key: &str, value: &str) {
+        unsafe { host::command_env(self.0, key.as_ptr(), key.len(), value.as_ptr(), value.len()) }
+    }
+
+    fn current_dir(&self, dir: &File) -> io::Result<()> {
+        let dir = dir.descriptor()?;
+        unsafe { host::command_current_dir(self.0, dir.handle()) };
+        Ok(())
+    }
+
+    fn spawn(&self) -> io::Result<Process> {
+        let mut ret = host::RetArea::new();
+        unsafe {
+            host::command_spawn(self.0, &mut ret);
+            lift_spawn(&ret).map(|handle| Process { handle })
+        }
+    }
+}
+
+impl Drop for HostCommand {
+    fn drop(&mut self) {
+        unsafe { host::command_drop(self.0) }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Command
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct Command {
+    program: OsString,
+    args: Vec<OsString>,
+    env: CommandEnv,
+
+    cwd: Option<OsString>,
+    stdin: Option<Stdio>,
+    stdout: Option<Stdio>,
+    stderr: Option<Stdio>,
+}
+
+// passed back to std::process with the pipes connected to the child, if any
+// were requested
+pub struct StdioPipes {
+    pub stdin: Option<AnonPipe>,
+    pub stdout: Option<AnonPipe>,
+    pub stderr: Option<AnonPipe>,
+}
+
+#[derive(Debug)]
+pub enum Stdio {
+    Inherit,
+    Null,
+    MakePipe,
+    ParentStdout,
+    ParentStderr,
+    Pipe(AnonPipe),
+    InheritFile(File),
+}
+
+impl Stdio {
+    /// Describes this stream to the host as a `stdio` variant. Pipes handed
+    /// over to the child are owned by the host from here on. `child_reads`
+    /// tells whether this is the child's stdin.
+    fn into_host(self, child_reads: bool) -> io::Result<(u32, u32)> {
+        Ok(match self {
+            Stdio::Inherit => (host::STDIO_INHERIT, 0),
+            Stdio::Null => (host::STDIO_NULL, 0),
+            Stdio::MakePipe => (host::STDIO_PIPE, 0),
+            Stdio::ParentStdout => (host::STDIO_PARENT_STDOUT, 0),
+            Stdio::ParentStderr => (host::STDIO_PARENT_STDERR, 0),
+            Stdio::Pipe(pipe) => match pipe.into_inner() {
+                PipeStream::Read(input) if child_reads => (host::STDIO_INPUT, input.take_handle()),
+                PipeStream::Write(output) if !child_reads => {
+                    (host::STDIO_OUTPUT, output.take_handle())
+                }
+                _ => {
+                    return Err(io::const_io_error!(
+                        io::ErrorKind::InvalidInput,
+                        "pipe end cannot be used in this direction",
+                    ));
+                }
+            },
+            Stdio::InheritFile(_) => {
+                return Err(io::const_io_error!(
+                    io::ErrorKind::Unsupported,
+                    "files cannot be passed to child processes on this platform",
+                ));
+            }
+        })
+    }
+}
+
+impl Command {
+    pub fn new(program: &OsStr) -> Command {
+        Command {
+            program: program.to_owned(),
+            args: vec![program.to_owned()],
+            env: Default::default(),
+            cwd: None,
+            stdin: None,
+            stdout: None,
+            stderr: None,
+        }
+    }
+
+    pub fn arg(&mut self, arg: &OsStr) {
+        self.args.push(arg.to_owned());
+    }
+
+    pub fn env_mut(&mut self) -> &mut CommandEnv {
+        &mut self.env
+    }
+
+    pub fn cwd(&mut self, dir: &OsStr) {
+        self.cwd = Some(dir.to_owned());
+    }
+
+    pub fn stdin(&mut self, stdin: Stdio) {
+        self.stdin = Some(stdin);
+    }
+
+    pub fn stdout(&mut self, stdout: Stdio) {
+        self.stdout = Some(stdout);
+    }
+
+    pub fn stderr(&mut self, stderr: Stdio) {
+        self.stderr = Some(stderr);
+    }
+
+    pub fn get_program(&self) -> &OsStr {
+        &self.program
+    }
+
+    pub fn get_args(&self) -> CommandArgs<'_> {
+        let mut iter = self.args.iter();
+        iter.next();
+        CommandArgs { iter }
+    }
+
+    pub fn get_envs(&self) -> CommandEnvs<'_> {
+        self.env.iter()
+    }
+
+    pub fn get_current_dir(&self) -> Option<&Path> {
+        self.cwd.as_ref().map(|cs| Path::new(cs))
+    }
+
+    pub fn spawn(
+        &mut self,
+        default: Stdio,
+        needs_stdin: bool,
+    ) -> io::Result<(Process, StdioPipes)> {
+        let (program, args, env) = self.host_invocation()?;
+        let cwd = match &self.cwd {
+            Some(cwd) => {
+                let mut opts = OpenOptions::new();
+                opts.read(true);
+                opts.directory(true);
+                Some(File::open(Path::new(cwd), &opts)?)
+            }
+            None => None,
+        };
+
+        let default_stdin = if needs_stdin { &default } else { &Stdio::Null };
+        let stdin = Stdio::resolve(&mut self.stdin, default_stdin);
+        let stdout = Stdio::resolve(&mut self.stdout, &default);
+        let stderr = Stdio::resolve(&mut self.stderr, &default);
+
+        let command = HostCommand::new(program);
+        for arg in args {
+            command.arg(arg);
+        }
+        for (key, value) in &env {
+            command.env(key, value);
+        }
+        if let Some(cwd) = &cwd {
+            command.current_dir(cwd)?;
+        }
+        // Streams passed to the command are dropped along with it if anything
+        // fails from here on.
+        let (kind, stream) = stdin.into_host(true)?;
+        unsafe { host::command_stdin(command.0, kind, stream) };
+        let (kind, stream) = stdout.into_host(false)?;
+        unsafe { host::command_stdout(command.0, kind, stream) };
+        let (kind, stream) = stderr.into_host(false)?;
+        unsafe { host::command_stderr(command.0, kind, stream) };
+        let process = command.spawn()?;
+
+        // SAFETY: the host hands over ownership of the streams it returns.
+        let pipes = unsafe {
+            StdioPipes {
+                stdin: process.take_stream(host::child_take_stdin).map(|handle| {
+                    AnonPipe::from_inner(PipeStream::Write(OutputStream::from_handle(handle)))
+                }),
+                stdout: process.take_stream(host::child_take_stdout).map(|handle| {
+                    AnonPipe::from_inner(PipeStream::Read(InputStream::from_handle(handle)))
+                }),
+                stderr: process.take_stream(host::child_take_stderr).map(|handle| {
+                    AnonPipe::from_inner(PipeStream::Read(InputStream::from_handle(handle)))
+                }),
+            }
+        };
+        Ok((process, pipes))
+    }
+
+    /// Converts the program, the arguments and the environment to the strings
+    /// passed to the host. The child gets exactly the environment the guest
+    /// sees.
+    fn host_invocation(&self) -> io::Result<(&str, Vec<&str>, Vec<(String, String)>)> {
+        let program = host_str(&self.program)?;
+        let args = self.args.iter().map(|arg| host_str(arg)).collect::<io::Result<Vec<_>>>()?;
+        let env = self
+            .env
+            .capture()
+            .iter()
+            .map(|(key, value)| Ok((host_str(key)?.to_owned(), host_str(value)?.to_owned())))
+            .collect::<io::Result<Vec<_>>>()?;
+        Ok((program, args, env))
+    }
+
+    pub fn output(&mut self) -> io::Result<(ExitStatus, Vec<u8>, Vec<u8>)> {
+        let (proc, pipes) = self.spawn(Stdio::MakePipe, false)?;
+        crate::sys_common::process::wait_with_output(proc, pipes)
+    }
+}
+
+impl Stdio {
+    /// Picks the configuration of a stream for the next child. Pipes and
+    /// files can only be handed to a single child, so they are taken out of
+    /// the `Command`; everything else stays for the next `spawn`.
+    fn resolve(configured: &mut Option<Stdio>, default: &Stdio) -> Stdio {
+        match configured.as_ref().unwrap_or(default) {
+            Stdio::Inherit => Stdio::Inherit,
+            Stdio::Null => Stdio::Null,
+            Stdio::MakePipe => Stdio::MakePipe,
+            Stdio::ParentStdout => Stdio::ParentStdout,
+            Stdio::ParentStderr => Stdio::ParentStderr,
+            Stdio::Pipe(_) | Stdio::InheritFile(_) => configured.take().unwrap(),
+        }
+    }
+}
+
+impl From<AnonPipe> for Stdio {
+    fn from(pipe: AnonPipe) -> Stdio {
+        Stdio::Pipe(pipe)
+    }
+}
+
+impl From<io::Stdout> for Stdio {
+    fn from(_: io::Stdout) -> Stdio {
+        Stdio::ParentStdout
+    }
+}
+
+impl From<io::Stderr> for Stdio {
+    fn from(_: io::Stderr) -> Stdio {
+        Stdio::ParentStderr
+    }
+}
+
+impl From<File> for Stdio {
+    fn from(file: File) -> Stdio {
+        Stdio::InheritFile(file)
+    }
+}
+
+impl fmt::Debug for Command {
+    // show all attributes
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            let mut debug_command = f.debug_struct("Command");
+            debug_command.field("program", &self.program).field("args", &self.args);
+            if !self.env.is_unchanged() {
+                debug_command.field("env", &self.env);
+            }
+
+            if self.cwd.is_some() {
+                debug_command.field("cwd", &self.cwd);
+            }
+
+            if self.stdin.is_some() {
+                debug_command.field("stdin", &self.stdin);
+            }
+            if self.stdout.is_some() {
+                debug_command.field("stdout", &self.stdout);
+            }
+            if self.stderr.is_some() {
+                debug_command.field("stderr", &self.stderr);
+            }
+
+            debug_command.finish()
+        } else {
+            if let Some(ref cwd) = self.cwd {
+                write!(f, "cd {cwd:?} && ")?;
+            }
+            if self.env.does_clear() {
+                write!(f, "env -i ")?;
+                // Altered env vars will be printed next, that should exactly work as expected.
+            } else {
+                // Removed env vars need the command to be wrapped in `env`.
+                let mut any_removed = false;
+                for (key, value_opt) in self.get_envs() {
+                    if value_opt.is_none() {
+                        if !any_removed {
+                            write!(f, "env ")?;
+                            any_removed = true;
+                        }
+                        write!(f, "-u {} ", key.to_string_lossy())?;
+                    }
+                }
+            }
+            // Altered env vars can just be added in front of the program.
+            for (key, value_opt) in self.get_envs() {
+                if let Some(value) = value_opt {
+                    write!(f, "{}={value:?} ", key.to_string_lossy())?;
+                }
+            }
+            if self.program != self.args[0] {
+                write!(f, "[{:?}] ", self.program)?;
+            }
+            write!(f, "{:?}", self.args[0])?;
+
+            for arg in &self.args[1..] {
+                write!(f, " {:?}", arg)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ExitStatus {
+    /// The process exited with this code.
+    Exited(i32),
+    /// The host terminated the process for this host-specific reason.
+    Terminated(i32),
+}
+
+impl ExitStatus {
+    pub fn exit_ok(&self) -> Result<(), ExitStatusError> {
+        match *self {
+            ExitStatus::Exited(0) => Ok(()),
+            _ => Err(ExitStatusError(*self)),
+        }
+    }
+
+    pub fn code(&self) -> Option<i32> {
+        match *self {
+            ExitStatus::Exited(code) => Some(code),
+            ExitStatus::Terminated(_) => None,
+        }
+    }
+}
+
+impl Default for ExitStatus {
+    fn default() -> ExitStatus {
+        ExitStatus::Exited(0)
+    }
+}
+
+impl fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ExitStatus::Exited(code) => write!(f, "exit status: {code}"),
+            ExitStatus::Terminated(reason) => write!(f, "terminated by the host: {reason}"),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct ExitStatusError(ExitStatus);
+
+impl Into<ExitStatus> for ExitStatusError {
+    fn into(self) -> ExitStatus {
+        self.0
+    }
+}
+
+impl ExitStatusError {
+    pub fn code(self) -> Option<NonZero<i32>> {
+        self.0.code().and_then(NonZero::new)
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct ExitCode(u8);
+
+impl ExitCode {
+    pub const SUCCESS: ExitCode = ExitCode(0);
+    pub const FAILURE: ExitCode = ExitCode(1);
+
+    pub fn as_i32(&self) -> i32 {
+        self.0 as i32
+    }
+}
+
+impl From<u8> for ExitCode {
+    fn from(code: u8) -> Self {
+        Self(code)
+    }
+}
+
+/// An owned `child` resource.
+pub struct Process {
+    handle: u32,
+}
+
+impl Process {
+    pub fn id(&self) -> u32 {
+        unsafe { host::child_id(self.handle) }
+    }
+
+    pub fn kill(&mut self) -> io::Result<()> {
+        let mut ret = host::RetArea::new();
+        unsafe {
+            host::child_kill(self.handle, &mut ret);
+            lift_kill(&ret)
+        }
+    }
+
+    pub fn wait(&mut self) -> io::Result<ExitStatus> {
+        loop {
+            if let Some(status) = self.try_wait()? {
+                return Ok(status);
+            }
+            let exited = unsafe { Pollable::from_handle(host::child_subscribe(self.handle)) };
+            exited.block();
+        }
+    }
+
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        let mut ret = host::RetArea::new();
+        unsafe {
+            host::child_try_wait(self.handle, &mut ret);
+            lift_try_wait(&ret)
+        }
+    }
+
+    /// Takes one of the pipes the host created for the child, returning the
+    /// handle of the stream if there is one.
+    fn take_stream(&self, take: unsafe extern "C" fn(u32, *mut host::RetArea)) -> Option<u32> {
+        let mut ret = host::RetArea::new();
+        unsafe {
+            take(self.handle, &mut ret);
+            lift_stream(&ret)
+        }
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        unsafe { host::child_drop(self.handle) }
+    }
+}
+
+pub struct CommandArgs<'a> {
+    iter: crate::slice::Iter<'a, OsString>,
+}
+
+impl<'a> Iterator for CommandArgs<'a> {
+    type Item = &'a OsStr;
+    fn next(&mut self) -> Option<&'a OsStr> {
+        self.iter.next().map(|os| &**os)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for CommandArgs<'a> {
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+    fn is_empty(&self) -> bool {
+        self.iter.is_empty()
+    }
+}
+
+impl<'a> fmt::Debug for CommandArgs<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter.clone()).finish()
+    }
+}
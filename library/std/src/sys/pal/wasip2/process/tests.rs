@@ -0,0 +1,151 @@
+use super::{Command, ExitStatus, Stdio, host, lift_kill, lift_spawn, lift_stream, lift_try_wait};
+use crate::ffi::OsStr;
+use crate::io::ErrorKind;
+use crate::os::wasi::ffi::OsStrExt;
+use crate::sys::fs::{File, OpenOptions};
+use crate::sys_common::io::test::tmpdir;
+
+/// `error-code.no-entry` of `wasi:filesystem`.
+const NO_ENTRY: u8 = 20;
+
+/// A return area as the host would fill it in: `(offset, byte)` pairs, with
+/// `u32`s spelled out in little-endian.
+fn ret(bytes: &[(usize, u8)]) -> host::RetArea {
+    let mut area = [0xaa; 16];
+    for &(offset, byte) in bytes {
+        area[offset] = byte;
+    }
+    host::RetArea::from_bytes(area)
+}
+
+#[test]
+fn invocation_starts_with_the_zeroth_argument() {
+    let mut command = Command::new(OsStr::new("prog"));
+    command.arg(OsStr::new("a b"));
+    command.arg(OsStr::new(""));
+    let (program, args, _) = command.host_invocation().unwrap();
+    assert_eq!(program, "prog");
+    assert_eq!(args, ["prog", "a b", ""]);
+}
+
+#[test]
+fn invocation_passes_the_complete_environment() {
+    let mut command = Command::new(OsStr::new("prog"));
+    command.env_mut().clear();
+    command.env_mut().set(OsStr::new("B"), OsStr::new("2"));
+    command.env_mut().set(OsStr::new("A"), OsStr::new("1"));
+    command.env_mut().set(OsStr::new("C"), OsStr::new("3"));
+    command.env_mut().remove(OsStr::new("C"));
+    let (_, _, env) = command.host_invocation().unwrap();
+    assert_eq!(env, [("A".to_owned(), "1".to_owned()), ("B".to_owned(), "2".to_owned())]);
+}
+
+#[test]
+fn invocation_rejects_strings_the_host_cannot_take() {
+    let mut command = Command::new(OsStr::new("prog"));
+    command.arg(OsStr::from_bytes(b"\xff"));
+    assert_eq!(command.host_invocation().unwrap_err().kind(), ErrorKind::InvalidInput);
+
+    let mut command = Command::new(OsStr::new("prog"));
+    command.env_mut().set(OsStr::new("KEY"), OsStr::new("a\0b"));
+    assert_eq!(command.host_invocation().unwrap_err().kind(), ErrorKind::InvalidInput);
+
+    let command = Command::new(OsStr::new("pr\0g"));
+    assert_eq!(command.host_invocation().unwrap_err().kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn stdio_variants() {
+    let cases = [
+        (Stdio::Inherit, host::STDIO_INHERIT),
+        (Stdio::Null, host::STDIO_NULL),
+        (Stdio::MakePipe, host::STDIO_PIPE),
+        (Stdio::ParentStdout, host::STDIO_PARENT_STDOUT),
+        (Stdio::ParentStderr, host::STDIO_PARENT_STDERR),
+    ];
+    for (stdio, kind) in cases {
+        assert_eq!(stdio.into_host(false).unwrap(), (kind, 0));
+    }
+
+    let tmpdir = tmpdir();
+    let mut opts = OpenOptions::new();
+    opts.write(true);
+    opts.create(true);
+    let file = File::open(&tmpdir.join("file"), &opts).unwrap();
+    let err = Stdio::InheritFile(file).into_host(false).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+}
+
+#[test]
+fn stdio_resolution() {
+    // Unconfigured streams use the default of the spawning function.
+    let mut configured = None;
+    assert!(matches!(Stdio::resolve(&mut configured, &Stdio::MakePipe), Stdio::MakePipe));
+
+    // Configured streams win, and stay configured for the next spawn.
+    let mut configured = Some(Stdio::Null);
+    assert!(matches!(Stdio::resolve(&mut configured, &Stdio::MakePipe), Stdio::Null));
+    assert!(matches!(configured, Some(Stdio::Null)));
+
+    // Files can only be handed over once.
+    let tmpdir = tmpdir();
+    let mut opts = OpenOptions::new();
+    opts.write(true);
+    opts.create(true);
+    let file = File::open(&tmpdir.join("file"), &opts).unwrap();
+    let mut configured = Some(Stdio::InheritFile(file));
+    assert!(matches!(Stdio::resolve(&mut configured, &Stdio::Null), Stdio::InheritFile(_)));
+    assert!(configured.is_none());
+}
+
+#[test]
+fn spawn_result() {
+    let handle = unsafe { lift_spawn(&ret(&[(0, 0), (4, 7), (5, 0), (6, 0), (7, 0)])) };
+    assert_eq!(handle.unwrap(), 7);
+    let err = unsafe { lift_spawn(&ret(&[(0, 1), (4, NO_ENTRY)])) }.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+#[test]
+fn kill_result() {
+    assert!(unsafe { lift_kill(&ret(&[(0, 0)])) }.is_ok());
+    let err = unsafe { lift_kill(&ret(&[(0, 1), (1, NO_ENTRY)])) }.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+#[test]
+fn try_wait_result() {
+    let running = unsafe { lift_try_wait(&ret(&[(0, 0), (4, 0)])) };
+    assert_eq!(running.unwrap(), None);
+
+    let exited = [(0, 0), (4, 1), (8, 0), (12, 0xfe), (13, 0xff), (14, 0xff), (15, 0xff)];
+    let exited = unsafe { lift_try_wait(&ret(&exited)) }.unwrap().unwrap();
+    assert_eq!(exited, ExitStatus::Exited(-2));
+    assert_eq!(exited.code(), Some(-2));
+    assert!(exited.exit_ok().is_err());
+
+    let terminated = [(0, 0), (4, 1), (8, 1), (12, 9), (13, 0), (14, 0), (15, 0)];
+    let terminated = unsafe { lift_try_wait(&ret(&terminated)) }.unwrap().unwrap();
+    assert_eq!(terminated, ExitStatus::Terminated(9));
+    assert_eq!(terminated.code(), None);
+    assert_eq!(terminated.to_string(), "terminated by the host: 9");
+
+    let err = unsafe { lift_try_wait(&ret(&[(0, 1), (4, NO_ENTRY)])) }.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+#[test]
+fn take_stream_result() {
+    assert_eq!(unsafe { lift_stream(&ret(&[(0, 0)])) }, None);
+    let stream = [(0, 1), (4, 3), (5, 1), (6, 0), (7, 0)];
+    assert_eq!(unsafe { lift_stream(&ret(&stream)) }, Some(0x103));
+}
+
+#[test]
+fn exit_status() {
+    assert!(ExitStatus::Exited(0).exit_ok().is_ok());
+    assert_eq!(ExitStatus::Exited(0).to_string(), "exit status: 0");
+    let err = ExitStatus::Exited(3).exit_ok().unwrap_err();
+    assert_eq!(err.code().map(|code| code.get()), Some(3));
+    assert_eq!(ExitStatus::Terminated(9).exit_ok().unwrap_err().code(), None);
+}
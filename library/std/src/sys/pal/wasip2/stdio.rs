@@ -0,0 +1,88 @@
+#![forbid(unsafe_op_in_unsafe_fn)]
+
+use wasip2::cli::{stderr, stdin, stdout};
+use wasip2::io::streams::{InputStream, OutputStream, StreamError};
+
+use super::error::stream_err2io;
+use crate::io;
+
+pub struct Stdin;
+pub struct Stdout;
+pub struct Stderr;
+
+/// The largest number of bytes `blocking-write-and-flush` accepts at once.
+const MAX_WRITE: usize = 4096;
+
+fn read_stream(stream: &InputStream, data: &mut [u8]) -> io::Result<usize> {
+    let len = u64::try_from(data.len()).unwrap_or(u64::MAX);
+    match stream.blocking_read(len) {
+        Ok(bytes) => {
+            data[..bytes.len()].copy_from_slice(&bytes);
+            Ok(bytes.len())
+        }
+        Err(StreamError::Closed) => Ok(0),
+        Err(e) => Err(stream_err2io(e)),
+    }
+}
+
+fn write_stream(stream: &OutputStream, data: &[u8]) -> io::Result<usize> {
+    let data = &data[..data.len().min(MAX_WRITE)];
+    stream.blocking_write_and_flush(data).map_err(stream_err2io)?;
+    Ok(data.len())
+}
+
+impl Stdin {
+    pub const fn new() -> Stdin {
+        Stdin
+    }
+}
+
+impl io::Read for Stdin {
+    fn read(&mut self, data: &mut [u8]) -> io::Result<usize> {
+        read_stream(&stdin::get_stdin(), data)
+    }
+}
+
+impl Stdout {
+    pub const fn new() -> Stdout {
+        Stdout
+    }
+}
+
+impl io::Write for Stdout {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        write_stream(&stdout::get_stdout(), data)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        stdout::get_stdout().blocking_flush().map_err(stream_err2io)
+    }
+}
+
+impl Stderr {
+    pub const fn new() -> Stderr {
+        Stderr
+    }
+}
+
+impl io::Write for Stderr {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        write_stream(&stderr::get_stderr(), data)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        stderr::get_stderr().blocking_flush().map_err(stream_err2io)
+    }
+}
+
+pub const STDIN_BUF_SIZE: usize = crate::sys_common::io::DEFAULT_BUF_SIZE;
+
+pub fn is_ebadf(_err: &io::Error) -> bool {
+    // The stdio streams of a component always exist, a missing one is
+    // represented by a stream that is already closed instead.
+    false
+}
+
+pub fn panic_output() -> Option<impl io::Write> {
+    Some(Stderr::new())
+}
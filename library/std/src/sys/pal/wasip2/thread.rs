@@ -0,0 +1,46 @@
+#![forbid(unsafe_op_in_unsafe_fn)]
+
+use wasip2::clocks::monotonic_clock;
+
+use crate::ffi::CStr;
+use crate::io;
+use crate::num::NonZero;
+use crate::sys::unsupported;
+use crate::time::Duration;
+
+pub struct Thread(!);
+
+pub const DEFAULT_MIN_STACK_SIZE: usize = 64 * 1024;
+
+impl Thread {
+    // unsafe: see thread::Builder::spawn_unchecked for safety requirements
+    pub unsafe fn new(_stack: usize, _p: Box<dyn FnOnce()>) -> io::Result<Thread> {
+        unsupported()
+    }
+
+    pub fn yield_now() {
+        // There is only ever one thread in a component, so there is nothing
+        // to yield to.
+    }
+
+    pub fn set_name(_name: &CStr) {
+        // nope
+    }
+
+    pub fn sleep(dur: Duration) {
+        let mut nanos = dur.as_nanos();
+        while nanos > 0 {
+            let timeout = u64::try_from(nanos).unwrap_or(u64::MAX);
+            monotonic_clock::subscribe_duration(timeout).block();
+            nanos -= u128::from(timeout);
+        }
+    }
+
+    pub fn join(self) {
+        self.0
+    }
+}
+
+pub fn available_parallelism() -> io::Result<NonZero<usize>> {
+    unsupported()
+}
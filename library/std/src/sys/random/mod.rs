@@ -64,6 +64,9 @@
     } else if #[cfg(target_os = "vxworks")] {
         mod vxworks;
         pub use vxworks::fill_bytes;
+    } else if #[cfg(all(target_os = "wasi", target_env = "p2"))] {
+        mod wasip2;
+        pub use wasip2::fill_bytes;
     } else if #[cfg(target_os = "wasi")] {
         mod wasi;
         pub use wasi::fill_bytes;
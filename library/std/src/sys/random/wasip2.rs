@@ -0,0 +1,11 @@
+pub fn fill_bytes(mut bytes: &mut [u8]) {
+    // `get-random-bytes` may return fewer bytes than requested, so keep
+    // asking until the buffer is full.
+    while !bytes.is_empty() {
+        let len = u64::try_from(bytes.len()).unwrap_or(u64::MAX);
+        let random = wasip2::random::random::get_random_bytes(len);
+        let n = random.len().min(bytes.len());
+        bytes[..n].copy_from_slice(&random[..n]);
+        bytes = &mut bytes[n..];
+    }
+}
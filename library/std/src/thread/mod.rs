@@ -1908,6 +1908,11 @@ fn _assert_both<T: Send + Sync>() {}
 ///   (This does not apply to cgroup v2, or to processes not in a
 ///   cgroup.)
 ///
+/// On `wasm32-wasip1-threads`:
+/// - The host has no way to report the amount of parallelism, so the
+///   `RUST_WASI_AVAILABLE_PARALLELISM` environment variable is used if it is set
+///   to a non-zero integer. Otherwise an error is returned.
+///
 /// On all targets:
 /// - It may overcount the amount of parallelism available when running in a VM
 /// with CPU usage limits (e.g. an overcommitted host).
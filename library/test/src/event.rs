@@ -5,6 +5,14 @@
 use super::time::TestExecTime;
 use super::types::{TestDesc, TestId};
 
+/// Where a test panicked, as reported to the panic hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanicLocation {
+    pub file: String,
+    pub line: u32,
+    pub col: u32,
+}
+
 #[derive(Debug, Clone)]
 pub struct CompletedTest {
     pub id: TestId,
@@ -12,6 +20,8 @@ pub struct CompletedTest {
     pub result: TestResult,
     pub exec_time: Option<TestExecTime>,
     pub stdout: Vec<u8>,
+    /// Only known for tests that ran in this process and panicked on their own thread.
+    pub panic_location: Option<PanicLocation>,
 }
 
 impl CompletedTest {
@@ -22,7 +32,7 @@ pub fn new(
         exec_time: Option<TestExecTime>,
         stdout: Vec<u8>,
     ) -> Self {
-        Self { id, desc, result, exec_time, stdout }
+        Self { id, desc, result, exec_time, stdout, panic_location: None }
     }
 }
 
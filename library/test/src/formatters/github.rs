@@ -0,0 +1,205 @@
+use std::io;
+use std::io::prelude::Write;
+
+use super::{OutputFormatter, failure_location};
+use crate::bench::fmt_bench_samples;
+use crate::console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation};
+use crate::event::PanicLocation;
+use crate::test_result::TestResult;
+use crate::time;
+use crate::types::TestDesc;
+
+/// Writes results as plain lines, turning failures into GitHub Actions
+/// [workflow commands](https://docs.github.com/en/actions/using-workflows/workflow-commands-for-github-actions)
+/// so that they show up as annotations on the failing line.
+pub(crate) struct GithubFormatter<T> {
+    out: OutputLocation<T>,
+}
+
+impl<T: Write> GithubFormatter<T> {
+    pub fn new(out: OutputLocation<T>) -> Self {
+        Self { out }
+    }
+
+    #[cfg(test)]
+    pub fn output_location(&self) -> &OutputLocation<T> {
+        &self.out
+    }
+
+    /// Writes `s` as a single line. Line breaks, like those of multi-line
+    /// messages, are escaped the same way as in workflow commands, so that
+    /// what follows them can't be mistaken for a command.
+    fn write_line(&mut self, s: &str) -> io::Result<()> {
+        let s = s.replace('\r', "%0D").replace('\n', "%0A");
+        self.out.write_all(s.as_bytes())?;
+        self.out.write_all(b"\n")
+    }
+
+    fn write_annotation(
+        &mut self,
+        command: &str,
+        desc: &TestDesc,
+        panic_location: Option<&PanicLocation>,
+        title: &str,
+        message: &str,
+        output: &str,
+    ) -> io::Result<()> {
+        let (file, line, col) = failure_location(desc, panic_location);
+        let message =
+            if output.is_empty() { message.to_string() } else { format!("{message}\n\n{output}") };
+        self.write_line(&format!(
+            "::{command} file={},line={line},col={col},title={}::{}",
+            EscapedProperty(file),
+            EscapedProperty(title),
+            EscapedData(&message)
+        ))
+    }
+}
+
+impl<T: Write> OutputFormatter for GithubFormatter<T> {
+    fn write_discovery_start(&mut self) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::NotFound, "Not yet implemented!"))
+    }
+
+    fn write_test_discovered(&mut self, _desc: &TestDesc, _test_type: &str) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::NotFound, "Not yet implemented!"))
+    }
+
+    fn write_discovery_finish(&mut self, _state: &ConsoleTestDiscoveryState) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::NotFound, "Not yet implemented!"))
+    }
+
+    fn write_run_start(&mut self, test_count: usize, shuffle_seed: Option<u64>) -> io::Result<()> {
+        let noun = if test_count != 1 { "tests" } else { "test" };
+        let shuffle_seed_msg = if let Some(shuffle_seed) = shuffle_seed {
+            format!(" (shuffle seed: {shuffle_seed})")
+        } else {
+            String::new()
+        };
+        self.write_line(&format!("running {test_count} {noun}{shuffle_seed_msg}"))
+    }
+
+    fn write_test_start(&mut self, _desc: &TestDesc) -> io::Result<()> {
+        // Results are written on a single line once they are known.
+        Ok(())
+    }
+
+    fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
+        let message = format!(
+            "test {} has been running for over {} seconds",
+            desc.name,
+            time::TEST_WARN_TIMEOUT_S
+        );
+        self.write_annotation("warning", desc, None, "slow test", &message, "")
+    }
+
+    fn write_result(
+        &mut self,
+        desc: &TestDesc,
+        result: &TestResult,
+        _exec_time: Option<&time::TestExecTime>,
+        panic_location: Option<&PanicLocation>,
+        stdout: &[u8],
+        _state: &ConsoleTestState,
+    ) -> io::Result<()> {
+        let stdout = String::from_utf8_lossy(stdout);
+        let name = desc.name.as_slice();
+        match *result {
+            TestResult::TrOk => self.write_line(&format!("test {name} ... ok")),
+
+            TestResult::TrIgnored => match desc.ignore_message {
+                Some(msg) => self.write_line(&format!("test {name} ... ignored, {msg}")),
+                None => self.write_line(&format!("test {name} ... ignored")),
+            },
+
+            TestResult::TrBench(ref bs) => {
+                self.write_line(&format!("test {name} ... bench: {}", fmt_bench_samples(bs)))
+            }
+
+            TestResult::TrFailed => {
+                self.write_line(&format!("test {name} ... FAILED"))?;
+                let title = format!("test {name} failed");
+                self.write_annotation("error", desc, panic_location, &title, "test failed", &stdout)
+            }
+
+            TestResult::TrFailedMsg(ref m) => {
+                self.write_line(&format!("test {name} ... FAILED"))?;
+                let title = format!("test {name} failed");
+                self.write_annotation("error", desc, panic_location, &title, m, &stdout)
+            }
+
+            TestResult::TrTimedFail => {
+                self.write_line(&format!("test {name} ... FAILED (time limit exceeded)"))?;
+                let title = format!("test {name} exceeded its time limit");
+                self.write_annotation(
+                    "error",
+                    desc,
+                    panic_location,
+                    &title,
+                    "time limit exceeded",
+                    &stdout,
+                )
+            }
+
+            TestResult::TrTimedOut(timeout) => {
+                self.write_line(&format!("test {name} ... FAILED (timed out)"))?;
+                let title = format!("test {name} timed out");
+                let message = format!("timed out after {timeout:?}");
+                self.write_annotation("error", desc, panic_location, &title, &message, &stdout)
+            }
+        }
+    }
+
+    fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
+        let success = state.failed == 0;
+        let mut summary = format!(
+            "test result: {}. {} passed; {} failed; {} ignored; {} measured; {} filtered out",
+            if success { "ok" } else { "FAILED" },
+            state.passed,
+            state.failed,
+            state.ignored,
+            state.measured,
+            state.filtered_out
+        );
+        if let Some(ref exec_time) = state.exec_time {
+            summary.push_str(&format!("; finished in {exec_time}"));
+        }
+        self.write_line(&summary)?;
+
+        Ok(success)
+    }
+}
+
+/// Escapes the message of a workflow command.
+struct EscapedData<'a>(&'a str);
+
+impl std::fmt::Display for EscapedData<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '%' => f.write_str("%25")?,
+                '\r' => f.write_str("%0D")?,
+                '\n' => f.write_str("%0A")?,
+                c => write!(f, "{c}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Escapes a property of a workflow command, which additionally cannot contain the separators
+/// between properties.
+struct EscapedProperty<'a>(&'a str);
+
+impl std::fmt::Display for EscapedProperty<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                ':' => f.write_str("%3A")?,
+                ',' => f.write_str("%2C")?,
+                c => write!(f, "{}", EscapedData(c.encode_utf8(&mut [0; 4])))?,
+            }
+        }
+        Ok(())
+    }
+}
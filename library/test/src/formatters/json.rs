@@ -4,17 +4,20 @@
 
 use super::OutputFormatter;
 use crate::console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation};
+use crate::event::PanicLocation;
+use crate::options::TestShard;
 use crate::test_result::TestResult;
 use crate::time;
 use crate::types::TestDesc;
 
 pub(crate) struct JsonFormatter<T> {
     out: OutputLocation<T>,
+    shard: Option<TestShard>,
 }
 
 impl<T: Write> JsonFormatter<T> {
-    pub fn new(out: OutputLocation<T>) -> Self {
-        Self { out }
+    pub fn new(out: OutputLocation<T>, shard: Option<TestShard>) -> Self {
+        Self { out, shard }
     }
 
     fn writeln_message(&mut self, s: &str) -> io::Result<()> {
@@ -100,9 +103,14 @@ fn write_run_start(&mut self, test_count: usize, shuffle_seed: Option<u64>) -> i
         } else {
             String::new()
         };
+        let shard_json = if let Some(TestShard { index, count }) = self.shard {
+            format!(r#", "shard_index": {index}, "shard_count": {count}"#)
+        } else {
+            String::new()
+        };
         let newline = "\n";
         self.writeln_message(&format!(
-            r#"{{ "type": "suite", "event": "started", "test_count": {test_count}{shuffle_seed_json} }}{newline}"#
+            r#"{{ "type": "suite", "event": "started", "test_count": {test_count}{shuffle_seed_json}{shard_json} }}{newline}"#
             ))
     }
 
@@ -119,6 +127,7 @@ fn write_result(
         desc: &TestDesc,
         result: &TestResult,
         exec_time: Option<&time::TestExecTime>,
+        _: Option<&PanicLocation>,
         stdout: &[u8],
         state: &ConsoleTestState,
     ) -> io::Result<()> {
@@ -146,6 +155,15 @@ fn write_result(
                 Some(r#""reason": "time limit exceeded""#),
             ),
 
+            TestResult::TrTimedOut(timeout) => self.write_event(
+                "test",
+                desc.name.as_slice(),
+                "failed",
+                exec_time,
+                stdout,
+                Some(&*format!(r#""reason": "timed out", "timeout": {}"#, timeout.as_secs_f64())),
+            ),
+
             TestResult::TrFailedMsg(ref m) => self.write_event(
                 "test",
                 desc.name.as_slice(),
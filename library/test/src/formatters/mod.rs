@@ -2,18 +2,23 @@
 use std::io::prelude::Write;
 
 use crate::console::{ConsoleTestDiscoveryState, ConsoleTestState};
+use crate::event::PanicLocation;
 use crate::test_result::TestResult;
 use crate::time;
 use crate::types::{TestDesc, TestName};
 
+mod github;
 mod json;
 mod junit;
 mod pretty;
+mod tap;
 mod terse;
 
+pub(crate) use self::github::GithubFormatter;
 pub(crate) use self::json::JsonFormatter;
 pub(crate) use self::junit::JunitFormatter;
 pub(crate) use self::pretty::PrettyFormatter;
+pub(crate) use self::tap::TapFormatter;
 pub(crate) use self::terse::TerseFormatter;
 
 pub(crate) trait OutputFormatter {
@@ -29,6 +34,7 @@ fn write_result(
         desc: &TestDesc,
         result: &TestResult,
         exec_time: Option<&time::TestExecTime>,
+        panic_location: Option<&PanicLocation>,
         stdout: &[u8],
         state: &ConsoleTestState,
     ) -> io::Result<()>;
@@ -43,3 +49,15 @@ pub(crate) fn write_stderr_delimiter(test_output: &mut Vec<u8>, test_name: &Test
     }
     writeln!(test_output, "---- {test_name} stderr ----").unwrap();
 }
+
+/// Returns the file, line and column a failed test should be reported at: where it panicked, if
+/// known, and otherwise where it is declared.
+pub(crate) fn failure_location<'a>(
+    desc: &'a TestDesc,
+    panic_location: Option<&'a PanicLocation>,
+) -> (&'a str, usize, usize) {
+    match panic_location {
+        Some(location) => (&location.file, location.line as usize, location.col as usize),
+        None => (desc.source_file, desc.start_line, desc.start_col),
+    }
+}
@@ -4,6 +4,7 @@
 use super::OutputFormatter;
 use crate::bench::fmt_bench_samples;
 use crate::console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation};
+use crate::event::PanicLocation;
 use crate::test_result::TestResult;
 use crate::types::TestDesc;
 use crate::{term, time};
@@ -55,6 +56,10 @@ pub fn write_time_failed(&mut self) -> io::Result<()> {
         self.write_short_result("FAILED (time limit exceeded)", term::color::RED)
     }
 
+    pub fn write_timed_out(&mut self) -> io::Result<()> {
+        self.write_short_result("FAILED (timed out)", term::color::RED)
+    }
+
     pub fn write_bench(&mut self) -> io::Result<()> {
         self.write_pretty("bench", term::color::CYAN)
     }
@@ -233,6 +238,7 @@ fn write_result(
         desc: &TestDesc,
         result: &TestResult,
         exec_time: Option<&time::TestExecTime>,
+        _: Option<&PanicLocation>,
         _: &[u8],
         _: &ConsoleTestState,
     ) -> io::Result<()> {
@@ -249,6 +255,7 @@ fn write_result(
                 self.write_plain(format!(": {}", fmt_bench_samples(bs)))?;
             }
             TestResult::TrTimedFail => self.write_time_failed()?,
+            TestResult::TrTimedOut(_) => self.write_timed_out()?,
         }
 
         self.write_time(desc, exec_time)?;
@@ -0,0 +1,239 @@
+use std::io;
+use std::io::prelude::Write;
+
+use super::{OutputFormatter, failure_location};
+use crate::bench::fmt_bench_samples;
+use crate::console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation};
+use crate::event::PanicLocation;
+use crate::test_result::TestResult;
+use crate::time;
+use crate::types::TestDesc;
+
+/// Writes results as a [TAP 14](https://testanything.org/tap-version-14-specification.html)
+/// stream.
+pub(crate) struct TapFormatter<T> {
+    out: OutputLocation<T>,
+    test_number: usize,
+}
+
+impl<T: Write> TapFormatter<T> {
+    pub fn new(out: OutputLocation<T>) -> Self {
+        Self { out, test_number: 0 }
+    }
+
+    #[cfg(test)]
+    pub fn output_location(&self) -> &OutputLocation<T> {
+        &self.out
+    }
+
+    fn write_line(&mut self, s: &str) -> io::Result<()> {
+        assert!(!s.contains('\n'));
+
+        self.out.write_all(s.as_bytes())?;
+        self.out.write_all(b"\n")
+    }
+
+    fn write_test_point(&mut self, ok: bool, desc: &TestDesc, directive: &str) -> io::Result<()> {
+        self.test_number += 1;
+        let status = if ok { "ok" } else { "not ok" };
+        let name = EscapedDescription(desc.name.as_slice());
+        self.write_line(&format!("{status} {} - {name}{directive}", self.test_number))
+    }
+
+    /// Writes the YAML diagnostics block that follows a test point.
+    fn write_diagnostics(
+        &mut self,
+        desc: &TestDesc,
+        panic_location: Option<&PanicLocation>,
+        message: Option<&str>,
+        exec_time: Option<&time::TestExecTime>,
+        output: &str,
+    ) -> io::Result<()> {
+        self.write_line("  ---")?;
+        if let Some(message) = message {
+            self.write_line(&format!("  message: {}", YamlString(message)))?;
+            self.write_line("  severity: fail")?;
+            let (file, line, col) = failure_location(desc, panic_location);
+            self.write_line("  at:")?;
+            self.write_line(&format!("    file: {}", YamlString(file)))?;
+            self.write_line(&format!("    line: {line}"))?;
+            self.write_line(&format!("    column: {col}"))?;
+        }
+        if let Some(exec_time) = exec_time {
+            self.write_line(&format!("  duration_ms: {}", exec_time.0.as_secs_f64() * 1000.0))?;
+        }
+        if !output.is_empty() {
+            self.write_line("  output: |")?;
+            for line in output.lines() {
+                self.write_line(&format!("    {line}"))?;
+            }
+        }
+        self.write_line("  ...")
+    }
+}
+
+impl<T: Write> OutputFormatter for TapFormatter<T> {
+    fn write_discovery_start(&mut self) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::NotFound, "Not yet implemented!"))
+    }
+
+    fn write_test_discovered(&mut self, _desc: &TestDesc, _test_type: &str) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::NotFound, "Not yet implemented!"))
+    }
+
+    fn write_discovery_finish(&mut self, _state: &ConsoleTestDiscoveryState) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::NotFound, "Not yet implemented!"))
+    }
+
+    fn write_run_start(&mut self, test_count: usize, shuffle_seed: Option<u64>) -> io::Result<()> {
+        self.write_line("TAP version 14")?;
+        self.write_line(&format!("1..{test_count}"))?;
+        if let Some(shuffle_seed) = shuffle_seed {
+            self.write_line(&format!("# shuffle seed: {shuffle_seed}"))?;
+        }
+        Ok(())
+    }
+
+    fn write_test_start(&mut self, _desc: &TestDesc) -> io::Result<()> {
+        // Test points are written once the result is known.
+        Ok(())
+    }
+
+    fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
+        self.write_line(&format!(
+            "# test {} has been running for over {} seconds",
+            EscapedDescription(desc.name.as_slice()),
+            time::TEST_WARN_TIMEOUT_S
+        ))
+    }
+
+    fn write_result(
+        &mut self,
+        desc: &TestDesc,
+        result: &TestResult,
+        exec_time: Option<&time::TestExecTime>,
+        panic_location: Option<&PanicLocation>,
+        stdout: &[u8],
+        state: &ConsoleTestState,
+    ) -> io::Result<()> {
+        let stdout = String::from_utf8_lossy(stdout);
+        match *result {
+            TestResult::TrOk => {
+                self.write_test_point(true, desc, "")?;
+                if state.options.display_output && !stdout.is_empty() {
+                    self.write_diagnostics(desc, panic_location, None, exec_time, &stdout)?;
+                }
+                Ok(())
+            }
+
+            TestResult::TrFailed => {
+                self.write_test_point(false, desc, "")?;
+                self.write_diagnostics(
+                    desc,
+                    panic_location,
+                    Some("test failed"),
+                    exec_time,
+                    &stdout,
+                )
+            }
+
+            TestResult::TrFailedMsg(ref m) => {
+                self.write_test_point(false, desc, "")?;
+                self.write_diagnostics(desc, panic_location, Some(m.as_str()), exec_time, &stdout)
+            }
+
+            TestResult::TrTimedFail => {
+                self.write_test_point(false, desc, "")?;
+                self.write_diagnostics(
+                    desc,
+                    panic_location,
+                    Some("time limit exceeded"),
+                    exec_time,
+                    &stdout,
+                )
+            }
+
+            TestResult::TrTimedOut(timeout) => {
+                self.write_test_point(false, desc, "")?;
+                let message = format!("timed out after {timeout:?}");
+                self.write_diagnostics(desc, panic_location, Some(&message), exec_time, &stdout)
+            }
+
+            TestResult::TrIgnored => {
+                // The test is reported as a subtest with an empty plan, which is how TAP says
+                // that everything in it was skipped.
+                let reason = match desc.ignore_message {
+                    Some(msg) => format!(" {}", EscapedDescription(msg)),
+                    None => String::new(),
+                };
+                self.write_line(&format!(
+                    "# Subtest: {}",
+                    EscapedDescription(desc.name.as_slice())
+                ))?;
+                self.write_line(&format!("    1..0 # SKIP{reason}"))?;
+                self.write_test_point(true, desc, &format!(" # SKIP{reason}"))
+            }
+
+            TestResult::TrBench(ref bs) => {
+                // The measurement is reported as a subtest of the benchmark.
+                self.write_line(&format!(
+                    "# Subtest: {}",
+                    EscapedDescription(desc.name.as_slice())
+                ))?;
+                self.write_line("    1..1")?;
+                self.write_line(&format!(
+                    "    ok 1 - {}",
+                    EscapedDescription(fmt_bench_samples(bs).trim())
+                ))?;
+                self.write_test_point(true, desc, "")
+            }
+        }
+    }
+
+    fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
+        self.write_line(&format!("# pass {}", state.passed))?;
+        self.write_line(&format!("# fail {}", state.failed))?;
+        self.write_line(&format!("# skip {}", state.ignored))?;
+        self.write_line(&format!("# measured {}", state.measured))?;
+        self.write_line(&format!("# filtered out {}", state.filtered_out))?;
+        if let Some(ref exec_time) = state.exec_time {
+            self.write_line(&format!("# finished in {exec_time}"))?;
+        }
+
+        Ok(state.failed == 0)
+    }
+}
+
+/// Escapes a test point description, in which `#` would start a directive, and keeps it on a
+/// single line.
+struct EscapedDescription<S: AsRef<str>>(S);
+
+impl<S: AsRef<str>> std::fmt::Display for EscapedDescription<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for c in self.0.as_ref().chars() {
+            match c {
+                '\\' => f.write_str("\\\\")?,
+                '#' => f.write_str("\\#")?,
+                '\n' | '\r' => f.write_str(" ")?,
+                c => write!(f, "{c}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes a string as a YAML scalar that fits on a single line.
+struct YamlString<S: AsRef<str>>(S);
+
+impl<S: AsRef<str>> std::fmt::Display for YamlString<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Single-quoted scalars cannot contain line breaks, but double-quoted ones can escape
+        // them.
+        let s = self.0.as_ref();
+        if s.contains(['\n', '\r']) {
+            write!(f, "{s:?}")
+        } else {
+            write!(f, "'{}'", s.replace('\'', "''"))
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use super::shuffle::shuffle_by_names;
+use crate::options::TestShard;
+use crate::types::{TestDescAndFn, TestName};
+
+/// Keeps the tests that belong to `shard`.
+///
+/// Tests are dealt out to the shards in turn, after ordering them by name or, if a
+/// `shuffle_seed` was given, in the order that seed produces. This only depends on the set
+/// of test names, so the shards stay disjoint across runs with the same filters and seed.
+pub fn shard_tests(
+    shard: TestShard,
+    shuffle_seed: Option<u64>,
+    mut tests: Vec<TestDescAndFn>,
+) -> Vec<TestDescAndFn> {
+    let mut order: Vec<usize> = (0..tests.len()).collect();
+    order.sort_by(|&a, &b| tests[a].desc.name.as_slice().cmp(tests[b].desc.name.as_slice()));
+    if let Some(shuffle_seed) = shuffle_seed {
+        let test_names: Vec<&TestName> = order.iter().map(|&i| &tests[i].desc.name).collect();
+        shuffle_by_names(shuffle_seed, &test_names, &mut order);
+    }
+
+    let mut in_shard = vec![false; tests.len()];
+    for (position, &i) in order.iter().enumerate() {
+        in_shard[i] = position % shard.count == shard.index;
+    }
+    let mut in_shard = in_shard.into_iter();
+    tests.retain(|_| in_shard.next().unwrap());
+    tests
+}
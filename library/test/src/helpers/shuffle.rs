@@ -26,6 +26,13 @@ pub fn shuffle_tests(shuffle_seed: u64, tests: &mut [(TestId, TestDescAndFn)]) {
     shuffle(&mut rng, tests);
 }
 
+/// Shuffles `items` like `shuffle_tests` would shuffle tests named `test_names`.
+pub(crate) fn shuffle_by_names<T>(shuffle_seed: u64, test_names: &[&TestName], items: &mut [T]) {
+    let test_names_hash = calculate_hash(&test_names);
+    let mut rng = Rng::new(shuffle_seed, test_names_hash);
+    shuffle(&mut rng, items);
+}
+
 // `shuffle` is from `rust-analyzer/src/cli/analysis_stats.rs`.
 fn shuffle<T>(rng: &mut Rng, slice: &mut [T]) {
     for i in 0..slice.len() {
@@ -32,7 +32,7 @@
 pub use self::ColorConfig::*;
 pub use self::bench::{Bencher, black_box};
 pub use self::console::run_tests_console;
-pub use self::options::{ColorConfig, Options, OutputFormat, RunIgnored, ShouldPanic};
+pub use self::options::{ColorConfig, Options, OutputFormat, RunIgnored, ShouldPanic, TestShard};
 pub use self::types::TestName::*;
 pub use self::types::*;
 
@@ -41,7 +41,7 @@ pub mod test {
     pub use crate::bench::Bencher;
     pub use crate::cli::{TestOpts, parse_opts};
     pub use crate::helpers::metrics::{Metric, MetricMap};
-    pub use crate::options::{Options, RunIgnored, RunStrategy, ShouldPanic};
+    pub use crate::options::{Options, RunIgnored, RunStrategy, ShouldPanic, TestShard};
     pub use crate::test_result::{TestResult, TrFailed, TrFailedMsg, TrIgnored, TrOk};
     pub use crate::time::{TestExecTime, TestTimeOptions};
     pub use crate::types::{
@@ -51,13 +51,14 @@ pub mod test {
     pub use crate::{assert_test_result, filter_tests, run_test, test_main, test_main_static};
 }
 
+use std::cell::Cell;
 use std::collections::VecDeque;
 use std::io::prelude::Write;
 use std::mem::ManuallyDrop;
 use std::panic::{self, AssertUnwindSafe, PanicHookInfo, catch_unwind};
 use std::process::{self, Command, Termination};
-use std::sync::mpsc::{Sender, channel};
-use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender, channel};
+use std::sync::{Arc, Mutex, Once};
 use std::time::{Duration, Instant};
 use std::{env, io, thread};
 
@@ -79,8 +80,9 @@ pub mod test {
 
 use core::any::Any;
 
-use event::{CompletedTest, TestEvent};
+use event::{CompletedTest, PanicLocation, TestEvent};
 use helpers::concurrency::get_concurrency;
+use helpers::shard::shard_tests;
 use helpers::shuffle::{get_shuffle_seed, shuffle_tests};
 use options::RunStrategy;
 use test_result::*;
@@ -264,7 +266,6 @@ pub fn run_tests<F>(
 {
     use std::collections::HashMap;
     use std::hash::{BuildHasherDefault, DefaultHasher};
-    use std::sync::mpsc::RecvTimeoutError;
 
     struct RunningTest {
         join_handle: Option<thread::JoinHandle<()>>,
@@ -342,13 +343,22 @@ struct TimeoutEntry {
         RunStrategy::InProcess
     };
 
+    // Tests running in a subprocess kill it themselves once `--test-timeout` elapses, but
+    // threads cannot be stopped, so in-process tests are abandoned instead.
+    let abandon_timeout = match run_strategy {
+        RunStrategy::InProcess => opts.test_timeout,
+        RunStrategy::SpawnPrimary => None,
+    };
+    let mut abandoned_tests = false;
+
     let mut running_tests: TestMap = HashMap::default();
     let mut timeout_queue: VecDeque<TimeoutEntry> = VecDeque::new();
+    let mut deadline_queue: VecDeque<TimeoutEntry> = VecDeque::new();
 
     fn get_timed_out_tests(
         running_tests: &TestMap,
         timeout_queue: &mut VecDeque<TimeoutEntry>,
-    ) -> Vec<TestDesc> {
+    ) -> Vec<TimeoutEntry> {
         let now = Instant::now();
         let mut timed_out = Vec::new();
         while let Some(timeout_entry) = timeout_queue.front() {
@@ -357,7 +367,7 @@ fn get_timed_out_tests(
             }
             let timeout_entry = timeout_queue.pop_front().unwrap();
             if running_tests.contains_key(&timeout_entry.id) {
-                timed_out.push(timeout_entry.desc);
+                timed_out.push(timeout_entry);
             }
         }
         timed_out
@@ -373,22 +383,37 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
     if concurrency == 1 {
         while !remaining.is_empty() {
             let (id, test) = remaining.pop_front().unwrap();
-            let event = TestEvent::TeWait(test.desc.clone());
+            let desc = test.desc.clone();
+            let event = TestEvent::TeWait(desc.clone());
             notify_about_test_event(event)?;
             let join_handle = run_test(opts, !opts.run_tests, id, test, run_strategy, tx.clone());
+            let deadline = abandon_timeout.map(|timeout| Instant::now() + timeout);
             // Wait for the test to complete.
-            let mut completed_test = rx.recv().unwrap();
-            RunningTest { join_handle }.join(&mut completed_test);
+            let completed_test = match wait_for_test(&rx, id, deadline) {
+                Some(mut completed_test) => {
+                    RunningTest { join_handle }.join(&mut completed_test);
+                    completed_test
+                }
+                None => {
+                    abandoned_tests = true;
+                    let result = TrTimedOut(abandon_timeout.unwrap());
+                    CompletedTest::new(id, desc, result, None, Vec::new())
+                }
+            };
 
             let fail_fast = match completed_test.result {
                 TrIgnored | TrOk | TrBench(_) => false,
-                TrFailed | TrFailedMsg(_) | TrTimedFail => opts.fail_fast,
+                TrFailed | TrFailedMsg(_) | TrTimedFail | TrTimedOut(_) => opts.fail_fast,
             };
 
             let event = TestEvent::TeResult(completed_test);
             notify_about_test_event(event)?;
 
             if fail_fast {
+                if abandoned_tests {
+                    // Prevent abandoned test threads from panicking
+                    std::mem::forget(rx);
+                }
                 return Ok(());
             }
         }
@@ -404,22 +429,36 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
                 let join_handle =
                     run_test(opts, !opts.run_tests, id, test, run_strategy, tx.clone());
                 running_tests.insert(id, RunningTest { join_handle });
+                if let Some(test_timeout) = abandon_timeout {
+                    let timeout = Instant::now() + test_timeout;
+                    deadline_queue.push_back(TimeoutEntry { id, desc: desc.clone(), timeout });
+                }
                 timeout_queue.push_back(TimeoutEntry { id, desc, timeout });
                 pending += 1;
             }
 
             let mut res;
+            let mut timed_out = Vec::new();
             loop {
-                if let Some(timeout) = calc_timeout(&timeout_queue) {
+                let timeout = [calc_timeout(&timeout_queue), calc_timeout(&deadline_queue)]
+                    .into_iter()
+                    .flatten()
+                    .min();
+                if let Some(timeout) = timeout {
                     res = rx.recv_timeout(timeout);
                     for test in get_timed_out_tests(&running_tests, &mut timeout_queue) {
-                        let event = TestEvent::TeTimeout(test);
+                        let event = TestEvent::TeTimeout(test.desc);
                         notify_about_test_event(event)?;
                     }
 
                     match res {
                         Err(RecvTimeoutError::Timeout) => {
-                            // Result is not yet ready, continue waiting.
+                            // Result is not yet ready, continue waiting unless a test has
+                            // exceeded its `--test-timeout`.
+                            timed_out = get_timed_out_tests(&running_tests, &mut deadline_queue);
+                            if !timed_out.is_empty() {
+                                break;
+                            }
                         }
                         _ => {
                             // We've got a result, stop the loop.
@@ -432,23 +471,38 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
                 }
             }
 
-            let mut completed_test = res.unwrap();
-            let running_test = running_tests.remove(&completed_test.id).unwrap();
-            running_test.join(&mut completed_test);
+            let mut completed_tests = Vec::new();
+            for TimeoutEntry { id, desc, .. } in timed_out {
+                // The thread keeps running in the background, and whatever it reports later
+                // is dropped below since it is no longer in `running_tests`.
+                running_tests.remove(&id);
+                abandoned_tests = true;
+                let result = TrTimedOut(abandon_timeout.unwrap());
+                completed_tests.push(CompletedTest::new(id, desc, result, None, Vec::new()));
+            }
+            if completed_tests.is_empty() {
+                let mut completed_test = res.unwrap();
+                if let Some(running_test) = running_tests.remove(&completed_test.id) {
+                    running_test.join(&mut completed_test);
+                    completed_tests.push(completed_test);
+                }
+            }
 
-            let fail_fast = match completed_test.result {
-                TrIgnored | TrOk | TrBench(_) => false,
-                TrFailed | TrFailedMsg(_) | TrTimedFail => opts.fail_fast,
-            };
+            for completed_test in completed_tests {
+                let fail_fast = match completed_test.result {
+                    TrIgnored | TrOk | TrBench(_) => false,
+                    TrFailed | TrFailedMsg(_) | TrTimedFail | TrTimedOut(_) => opts.fail_fast,
+                };
 
-            let event = TestEvent::TeResult(completed_test);
-            notify_about_test_event(event)?;
-            pending -= 1;
+                let event = TestEvent::TeResult(completed_test);
+                notify_about_test_event(event)?;
+                pending -= 1;
 
-            if fail_fast {
-                // Prevent remaining test threads from panicking
-                std::mem::forget(rx);
-                return Ok(());
+                if fail_fast {
+                    // Prevent remaining test threads from panicking
+                    std::mem::forget(rx);
+                    return Ok(());
+                }
             }
         }
     }
@@ -460,16 +514,44 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
             notify_about_test_event(event)?;
             let join_handle = run_test(opts, false, id, b, run_strategy, tx.clone());
             // Wait for the test to complete.
-            let mut completed_test = rx.recv().unwrap();
+            let mut completed_test = wait_for_test(&rx, id, None).unwrap();
             RunningTest { join_handle }.join(&mut completed_test);
 
             let event = TestEvent::TeResult(completed_test);
             notify_about_test_event(event)?;
         }
     }
+
+    if abandoned_tests {
+        // Prevent abandoned test threads from panicking
+        std::mem::forget(rx);
+    }
     Ok(())
 }
 
+/// Waits for the result of the test `id`, dropping any results that tests abandoned after
+/// their `--test-timeout` report in the meantime. Returns `None` if `deadline` passes first.
+fn wait_for_test(
+    rx: &Receiver<CompletedTest>,
+    id: TestId,
+    deadline: Option<Instant>,
+) -> Option<CompletedTest> {
+    loop {
+        let completed_test = match deadline {
+            Some(deadline) => {
+                match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Err(RecvTimeoutError::Timeout) => return None,
+                    res => res.unwrap(),
+                }
+            }
+            None => rx.recv().unwrap(),
+        };
+        if completed_test.id == id {
+            return Some(completed_test);
+        }
+    }
+}
+
 pub fn filter_tests(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<TestDescAndFn> {
     let mut filtered = tests;
     let matches_filter = |test: &TestDescAndFn, filter: &str| {
@@ -508,6 +590,11 @@ pub fn filter_tests(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<TestDescA
         RunIgnored::No => {}
     }
 
+    // Only keep the tests of the requested shard
+    if let Some(shard) = opts.shard {
+        filtered = shard_tests(shard, opts.shuffle_seed, filtered);
+    }
+
     filtered
 }
 
@@ -559,7 +646,10 @@ pub fn run_test(
             let name = desc.name.clone();
             let nocapture = opts.nocapture;
             let time_options = opts.time_options;
+            let test_timeout = opts.test_timeout;
             let bench_benchmarks = opts.bench_benchmarks;
+            // Only these formatters point at the line a test failed on.
+            let panic_locations = matches!(opts.format, OutputFormat::Tap | OutputFormat::Github);
 
             let runtest = move || match strategy {
                 RunStrategy::InProcess => run_test_in_process(
@@ -570,6 +660,7 @@ pub fn run_test(
                     runnable_test,
                     monitor_ch,
                     time_options,
+                    panic_locations,
                 ),
                 RunStrategy::SpawnPrimary => spawn_test_subprocess(
                     id,
@@ -578,6 +669,7 @@ pub fn run_test(
                     time_options.is_some(),
                     monitor_ch,
                     time_options,
+                    test_timeout,
                     bench_benchmarks,
                 ),
             };
@@ -624,6 +716,31 @@ fn __rust_begin_short_backtrace<T, F: FnOnce() -> T>(f: F) -> T {
     black_box(result)
 }
 
+thread_local! {
+    /// Where the test running on this thread last panicked.
+    static PANIC_LOCATION: Cell<Option<PanicLocation>> = const { Cell::new(None) };
+}
+
+/// Wraps the current panic hook in one that also records where each thread
+/// panicked, so that formatters can point at the failing line without having
+/// to rely on the captured output.
+fn record_panic_locations() {
+    static INSTALLED: Once = Once::new();
+    INSTALLED.call_once(|| {
+        let previous_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if let Some(location) = info.location() {
+                PANIC_LOCATION.set(Some(PanicLocation {
+                    file: location.file().to_owned(),
+                    line: location.line(),
+                    col: location.column(),
+                }));
+            }
+            previous_hook(info);
+        }));
+    });
+}
+
 fn run_test_in_process(
     id: TestId,
     desc: TestDesc,
@@ -632,6 +749,7 @@ fn run_test_in_process(
     runnable_test: RunnableTest,
     monitor_ch: Sender<CompletedTest>,
     time_opts: Option<time::TestTimeOptions>,
+    panic_locations: bool,
 ) {
     // Buffer for capturing standard I/O
     let data = Arc::new(Mutex::new(Vec::new()));
@@ -640,21 +758,31 @@ fn run_test_in_process(
         io::set_output_capture(Some(data.clone()));
     }
 
+    if panic_locations {
+        record_panic_locations();
+    }
+    PANIC_LOCATION.take();
+
     let start = report_time.then(Instant::now);
-    let result = fold_err(catch_unwind(AssertUnwindSafe(|| runnable_test.run())));
+    let result = catch_unwind(AssertUnwindSafe(|| runnable_test.run()));
+    let panicked = result.is_err();
+    let result = fold_err(result);
     let exec_time = start.map(|start| {
         let duration = start.elapsed();
         TestExecTime(duration)
     });
 
     io::set_output_capture(None);
+    // Panics that the test caught itself don't say where it failed.
+    let panic_location = PANIC_LOCATION.take().filter(|_| panic_locations && panicked);
 
     let test_result = match result {
         Ok(()) => calc_result(&desc, Ok(()), &time_opts, &exec_time),
         Err(e) => calc_result(&desc, Err(e.as_ref()), &time_opts, &exec_time),
     };
     let stdout = data.lock().unwrap_or_else(|e| e.into_inner()).to_vec();
-    let message = CompletedTest::new(id, desc, test_result, exec_time, stdout);
+    let mut message = CompletedTest::new(id, desc, test_result, exec_time, stdout);
+    message.panic_location = panic_location;
     monitor_ch.send(message).unwrap();
 }
 
@@ -678,6 +806,7 @@ fn spawn_test_subprocess(
     report_time: bool,
     monitor_ch: Sender<CompletedTest>,
     time_opts: Option<time::TestTimeOptions>,
+    test_timeout: Option<Duration>,
     bench_benchmarks: bool,
 ) {
     let (result, test_output, exec_time) = (|| {
@@ -695,7 +824,11 @@ fn spawn_test_subprocess(
         }
 
         let start = report_time.then(Instant::now);
-        let output = match command.output() {
+        let output = match test_timeout {
+            Some(test_timeout) => output_with_timeout(command, nocapture, test_timeout),
+            None => command.output().map(|out| (Some(out.status), out.stdout, out.stderr)),
+        };
+        let output = match output {
             Ok(out) => out,
             Err(e) => {
                 let err = format!("Failed to spawn {} as child for test: {:?}", args[0], e);
@@ -707,12 +840,15 @@ fn spawn_test_subprocess(
             TestExecTime(duration)
         });
 
-        let std::process::Output { stdout, stderr, status } = output;
+        let (status, stdout, stderr) = output;
         let mut test_output = stdout;
         formatters::write_stderr_delimiter(&mut test_output, &desc.name);
         test_output.extend_from_slice(&stderr);
 
-        let result = get_result_from_exit_code(&desc, status, &time_opts, &exec_time);
+        let result = match status {
+            Some(status) => get_result_from_exit_code(&desc, status, &time_opts, &exec_time),
+            None => TrTimedOut(test_timeout.unwrap()),
+        };
         (result, test_output, exec_time)
     })();
 
@@ -720,6 +856,54 @@ fn spawn_test_subprocess(
     monitor_ch.send(message).unwrap();
 }
 
+/// Like `Command::output`, but kills the child if it is still running after `timeout`, in
+/// which case no exit status is returned.
+fn output_with_timeout(
+    mut command: Command,
+    nocapture: bool,
+    timeout: Duration,
+) -> io::Result<(Option<process::ExitStatus>, Vec<u8>, Vec<u8>)> {
+    if !nocapture {
+        command.stdout(process::Stdio::piped());
+        command.stderr(process::Stdio::piped());
+    }
+    let deadline = Instant::now() + timeout;
+    let mut child = command.spawn()?;
+
+    // Drain the pipes while waiting, so that the child cannot block on a full pipe.
+    fn drain(pipe: Option<impl io::Read + Send + 'static>) -> Option<thread::JoinHandle<Vec<u8>>> {
+        pipe.map(|mut pipe| {
+            thread::spawn(move || {
+                let mut buf = Vec::new();
+                let _ = pipe.read_to_end(&mut buf);
+                buf
+            })
+        })
+    }
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let mut delay = Duration::from_millis(1);
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            child.kill()?;
+            child.wait()?;
+            break None;
+        }
+        thread::sleep(delay.min(deadline - now));
+        delay = (delay * 2).min(Duration::from_millis(100));
+    };
+
+    let collect = |pipe: Option<thread::JoinHandle<Vec<u8>>>| {
+        pipe.map(|pipe| pipe.join().unwrap_or_default()).unwrap_or_default()
+    };
+    Ok((status, collect(stdout), collect(stderr)))
+}
+
 fn run_test_in_spawned_subprocess(desc: TestDesc, runnable_test: RunnableTest) -> ! {
     let builtin_panic_hook = panic::take_hook();
     let record_result = Arc::new(move |panic_info: Option<&'_ PanicHookInfo<'_>>| {
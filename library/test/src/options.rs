@@ -36,6 +36,10 @@ pub enum OutputFormat {
     Json,
     /// JUnit output
     Junit,
+    /// TAP 14 output
+    Tap,
+    /// GitHub Actions workflow commands
+    Github,
 }
 
 /// Whether ignored test should be run or not
@@ -47,6 +51,16 @@ pub enum RunIgnored {
     Only,
 }
 
+/// Selects one of several disjoint subsets of the tests, so that a test suite
+/// can be split across multiple runs.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TestShard {
+    /// Zero-based index of the shard to run.
+    pub index: usize,
+    /// Total number of shards.
+    pub count: usize,
+}
+
 #[derive(Clone, Copy)]
 pub enum RunStrategy {
     /// Runs the test in the current process, and sends the result back over the
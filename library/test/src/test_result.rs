@@ -2,6 +2,7 @@
 #[cfg(unix)]
 use std::os::unix::process::ExitStatusExt;
 use std::process::ExitStatus;
+use std::time::Duration;
 
 pub use self::TestResult::*;
 use super::bench::BenchSamples;
@@ -35,6 +36,8 @@ pub enum TestResult {
     TrIgnored,
     TrBench(BenchSamples),
     TrTimedFail,
+    /// The test was still running when its `--test-timeout` elapsed.
+    TrTimedOut(Duration),
 }
 
 /// Creates a `TestResult` depending on the raw result of test execution
@@ -1,7 +1,8 @@
 use super::*;
 use crate::{
     console::OutputLocation,
-    formatters::PrettyFormatter,
+    event::PanicLocation,
+    formatters::{GithubFormatter, OutputFormatter, PrettyFormatter, TapFormatter},
     test::{
         MetricMap,
         // FIXME (introduced by #65251)
@@ -29,9 +30,11 @@ fn new() -> TestOpts {
             format: OutputFormat::Pretty,
             shuffle: false,
             shuffle_seed: None,
+            shard: None,
             test_threads: None,
             skip: vec![],
             time_options: None,
+            test_timeout: None,
             options: Options::new(),
             fail_fast: false,
         }
@@ -478,6 +481,33 @@ fn parse_include_ignored_flag() {
     assert_eq!(opts.run_ignored, RunIgnored::Yes);
 }
 
+#[test]
+fn parse_shard_options() {
+    let args = vec![
+        "progname".to_string(),
+        "-Zunstable-options".to_string(),
+        "--shard-index=1".to_string(),
+        "--shard-count=3".to_string(),
+    ];
+    let opts = parse_opts(&args).unwrap().unwrap();
+    assert_eq!(opts.shard, Some(TestShard { index: 1, count: 3 }));
+
+    let args = vec![
+        "progname".to_string(),
+        "-Zunstable-options".to_string(),
+        "--shard-index=3".to_string(),
+        "--shard-count=3".to_string(),
+    ];
+    assert!(parse_opts(&args).unwrap().is_err());
+
+    let args = vec![
+        "progname".to_string(),
+        "-Zunstable-options".to_string(),
+        "--shard-count=3".to_string(),
+    ];
+    assert!(parse_opts(&args).unwrap().is_err());
+}
+
 #[test]
 pub fn filter_for_ignored_option() {
     // When we run ignored tests the test filter should filter out all the
@@ -731,6 +761,50 @@ pub fn order_depends_on_more_than_seed() {
     assert!(left.iter().zip(right).any(|(a, b)| a.0 != b.0));
 }
 
+#[test]
+pub fn shards_partition_tests() {
+    for shuffle_seed in [None, Some(42)] {
+        let mut names = Vec::new();
+        for index in 0..3 {
+            let opts = TestOpts {
+                shard: Some(TestShard { index, count: 3 }),
+                shuffle_seed,
+                ..TestOpts::new()
+            };
+            let shard = filter_tests(&opts, sample_tests());
+            assert!(shard.len() >= sample_tests().len() / 3);
+            names.extend(shard.into_iter().map(|test| test.desc.name.to_string()));
+        }
+
+        // Every test ends up in exactly one shard.
+        let mut expected: Vec<_> =
+            sample_tests().into_iter().map(|test| test.desc.name.to_string()).collect();
+        expected.sort();
+        names.sort();
+        assert_eq!(names, expected);
+    }
+}
+
+#[test]
+pub fn shards_do_not_depend_on_test_order() {
+    let opts = TestOpts {
+        shard: Some(TestShard { index: 0, count: 2 }),
+        shuffle_seed: Some(7),
+        ..TestOpts::new()
+    };
+
+    let mut reversed = sample_tests();
+    reversed.reverse();
+
+    let mut left: Vec<_> =
+        filter_tests(&opts, sample_tests()).into_iter().map(|t| t.desc.name.to_string()).collect();
+    let mut right: Vec<_> =
+        filter_tests(&opts, reversed).into_iter().map(|t| t.desc.name.to_string()).collect();
+    left.sort();
+    right.sort();
+    assert_eq!(left, right);
+}
+
 #[test]
 pub fn test_metricmap_compare() {
     let mut m1 = MetricMap::new();
@@ -922,3 +996,183 @@ fn f(_: &mut Bencher) -> Result<(), String> {
     let result = rx.recv().unwrap().result;
     assert_eq!(result, TrFailed);
 }
+
+#[test]
+fn failure_location_prefers_panic_location() {
+    let mut desc = typed_test_desc(TestType::UnitTest);
+    desc.source_file = "src/lib.rs";
+    desc.start_line = 10;
+    desc.start_col = 4;
+
+    let location = PanicLocation { file: "src/lib.rs".to_string(), line: 12, col: 9 };
+    assert_eq!(crate::formatters::failure_location(&desc, Some(&location)), ("src/lib.rs", 12, 9));
+
+    // Without a recorded panic, the location of the test itself is used.
+    assert_eq!(crate::formatters::failure_location(&desc, None), ("src/lib.rs", 10, 4));
+}
+
+// FIXME: Re-enable emscripten once it can catch panics again (introduced by #65251)
+#[test]
+#[cfg(not(target_os = "emscripten"))]
+#[cfg_attr(not(panic = "unwind"), ignore = "test requires unwinding support")]
+fn test_records_panic_location() {
+    fn f() -> Result<(), String> {
+        panic!("an error message");
+    }
+    let desc =
+        TestDescAndFn { desc: typed_test_desc(TestType::UnitTest), testfn: DynTestFn(Box::new(f)) };
+    let mut opts = TestOpts::new();
+    opts.format = OutputFormat::Tap;
+    let (tx, rx) = channel();
+    run_test(&opts, false, TestId(0), desc, RunStrategy::InProcess, tx);
+    let completed = rx.recv().unwrap();
+    assert_eq!(completed.result, TrFailed);
+    let location = completed.panic_location.unwrap();
+    assert_eq!(location.file, file!());
+
+    // Other formatters don't use the location.
+    let desc =
+        TestDescAndFn { desc: typed_test_desc(TestType::UnitTest), testfn: DynTestFn(Box::new(f)) };
+    let (tx, rx) = channel();
+    run_test(&TestOpts::new(), false, TestId(0), desc, RunStrategy::InProcess, tx);
+    let completed = rx.recv().unwrap();
+    assert_eq!(completed.result, TrFailed);
+    assert!(completed.panic_location.is_none());
+}
+
+#[test]
+#[cfg(not(target_os = "emscripten"))]
+#[cfg_attr(not(panic = "unwind"), ignore = "test requires unwinding support")]
+fn test_ignores_caught_panic_location() {
+    fn f() -> Result<(), String> {
+        let _ = std::panic::catch_unwind(|| panic!("caught"));
+        Err("an error message".to_string())
+    }
+    let desc =
+        TestDescAndFn { desc: typed_test_desc(TestType::UnitTest), testfn: DynTestFn(Box::new(f)) };
+    let mut opts = TestOpts::new();
+    opts.format = OutputFormat::Github;
+    let (tx, rx) = channel();
+    run_test(&opts, false, TestId(0), desc, RunStrategy::InProcess, tx);
+    let completed = rx.recv().unwrap();
+    assert_eq!(completed.result, TrFailed);
+    assert!(completed.panic_location.is_none());
+}
+
+#[test]
+fn tap_reports_ignored_tests_as_skipped_subtests() {
+    let mut desc = typed_test_desc(TestType::UnitTest);
+    desc.ignore = true;
+    desc.ignore_message = Some("not yet");
+
+    let mut out = TapFormatter::new(OutputLocation::Raw(Vec::new()));
+    let st = console::ConsoleTestState::new(&TestOpts::new()).unwrap();
+    out.write_result(&desc, &TrIgnored, None, None, &[], &st).unwrap();
+    let s = match out.output_location() {
+        &OutputLocation::Raw(ref m) => String::from_utf8_lossy(&m[..]).into_owned(),
+        &OutputLocation::Pretty(_) => unreachable!(),
+    };
+
+    assert_eq!(s, "# Subtest: whatever\n    1..0 # SKIP not yet\nok 1 - whatever # SKIP not yet\n");
+}
+
+#[test]
+fn github_escapes_line_breaks() {
+    let mut desc = typed_test_desc(TestType::UnitTest);
+    desc.ignore_message = Some("not yet\n::error::injected");
+
+    let mut out = GithubFormatter::new(OutputLocation::Raw(Vec::new()));
+    let st = console::ConsoleTestState::new(&TestOpts::new()).unwrap();
+    out.write_result(&desc, &TrIgnored, None, None, &[], &st).unwrap();
+    let failed = TrFailedMsg("first\r\nsecond".to_string());
+    out.write_result(&desc, &failed, None, None, &[], &st).unwrap();
+    let s = match out.output_location() {
+        &OutputLocation::Raw(ref m) => String::from_utf8_lossy(&m[..]).into_owned(),
+        &OutputLocation::Pretty(_) => unreachable!(),
+    };
+
+    let lines: Vec<_> = s.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "test whatever ... ignored, not yet%0A::error::injected");
+    assert_eq!(lines[1], "test whatever ... FAILED");
+    assert!(lines[2].ends_with("::first%0D%0Asecond"), "{}", lines[2]);
+}
+
+/// Hangs when it runs as the only test of a subprocess, which happens on the main thread,
+/// rather than on a thread named after the test.
+#[test]
+fn test_timeout_subprocess_hangs() {
+    if thread::current().name() == Some("main") {
+        thread::sleep(Duration::from_secs(60));
+    }
+}
+
+#[test]
+#[cfg(not(any(target_os = "emscripten", target_family = "wasm")))]
+fn test_timeout_kills_hung_subprocess() {
+    let desc = TestDescAndFn {
+        desc: TestDesc {
+            name: StaticTestName("tests::test_timeout_subprocess_hangs"),
+            ..typed_test_desc(TestType::UnitTest)
+        },
+        // Dynamic tests cannot run in a subprocess.
+        testfn: StaticTestFn(|| Ok(())),
+    };
+    let opts = TestOpts { test_timeout: Some(Duration::from_millis(500)), ..TestOpts::new() };
+    let (tx, rx) = channel();
+    let start = Instant::now();
+    let handle = run_test(&opts, false, TestId(0), desc, RunStrategy::SpawnPrimary, tx);
+    let completed = rx.recv().unwrap();
+    assert_eq!(completed.result, TrTimedOut(Duration::from_millis(500)));
+    // The child was killed rather than waited for.
+    assert!(start.elapsed() < Duration::from_secs(30));
+    if let Some(handle) = handle {
+        handle.join().unwrap();
+    }
+}
+
+#[test]
+#[cfg(not(target_os = "emscripten"))]
+fn test_timeout_abandons_hung_tests() {
+    fn hang() -> Result<(), String> {
+        thread::sleep(Duration::from_secs(5));
+        Ok(())
+    }
+    fn pass() -> Result<(), String> {
+        Ok(())
+    }
+
+    for test_threads in [1, 2] {
+        let tests = [("a_hangs", hang as fn() -> Result<(), String>), ("b_passes", pass)]
+            .into_iter()
+            .map(|(name, f)| TestDescAndFn {
+                desc: TestDesc { name: StaticTestName(name), ..typed_test_desc(TestType::Unknown) },
+                testfn: DynTestFn(Box::new(f)),
+            })
+            .collect();
+        let (tx, rx) = channel();
+        let notify = move |event: TestEvent| {
+            if let TestEvent::TeResult(result) = event {
+                tx.send((result.desc.name.to_string(), result.result)).unwrap();
+            }
+            Ok(())
+        };
+        let opts = TestOpts {
+            run_tests: true,
+            test_threads: Some(test_threads),
+            test_timeout: Some(Duration::from_millis(100)),
+            ..TestOpts::new()
+        };
+        run_tests(&opts, tests, notify).unwrap();
+
+        let mut results: Vec<_> = rx.iter().collect();
+        results.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            results,
+            vec![
+                ("a_hangs".to_string(), TrTimedOut(Duration::from_millis(100))),
+                ("b_passes".to_string(), TrOk),
+            ]
+        );
+    }
+}
@@ -392,7 +392,8 @@ pub(crate) fn from_matches(
             }
         };
 
-        let dcx = new_dcx(error_format, None, diagnostic_width, &unstable_opts);
+        let dcx =
+            new_dcx(error_format, None, diagnostic_width, &unstable_opts, early_dcx.sarif_log());
         let dcx = dcx.handle();
 
         // check for deprecated options
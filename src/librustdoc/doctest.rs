@@ -151,6 +151,7 @@ pub(crate) fn run(
         override_queries: None,
         make_codegen_backend: None,
         registry: rustc_driver::diagnostics_registry(),
+        sarif_log: None,
         ice_file: None,
         using_internal_features: Arc::default(),
         expanded_args: options.expanded_args.clone(),
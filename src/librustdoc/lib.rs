@@ -788,8 +788,13 @@ fn main_args(
             None => return Ok(()),
         };
 
-    let dcx =
-        core::new_dcx(options.error_format, None, options.diagnostic_width, &options.unstable_opts);
+    let dcx = core::new_dcx(
+        options.error_format,
+        None,
+        options.diagnostic_width,
+        &options.unstable_opts,
+        early_dcx.sarif_log(),
+    );
     let dcx = dcx.handle();
 
     let input = match input {
@@ -809,8 +814,13 @@ fn main_args(
         (false, Some(md_input)) => {
             let md_input = md_input.to_owned();
             let edition = options.edition;
-            let config =
-                core::create_config(input, options, &render_options, using_internal_features);
+            let config = core::create_config(
+                input,
+                options,
+                &render_options,
+                using_internal_features,
+                early_dcx.sarif_log().clone(),
+            );
 
             // `markdown::render` can invoke `doctest::make_test`, which
             // requires session globals and a thread pool, so we use
@@ -843,7 +853,13 @@ fn main_args(
     let scrape_examples_options = options.scrape_examples_options.clone();
     let bin_crate = options.bin_crate;
 
-    let config = core::create_config(input, options, &render_options, using_internal_features);
+    let config = core::create_config(
+        input,
+        options,
+        &render_options,
+        using_internal_features,
+        early_dcx.sarif_log().clone(),
+    );
 
     interface::run_compiler(config, |compiler| {
         let sess = &compiler.sess;
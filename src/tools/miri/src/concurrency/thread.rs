@@ -174,6 +174,8 @@ pub enum BlockReason {
     InitOnce(InitOnceId),
     /// Blocked on epoll.
     Epoll,
+    /// Blocked on a socket, until a connection is established or data can be sent or received.
+    Socket,
 }
 
 /// The state of a thread.
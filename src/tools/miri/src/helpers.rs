@@ -787,7 +787,8 @@ fn get_last_error(&mut self) -> InterpResult<'tcx, Scalar> {
     fn io_error_to_errnum(&self, err: std::io::Error) -> InterpResult<'tcx, Scalar> {
         let this = self.eval_context_ref();
         let target = &this.tcx.sess.target;
-        if target.families.iter().any(|f| f == "unix") {
+        // wasi-libc uses the WASI errno values for its `E*` constants, so it is covered as well.
+        if target.families.iter().any(|f| f == "unix") || target.os == "wasi" {
             for &(name, kind) in UNIX_IO_ERROR_TABLE {
                 if err.kind() == kind {
                     return Ok(this.eval_libc(name));
@@ -817,7 +818,8 @@ fn try_errnum_to_io_error(
     ) -> InterpResult<'tcx, Option<std::io::ErrorKind>> {
         let this = self.eval_context_ref();
         let target = &this.tcx.sess.target;
-        if target.families.iter().any(|f| f == "unix") {
+        // See `io_error_to_errnum` for why wasi is included.
+        if target.families.iter().any(|f| f == "unix") || target.os == "wasi" {
             let errnum = errnum.to_i32()?;
             for &(name, kind) in UNIX_IO_ERROR_TABLE {
                 if errnum == this.eval_libc_i32(name) {
@@ -475,6 +475,8 @@ pub struct MiriMachine<'tcx> {
 
     /// The list of all EpollEventInterest.
     pub(crate) epoll_interests: shims::EpollInterestTable,
+    /// The ports in use on the emulated loopback network.
+    pub(crate) network: shims::Network,
 
     /// This machine's monotone clock.
     pub(crate) clock: Clock,
@@ -669,6 +671,7 @@ pub(crate) fn new(config: &MiriConfig, layout_cx: LayoutCx<'tcx>) -> Self {
             validation: config.validation,
             fds: shims::FdTable::init(config.mute_stdout_stderr),
             epoll_interests: shims::EpollInterestTable::new(),
+            network: shims::Network::new(),
             dirs: Default::default(),
             layouts,
             threads,
@@ -739,6 +742,9 @@ pub(crate) fn late_init(
         on_main_stack_empty: StackEmptyCallback<'tcx>,
     ) -> InterpResult<'tcx> {
         EnvVars::init(this, config)?;
+        if this.tcx.sess.target.os == "wasi" {
+            shims::PreopenDir::init(this);
+        }
         MiriMachine::init_extern_statics(this)?;
         ThreadManager::init(this, on_main_stack_empty);
         Ok(())
@@ -809,6 +815,7 @@ fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
             alloc_addresses,
             fds,
             epoll_interests:_,
+            network: _,
             tcx: _,
             isolated_op: _,
             validation: _,
@@ -45,12 +45,13 @@ pub(crate) fn init(
             env_vars.insert(OsString::from(name), OsString::from(value));
         }
 
-        let env_vars = if ecx.target_os_is_unix() {
+        // wasi-libc keeps its environment just like a unix libc does.
+        let env_vars = if ecx.target_os_is_unix() || ecx.tcx.sess.target.os == "wasi" {
             EnvVars::Unix(UnixEnvVars::new(ecx, env_vars)?)
         } else if ecx.tcx.sess.target.os == "windows" {
             EnvVars::Windows(WindowsEnvVars::new(ecx, env_vars)?)
         } else {
-            // Used e.g. for wasi
+            // Used e.g. for wasm32-unknown-unknown
             EnvVars::Uninit
         };
         ecx.machine.env_vars = env_vars;
@@ -6,8 +6,13 @@
 use chrono::{DateTime, Datelike, Offset, Timelike, Utc};
 use chrono_tz::Tz;
 
+use crate::shims::wasi::EvalContextExt as _;
 use crate::*;
 
+// The WASI clock IDs, which wasi-libc does not expose as integers.
+const WASI_CLOCKID_REALTIME: u32 = 0;
+const WASI_CLOCKID_MONOTONIC: u32 = 1;
+
 /// Returns the time elapsed between the provided time and the unix epoch as a `Duration`.
 pub fn system_time_to_duration<'tcx>(time: &SystemTime) -> InterpResult<'tcx, Duration> {
     time.duration_since(SystemTime::UNIX_EPOCH)
@@ -308,6 +313,60 @@ fn mach_timebase_info(&mut self, info_op: &OpTy<'tcx>) -> InterpResult<'tcx, Sca
         Ok(Scalar::from_i32(0)) // KERN_SUCCESS
     }
 
+    fn clock_time_get(
+        &mut self,
+        clock_id_op: &OpTy<'tcx>,
+        _precision_op: &OpTy<'tcx>,
+        time_op: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        this.assert_target_os("wasi", "clock_time_get");
+
+        let clock_id = this.read_scalar(clock_id_op)?.to_u32()?;
+        let time = this.read_wasi_pointer(time_op)?;
+
+        // As with `clock_gettime`, only the realtime and monotonic clocks are supported; the
+        // CPU-time clocks are optional in WASI.
+        let duration = match clock_id {
+            WASI_CLOCKID_REALTIME => {
+                this.check_no_isolation("`clock_time_get` with the realtime clock")?;
+                system_time_to_duration(&SystemTime::now())?
+            }
+            WASI_CLOCKID_MONOTONIC =>
+                this.machine.clock.now().duration_since(this.machine.clock.epoch()),
+            _ => return Ok(this.eval_libc("EINVAL")),
+        };
+        let nanos = u64::try_from(duration.as_nanos()).map_err(|_| {
+            err_unsup_format!("times later than 2^64 nanoseconds are not supported")
+        })?;
+
+        let time = this.ptr_to_mplace(time, this.machine.layouts.u64);
+        this.write_int(nanos, &time)?;
+        Ok(Scalar::from_i32(0))
+    }
+
+    fn clock_res_get(
+        &mut self,
+        clock_id_op: &OpTy<'tcx>,
+        resolution_op: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        this.assert_target_os("wasi", "clock_res_get");
+
+        let clock_id = this.read_scalar(clock_id_op)?.to_u32()?;
+        let resolution = this.read_wasi_pointer(resolution_op)?;
+
+        if !matches!(clock_id, WASI_CLOCKID_REALTIME | WASI_CLOCKID_MONOTONIC) {
+            return Ok(this.eval_libc("EINVAL"));
+        }
+        // Both clocks report nanoseconds.
+        let resolution = this.ptr_to_mplace(resolution, this.machine.layouts.u64);
+        this.write_int(1, &resolution)?;
+        Ok(Scalar::from_i32(0))
+    }
+
     fn nanosleep(
         &mut self,
         req_op: &OpTy<'tcx>,
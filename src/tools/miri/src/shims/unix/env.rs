@@ -141,7 +141,11 @@ impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
 pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
     fn getenv(&mut self, name_op: &OpTy<'tcx>) -> InterpResult<'tcx, Pointer> {
         let this = self.eval_context_mut();
-        this.assert_target_os_is_unix("getenv");
+        // Also used for wasi, whose libc is modeled after the unix ones.
+        assert!(
+            this.target_os_is_unix() || this.tcx.sess.target.os == "wasi",
+            "`getenv` is only available for unix and wasi targets",
+        );
 
         let name_ptr = this.read_pointer(name_op)?;
         let name = this.read_os_str_from_c_str(name_ptr)?;
@@ -10,7 +10,8 @@
 
 use rustc_target::abi::Size;
 
-use crate::shims::unix::linux::epoll::EpollReadyEvents;
+pub use crate::shims::unix::linux::epoll::EpollReadyEvents;
+use crate::shims::unix::linux::epoll::EvalContextExt as _;
 use crate::shims::unix::*;
 use crate::*;
 
@@ -698,4 +699,11 @@ fn return_written_byte_count_or_error(
         this.write_int(result, dest)?;
         Ok(())
     }
+
+    /// Reports that `fd_ref` may have become readable or writable, or that it was hung up, to
+    /// whatever is waiting for that, i.e. to epoll on Linux. See `get_epoll_ready_events`.
+    fn fd_readiness_changed(&mut self, fd_ref: &FileDescriptionRef) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        this.check_and_update_readiness(fd_ref)
+    }
 }
@@ -20,9 +20,9 @@
 use self::fd::FlockOp;
 
 #[derive(Debug)]
-struct FileHandle {
-    file: File,
-    writable: bool,
+pub struct FileHandle {
+    pub(crate) file: File,
+    pub(crate) writable: bool,
 }
 
 impl FileDescription for FileHandle {
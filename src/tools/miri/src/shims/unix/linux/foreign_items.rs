@@ -71,6 +71,13 @@ fn emulate_foreign_item_inner(
                 this.write_scalar(result, dest)?;
             }
 
+            // Sockets
+            "accept4" => {
+                let [socket, address, address_len, flags] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                this.accept4(socket, address, address_len, Some(flags), dest)?;
+            }
+
             // Threading
             "pthread_setname_np" => {
                 let [thread, name] =
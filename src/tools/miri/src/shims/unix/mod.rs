@@ -4,6 +4,7 @@
 mod fd;
 mod fs;
 mod mem;
+mod socket;
 mod sync;
 mod thread;
 mod unnamed_socket;
@@ -16,13 +17,15 @@
 
 pub use self::env::UnixEnvVars;
 pub use self::fd::{FdTable, FileDescription};
-pub use self::fs::DirTable;
+pub use self::fs::{DirTable, FileHandle};
 pub use self::linux::epoll::EpollInterestTable;
+pub use self::socket::Network;
 // All the Unix-specific extension traits
 pub use self::env::EvalContextExt as _;
 pub use self::fd::EvalContextExt as _;
 pub use self::fs::EvalContextExt as _;
 pub use self::mem::EvalContextExt as _;
+pub use self::socket::EvalContextExt as _;
 pub use self::sync::EvalContextExt as _;
 pub use self::thread::EvalContextExt as _;
 pub use self::unnamed_socket::EvalContextExt as _;
@@ -0,0 +1,1470 @@
+//! This implements IPv4 and IPv6 TCP and UDP sockets on an emulated loopback network.
+//! No host networking is involved: all sockets live inside Miri, and only loopback (and
+//! unspecified) addresses are reachable, so programs that talk to a local server work even
+//! with isolation enabled.
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::io;
+use std::io::{Error, ErrorKind, Read};
+use std::iter;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::ops::RangeInclusive;
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_target::abi::Size;
+
+use crate::shims::unix::fd::{EpollReadyEvents, FileDescriptionRef, WeakFileDescriptionRef};
+use crate::shims::unix::*;
+use crate::{concurrency::VClock, *};
+
+/// The maximum number of bytes that can be buffered for reading on a socket.
+/// Like for socketpairs, this number is arbitrary as the value can be configured
+/// in the real system.
+const MAX_SOCKET_BUFFER_CAPACITY: usize = 212992;
+
+/// The largest payload that fits into a UDP datagram.
+const MAX_DATAGRAM_SIZE: usize = 65507;
+
+/// The ports that are picked when binding to port 0. This is the default range on Linux.
+const EPHEMERAL_PORTS: RangeInclusive<u16> = 32768..=60999;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Domain {
+    Inet,
+    Inet6,
+}
+
+impl Domain {
+    fn of(addr: &SocketAddr) -> Self {
+        match addr {
+            SocketAddr::V4(_) => Domain::Inet,
+            SocketAddr::V6(_) => Domain::Inet6,
+        }
+    }
+
+    fn loopback(self) -> IpAddr {
+        match self {
+            Domain::Inet => Ipv4Addr::LOCALHOST.into(),
+            Domain::Inet6 => Ipv6Addr::LOCALHOST.into(),
+        }
+    }
+
+    fn unspecified(self) -> IpAddr {
+        match self {
+            Domain::Inet => Ipv4Addr::UNSPECIFIED.into(),
+            Domain::Inet6 => Ipv6Addr::UNSPECIFIED.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum SocketKind {
+    Stream,
+    Datagram,
+}
+
+/// The ports that are in use on the loopback network.
+#[derive(Debug)]
+pub struct Network {
+    /// The sockets that are bound to a port. Since all addresses are loopback addresses, the
+    /// port is enough to tell sockets of the same kind and domain apart. The references are weak
+    /// so that closing a socket frees its port.
+    ports: FxHashMap<(SocketKind, Domain, u16), WeakFileDescriptionRef>,
+    /// The next port to try when binding to port 0.
+    next_ephemeral_port: u16,
+}
+
+impl Network {
+    pub fn new() -> Self {
+        Network { ports: FxHashMap::default(), next_ephemeral_port: *EPHEMERAL_PORTS.start() }
+    }
+
+    fn is_in_use(&self, kind: SocketKind, domain: Domain, port: u16) -> bool {
+        self.lookup(kind, domain, port).is_some()
+    }
+
+    /// Returns the socket that is bound to `port`, if any.
+    fn lookup(&self, kind: SocketKind, domain: Domain, port: u16) -> Option<FileDescriptionRef> {
+        self.ports.get(&(kind, domain, port))?.upgrade()
+    }
+
+    /// Binds `socket` to `port`, or to a free ephemeral port if `port` is 0.
+    /// Returns the port, or `None` if it is already in use.
+    fn bind(
+        &mut self,
+        kind: SocketKind,
+        domain: Domain,
+        port: u16,
+        socket: &FileDescriptionRef,
+    ) -> Option<u16> {
+        let port = if port == 0 { self.find_ephemeral_port(kind, domain)? } else { port };
+        if self.is_in_use(kind, domain, port) {
+            return None;
+        }
+        self.ports.insert((kind, domain, port), socket.downgrade());
+        Some(port)
+    }
+
+    fn find_ephemeral_port(&mut self, kind: SocketKind, domain: Domain) -> Option<u16> {
+        for _ in EPHEMERAL_PORTS {
+            let port = self.next_ephemeral_port;
+            self.next_ephemeral_port =
+                if port == *EPHEMERAL_PORTS.end() { *EPHEMERAL_PORTS.start() } else { port + 1 };
+            if !self.is_in_use(kind, domain, port) {
+                return Some(port);
+            }
+        }
+        None
+    }
+
+    /// Forgets about the socket bound to `port` if it has been closed.
+    fn release(&mut self, kind: SocketKind, domain: Domain, port: u16) {
+        if !self.is_in_use(kind, domain, port) {
+            self.ports.remove(&(kind, domain, port));
+        }
+    }
+}
+
+/// Maps `addr` to the address that connecting or sending to it reaches: the unspecified
+/// address means the local host, i.e. the loopback address. Returns `None` for any other
+/// address, as those are unreachable on our network.
+fn resolve_loopback(addr: SocketAddr) -> Option<SocketAddr> {
+    if addr.ip().is_unspecified() {
+        Some(SocketAddr::new(Domain::of(&addr).loopback(), addr.port()))
+    } else if addr.ip().is_loopback() {
+        Some(addr)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug)]
+struct Buffer {
+    buf: VecDeque<u8>,
+    clock: VClock,
+}
+
+impl Buffer {
+    fn new() -> Self {
+        Buffer { buf: VecDeque::new(), clock: VClock::default() }
+    }
+}
+
+/// A TCP or UDP socket.
+#[derive(Debug)]
+struct Socket {
+    domain: Domain,
+    kind: SocketKind,
+    is_nonblock: Cell<bool>,
+    /// The value of the `TCP_NODELAY` option. It has no effect, but programs may read it back.
+    nodelay: Cell<bool>,
+    /// The address this socket is bound to, if any.
+    local_addr: Cell<Option<SocketAddr>>,
+    state: RefCell<SocketState>,
+    /// The threads that are blocked until the state of this socket changes.
+    blocked_threads: RefCell<Vec<ThreadId>>,
+}
+
+#[derive(Debug)]
+enum SocketState {
+    /// A stream socket that is neither listening nor connected.
+    Unconnected,
+    /// A stream socket that accepts connections.
+    Listening(Listener),
+    /// A stream socket that is connected to a peer.
+    Connected(Connection),
+    /// A datagram socket.
+    Datagram(DatagramState),
+}
+
+#[derive(Debug)]
+struct Listener {
+    /// The server ends of the connections that have been established but not accepted yet.
+    /// We do not enforce the backlog limit.
+    pending: VecDeque<FileDescriptionRef>,
+    /// Remembers the clocks of `connect` calls so that `accept` can synchronize with them.
+    clock: VClock,
+}
+
+#[derive(Debug)]
+struct Connection {
+    peer_addr: SocketAddr,
+    /// The socket at the other end of the connection. This is a weak reference because the peer
+    /// may be closed before us; reads then return EOF and writes trigger EPIPE.
+    peer: WeakFileDescriptionRef,
+    /// The data the peer sent to us that has not been read yet.
+    readbuf: Buffer,
+    /// Whether we shut down reading. Reads then return EOF once `readbuf` is drained.
+    read_shutdown: bool,
+    /// Whether we shut down writing. Writes then trigger EPIPE.
+    write_shutdown: bool,
+    /// Whether the peer shut down writing. Reads then return EOF once `readbuf` is drained.
+    peer_write_shutdown: bool,
+}
+
+impl Connection {
+    fn new(peer_addr: SocketAddr, peer: WeakFileDescriptionRef) -> Self {
+        Connection {
+            peer_addr,
+            peer,
+            readbuf: Buffer::new(),
+            read_shutdown: false,
+            write_shutdown: false,
+            peer_write_shutdown: false,
+        }
+    }
+
+    /// Whether reading from an empty `readbuf` returns EOF rather than blocking.
+    fn is_eof(&self) -> bool {
+        self.read_shutdown || self.peer_write_shutdown || self.peer.upgrade().is_none()
+    }
+}
+
+#[derive(Debug)]
+struct DatagramState {
+    /// The address set by `connect`. Datagrams are sent there by default, and only datagrams
+    /// from there are received.
+    peer_addr: Option<SocketAddr>,
+    queue: VecDeque<Datagram>,
+    /// The total size of the payloads in `queue`.
+    queued_bytes: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Datagram {
+    source: SocketAddr,
+    data: Vec<u8>,
+    clock: VClock,
+}
+
+impl Socket {
+    fn new(domain: Domain, kind: SocketKind, is_nonblock: bool) -> Self {
+        let state = match kind {
+            SocketKind::Stream => SocketState::Unconnected,
+            SocketKind::Datagram =>
+                SocketState::Datagram(DatagramState {
+                    peer_addr: None,
+                    queue: VecDeque::new(),
+                    queued_bytes: 0,
+                }),
+        };
+        Socket {
+            domain,
+            kind,
+            is_nonblock: Cell::new(is_nonblock),
+            nodelay: Cell::new(false),
+            local_addr: Cell::new(None),
+            state: RefCell::new(state),
+            blocked_threads: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl FileDescription for Socket {
+    fn name(&self) -> &'static str {
+        "socket"
+    }
+
+    fn get_epoll_ready_events<'tcx>(&self) -> InterpResult<'tcx, EpollReadyEvents> {
+        // We only check the status of EPOLLIN, EPOLLOUT, EPOLLHUP and EPOLLRDHUP flags.
+        let mut epoll_ready_events = EpollReadyEvents::new();
+
+        match &*self.state.borrow() {
+            SocketState::Unconnected => {
+                // This matches what Linux reports for a fresh TCP socket.
+                epoll_ready_events.epollout = true;
+                epoll_ready_events.epollhup = true;
+            }
+            SocketState::Listening(listener) => {
+                epoll_ready_events.epollin = !listener.pending.is_empty();
+            }
+            SocketState::Connected(conn) => {
+                epoll_ready_events.epollin = !conn.readbuf.buf.is_empty() || conn.is_eof();
+                match conn.peer.upgrade() {
+                    Some(peer) => {
+                        let peer = peer.downcast::<Socket>().unwrap();
+                        if let SocketState::Connected(peer_conn) = &*peer.state.borrow() {
+                            let data_size = peer_conn.readbuf.buf.len();
+                            epoll_ready_events.epollout =
+                                conn.write_shutdown || data_size < MAX_SOCKET_BUFFER_CAPACITY;
+                        }
+                        epoll_ready_events.epollrdhup = conn.peer_write_shutdown;
+                    }
+                    None => {
+                        // Reads return EOF and writes trigger EPIPE, so neither blocks.
+                        epoll_ready_events.epollout = true;
+                        epoll_ready_events.epollrdhup = true;
+                        epoll_ready_events.epollhup = true;
+                    }
+                }
+            }
+            SocketState::Datagram(dgram) => {
+                epoll_ready_events.epollin = !dgram.queue.is_empty();
+                // Sending a datagram never blocks.
+                epoll_ready_events.epollout = true;
+            }
+        }
+        Ok(epoll_ready_events)
+    }
+
+    fn close<'tcx>(
+        self: Box<Self>,
+        communicate_allowed: bool,
+        ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        // Accepted sockets share the port of their listener, so this must only free the
+        // port if it is not used by another socket.
+        if let Some(addr) = self.local_addr.get() {
+            ecx.machine.network.release(self.kind, self.domain, addr.port());
+        }
+        match self.state.into_inner() {
+            SocketState::Connected(conn) =>
+                if let Some(peer) = conn.peer.upgrade() {
+                    // Notify the peer that close has happened, since that can unblock reads
+                    // and writes.
+                    socket_state_changed(ecx, &peer)?;
+                },
+            // Connections that were never accepted get closed as well, which their clients
+            // observe as the peer going away.
+            SocketState::Listening(listener) =>
+                for pending in listener.pending {
+                    pending.close(communicate_allowed, ecx)?.unwrap();
+                },
+            SocketState::Unconnected | SocketState::Datagram(_) => {}
+        }
+        Ok(Ok(()))
+    }
+
+    fn read<'tcx>(
+        &self,
+        self_ref: &FileDescriptionRef,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        dest: &MPlaceTy<'tcx>,
+        ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx> {
+        socket_recv(ecx, self_ref.downgrade(), ptr, len, false, false, None, dest)
+    }
+
+    fn write<'tcx>(
+        &self,
+        self_ref: &FileDescriptionRef,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        dest: &MPlaceTy<'tcx>,
+        ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx> {
+        match self.kind {
+            SocketKind::Stream => socket_send(ecx, self_ref.downgrade(), ptr, len, false, dest),
+            SocketKind::Datagram => socket_send_datagram(ecx, self_ref, ptr, len, None, dest),
+        }
+    }
+}
+
+/// Reports a readiness change of `socket_ref`, and wakes up the threads that are blocked on it
+/// so they can try again.
+fn socket_state_changed<'tcx>(
+    ecx: &mut MiriInterpCx<'tcx>,
+    socket_ref: &FileDescriptionRef,
+) -> InterpResult<'tcx> {
+    ecx.fd_readiness_changed(socket_ref)?;
+    let socket = socket_ref.downcast::<Socket>().unwrap();
+    let blocked_threads = socket.blocked_threads.take();
+    for thread_id in blocked_threads {
+        ecx.unblock_thread(thread_id, BlockReason::Socket)?;
+    }
+    Ok(())
+}
+
+/// Blocks the active thread until the state of `socket` changes; `callback` should then
+/// retry the operation.
+fn block_on_socket<'tcx>(
+    ecx: &mut MiriInterpCx<'tcx>,
+    socket: &Socket,
+    callback: impl UnblockCallback<'tcx> + 'tcx,
+) {
+    socket.blocked_threads.borrow_mut().push(ecx.active_thread());
+    ecx.block_thread(BlockReason::Socket, None, callback);
+}
+
+/// Sets the last error to `errno` and returns -1.
+fn socket_error<'tcx>(ecx: &mut MiriInterpCx<'tcx>, errno: &str) -> InterpResult<'tcx, Scalar> {
+    let errno = ecx.eval_libc(errno);
+    ecx.set_last_error(errno)?;
+    Ok(Scalar::from_i32(-1))
+}
+
+/// Returns the local address of `socket_ref`. If the socket is not bound yet, it first gets
+/// bound to a free ephemeral port on the loopback address. Returns `None` if no port is free.
+fn local_addr_or_bind(
+    ecx: &mut MiriInterpCx<'_>,
+    socket_ref: &FileDescriptionRef,
+) -> Option<SocketAddr> {
+    let socket = socket_ref.downcast::<Socket>().unwrap();
+    if let Some(addr) = socket.local_addr.get() {
+        return Some(addr);
+    }
+    let port = ecx.machine.network.bind(socket.kind, socket.domain, 0, socket_ref)?;
+    let addr = SocketAddr::new(socket.domain.loopback(), port);
+    socket.local_addr.set(Some(addr));
+    Some(addr)
+}
+
+/// Receives up to `len` bytes into `ptr`, and writes the number of received bytes to `dest`.
+/// If `source` is given, the sender of a datagram is written to this `sockaddr` and
+/// `socklen_t` pair. If nothing can be received yet, this either fails with EAGAIN or blocks
+/// and tries again once the state of the socket changed.
+fn socket_recv<'tcx>(
+    ecx: &mut MiriInterpCx<'tcx>,
+    weak_socket: WeakFileDescriptionRef,
+    ptr: Pointer,
+    len: usize,
+    peek: bool,
+    dont_wait: bool,
+    source: Option<(Pointer, Pointer)>,
+    dest: &MPlaceTy<'tcx>,
+) -> InterpResult<'tcx> {
+    let Some(socket_ref) = weak_socket.upgrade() else {
+        throw_unsup_format!("socket got closed while a thread was blocked on it");
+    };
+    let socket = socket_ref.downcast::<Socket>().unwrap();
+    let mut state = socket.state.borrow_mut();
+    match &mut *state {
+        SocketState::Unconnected | SocketState::Listening(_) => {
+            drop(state);
+            let result = Err(Error::from(ErrorKind::NotConnected));
+            return ecx.return_read_bytes_and_count(ptr, &[], result, dest);
+        }
+        SocketState::Connected(conn) =>
+            if len == 0 || !conn.readbuf.buf.is_empty() || conn.is_eof() {
+                // Synchronize with all previous writes to this buffer.
+                ecx.acquire_clock(&conn.readbuf.clock);
+                let mut bytes = vec![0; len.min(conn.readbuf.buf.len())];
+                if peek {
+                    for (byte, data) in bytes.iter_mut().zip(&conn.readbuf.buf) {
+                        *byte = *data;
+                    }
+                } else {
+                    conn.readbuf.buf.read_exact(&mut bytes).unwrap();
+                }
+                let peer = conn.peer.upgrade();
+                drop(state);
+                // The peer may be able to write again.
+                if let Some(peer) = peer
+                    && !peek
+                    && !bytes.is_empty()
+                {
+                    socket_state_changed(ecx, &peer)?;
+                }
+                let result = Ok(bytes.len());
+                return ecx.return_read_bytes_and_count(ptr, &bytes, result, dest);
+            },
+        SocketState::Datagram(dgram) => {
+            let datagram =
+                if peek { dgram.queue.front().cloned() } else { dgram.queue.pop_front() };
+            if let Some(datagram) = datagram {
+                if !peek {
+                    dgram.queued_bytes -= datagram.data.len();
+                }
+                drop(state);
+                ecx.acquire_clock(&datagram.clock);
+                if let Some((addr, addrlen)) = source {
+                    ecx.write_socket_address(datagram.source, addr, addrlen)?;
+                }
+                // Datagrams that do not fit into the buffer get truncated.
+                let result = Ok(len.min(datagram.data.len()));
+                return ecx.return_read_bytes_and_count(ptr, &datagram.data, result, dest);
+            }
+        }
+    }
+    drop(state);
+
+    // There is nothing to receive yet.
+    if socket.is_nonblock.get() || dont_wait {
+        let result = Err(Error::from(ErrorKind::WouldBlock));
+        return ecx.return_read_bytes_and_count(ptr, &[], result, dest);
+    }
+    let dest = dest.clone();
+    block_on_socket(
+        ecx,
+        socket,
+        callback!(
+            @capture<'tcx> {
+                weak_socket: WeakFileDescriptionRef,
+                ptr: Pointer,
+                len: usize,
+                peek: bool,
+                dont_wait: bool,
+                source: Option<(Pointer, Pointer)>,
+                dest: MPlaceTy<'tcx>,
+            }
+            @unblock = |this| {
+                socket_recv(this, weak_socket, ptr, len, peek, dont_wait, source, &dest)
+            }
+        ),
+    );
+    Ok(())
+}
+
+/// Sends up to `len` bytes from `ptr` over the connection of a stream socket, and writes the
+/// number of sent bytes to `dest`. If the peer's buffer is full, this either fails with EAGAIN
+/// or blocks and tries again once the peer read some data.
+fn socket_send<'tcx>(
+    ecx: &mut MiriInterpCx<'tcx>,
+    weak_socket: WeakFileDescriptionRef,
+    ptr: Pointer,
+    len: usize,
+    dont_wait: bool,
+    dest: &MPlaceTy<'tcx>,
+) -> InterpResult<'tcx> {
+    let Some(socket_ref) = weak_socket.upgrade() else {
+        throw_unsup_format!("socket got closed while a thread was blocked on it");
+    };
+    let socket = socket_ref.downcast::<Socket>().unwrap();
+    let state = socket.state.borrow();
+    let SocketState::Connected(conn) = &*state else {
+        drop(state);
+        let result = Err(Error::from(ErrorKind::NotConnected));
+        return ecx.return_written_byte_count_or_error(result, dest);
+    };
+    // After we shut down writing, or if the peer is gone, writes trigger EPIPE.
+    let peer_ref = if conn.write_shutdown { None } else { conn.peer.upgrade() };
+    drop(state);
+    let Some(peer_ref) = peer_ref else {
+        let result = Err(Error::from(ErrorKind::BrokenPipe));
+        return ecx.return_written_byte_count_or_error(result, dest);
+    };
+    if len == 0 {
+        return ecx.return_written_byte_count_or_error(Ok(0), dest);
+    }
+
+    let peer = peer_ref.downcast::<Socket>().unwrap();
+    let mut peer_state = peer.state.borrow_mut();
+    let SocketState::Connected(peer_conn) = &mut *peer_state else {
+        bug!("the peer of a connected socket must be connected");
+    };
+    let available_space = MAX_SOCKET_BUFFER_CAPACITY.strict_sub(peer_conn.readbuf.buf.len());
+    if available_space == 0 {
+        drop(peer_state);
+        if socket.is_nonblock.get() || dont_wait {
+            let result = Err(Error::from(ErrorKind::WouldBlock));
+            return ecx.return_written_byte_count_or_error(result, dest);
+        }
+        // Reading from the peer's buffer notifies us, so we can retry then.
+        let dest = dest.clone();
+        block_on_socket(
+            ecx,
+            socket,
+            callback!(
+                @capture<'tcx> {
+                    weak_socket: WeakFileDescriptionRef,
+                    ptr: Pointer,
+                    len: usize,
+                    dont_wait: bool,
+                    dest: MPlaceTy<'tcx>,
+                }
+                @unblock = |this| {
+                    socket_send(this, weak_socket, ptr, len, dont_wait, &dest)
+                }
+            ),
+        );
+        return Ok(());
+    }
+    // Remember this clock so `recv` can synchronize with us.
+    if let Some(clock) = &ecx.release_clock() {
+        peer_conn.readbuf.clock.join(clock);
+    }
+    // Do full write / partial write based on the space available.
+    let actual_write_size = len.min(available_space);
+    let bytes = ecx.read_bytes_ptr_strip_provenance(ptr, Size::from_bytes(actual_write_size))?;
+    peer_conn.readbuf.buf.extend(bytes);
+    drop(peer_state);
+
+    // The peer became readable.
+    socket_state_changed(ecx, &peer_ref)?;
+
+    let result = Ok(actual_write_size);
+    ecx.return_written_byte_count_or_error(result, dest)
+}
+
+/// Sends a datagram with the `len` bytes at `ptr` to `to`, or to the peer set by `connect`,
+/// and writes `len` to `dest`. This never blocks: like on a real network, datagrams to ports
+/// nobody is bound to, and datagrams that do not fit into the receive buffer, are dropped.
+fn socket_send_datagram<'tcx>(
+    ecx: &mut MiriInterpCx<'tcx>,
+    socket_ref: &FileDescriptionRef,
+    ptr: Pointer,
+    len: usize,
+    to: Option<SocketAddr>,
+    dest: &MPlaceTy<'tcx>,
+) -> InterpResult<'tcx> {
+    let socket = socket_ref.downcast::<Socket>().unwrap();
+    let peer_addr = match &*socket.state.borrow() {
+        SocketState::Datagram(dgram) => dgram.peer_addr,
+        _ => bug!("datagram socket in stream state"),
+    };
+    let Some(to) = to.or(peer_addr) else {
+        let result = socket_error(ecx, "EDESTADDRREQ")?;
+        return ecx.write_scalar(result, dest);
+    };
+    let Some(to) = resolve_loopback(to) else {
+        let result = Err(Error::from(ErrorKind::NetworkUnreachable));
+        return ecx.return_written_byte_count_or_error(result, dest);
+    };
+    if len > MAX_DATAGRAM_SIZE {
+        let result = socket_error(ecx, "EMSGSIZE")?;
+        return ecx.write_scalar(result, dest);
+    }
+    let Some(source) = local_addr_or_bind(ecx, socket_ref) else {
+        let result = Err(Error::from(ErrorKind::AddrNotAvailable));
+        return ecx.return_written_byte_count_or_error(result, dest);
+    };
+    let source = resolve_loopback(source).unwrap();
+
+    if let Some(receiver_ref) =
+        ecx.machine.network.lookup(SocketKind::Datagram, socket.domain, to.port())
+    {
+        let receiver = receiver_ref.downcast::<Socket>().unwrap();
+        let mut receiver_state = receiver.state.borrow_mut();
+        let SocketState::Datagram(receiver_dgram) = &mut *receiver_state else {
+            bug!("datagram socket in stream state");
+        };
+        // A connected datagram socket only receives from its peer.
+        let accepted = receiver_dgram.peer_addr.is_none_or(|peer| peer == source);
+        if accepted && receiver_dgram.queued_bytes + len <= MAX_SOCKET_BUFFER_CAPACITY {
+            let data = ecx.read_bytes_ptr_strip_provenance(ptr, Size::from_bytes(len))?.to_vec();
+            // Remember this clock so `recv` can synchronize with us.
+            let mut clock = VClock::default();
+            if let Some(release_clock) = &ecx.release_clock() {
+                clock.join(release_clock);
+            }
+            receiver_dgram.queued_bytes += len;
+            receiver_dgram.queue.push_back(Datagram { source, data, clock });
+            drop(receiver_state);
+            socket_state_changed(ecx, &receiver_ref)?;
+        }
+    }
+
+    let result = Ok(len);
+    ecx.return_written_byte_count_or_error(result, dest)
+}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// For more information on the arguments see the socket manpage:
+    /// <https://man7.org/linux/man-pages/man2/socket.2.html>
+    fn socket(
+        &mut self,
+        domain: &OpTy<'tcx>,
+        type_: &OpTy<'tcx>,
+        protocol: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let domain = this.read_scalar(domain)?.to_i32()?;
+        let mut type_ = this.read_scalar(type_)?.to_i32()?;
+        let protocol = this.read_scalar(protocol)?.to_i32()?;
+
+        let mut is_sock_nonblock = false;
+
+        // Parse and remove the type flags that we support.
+        // SOCK_NONBLOCK only exists on Linux.
+        if this.tcx.sess.target.os == "linux" {
+            if type_ & this.eval_libc_i32("SOCK_NONBLOCK") == this.eval_libc_i32("SOCK_NONBLOCK") {
+                is_sock_nonblock = true;
+                type_ &= !(this.eval_libc_i32("SOCK_NONBLOCK"));
+            }
+            if type_ & this.eval_libc_i32("SOCK_CLOEXEC") == this.eval_libc_i32("SOCK_CLOEXEC") {
+                type_ &= !(this.eval_libc_i32("SOCK_CLOEXEC"));
+            }
+        }
+
+        // Fail on unsupported input.
+        let domain = if domain == this.eval_libc_i32("AF_INET") {
+            Domain::Inet
+        } else if domain == this.eval_libc_i32("AF_INET6") {
+            Domain::Inet6
+        } else {
+            throw_unsup_format!(
+                "socket: domain {:#x} is unsupported, only AF_INET and AF_INET6 are allowed",
+                domain
+            );
+        };
+        let kind = if type_ == this.eval_libc_i32("SOCK_STREAM") {
+            SocketKind::Stream
+        } else if type_ == this.eval_libc_i32("SOCK_DGRAM") {
+            SocketKind::Datagram
+        } else {
+            throw_unsup_format!(
+                "socket: type {:#x} is unsupported, only SOCK_STREAM, SOCK_DGRAM, \
+                                 SOCK_CLOEXEC and SOCK_NONBLOCK are allowed",
+                type_
+            );
+        };
+        let default_protocol = match kind {
+            SocketKind::Stream => this.eval_libc_i32("IPPROTO_TCP"),
+            SocketKind::Datagram => this.eval_libc_i32("IPPROTO_UDP"),
+        };
+        if protocol != 0 && protocol != default_protocol {
+            throw_unsup_format!(
+                "socket: socket protocol {protocol} is unsupported, \
+                                 only 0, IPPROTO_TCP and IPPROTO_UDP are allowed",
+            );
+        }
+
+        let fd = this.machine.fds.insert_new(Socket::new(domain, kind, is_sock_nonblock));
+        Ok(Scalar::from_i32(fd))
+    }
+
+    fn bind(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        address: &OpTy<'tcx>,
+        address_len: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(socket)?.to_i32()?;
+        let address = this.read_pointer(address)?;
+        let address_len = this.read_scalar(address_len)?.to_u32()?;
+
+        let Some(socket_ref) = this.machine.fds.get(fd_num) else {
+            return Ok(Scalar::from_i32(this.fd_not_found()?));
+        };
+        let Some(socket) = socket_ref.downcast::<Socket>() else {
+            return socket_error(this, "ENOTSOCK");
+        };
+        let addr = match read_socket_address(this, address, address_len, socket.domain)? {
+            Ok(addr) => addr,
+            Err(errno) => return socket_error(this, errno),
+        };
+
+        if socket.local_addr.get().is_some() {
+            return socket_error(this, "EINVAL");
+        }
+        if !addr.ip().is_loopback() && !addr.ip().is_unspecified() {
+            // Only the loopback interface exists.
+            return socket_error(this, "EADDRNOTAVAIL");
+        }
+        let Some(port) =
+            this.machine.network.bind(socket.kind, socket.domain, addr.port(), &socket_ref)
+        else {
+            return socket_error(this, "EADDRINUSE");
+        };
+        socket.local_addr.set(Some(SocketAddr::new(addr.ip(), port)));
+
+        Ok(Scalar::from_i32(0))
+    }
+
+    fn listen(&mut self, socket: &OpTy<'tcx>, backlog: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(socket)?.to_i32()?;
+        // We do not limit the number of pending connections.
+        let _backlog = this.read_scalar(backlog)?.to_i32()?;
+
+        let Some(socket_ref) = this.machine.fds.get(fd_num) else {
+            return Ok(Scalar::from_i32(this.fd_not_found()?));
+        };
+        let Some(socket) = socket_ref.downcast::<Socket>() else {
+            return socket_error(this, "ENOTSOCK");
+        };
+        if socket.kind != SocketKind::Stream {
+            return socket_error(this, "EOPNOTSUPP");
+        }
+        match &*socket.state.borrow() {
+            SocketState::Unconnected => {}
+            SocketState::Listening(_) => return Ok(Scalar::from_i32(0)),
+            SocketState::Connected(_) | SocketState::Datagram(_) =>
+                return socket_error(this, "EINVAL"),
+        }
+        // An unbound socket gets bound to an ephemeral port.
+        if local_addr_or_bind(this, &socket_ref).is_none() {
+            return socket_error(this, "EADDRINUSE");
+        }
+        socket.state.replace(SocketState::Listening(Listener {
+            pending: VecDeque::new(),
+            clock: VClock::default(),
+        }));
+
+        Ok(Scalar::from_i32(0))
+    }
+
+    /// Implements both `accept` (with `flags` being `None`) and `accept4`. Writes the new
+    /// file descriptor to `dest`, or blocks until a connection is established.
+    fn accept4(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        address: &OpTy<'tcx>,
+        address_len: &OpTy<'tcx>,
+        flags: Option<&OpTy<'tcx>>,
+        dest: &MPlaceTy<'tcx>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(socket)?.to_i32()?;
+        let address = this.read_pointer(address)?;
+        let address_len = this.read_pointer(address_len)?;
+
+        let mut is_sock_nonblock = false;
+        if let Some(flags) = flags {
+            let mut flags = this.read_scalar(flags)?.to_i32()?;
+            // As usual we ignore CLOEXEC.
+            is_sock_nonblock = flags & this.eval_libc_i32("SOCK_NONBLOCK") != 0;
+            flags &= !(this.eval_libc_i32("SOCK_NONBLOCK") | this.eval_libc_i32("SOCK_CLOEXEC"));
+            if flags != 0 {
+                let einval = this.eval_libc("EINVAL");
+                this.set_last_error(einval)?;
+                return this.write_int(-1, dest);
+            }
+        }
+
+        let Some(socket_ref) = this.machine.fds.get(fd_num) else {
+            let res: i32 = this.fd_not_found()?;
+            return this.write_int(res, dest);
+        };
+        let Some(socket) = socket_ref.downcast::<Socket>() else {
+            let result = socket_error(this, "ENOTSOCK")?;
+            return this.write_scalar(result, dest);
+        };
+        if !matches!(&*socket.state.borrow(), SocketState::Listening(_)) {
+            let result = socket_error(this, "EINVAL")?;
+            return this.write_scalar(result, dest);
+        }
+
+        let address = if this.ptr_is_null(address)? { None } else { Some((address, address_len)) };
+        socket_accept(this, socket_ref.downgrade(), address, is_sock_nonblock, dest)
+    }
+
+    fn connect(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        address: &OpTy<'tcx>,
+        address_len: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(socket)?.to_i32()?;
+        let address = this.read_pointer(address)?;
+        let address_len = this.read_scalar(address_len)?.to_u32()?;
+
+        let Some(socket_ref) = this.machine.fds.get(fd_num) else {
+            return Ok(Scalar::from_i32(this.fd_not_found()?));
+        };
+        let Some(socket) = socket_ref.downcast::<Socket>() else {
+            return socket_error(this, "ENOTSOCK");
+        };
+        let addr = match read_socket_address(this, address, address_len, socket.domain)? {
+            Ok(addr) => addr,
+            Err(errno) => return socket_error(this, errno),
+        };
+        let Some(addr) = resolve_loopback(addr) else {
+            this.set_last_error_from_io_error(ErrorKind::NetworkUnreachable.into())?;
+            return Ok(Scalar::from_i32(-1));
+        };
+
+        if socket.kind == SocketKind::Datagram {
+            // This only sets the default destination, and binds the socket if needed.
+            if local_addr_or_bind(this, &socket_ref).is_none() {
+                this.set_last_error_from_io_error(ErrorKind::AddrNotAvailable.into())?;
+                return Ok(Scalar::from_i32(-1));
+            }
+            let mut state = socket.state.borrow_mut();
+            let SocketState::Datagram(dgram) = &mut *state else {
+                bug!("datagram socket in stream state");
+            };
+            dgram.peer_addr = Some(addr);
+            return Ok(Scalar::from_i32(0));
+        }
+
+        match &*socket.state.borrow() {
+            SocketState::Unconnected => {}
+            SocketState::Connected(_) => return socket_error(this, "EISCONN"),
+            SocketState::Listening(_) | SocketState::Datagram(_) =>
+                return socket_error(this, "EINVAL"),
+        }
+        let listener_ref =
+            this.machine.network.lookup(SocketKind::Stream, socket.domain, addr.port());
+        let listener_ref = listener_ref.filter(|listener| {
+            let listener = listener.downcast::<Socket>().unwrap();
+            matches!(&*listener.state.borrow(), SocketState::Listening(_))
+        });
+        let Some(listener_ref) = listener_ref else {
+            this.set_last_error_from_io_error(ErrorKind::ConnectionRefused.into())?;
+            return Ok(Scalar::from_i32(-1));
+        };
+        let Some(local_addr) = local_addr_or_bind(this, &socket_ref) else {
+            this.set_last_error_from_io_error(ErrorKind::AddrNotAvailable.into())?;
+            return Ok(Scalar::from_i32(-1));
+        };
+        let local_addr = resolve_loopback(local_addr).unwrap();
+
+        // The connection is established right away, so unlike on a real system, even a
+        // non-blocking `connect` never fails with EINPROGRESS.
+        let server = Socket::new(socket.domain, SocketKind::Stream, false);
+        server.local_addr.set(Some(addr));
+        server
+            .state
+            .replace(SocketState::Connected(Connection::new(local_addr, socket_ref.downgrade())));
+        let server_ref = this.machine.fds.new_ref(server);
+        socket.state.replace(SocketState::Connected(Connection::new(addr, server_ref.downgrade())));
+
+        let listener = listener_ref.downcast::<Socket>().unwrap();
+        let mut listener_state = listener.state.borrow_mut();
+        let SocketState::Listening(listener) = &mut *listener_state else { unreachable!() };
+        // Remember this clock so `accept` can synchronize with us.
+        if let Some(clock) = &this.release_clock() {
+            listener.clock.join(clock);
+        }
+        listener.pending.push_back(server_ref);
+        drop(listener_state);
+
+        // The listener can accept now, and we can write.
+        socket_state_changed(this, &listener_ref)?;
+        socket_state_changed(this, &socket_ref)?;
+
+        Ok(Scalar::from_i32(0))
+    }
+
+    /// Implements both `send` (with `address` being `None`) and `sendto`.
+    fn sendto(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        buffer: &OpTy<'tcx>,
+        length: &OpTy<'tcx>,
+        flags: &OpTy<'tcx>,
+        address: Option<(&OpTy<'tcx>, &OpTy<'tcx>)>,
+        dest: &MPlaceTy<'tcx>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(socket)?.to_i32()?;
+        let buffer = this.read_pointer(buffer)?;
+        let length = this.read_target_usize(length)?;
+        let (_peek, dont_wait) = this.read_msg_flags(flags, &["MSG_NOSIGNAL"])?;
+
+        // Check that the *entire* buffer is actually valid memory.
+        this.check_ptr_access(buffer, Size::from_bytes(length), CheckInAllocMsg::MemoryAccessTest)?;
+        let length = length.min(u64::try_from(this.target_isize_max()).unwrap());
+        let length = usize::try_from(length).unwrap();
+
+        let Some(socket_ref) = this.machine.fds.get(fd_num) else {
+            let res: i32 = this.fd_not_found()?;
+            return this.write_int(res, dest);
+        };
+        let Some(socket) = socket_ref.downcast::<Socket>() else {
+            let result = socket_error(this, "ENOTSOCK")?;
+            return this.write_scalar(result, dest);
+        };
+
+        match socket.kind {
+            // Like Linux, we ignore the address for connection-mode sockets.
+            SocketKind::Stream =>
+                socket_send(this, socket_ref.downgrade(), buffer, length, dont_wait, dest),
+            SocketKind::Datagram => {
+                let to = match address {
+                    Some((address, address_len)) => {
+                        let address = this.read_pointer(address)?;
+                        let address_len = this.read_scalar(address_len)?.to_u32()?;
+                        if this.ptr_is_null(address)? {
+                            None
+                        } else {
+                            match read_socket_address(this, address, address_len, socket.domain)? {
+                                Ok(addr) => Some(addr),
+                                Err(errno) => {
+                                    let result = socket_error(this, errno)?;
+                                    return this.write_scalar(result, dest);
+                                }
+                            }
+                        }
+                    }
+                    None => None,
+                };
+                socket_send_datagram(this, &socket_ref, buffer, length, to, dest)
+            }
+        }
+    }
+
+    /// Implements both `recv` (with `address` being `None`) and `recvfrom`.
+    fn recvfrom(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        buffer: &OpTy<'tcx>,
+        length: &OpTy<'tcx>,
+        flags: &OpTy<'tcx>,
+        address: Option<(&OpTy<'tcx>, &OpTy<'tcx>)>,
+        dest: &MPlaceTy<'tcx>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(socket)?.to_i32()?;
+        let buffer = this.read_pointer(buffer)?;
+        let length = this.read_target_usize(length)?;
+        let (peek, dont_wait) = this.read_msg_flags(flags, &["MSG_PEEK"])?;
+        let address = match address {
+            Some((address, address_len)) => {
+                let address = this.read_pointer(address)?;
+                let address_len = this.read_pointer(address_len)?;
+                if this.ptr_is_null(address)? { None } else { Some((address, address_len)) }
+            }
+            None => None,
+        };
+
+        // Check that the *entire* buffer is actually valid memory.
+        this.check_ptr_access(buffer, Size::from_bytes(length), CheckInAllocMsg::MemoryAccessTest)?;
+        let length = length.min(u64::try_from(this.target_isize_max()).unwrap());
+        let length = usize::try_from(length).unwrap();
+
+        let Some(socket_ref) = this.machine.fds.get(fd_num) else {
+            let res: i32 = this.fd_not_found()?;
+            return this.write_int(res, dest);
+        };
+        if socket_ref.downcast::<Socket>().is_none() {
+            let result = socket_error(this, "ENOTSOCK")?;
+            return this.write_scalar(result, dest);
+        }
+
+        socket_recv(this, socket_ref.downgrade(), buffer, length, peek, dont_wait, address, dest)
+    }
+
+    fn getsockname(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        address: &OpTy<'tcx>,
+        address_len: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(socket)?.to_i32()?;
+        let address = this.read_pointer(address)?;
+        let address_len = this.read_pointer(address_len)?;
+
+        let Some(socket_ref) = this.machine.fds.get(fd_num) else {
+            return Ok(Scalar::from_i32(this.fd_not_found()?));
+        };
+        let Some(socket) = socket_ref.downcast::<Socket>() else {
+            return socket_error(this, "ENOTSOCK");
+        };
+        // An unbound socket reports the unspecified address with port 0.
+        let addr = socket
+            .local_addr
+            .get()
+            .unwrap_or_else(|| SocketAddr::new(socket.domain.unspecified(), 0));
+        this.write_socket_address(addr, address, address_len)?;
+
+        Ok(Scalar::from_i32(0))
+    }
+
+    fn getpeername(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        address: &OpTy<'tcx>,
+        address_len: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(socket)?.to_i32()?;
+        let address = this.read_pointer(address)?;
+        let address_len = this.read_pointer(address_len)?;
+
+        let Some(socket_ref) = this.machine.fds.get(fd_num) else {
+            return Ok(Scalar::from_i32(this.fd_not_found()?));
+        };
+        let Some(socket) = socket_ref.downcast::<Socket>() else {
+            return socket_error(this, "ENOTSOCK");
+        };
+        let peer_addr = match &*socket.state.borrow() {
+            SocketState::Connected(conn) => Some(conn.peer_addr),
+            SocketState::Datagram(dgram) => dgram.peer_addr,
+            SocketState::Unconnected | SocketState::Listening(_) => None,
+        };
+        let Some(peer_addr) = peer_addr else {
+            this.set_last_error_from_io_error(ErrorKind::NotConnected.into())?;
+            return Ok(Scalar::from_i32(-1));
+        };
+        this.write_socket_address(peer_addr, address, address_len)?;
+
+        Ok(Scalar::from_i32(0))
+    }
+
+    /// Options that do not affect our emulation are accepted and ignored.
+    fn setsockopt(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        level: &OpTy<'tcx>,
+        option_name: &OpTy<'tcx>,
+        option_value: &OpTy<'tcx>,
+        option_len: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(socket)?.to_i32()?;
+        let level = this.read_scalar(level)?.to_i32()?;
+        let option_name = this.read_scalar(option_name)?.to_i32()?;
+        let option_value = this.read_pointer(option_value)?;
+        let option_len = this.read_scalar(option_len)?.to_u32()?;
+
+        let Some(socket_ref) = this.machine.fds.get(fd_num) else {
+            return Ok(Scalar::from_i32(this.fd_not_found()?));
+        };
+        let Some(socket) = socket_ref.downcast::<Socket>() else {
+            return socket_error(this, "ENOTSOCK");
+        };
+        // All the options we support are integers.
+        let int_layout = this.machine.layouts.i32;
+        if u64::from(option_len) < int_layout.size.bytes() {
+            return socket_error(this, "EINVAL");
+        }
+        let value = this.read_scalar(&this.ptr_to_mplace(option_value, int_layout))?.to_i32()?;
+
+        let is_socket_option = |this: &MiriInterpCx<'tcx>, name: &str| {
+            level == this.eval_libc_i32("SOL_SOCKET") && option_name == this.eval_libc_i32(name)
+        };
+        if level == this.eval_libc_i32("IPPROTO_TCP")
+            && option_name == this.eval_libc_i32("TCP_NODELAY")
+        {
+            socket.nodelay.set(value != 0);
+        } else if is_socket_option(this, "SO_REUSEADDR")
+            || is_socket_option(this, "SO_KEEPALIVE")
+            || (this.tcx.sess.target.os == "macos" && is_socket_option(this, "SO_NOSIGPIPE"))
+            || (level == this.eval_libc_i32("IPPROTO_IPV6")
+                && option_name == this.eval_libc_i32("IPV6_V6ONLY"))
+        {
+            // Nothing to do: Ports are not shared anyway, connections do not time out, and
+            // IPv4 and IPv6 sockets cannot talk to each other.
+        } else {
+            throw_unsup_format!(
+                "setsockopt: option {option_name:#x} at level {level:#x} is unsupported"
+            );
+        }
+
+        Ok(Scalar::from_i32(0))
+    }
+
+    fn getsockopt(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        level: &OpTy<'tcx>,
+        option_name: &OpTy<'tcx>,
+        option_value: &OpTy<'tcx>,
+        option_len: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(socket)?.to_i32()?;
+        let level = this.read_scalar(level)?.to_i32()?;
+        let option_name = this.read_scalar(option_name)?.to_i32()?;
+        let option_value = this.read_pointer(option_value)?;
+        let option_len = this.deref_pointer_as(option_len, this.libc_ty_layout("socklen_t"))?;
+
+        let Some(socket_ref) = this.machine.fds.get(fd_num) else {
+            return Ok(Scalar::from_i32(this.fd_not_found()?));
+        };
+        let Some(socket) = socket_ref.downcast::<Socket>() else {
+            return socket_error(this, "ENOTSOCK");
+        };
+
+        let value = if level == this.eval_libc_i32("SOL_SOCKET")
+            && option_name == this.eval_libc_i32("SO_ERROR")
+        {
+            // Connecting never fails asynchronously, so there is never a pending error.
+            0
+        } else if level == this.eval_libc_i32("SOL_SOCKET")
+            && option_name == this.eval_libc_i32("SO_TYPE")
+        {
+            match socket.kind {
+                SocketKind::Stream => this.eval_libc_i32("SOCK_STREAM"),
+                SocketKind::Datagram => this.eval_libc_i32("SOCK_DGRAM"),
+            }
+        } else if level == this.eval_libc_i32("IPPROTO_TCP")
+            && option_name == this.eval_libc_i32("TCP_NODELAY")
+        {
+            socket.nodelay.get().into()
+        } else {
+            throw_unsup_format!(
+                "getsockopt: option {option_name:#x} at level {level:#x} is unsupported"
+            );
+        };
+
+        // All the options we support are integers.
+        let int_layout = this.machine.layouts.i32;
+        let len = this.read_scalar(&option_len)?.to_u32()?;
+        if u64::from(len) < int_layout.size.bytes() {
+            return socket_error(this, "EINVAL");
+        }
+        this.write_int(value, &this.ptr_to_mplace(option_value, int_layout))?;
+        this.write_int(int_layout.size.bytes(), &option_len)?;
+
+        Ok(Scalar::from_i32(0))
+    }
+
+    fn shutdown(&mut self, socket: &OpTy<'tcx>, how: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(socket)?.to_i32()?;
+        let how = this.read_scalar(how)?.to_i32()?;
+
+        let Some(socket_ref) = this.machine.fds.get(fd_num) else {
+            return Ok(Scalar::from_i32(this.fd_not_found()?));
+        };
+        let Some(socket) = socket_ref.downcast::<Socket>() else {
+            return socket_error(this, "ENOTSOCK");
+        };
+        let (read, write) = if how == this.eval_libc_i32("SHUT_RD") {
+            (true, false)
+        } else if how == this.eval_libc_i32("SHUT_WR") {
+            (false, true)
+        } else if how == this.eval_libc_i32("SHUT_RDWR") {
+            (true, true)
+        } else {
+            return socket_error(this, "EINVAL");
+        };
+
+        let mut state = socket.state.borrow_mut();
+        let SocketState::Connected(conn) = &mut *state else {
+            drop(state);
+            this.set_last_error_from_io_error(ErrorKind::NotConnected.into())?;
+            return Ok(Scalar::from_i32(-1));
+        };
+        conn.read_shutdown |= read;
+        conn.write_shutdown |= write;
+        let peer_ref = conn.peer.upgrade();
+        drop(state);
+
+        if write && let Some(peer_ref) = &peer_ref {
+            let peer = peer_ref.downcast::<Socket>().unwrap();
+            let mut peer_state = peer.state.borrow_mut();
+            let SocketState::Connected(peer_conn) = &mut *peer_state else {
+                bug!("the peer of a connected socket must be connected");
+            };
+            peer_conn.peer_write_shutdown = true;
+        }
+        // Blocked reads on both ends may return EOF now.
+        socket_state_changed(this, &socket_ref)?;
+        if let Some(peer_ref) = &peer_ref {
+            socket_state_changed(this, peer_ref)?;
+        }
+
+        Ok(Scalar::from_i32(0))
+    }
+
+    /// Only `FIONBIO` on sockets is supported, which is what std uses to make sockets
+    /// non-blocking.
+    fn ioctl(&mut self, args: &[OpTy<'tcx>]) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        if args.len() < 3 {
+            throw_ub_format!(
+                "incorrect number of arguments for ioctl: got {}, expected at least 3",
+                args.len()
+            );
+        }
+        let fd_num = this.read_scalar(&args[0])?.to_i32()?;
+        let request = this.read_scalar(&args[1])?.to_uint(args[1].layout.size)?;
+
+        let fionbio = this.eval_libc("FIONBIO");
+        if request != fionbio.to_uint(fionbio.size())? {
+            throw_unsup_format!("the {:#x} request is not supported for `ioctl`", request);
+        }
+        let Some(fd) = this.machine.fds.get(fd_num) else {
+            return Ok(Scalar::from_i32(this.fd_not_found()?));
+        };
+        let Some(socket) = fd.downcast::<Socket>() else {
+            throw_unsup_format!("ioctl: FIONBIO is only supported on sockets");
+        };
+        let value = this.deref_pointer_as(&args[2], this.machine.layouts.i32)?;
+        let value = this.read_scalar(&value)?.to_i32()?;
+        socket.is_nonblock.set(value != 0);
+
+        Ok(Scalar::from_i32(0))
+    }
+
+    /// Parses the `flags` argument of `send` and `recv`, returning whether `MSG_PEEK` and
+    /// `MSG_DONTWAIT` are set. `MSG_DONTWAIT` and the flags in `allowed` are supported.
+    fn read_msg_flags(
+        &self,
+        flags: &OpTy<'tcx>,
+        allowed: &[&str],
+    ) -> InterpResult<'tcx, (bool, bool)> {
+        let this = self.eval_context_ref();
+
+        let mut flags = this.read_scalar(flags)?.to_i32()?;
+        let mut take_flag = |name: &str| {
+            let flag = this.eval_libc_i32(name);
+            let is_set = flags & flag != 0;
+            flags &= !flag;
+            is_set
+        };
+        let dont_wait = take_flag("MSG_DONTWAIT");
+        let peek = allowed.contains(&"MSG_PEEK") && take_flag("MSG_PEEK");
+        // We never raise SIGPIPE anyway. The flag only exists on Linux.
+        if allowed.contains(&"MSG_NOSIGNAL") && this.tcx.sess.target.os == "linux" {
+            take_flag("MSG_NOSIGNAL");
+        }
+        if flags != 0 {
+            throw_unsup_format!("unsupported flags {:#x} in `send`/`recv`", flags);
+        }
+        Ok((peek, dont_wait))
+    }
+
+    /// Writes `addr` as a `sockaddr_in` or `sockaddr_in6` to `ptr`, which points to a buffer
+    /// whose size is stored in the `socklen_t` at `len_ptr`. Like the real system, this
+    /// truncates the address if the buffer is too small, and updates the size to the size of
+    /// the full address.
+    fn write_socket_address(
+        &mut self,
+        addr: SocketAddr,
+        ptr: Pointer,
+        len_ptr: Pointer,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let len_place = this.ptr_to_mplace(len_ptr, this.libc_ty_layout("socklen_t"));
+        let capacity = this.read_scalar(&len_place)?.to_u32()?;
+
+        // We build the full address in a temporary allocation, and copy as much of it
+        // as fits.
+        let layout = match addr {
+            SocketAddr::V4(_) => this.libc_ty_layout("sockaddr_in"),
+            SocketAddr::V6(_) => this.libc_ty_layout("sockaddr_in6"),
+        };
+        let size = layout.size;
+        let place = this.allocate(layout, MiriMemoryKind::Machine.into())?;
+        this.write_bytes_ptr(place.ptr(), iter::repeat(0u8).take(size.bytes_usize()))?;
+        let (prefix, af) = match addr {
+            SocketAddr::V4(_) => ("sin", "AF_INET"),
+            SocketAddr::V6(_) => ("sin6", "AF_INET6"),
+        };
+        let af = this.eval_libc_u32(af);
+        this.write_int_fields_named(&[(format!("{prefix}_family").as_str(), af.into())], &place)?;
+        // BSDs have a field for the size of the address.
+        let len_field = format!("{prefix}_len");
+        if this.projectable_has_field(&place, &len_field) {
+            this.write_int_fields_named(&[(len_field.as_str(), size.bytes().into())], &place)?;
+        }
+        // The port and address are stored in network byte order.
+        let port = this.project_field_named(&place, &format!("{prefix}_port"))?;
+        this.write_bytes_ptr(port.ptr(), addr.port().to_be_bytes())?;
+        match addr.ip() {
+            IpAddr::V4(ip) => {
+                let field = this.project_field_named(&place, "sin_addr")?;
+                this.write_bytes_ptr(field.ptr(), ip.octets())?;
+            }
+            IpAddr::V6(ip) => {
+                let field = this.project_field_named(&place, "sin6_addr")?;
+                this.write_bytes_ptr(field.ptr(), ip.octets())?;
+            }
+        }
+
+        let copy_size = size.bytes().min(capacity.into());
+        this.mem_copy(place.ptr(), ptr, Size::from_bytes(copy_size), /*nonoverlapping*/ true)?;
+        this.deallocate_ptr(place.ptr(), None, MiriMemoryKind::Machine.into())?;
+        this.write_int(size.bytes(), &len_place)?;
+        Ok(())
+    }
+}
+
+/// Reads a `sockaddr_in` or `sockaddr_in6` (depending on `domain`) that is `len` bytes
+/// long. Returns the name of the error to set if the address is not valid.
+fn read_socket_address<'tcx>(
+    ecx: &MiriInterpCx<'tcx>,
+    ptr: Pointer,
+    len: u32,
+    domain: Domain,
+) -> InterpResult<'tcx, Result<SocketAddr, &'static str>> {
+    let (layout, af, prefix) = match domain {
+        Domain::Inet => (ecx.libc_ty_layout("sockaddr_in"), "AF_INET", "sin"),
+        Domain::Inet6 => (ecx.libc_ty_layout("sockaddr_in6"), "AF_INET6", "sin6"),
+    };
+    if u64::from(len) < layout.size.bytes() {
+        return Ok(Err("EINVAL"));
+    }
+    let place = ecx.ptr_to_mplace(ptr, layout);
+
+    let family = ecx.project_field_named(&place, &format!("{prefix}_family"))?;
+    let family = ecx.read_scalar(&family)?.to_uint(family.layout.size)?;
+    if family != u128::from(ecx.eval_libc_u32(af)) {
+        return Ok(Err("EAFNOSUPPORT"));
+    }
+    // The port and address are stored in network byte order.
+    let port = ecx.project_field_named(&place, &format!("{prefix}_port"))?;
+    let port = ecx.read_bytes_ptr_strip_provenance(port.ptr(), Size::from_bytes(2))?;
+    let port = u16::from_be_bytes(port.try_into().unwrap());
+    let ip: IpAddr = match domain {
+        Domain::Inet => {
+            let ip = ecx.project_field_named(&place, "sin_addr")?;
+            let ip = ecx.read_bytes_ptr_strip_provenance(ip.ptr(), Size::from_bytes(4))?;
+            Ipv4Addr::from(<[u8; 4]>::try_from(ip).unwrap()).into()
+        }
+        Domain::Inet6 => {
+            let ip = ecx.project_field_named(&place, "sin6_addr")?;
+            let ip = ecx.read_bytes_ptr_strip_provenance(ip.ptr(), Size::from_bytes(16))?;
+            Ipv6Addr::from(<[u8; 16]>::try_from(ip).unwrap()).into()
+        }
+    };
+    Ok(Ok(SocketAddr::new(ip, port)))
+}
+
+/// Accepts a pending connection of the listening socket, writing its file descriptor to
+/// `dest`, and the address of the peer to `address` if given. If there are no pending
+/// connections, this either fails with EAGAIN or blocks and tries again once a client
+/// connected.
+fn socket_accept<'tcx>(
+    ecx: &mut MiriInterpCx<'tcx>,
+    weak_socket: WeakFileDescriptionRef,
+    address: Option<(Pointer, Pointer)>,
+    is_sock_nonblock: bool,
+    dest: &MPlaceTy<'tcx>,
+) -> InterpResult<'tcx> {
+    let Some(socket_ref) = weak_socket.upgrade() else {
+        throw_unsup_format!("socket got closed while a thread was blocked on it");
+    };
+    let socket = socket_ref.downcast::<Socket>().unwrap();
+    let mut state = socket.state.borrow_mut();
+    let SocketState::Listening(listener) = &mut *state else {
+        bug!("accepting on a socket that is not listening");
+    };
+    if let Some(connection) = listener.pending.pop_front() {
+        // Synchronize with the `connect` calls.
+        ecx.acquire_clock(&listener.clock);
+        drop(state);
+        let accepted = connection.downcast::<Socket>().unwrap();
+        accepted.is_nonblock.set(is_sock_nonblock);
+        let peer_addr = match &*accepted.state.borrow() {
+            SocketState::Connected(conn) => conn.peer_addr,
+            _ => bug!("pending connection is not connected"),
+        };
+        if let Some((addr, addrlen)) = address {
+            ecx.write_socket_address(peer_addr, addr, addrlen)?;
+        }
+        let fd = ecx.machine.fds.insert(connection);
+        return ecx.write_int(fd, dest);
+    }
+    drop(state);
+
+    // There are no pending connections yet.
+    if socket.is_nonblock.get() {
+        ecx.set_last_error_from_io_error(ErrorKind::WouldBlock.into())?;
+        return ecx.write_int(-1, dest);
+    }
+    let dest = dest.clone();
+    block_on_socket(
+        ecx,
+        socket,
+        callback!(
+            @capture<'tcx> {
+                weak_socket: WeakFileDescriptionRef,
+                address: Option<(Pointer, Pointer)>,
+                is_sock_nonblock: bool,
+                dest: MPlaceTy<'tcx>,
+            }
+            @unblock = |this| {
+                socket_accept(this, weak_socket, address, is_sock_nonblock, &dest)
+            }
+        ),
+    );
+    Ok(())
+}
@@ -2,6 +2,8 @@
 use rustc_target::spec::abi::Abi;
 
 use crate::shims::alloc::EvalContextExt as _;
+use crate::shims::unix::*;
+use crate::shims::wasi::*;
 use crate::*;
 
 pub fn is_dyn_sym(_name: &str) -> bool {
@@ -33,6 +35,106 @@ fn emulate_foreign_item_inner(
                 this.write_pointer(res, dest)?;
             }
 
+            // Environment
+            "getenv" => {
+                let [name] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.getenv(name)?;
+                this.write_pointer(result, dest)?;
+            }
+
+            // File descriptors
+            "fd_read" => {
+                let [fd, iovs, iovs_len, nread] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.fd_read(fd, iovs, iovs_len, None, nread)?;
+                this.write_scalar(result, dest)?;
+            }
+            "fd_pread" => {
+                let [fd, iovs, iovs_len, offset, nread] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.fd_read(fd, iovs, iovs_len, Some(offset), nread)?;
+                this.write_scalar(result, dest)?;
+            }
+            "fd_write" => {
+                let [fd, iovs, iovs_len, nwritten] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.fd_write(fd, iovs, iovs_len, None, nwritten)?;
+                this.write_scalar(result, dest)?;
+            }
+            "fd_pwrite" => {
+                let [fd, iovs, iovs_len, offset, nwritten] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.fd_write(fd, iovs, iovs_len, Some(offset), nwritten)?;
+                this.write_scalar(result, dest)?;
+            }
+            "fd_seek" => {
+                let [fd, offset, whence, newoffset] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.fd_seek(fd, offset, whence, newoffset)?;
+                this.write_scalar(result, dest)?;
+            }
+            "fd_tell" => {
+                let [fd, offset] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.fd_tell(fd, offset)?;
+                this.write_scalar(result, dest)?;
+            }
+            "fd_close" => {
+                let [fd] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.fd_close(fd)?;
+                this.write_scalar(result, dest)?;
+            }
+            "fd_filestat_get" => {
+                let [fd, buf] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.fd_filestat_get(fd, buf)?;
+                this.write_scalar(result, dest)?;
+            }
+            "fd_prestat_get" => {
+                let [fd, buf] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.fd_prestat_get(fd, buf)?;
+                this.write_scalar(result, dest)?;
+            }
+            "fd_prestat_dir_name" => {
+                let [fd, path, path_len] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.fd_prestat_dir_name(fd, path, path_len)?;
+                this.write_scalar(result, dest)?;
+            }
+
+            // File system
+            "path_open" => {
+                let args = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.path_open(&args)?;
+                this.write_scalar(result, dest)?;
+            }
+            "path_unlink_file" => {
+                let [dirfd, path, path_len] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.path_unlink_file(dirfd, path, path_len)?;
+                this.write_scalar(result, dest)?;
+            }
+            "__wasilibc_find_relpath" => {
+                let [path, abs_prefix, relative_path, relative_path_len] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result =
+                    this.wasilibc_find_relpath(path, abs_prefix, relative_path, relative_path_len)?;
+                this.write_scalar(result, dest)?;
+            }
+
+            // Time
+            "clock_time_get" => {
+                let [clock_id, precision, time] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.clock_time_get(clock_id, precision, time)?;
+                this.write_scalar(result, dest)?;
+            }
+            "clock_res_get" => {
+                let [clock_id, resolution] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.clock_res_get(clock_id, resolution)?;
+                this.write_scalar(result, dest)?;
+            }
+
             _ => return Ok(EmulateItemResult::NotSupported),
         }
         Ok(EmulateItemResult::NeedsReturn)
@@ -0,0 +1,520 @@
+//! File system access for WASI preview 1.
+//!
+//! WASI descriptors live in the same table as the unix ones, so reading, writing, seeking and
+//! closing reuse the unix shims. What is specific to WASI is that paths can only be opened
+//! relative to a directory the host preopened; Miri preopens the host's root directory as `/`.
+
+use std::fs::OpenOptions;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rustc_target::abi::Size;
+
+use crate::shims::time::system_time_to_duration;
+use crate::shims::unix::*;
+use crate::shims::wasi::EvalContextExt as _;
+use crate::*;
+
+/// The descriptor the preopened root directory is installed at, as WASI reserves 0 to 2 for the
+/// standard streams.
+const PREOPEN_FD: i32 = 3;
+
+// From the WASI preview 1 witx definitions.
+const PREOPENTYPE_DIR: u8 = 0;
+const OFLAGS_CREAT: u32 = 1 << 0;
+const OFLAGS_DIRECTORY: u32 = 1 << 1;
+const OFLAGS_EXCL: u32 = 1 << 2;
+const OFLAGS_TRUNC: u32 = 1 << 3;
+const FDFLAGS_APPEND: u32 = 1 << 0;
+const LOOKUPFLAGS_SYMLINK_FOLLOW: u32 = 1 << 0;
+const RIGHTS_FD_READ: u64 = 1 << 1;
+const RIGHTS_FD_WRITE: u64 = 1 << 6;
+const FILETYPE_UNKNOWN: u8 = 0;
+const FILETYPE_DIRECTORY: u8 = 3;
+const FILETYPE_REGULAR_FILE: u8 = 4;
+const FILETYPE_SYMBOLIC_LINK: u8 = 7;
+
+/// A directory preopened for the program, which `path_open` resolves paths against.
+#[derive(Debug)]
+pub struct PreopenDir {
+    /// The name the program sees the directory under.
+    name: String,
+    /// The directory on the host.
+    path: PathBuf,
+}
+
+impl PreopenDir {
+    pub(crate) fn init(ecx: &mut MiriInterpCx<'_>) {
+        let fd =
+            ecx.machine.fds.insert_new(PreopenDir { name: "/".into(), path: PathBuf::from("/") });
+        assert_eq!(fd, PREOPEN_FD);
+    }
+}
+
+impl FileDescription for PreopenDir {
+    fn name(&self) -> &'static str {
+        "preopened directory"
+    }
+
+    fn close<'tcx>(
+        self: Box<Self>,
+        _communicate_allowed: bool,
+        _ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        Ok(Ok(()))
+    }
+}
+
+impl<'tcx> EvalContextExtPrivate<'tcx> for crate::MiriInterpCx<'tcx> {}
+trait EvalContextExtPrivate<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Returns the first non-empty buffer of an array of `iovec`s, or an empty one if there is
+    /// none. Transferring fewer bytes than requested is always allowed, so that is all we hand
+    /// to the file description.
+    fn first_iovec(&self, iovs: Pointer, iovs_len: u32) -> InterpResult<'tcx, (Pointer, u64)> {
+        let this = self.eval_context_ref();
+        let iovec_layout = this.libc_ty_layout("iovec");
+        let iovs = this.ptr_to_mplace(iovs, iovec_layout);
+        for i in 0..u64::from(iovs_len) {
+            let iov = iovs.offset(iovec_layout.size * i, iovec_layout, this)?;
+            let len = this.read_target_usize(&this.project_field_named(&iov, "iov_len")?)?;
+            if len > 0 {
+                let buf = this.read_pointer(&this.project_field_named(&iov, "iov_base")?)?;
+                return Ok((buf, len));
+            }
+        }
+        Ok((Pointer::null(), 0))
+    }
+
+    /// Turns the result of one of the unix shims, which is `-1` on failure, into a WASI errno.
+    fn wasi_errno(&mut self, result: i64) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+        if result == -1 {
+            let errno = this.get_last_error()?.to_u32()?;
+            Ok(Scalar::from_i32(errno.try_into().unwrap()))
+        } else {
+            Ok(Scalar::from_i32(0))
+        }
+    }
+
+    /// Calls `read` or `write` from the unix shims on `buf`, storing the number of bytes
+    /// transferred at `nbytes`.
+    fn wasi_read_write(
+        &mut self,
+        write: bool,
+        fd: i32,
+        buf: Pointer,
+        count: u64,
+        offset: Option<u64>,
+        nbytes: Pointer,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+        let result = this.allocate(this.machine.layouts.isize, MiriMemoryKind::Machine.into())?;
+        let offset = offset.map(i128::from);
+        if write {
+            this.write(fd, buf, count, offset, &result)?;
+        } else {
+            this.read(fd, buf, count, offset, &result)?;
+        }
+        let n = this.read_scalar(&result)?.to_target_isize(this)?;
+        this.deallocate_ptr(result.ptr(), None, MiriMemoryKind::Machine.into())?;
+        if n >= 0 {
+            let nbytes = this.ptr_to_mplace(nbytes, this.machine.layouts.u32);
+            this.write_int(u64::try_from(n).unwrap(), &nbytes)?;
+        }
+        this.wasi_errno(n)
+    }
+
+    /// Reads a path argument, which WASI passes as a pointer and a length rather than as a
+    /// nul-terminated string.
+    fn read_wasi_path(
+        &self,
+        ptr_op: &OpTy<'tcx>,
+        len_op: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, PathBuf> {
+        let this = self.eval_context_ref();
+        let ptr = this.read_wasi_pointer(ptr_op)?;
+        let len = this.read_scalar(len_op)?.to_u32()?;
+        let bytes = this.read_bytes_ptr_strip_provenance(ptr, Size::from_bytes(len))?;
+        let Ok(path) = std::str::from_utf8(bytes) else {
+            throw_unsup_format!("WASI paths must be valid UTF-8");
+        };
+        Ok(PathBuf::from(path))
+    }
+
+    /// Resolves `path` against the preopened directory `dirfd`, returning the host path. On
+    /// failure, returns the errno to report instead.
+    fn resolve_wasi_path(
+        &self,
+        dirfd: i32,
+        path: &Path,
+    ) -> InterpResult<'tcx, Result<PathBuf, Scalar>> {
+        let this = self.eval_context_ref();
+        let Some(fd) = this.machine.fds.get(dirfd) else {
+            return Ok(Err(this.eval_libc("EBADF")));
+        };
+        let Some(dir) = fd.downcast::<PreopenDir>() else {
+            if fd.downcast::<FileHandle>().is_some() {
+                return Ok(Err(this.eval_libc("ENOTDIR")));
+            }
+            throw_unsup_format!("cannot resolve paths relative to {}", fd.name());
+        };
+        Ok(Ok(dir.path.join(path)))
+    }
+}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    fn fd_read(
+        &mut self,
+        fd_op: &OpTy<'tcx>,
+        iovs_op: &OpTy<'tcx>,
+        iovs_len_op: &OpTy<'tcx>,
+        offset_op: Option<&OpTy<'tcx>>,
+        nread_op: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        // Isolation check is done via `FileDescription` trait.
+
+        let fd = this.read_scalar(fd_op)?.to_i32()?;
+        let iovs = this.read_wasi_pointer(iovs_op)?;
+        let iovs_len = this.read_scalar(iovs_len_op)?.to_u32()?;
+        let offset = offset_op.map(|op| this.read_scalar(op)?.to_u64()).transpose()?;
+        let nread = this.read_wasi_pointer(nread_op)?;
+
+        let (buf, count) = this.first_iovec(iovs, iovs_len)?;
+        this.wasi_read_write(/* write */ false, fd, buf, count, offset, nread)
+    }
+
+    fn fd_write(
+        &mut self,
+        fd_op: &OpTy<'tcx>,
+        iovs_op: &OpTy<'tcx>,
+        iovs_len_op: &OpTy<'tcx>,
+        offset_op: Option<&OpTy<'tcx>>,
+        nwritten_op: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        // Isolation check is done via `FileDescription` trait.
+
+        let fd = this.read_scalar(fd_op)?.to_i32()?;
+        let iovs = this.read_wasi_pointer(iovs_op)?;
+        let iovs_len = this.read_scalar(iovs_len_op)?.to_u32()?;
+        let offset = offset_op.map(|op| this.read_scalar(op)?.to_u64()).transpose()?;
+        let nwritten = this.read_wasi_pointer(nwritten_op)?;
+
+        let (buf, count) = this.first_iovec(iovs, iovs_len)?;
+        this.wasi_read_write(/* write */ true, fd, buf, count, offset, nwritten)
+    }
+
+    fn fd_seek(
+        &mut self,
+        fd_op: &OpTy<'tcx>,
+        offset_op: &OpTy<'tcx>,
+        whence_op: &OpTy<'tcx>,
+        newoffset_op: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd_op)?.to_i32()?;
+        let offset = this.read_scalar(offset_op)?.to_i64()?;
+        // WASI's `whence` values match the `SEEK_*` constants of wasi-libc.
+        let whence = this.read_scalar(whence_op)?.to_i32()?;
+        let newoffset = this.read_wasi_pointer(newoffset_op)?;
+
+        let result = this.lseek64(fd, offset.into(), whence)?.to_i64()?;
+        if result >= 0 {
+            let newoffset = this.ptr_to_mplace(newoffset, this.machine.layouts.u64);
+            this.write_int(result, &newoffset)?;
+        }
+        this.wasi_errno(result)
+    }
+
+    fn fd_tell(
+        &mut self,
+        fd_op: &OpTy<'tcx>,
+        offset_op: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd_op)?.to_i32()?;
+        let offset = this.read_wasi_pointer(offset_op)?;
+
+        let result = this.lseek64(fd, 0, this.eval_libc_i32("SEEK_CUR"))?.to_i64()?;
+        if result >= 0 {
+            let offset = this.ptr_to_mplace(offset, this.machine.layouts.u64);
+            this.write_int(result, &offset)?;
+        }
+        this.wasi_errno(result)
+    }
+
+    fn fd_close(&mut self, fd_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let result = this.close(fd_op)?.to_i32()?;
+        this.wasi_errno(result.into())
+    }
+
+    fn fd_filestat_get(
+        &mut self,
+        fd_op: &OpTy<'tcx>,
+        buf_op: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(fd_op)?.to_i32()?;
+        let buf = this.read_wasi_pointer(buf_op)?;
+
+        let Some(fd) = this.machine.fds.get(fd_num) else {
+            return Ok(this.eval_libc("EBADF"));
+        };
+        let metadata = if let Some(file) = fd.downcast::<FileHandle>() {
+            file.file.metadata()
+        } else if let Some(dir) = fd.downcast::<PreopenDir>() {
+            this.check_no_isolation("`fd_filestat_get` on a preopened directory")?;
+            std::fs::metadata(&dir.path)
+        } else {
+            throw_unsup_format!("obtaining metadata is only supported on files and directories");
+        };
+        drop(fd);
+        let metadata = match metadata {
+            Ok(metadata) => metadata,
+            Err(e) => return this.io_error_to_errnum(e),
+        };
+
+        let file_type = metadata.file_type();
+        let filetype = if file_type.is_file() {
+            FILETYPE_REGULAR_FILE
+        } else if file_type.is_dir() {
+            FILETYPE_DIRECTORY
+        } else if file_type.is_symlink() {
+            FILETYPE_SYMBOLIC_LINK
+        } else {
+            FILETYPE_UNKNOWN
+        };
+        let nanos = |time: io::Result<std::time::SystemTime>| -> InterpResult<'tcx, u64> {
+            let Ok(time) = time else { return Ok(0) };
+            let duration = system_time_to_duration(&time)?;
+            Ok(duration.as_nanos().try_into().unwrap_or(u64::MAX))
+        };
+
+        // The fields of `filestat` are all 8-byte aligned: `dev`, `ino`, `filetype` (a `u8`),
+        // `nlink`, `size`, `atim`, `mtim` and `ctim`.
+        let (byte, word) = (this.machine.layouts.u8, this.machine.layouts.u64);
+        let fields = [
+            (word, 0),
+            (word, 0),
+            (byte, u64::from(filetype)),
+            (word, 1),
+            (word, metadata.len()),
+            (word, nanos(metadata.accessed())?),
+            (word, nanos(metadata.modified())?),
+            (word, nanos(metadata.created())?),
+        ];
+        let buf = this.ptr_to_mplace(buf, word);
+        for (i, (layout, value)) in (0u64..).zip(fields) {
+            let field = buf.offset(Size::from_bytes(i * 8), layout, this)?;
+            this.write_int(value, &field)?;
+        }
+        Ok(Scalar::from_i32(0))
+    }
+
+    fn fd_prestat_get(
+        &mut self,
+        fd_op: &OpTy<'tcx>,
+        buf_op: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd_op)?.to_i32()?;
+        let buf = this.read_wasi_pointer(buf_op)?;
+
+        // Anything but a preopened directory reports `EBADF`, which is what the search for
+        // preopens in wasi-libc and std skips over or stops at.
+        let Some(name_len) = this
+            .machine
+            .fds
+            .get(fd)
+            .and_then(|fd| fd.downcast::<PreopenDir>().map(|dir| dir.name.len()))
+        else {
+            return Ok(this.eval_libc("EBADF"));
+        };
+
+        // `prestat` is a `u8` tag followed by the length of the name as a `u32`.
+        let tag = this.ptr_to_mplace(buf, this.machine.layouts.u8);
+        this.write_int(PREOPENTYPE_DIR, &tag)?;
+        let len = tag.offset(Size::from_bytes(4), this.machine.layouts.u32, this)?;
+        this.write_int(u64::try_from(name_len).unwrap(), &len)?;
+        Ok(Scalar::from_i32(0))
+    }
+
+    fn fd_prestat_dir_name(
+        &mut self,
+        fd_op: &OpTy<'tcx>,
+        path_op: &OpTy<'tcx>,
+        path_len_op: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd_op)?.to_i32()?;
+        let path = this.read_wasi_pointer(path_op)?;
+        let path_len = this.read_scalar(path_len_op)?.to_u32()?;
+
+        let Some(name) = this
+            .machine
+            .fds
+            .get(fd)
+            .and_then(|fd| fd.downcast::<PreopenDir>().map(|dir| dir.name.clone()))
+        else {
+            return Ok(this.eval_libc("EBADF"));
+        };
+        if name.len() > usize::try_from(path_len).unwrap() {
+            return Ok(this.eval_libc("ENAMETOOLONG"));
+        }
+        // The name is not nul-terminated.
+        this.write_bytes_ptr(path, name.bytes())?;
+        Ok(Scalar::from_i32(0))
+    }
+
+    fn path_open(&mut self, args: &[OpTy<'tcx>; 9]) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let [dirfd, dirflags, path, path_len, oflags, rights_base, _rights_inheriting, fdflags, fd] =
+            args;
+        let dirfd = this.read_scalar(dirfd)?.to_i32()?;
+        let dirflags = this.read_scalar(dirflags)?.to_u32()?;
+        let path = this.read_wasi_path(path, path_len)?;
+        let oflags = this.read_scalar(oflags)?.to_u32()?;
+        let rights_base = this.read_scalar(rights_base)?.to_u64()?;
+        let fdflags = this.read_scalar(fdflags)?.to_u32()?;
+        let fd = this.read_wasi_pointer(fd)?;
+
+        // Symlinks are always followed, whether or not the program asked for it.
+        if dirflags & !LOOKUPFLAGS_SYMLINK_FOLLOW != 0 {
+            throw_unsup_format!("unsupported lookup flags {:#x}", dirflags);
+        }
+        if oflags & OFLAGS_DIRECTORY != 0 {
+            throw_unsup_format!("opening directories is not supported");
+        }
+        if oflags & !(OFLAGS_CREAT | OFLAGS_EXCL | OFLAGS_TRUNC) != 0 {
+            throw_unsup_format!("unsupported open flags {:#x}", oflags);
+        }
+        if fdflags & !FDFLAGS_APPEND != 0 {
+            throw_unsup_format!("unsupported descriptor flags {:#x}", fdflags);
+        }
+
+        // Rights are not enforced; we only look at them to tell whether the program wants to
+        // read or write.
+        let mut options = OpenOptions::new();
+        let writable = rights_base & RIGHTS_FD_WRITE != 0;
+        options.read(rights_base & RIGHTS_FD_READ != 0).write(writable);
+        options.append(fdflags & FDFLAGS_APPEND != 0);
+        options.truncate(oflags & OFLAGS_TRUNC != 0);
+        if oflags & OFLAGS_CREAT != 0 {
+            if oflags & OFLAGS_EXCL != 0 {
+                options.create_new(true);
+            } else {
+                options.create(true);
+            }
+        }
+
+        let path = match this.resolve_wasi_path(dirfd, &path)? {
+            Ok(path) => path,
+            Err(errno) => return Ok(errno),
+        };
+
+        // Reject if isolation is enabled.
+        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+            this.reject_in_isolation("`path_open`", reject_with)?;
+            return this.io_error_to_errnum(io::ErrorKind::PermissionDenied.into());
+        }
+
+        match options.open(path) {
+            Ok(file) => {
+                let fd_num = this.machine.fds.insert_new(FileHandle { file, writable });
+                let fd = this.ptr_to_mplace(fd, this.machine.layouts.u32);
+                this.write_int(fd_num, &fd)?;
+                Ok(Scalar::from_i32(0))
+            }
+            Err(e) => this.io_error_to_errnum(e),
+        }
+    }
+
+    fn path_unlink_file(
+        &mut self,
+        dirfd_op: &OpTy<'tcx>,
+        path_op: &OpTy<'tcx>,
+        path_len_op: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let dirfd = this.read_scalar(dirfd_op)?.to_i32()?;
+        let path = this.read_wasi_path(path_op, path_len_op)?;
+
+        let path = match this.resolve_wasi_path(dirfd, &path)? {
+            Ok(path) => path,
+            Err(errno) => return Ok(errno),
+        };
+
+        // Reject if isolation is enabled.
+        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+            this.reject_in_isolation("`path_unlink_file`", reject_with)?;
+            return this.io_error_to_errnum(io::ErrorKind::PermissionDenied.into());
+        }
+
+        match std::fs::remove_file(path) {
+            Ok(()) => Ok(Scalar::from_i32(0)),
+            Err(e) => this.io_error_to_errnum(e),
+        }
+    }
+
+    /// Implements the wasi-libc function std uses to find the preopened directory an absolute
+    /// path lives in, and the path relative to it.
+    fn wasilibc_find_relpath(
+        &mut self,
+        path_op: &OpTy<'tcx>,
+        abs_prefix_op: &OpTy<'tcx>,
+        relative_path_op: &OpTy<'tcx>,
+        relative_path_len_op: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let path = this.read_pointer(path_op)?;
+        let path = this.read_path_from_c_str(path)?.into_owned();
+        let abs_prefix = this.deref_pointer(abs_prefix_op)?;
+        let relative_path = this.deref_pointer(relative_path_op)?;
+        let relative_path_len = this.read_target_usize(relative_path_len_op)?;
+
+        // The program may have closed the preopen.
+        if !this.machine.fds.get(PREOPEN_FD).is_some_and(|fd| fd.downcast::<PreopenDir>().is_some())
+        {
+            return Ok(Scalar::from_i32(-1));
+        }
+
+        // wasi-libc resolves relative paths against its own working directory, which starts out
+        // as `/`. Use the host's instead, like the unix shims do, unless isolation forbids that.
+        let path = if path.is_relative() && this.machine.communicate() {
+            std::env::current_dir()
+                .map_err(|e| err_unsup_format!("cannot get the host's working directory: {e}"))?
+                .join(path)
+        } else {
+            path
+        };
+        let relative = path.strip_prefix("/").unwrap_or(&path);
+        let relative = if relative.as_os_str().is_empty() { Path::new(".") } else { relative };
+
+        // std passes its own buffer in `relative_path` for us to fill. If it is too small, it
+        // expects `ENOMEM` in `errno`, which Miri cannot provide on WASI.
+        let buf = this.read_pointer(&relative_path)?;
+        let (written, _) = this.write_path_to_c_str(relative, buf, relative_path_len)?;
+        if !written {
+            throw_unsup_format!(
+                "`__wasilibc_find_relpath` with paths longer than {relative_path_len} bytes is not supported"
+            );
+        }
+        // std ignores the prefix.
+        this.write_pointer(Pointer::null(), &abs_prefix)?;
+        Ok(Scalar::from_i32(PREOPEN_FD))
+    }
+}
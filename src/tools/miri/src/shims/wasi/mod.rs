@@ -1 +1,25 @@
 pub mod foreign_items;
+
+mod fs;
+
+pub use self::fs::PreopenDir;
+// All the WASI-specific extension traits
+pub use self::fs::EvalContextExt as _;
+
+use rustc_target::abi::Size;
+
+use crate::*;
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// WASI imports take pointers as plain `i32` addresses; this turns one back into a pointer.
+    fn read_wasi_pointer(&self, op: &OpTy<'tcx>) -> InterpResult<'tcx, Pointer> {
+        let this = self.eval_context_ref();
+        let addr = this.read_scalar(op)?.to_u32()?;
+        // The program exposed the pointer when casting it to an integer for the call, so undo
+        // that cast the same way a cast in the program would, without the int-to-pointer
+        // warning the program is not responsible for. (With strict provenance nothing gets
+        // exposed, so these pointers cannot be used.)
+        Ok(Pointer::new(Some(Provenance::Wildcard), Size::from_bytes(addr)))
+    }
+}
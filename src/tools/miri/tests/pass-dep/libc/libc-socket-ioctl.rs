@@ -0,0 +1,73 @@
+//@ignore-target: windows # No libc sockets on Windows
+//! Unlike `SOCK_NONBLOCK`, which only exists on Linux, `ioctl(FIONBIO)` makes sockets
+//! non-blocking on all Unix targets.
+
+use std::mem;
+
+fn errno() -> i32 {
+    std::io::Error::last_os_error().raw_os_error().unwrap()
+}
+
+fn loopback(port: u16) -> libc::sockaddr_in {
+    // Zero-initialize, as the BSDs have an additional `sin_len` field.
+    let mut addr: libc::sockaddr_in = unsafe { mem::zeroed() };
+    addr.sin_family = libc::AF_INET as libc::sa_family_t;
+    addr.sin_port = port.to_be();
+    addr.sin_addr.s_addr = u32::from_ne_bytes([127, 0, 0, 1]);
+    addr
+}
+
+fn set_nonblocking(fd: i32, nonblocking: bool) {
+    let mut nonblocking = libc::c_int::from(nonblocking);
+    assert_eq!(unsafe { libc::ioctl(fd, libc::FIONBIO, &mut nonblocking) }, 0);
+}
+
+fn main() {
+    let listener = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
+    assert!(listener >= 0);
+    let addr = loopback(0);
+    let len = mem::size_of::<libc::sockaddr_in>() as libc::socklen_t;
+    assert_eq!(unsafe { libc::bind(listener, (&addr as *const libc::sockaddr_in).cast(), len) }, 0);
+    assert_eq!(unsafe { libc::listen(listener, 16) }, 0);
+    let mut addr: libc::sockaddr_in = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<libc::sockaddr_in>() as libc::socklen_t;
+    let res = unsafe {
+        libc::getsockname(listener, (&mut addr as *mut libc::sockaddr_in).cast(), &mut len)
+    };
+    assert_eq!(res, 0);
+    let port = u16::from_be(addr.sin_port);
+
+    // Nothing to accept yet.
+    set_nonblocking(listener, true);
+    let res = unsafe { libc::accept(listener, std::ptr::null_mut(), std::ptr::null_mut()) };
+    assert_eq!(res, -1);
+    assert_eq!(errno(), libc::EWOULDBLOCK);
+
+    let client = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
+    assert!(client >= 0);
+    let addr = loopback(port);
+    assert_eq!(
+        unsafe { libc::connect(client, (&addr as *const libc::sockaddr_in).cast(), len) },
+        0
+    );
+    let server = unsafe { libc::accept(listener, std::ptr::null_mut(), std::ptr::null_mut()) };
+    assert!(server >= 0);
+
+    // Nothing to receive yet.
+    set_nonblocking(server, true);
+    let mut buf = [0u8; 4];
+    let res = unsafe { libc::recv(server, buf.as_mut_ptr().cast(), buf.len(), 0) };
+    assert_eq!(res, -1);
+    assert_eq!(errno(), libc::EWOULDBLOCK);
+
+    // Once it is blocking again, `recv` waits for data, of which there is some already.
+    set_nonblocking(server, false);
+    assert_eq!(unsafe { libc::send(client, b"abc".as_ptr().cast(), 3, 0) }, 3);
+    let res = unsafe { libc::recv(server, buf.as_mut_ptr().cast(), buf.len(), 0) };
+    assert_eq!(res, 3);
+    assert_eq!(&buf[..3], b"abc");
+
+    assert_eq!(unsafe { libc::close(client) }, 0);
+    assert_eq!(unsafe { libc::close(server) }, 0);
+    assert_eq!(unsafe { libc::close(listener) }, 0);
+}
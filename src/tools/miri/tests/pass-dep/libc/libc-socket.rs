@@ -0,0 +1,490 @@
+//@only-target: linux
+// test_blocking depends on a deterministic schedule.
+//@compile-flags: -Zmiri-preemption-rate=0
+
+use std::convert::TryInto;
+use std::mem;
+use std::thread;
+
+fn main() {
+    test_tcp();
+    test_bind_errors();
+    test_connect_errors();
+    test_nonblocking();
+    test_shutdown();
+    test_sockopt();
+    test_udp();
+    test_epoll();
+    test_blocking();
+}
+
+fn loopback(port: u16) -> libc::sockaddr_in {
+    libc::sockaddr_in {
+        sin_family: libc::AF_INET as libc::sa_family_t,
+        sin_port: port.to_be(),
+        sin_addr: libc::in_addr { s_addr: u32::from_ne_bytes([127, 0, 0, 1]) },
+        sin_zero: [0; 8],
+    }
+}
+
+fn errno() -> i32 {
+    std::io::Error::last_os_error().raw_os_error().unwrap()
+}
+
+fn bind(fd: i32, addr: &libc::sockaddr_in) -> i32 {
+    unsafe {
+        libc::bind(
+            fd,
+            (addr as *const libc::sockaddr_in).cast(),
+            mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+        )
+    }
+}
+
+fn connect(fd: i32, addr: &libc::sockaddr_in) -> i32 {
+    unsafe {
+        libc::connect(
+            fd,
+            (addr as *const libc::sockaddr_in).cast(),
+            mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+        )
+    }
+}
+
+fn local_addr(fd: i32) -> libc::sockaddr_in {
+    let mut addr: libc::sockaddr_in = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<libc::sockaddr_in>() as libc::socklen_t;
+    let res =
+        unsafe { libc::getsockname(fd, (&mut addr as *mut libc::sockaddr_in).cast(), &mut len) };
+    assert_eq!(res, 0);
+    assert_eq!(len as usize, mem::size_of::<libc::sockaddr_in>());
+    addr
+}
+
+/// Creates a listening TCP socket on an ephemeral port, and returns it and its port.
+fn listener(flags: i32) -> (i32, u16) {
+    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM | flags, 0) };
+    assert!(fd >= 0);
+    assert_eq!(bind(fd, &loopback(0)), 0);
+    assert_eq!(unsafe { libc::listen(fd, 16) }, 0);
+    let port = u16::from_be(local_addr(fd).sin_port);
+    assert_ne!(port, 0);
+    (fd, port)
+}
+
+fn send(fd: i32, data: &[u8], flags: i32) -> isize {
+    unsafe { libc::send(fd, data.as_ptr().cast(), data.len(), flags) }
+}
+
+fn recv(fd: i32, buf: &mut [u8], flags: i32) -> isize {
+    unsafe { libc::recv(fd, buf.as_mut_ptr().cast(), buf.len(), flags) }
+}
+
+fn test_tcp() {
+    let (listener, port) = listener(0);
+
+    let client = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
+    assert_eq!(connect(client, &loopback(port)), 0);
+    let client_addr = local_addr(client);
+
+    let mut peer: libc::sockaddr_in = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<libc::sockaddr_in>() as libc::socklen_t;
+    let server =
+        unsafe { libc::accept(listener, (&mut peer as *mut libc::sockaddr_in).cast(), &mut len) };
+    assert!(server >= 0);
+    assert_eq!(len as usize, mem::size_of::<libc::sockaddr_in>());
+    assert_eq!(peer.sin_port, client_addr.sin_port);
+    assert_eq!(peer.sin_addr.s_addr, u32::from_ne_bytes([127, 0, 0, 1]));
+    // The accepted socket uses the port of the listener.
+    assert_eq!(u16::from_be(local_addr(server).sin_port), port);
+
+    // Send data both ways, using both `send`/`recv` and `write`/`read`.
+    assert_eq!(send(client, b"hello", 0), 5);
+    let mut buf = [0; 16];
+    assert_eq!(recv(server, &mut buf, 0), 5);
+    assert_eq!(&buf[..5], b"hello");
+    let res = unsafe { libc::write(server, b"world".as_ptr().cast(), 5) };
+    assert_eq!(res, 5);
+    let res = unsafe { libc::read(client, buf.as_mut_ptr().cast(), 3) };
+    assert_eq!(res, 3);
+    assert_eq!(&buf[..3], b"wor");
+    assert_eq!(recv(client, &mut buf, 0), 2);
+    assert_eq!(&buf[..2], b"ld");
+
+    // Closing one end makes the other end read EOF, and writes fail with EPIPE.
+    assert_eq!(send(client, b"bye", 0), 3);
+    assert_eq!(unsafe { libc::close(client) }, 0);
+    assert_eq!(recv(server, &mut buf, 0), 3);
+    assert_eq!(recv(server, &mut buf, 0), 0);
+    assert_eq!(send(server, b"?", libc::MSG_NOSIGNAL), -1);
+    assert_eq!(errno(), libc::EPIPE);
+
+    assert_eq!(unsafe { libc::close(server) }, 0);
+    assert_eq!(unsafe { libc::close(listener) }, 0);
+}
+
+fn test_bind_errors() {
+    let (listener, port) = listener(0);
+
+    // The port is in use.
+    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
+    assert_eq!(bind(fd, &loopback(port)), -1);
+    assert_eq!(errno(), libc::EADDRINUSE);
+
+    // Only the loopback interface exists.
+    let mut addr = loopback(0);
+    addr.sin_addr.s_addr = u32::from_ne_bytes([192, 168, 0, 1]);
+    assert_eq!(bind(fd, &addr), -1);
+    assert_eq!(errno(), libc::EADDRNOTAVAIL);
+
+    // Closing the listener frees the port.
+    assert_eq!(unsafe { libc::close(listener) }, 0);
+    assert_eq!(bind(fd, &loopback(port)), 0);
+    // A socket can only be bound once.
+    assert_eq!(bind(fd, &loopback(0)), -1);
+    assert_eq!(errno(), libc::EINVAL);
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+}
+
+fn test_connect_errors() {
+    let (listener, port) = listener(0);
+    // Nobody listens on a bound socket that is not listening.
+    let bound = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
+    assert_eq!(bind(bound, &loopback(0)), 0);
+    let bound_port = u16::from_be(local_addr(bound).sin_port);
+
+    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
+    assert_eq!(connect(fd, &loopback(bound_port)), -1);
+    assert_eq!(errno(), libc::ECONNREFUSED);
+
+    let mut addr = loopback(port);
+    addr.sin_addr.s_addr = u32::from_ne_bytes([93, 184, 216, 34]);
+    assert_eq!(connect(fd, &addr), -1);
+    assert_eq!(errno(), libc::ENETUNREACH);
+
+    // Connecting to the unspecified address reaches the loopback address.
+    let mut addr = loopback(port);
+    addr.sin_addr.s_addr = 0;
+    assert_eq!(connect(fd, &addr), 0);
+    assert_eq!(connect(fd, &loopback(port)), -1);
+    assert_eq!(errno(), libc::EISCONN);
+
+    let mut peer: libc::sockaddr_in = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<libc::sockaddr_in>() as libc::socklen_t;
+    let res =
+        unsafe { libc::getpeername(fd, (&mut peer as *mut libc::sockaddr_in).cast(), &mut len) };
+    assert_eq!(res, 0);
+    assert_eq!(u16::from_be(peer.sin_port), port);
+    assert_eq!(peer.sin_addr.s_addr, u32::from_ne_bytes([127, 0, 0, 1]));
+    let res =
+        unsafe { libc::getpeername(bound, (&mut peer as *mut libc::sockaddr_in).cast(), &mut len) };
+    assert_eq!(res, -1);
+    assert_eq!(errno(), libc::ENOTCONN);
+
+    // Closing the listener closes the connections that were not accepted yet.
+    assert_eq!(unsafe { libc::close(listener) }, 0);
+    let mut buf = [0; 4];
+    assert_eq!(recv(fd, &mut buf, 0), 0);
+
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+    assert_eq!(unsafe { libc::close(bound) }, 0);
+}
+
+fn test_nonblocking() {
+    let (listener, port) = listener(libc::SOCK_NONBLOCK);
+
+    // Nothing to accept yet.
+    let res = unsafe { libc::accept(listener, std::ptr::null_mut(), std::ptr::null_mut()) };
+    assert_eq!(res, -1);
+    assert_eq!(errno(), libc::EAGAIN);
+
+    let client = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM | libc::SOCK_NONBLOCK, 0) };
+    assert_eq!(connect(client, &loopback(port)), 0);
+    let server = unsafe {
+        libc::accept4(listener, std::ptr::null_mut(), std::ptr::null_mut(), libc::SOCK_NONBLOCK)
+    };
+    assert!(server >= 0);
+
+    // Nothing to receive yet.
+    let mut buf = [0; 4];
+    assert_eq!(recv(server, &mut buf, 0), -1);
+    assert_eq!(errno(), libc::EAGAIN);
+
+    // `MSG_PEEK` leaves the data in the buffer.
+    assert_eq!(send(client, b"abc", 0), 3);
+    assert_eq!(recv(server, &mut buf, libc::MSG_PEEK), 3);
+    assert_eq!(&buf[..3], b"abc");
+    assert_eq!(recv(server, &mut buf[..2], 0), 2);
+    assert_eq!(&buf[..2], b"ab");
+    assert_eq!(recv(server, &mut buf, 0), 1);
+    assert_eq!(buf[0], b'c');
+
+    // `MSG_DONTWAIT` makes a blocking socket act like a non-blocking one.
+    let mut nonblocking: libc::c_int = 0;
+    assert_eq!(unsafe { libc::ioctl(client, libc::FIONBIO, &mut nonblocking) }, 0);
+    assert_eq!(recv(client, &mut buf, libc::MSG_DONTWAIT), -1);
+    assert_eq!(errno(), libc::EAGAIN);
+
+    // Fill the buffer of the server.
+    let data = vec![0u8; 0x10000];
+    let mut sent = 0;
+    loop {
+        let res = send(server, &data, libc::MSG_DONTWAIT);
+        if res == -1 {
+            assert_eq!(errno(), libc::EAGAIN);
+            break;
+        }
+        sent += res;
+    }
+    assert!(sent > 0);
+
+    assert_eq!(unsafe { libc::close(client) }, 0);
+    assert_eq!(unsafe { libc::close(server) }, 0);
+    assert_eq!(unsafe { libc::close(listener) }, 0);
+}
+
+fn test_shutdown() {
+    let (listener, port) = listener(0);
+    let client = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
+    assert_eq!(connect(client, &loopback(port)), 0);
+    let server = unsafe { libc::accept(listener, std::ptr::null_mut(), std::ptr::null_mut()) };
+
+    assert_eq!(send(client, b"abc", 0), 3);
+    assert_eq!(unsafe { libc::shutdown(client, libc::SHUT_WR) }, 0);
+    assert_eq!(send(client, b"abc", libc::MSG_NOSIGNAL), -1);
+    assert_eq!(errno(), libc::EPIPE);
+
+    // The buffered data can still be read, then the server reads EOF.
+    let mut buf = [0; 4];
+    assert_eq!(recv(server, &mut buf, 0), 3);
+    assert_eq!(recv(server, &mut buf, 0), 0);
+    // The other direction still works.
+    assert_eq!(send(server, b"xyz", 0), 3);
+    assert_eq!(recv(client, &mut buf, 0), 3);
+    assert_eq!(&buf[..3], b"xyz");
+
+    let unconnected = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
+    assert_eq!(unsafe { libc::shutdown(unconnected, libc::SHUT_RDWR) }, -1);
+    assert_eq!(errno(), libc::ENOTCONN);
+
+    assert_eq!(unsafe { libc::close(unconnected) }, 0);
+    assert_eq!(unsafe { libc::close(client) }, 0);
+    assert_eq!(unsafe { libc::close(server) }, 0);
+    assert_eq!(unsafe { libc::close(listener) }, 0);
+}
+
+fn test_sockopt() {
+    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
+    let size = mem::size_of::<libc::c_int>() as libc::socklen_t;
+
+    let on: libc::c_int = 1;
+    let res = unsafe {
+        libc::setsockopt(fd, libc::SOL_SOCKET, libc::SO_REUSEADDR, (&on as *const i32).cast(), size)
+    };
+    assert_eq!(res, 0);
+    let res = unsafe {
+        libc::setsockopt(fd, libc::IPPROTO_TCP, libc::TCP_NODELAY, (&on as *const i32).cast(), size)
+    };
+    assert_eq!(res, 0);
+
+    let mut value: libc::c_int = 0;
+    let mut len = size;
+    let res = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::IPPROTO_TCP,
+            libc::TCP_NODELAY,
+            (&mut value as *mut i32).cast(),
+            &mut len,
+        )
+    };
+    assert_eq!(res, 0);
+    assert_eq!(value, 1);
+    assert_eq!(len, size);
+    let res = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_TYPE,
+            (&mut value as *mut i32).cast(),
+            &mut len,
+        )
+    };
+    assert_eq!(res, 0);
+    assert_eq!(value, libc::SOCK_STREAM);
+    let res = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_ERROR,
+            (&mut value as *mut i32).cast(),
+            &mut len,
+        )
+    };
+    assert_eq!(res, 0);
+    assert_eq!(value, 0);
+
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+}
+
+fn test_udp() {
+    let a = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM, 0) };
+    let b = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM, libc::IPPROTO_UDP) };
+    assert_eq!(bind(a, &loopback(0)), 0);
+    assert_eq!(bind(b, &loopback(0)), 0);
+    let a_addr = local_addr(a);
+    let b_addr = local_addr(b);
+
+    let sendto = |fd: i32, data: &[u8], to: &libc::sockaddr_in| unsafe {
+        libc::sendto(
+            fd,
+            data.as_ptr().cast(),
+            data.len(),
+            0,
+            (to as *const libc::sockaddr_in).cast(),
+            mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+        )
+    };
+    let recvfrom = |fd: i32, buf: &mut [u8]| {
+        let mut from: libc::sockaddr_in = unsafe { mem::zeroed() };
+        let mut len = mem::size_of::<libc::sockaddr_in>() as libc::socklen_t;
+        let res = unsafe {
+            libc::recvfrom(
+                fd,
+                buf.as_mut_ptr().cast(),
+                buf.len(),
+                0,
+                (&mut from as *mut libc::sockaddr_in).cast(),
+                &mut len,
+            )
+        };
+        (res, from)
+    };
+
+    // Datagram boundaries are kept, and the sender is reported.
+    assert_eq!(sendto(a, b"first", &b_addr), 5);
+    assert_eq!(sendto(a, b"second", &b_addr), 6);
+    let mut buf = [0; 16];
+    let (res, from) = recvfrom(b, &mut buf);
+    assert_eq!(res, 5);
+    assert_eq!(&buf[..5], b"first");
+    assert_eq!(from.sin_port, a_addr.sin_port);
+    // Datagrams that do not fit get truncated.
+    let (res, _) = recvfrom(b, &mut buf[..3]);
+    assert_eq!(res, 3);
+    assert_eq!(&buf[..3], b"sec");
+
+    // A connected socket only receives from its peer.
+    assert_eq!(connect(a, &b_addr), 0);
+    let c = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM, 0) };
+    assert_eq!(sendto(c, b"ignored", &a_addr), 7);
+    assert_eq!(send(b, b"reply", 0), -1);
+    assert_eq!(errno(), libc::EDESTADDRREQ);
+    assert_eq!(sendto(b, b"reply", &a_addr), 5);
+    assert_eq!(recv(a, &mut buf, libc::MSG_DONTWAIT), 5);
+    assert_eq!(&buf[..5], b"reply");
+    assert_eq!(recv(a, &mut buf, libc::MSG_DONTWAIT), -1);
+    assert_eq!(errno(), libc::EAGAIN);
+    assert_eq!(send(a, b"again", 0), 5);
+    assert_eq!(recv(b, &mut buf, 0), 5);
+
+    // Datagrams to ports nobody is bound to are dropped.
+    assert_eq!(unsafe { libc::close(b) }, 0);
+    assert_eq!(send(a, b"lost", 0), 4);
+
+    assert_eq!(unsafe { libc::close(a) }, 0);
+    assert_eq!(unsafe { libc::close(c) }, 0);
+}
+
+#[track_caller]
+fn check_epoll_wait<const N: usize>(epfd: i32, expected_notifications: &[(u32, u64)]) {
+    let epoll_event = libc::epoll_event { events: 0, u64: 0 };
+    let mut array: [libc::epoll_event; N] = [epoll_event; N];
+    let maxsize = N;
+    let array_ptr = array.as_mut_ptr();
+    let res = unsafe { libc::epoll_wait(epfd, array_ptr, maxsize.try_into().unwrap(), 0) };
+    if res < 0 {
+        panic!("epoll_wait failed: {}", std::io::Error::last_os_error());
+    }
+    assert_eq!(
+        res,
+        expected_notifications.len().try_into().unwrap(),
+        "got wrong number of notifications"
+    );
+    let slice = unsafe { std::slice::from_raw_parts(array_ptr, res.try_into().unwrap()) };
+    for (return_event, expected_event) in slice.iter().zip(expected_notifications.iter()) {
+        let event = return_event.events;
+        let data = return_event.u64;
+        assert_eq!(event, expected_event.0, "got wrong events");
+        assert_eq!(data, expected_event.1, "got wrong data");
+    }
+}
+
+fn test_epoll() {
+    let epfd = unsafe { libc::epoll_create1(0) };
+    assert_ne!(epfd, -1);
+    let (listener, port) = listener(libc::SOCK_NONBLOCK);
+
+    // Using `as` cast since `EPOLLET` wraps around
+    let flags = (libc::EPOLLIN | libc::EPOLLOUT | libc::EPOLLRDHUP | libc::EPOLLET) as u32;
+    let mut ev = libc::epoll_event { events: flags, u64: listener as u64 };
+    let res = unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, listener, &mut ev) };
+    assert_eq!(res, 0);
+    // The listener has nothing to accept yet.
+    check_epoll_wait::<8>(epfd, &[]);
+
+    let client = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM | libc::SOCK_NONBLOCK, 0) };
+    assert_eq!(connect(client, &loopback(port)), 0);
+    check_epoll_wait::<8>(epfd, &[(libc::EPOLLIN as u32, listener as u64)]);
+
+    let server = unsafe { libc::accept(listener, std::ptr::null_mut(), std::ptr::null_mut()) };
+    let mut ev = libc::epoll_event { events: flags, u64: client as u64 };
+    let res = unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, client, &mut ev) };
+    assert_eq!(res, 0);
+    // The client can write.
+    check_epoll_wait::<8>(epfd, &[(libc::EPOLLOUT as u32, client as u64)]);
+
+    // Once the server sent something, the client can read.
+    assert_eq!(send(server, b"abc", 0), 3);
+    check_epoll_wait::<8>(epfd, &[((libc::EPOLLIN | libc::EPOLLOUT) as u32, client as u64)]);
+
+    // Closing the server hangs up the connection.
+    assert_eq!(unsafe { libc::close(server) }, 0);
+    let expected = (libc::EPOLLIN | libc::EPOLLOUT | libc::EPOLLRDHUP | libc::EPOLLHUP) as u32;
+    check_epoll_wait::<8>(epfd, &[(expected, client as u64)]);
+
+    assert_eq!(unsafe { libc::close(client) }, 0);
+    assert_eq!(unsafe { libc::close(listener) }, 0);
+    assert_eq!(unsafe { libc::close(epfd) }, 0);
+}
+
+fn test_blocking() {
+    let (listener, port) = listener(0);
+
+    // The server blocks in `accept` and then in `recv` until the client got around to it.
+    let server_thread = thread::spawn(move || {
+        let server = unsafe { libc::accept(listener, std::ptr::null_mut(), std::ptr::null_mut()) };
+        assert!(server >= 0);
+        let mut buf = [0; 8];
+        assert_eq!(recv(server, &mut buf, 0), 4);
+        assert_eq!(&buf[..4], b"ping");
+        assert_eq!(send(server, b"pong", 0), 4);
+        server
+    });
+
+    // Let the server thread block in `accept`.
+    thread::yield_now();
+    let client = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
+    assert_eq!(connect(client, &loopback(port)), 0);
+    // Let the server thread block in `recv`.
+    thread::yield_now();
+    assert_eq!(send(client, b"ping", 0), 4);
+    let mut buf = [0; 8];
+    assert_eq!(recv(client, &mut buf, 0), 4);
+    assert_eq!(&buf[..4], b"pong");
+
+    let server = server_thread.join().unwrap();
+    assert_eq!(unsafe { libc::close(client) }, 0);
+    assert_eq!(unsafe { libc::close(server) }, 0);
+    assert_eq!(unsafe { libc::close(listener) }, 0);
+}
@@ -0,0 +1,80 @@
+//@only-target: linux # Sockets are only supported on Linux
+// test_tcp depends on a deterministic schedule.
+//@compile-flags: -Zmiri-preemption-rate=0
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, Shutdown, TcpListener, TcpStream, UdpSocket};
+use std::thread;
+
+fn main() {
+    test_tcp();
+    test_tcp_nonblocking();
+    test_udp();
+}
+
+fn test_tcp() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    assert_eq!(addr.ip(), Ipv4Addr::LOCALHOST);
+
+    let client = thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.set_nodelay(true).unwrap();
+        assert!(stream.nodelay().unwrap());
+        assert_eq!(stream.peer_addr().unwrap(), addr);
+        stream.write_all(b"hello").unwrap();
+        stream.shutdown(Shutdown::Write).unwrap();
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).unwrap();
+        assert_eq!(reply, "world");
+    });
+
+    let (mut stream, peer) = listener.accept().unwrap();
+    assert_eq!(peer.ip(), Ipv4Addr::LOCALHOST);
+    let mut request = String::new();
+    stream.read_to_string(&mut request).unwrap();
+    assert_eq!(request, "hello");
+    stream.write_all(b"world").unwrap();
+    drop(stream);
+
+    client.join().unwrap();
+}
+
+fn test_tcp_nonblocking() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+    assert_eq!(listener.accept().unwrap_err().kind(), ErrorKind::WouldBlock);
+
+    let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (mut server, _) = listener.accept().unwrap();
+    server.set_nonblocking(true).unwrap();
+    let mut buf = [0; 4];
+    assert_eq!(server.read(&mut buf).unwrap_err().kind(), ErrorKind::WouldBlock);
+    client.write_all(b"ping").unwrap();
+    server.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"ping");
+
+    // Nobody listens anymore once the listener is gone.
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    assert_eq!(TcpStream::connect(addr).unwrap_err().kind(), ErrorKind::ConnectionRefused);
+}
+
+fn test_udp() {
+    let a = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let b = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+    a.send_to(b"ping", b.local_addr().unwrap()).unwrap();
+    let mut buf = [0; 16];
+    let (len, from) = b.recv_from(&mut buf).unwrap();
+    assert_eq!(&buf[..len], b"ping");
+    assert_eq!(from, a.local_addr().unwrap());
+
+    b.connect(from).unwrap();
+    b.send(b"pong").unwrap();
+    let (len, from) = a.peek_from(&mut buf).unwrap();
+    assert_eq!(&buf[..len], b"pong");
+    assert_eq!(from, b.local_addr().unwrap());
+    let len = a.recv(&mut buf).unwrap();
+    assert_eq!(&buf[..len], b"pong");
+}
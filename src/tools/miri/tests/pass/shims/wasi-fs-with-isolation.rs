@@ -0,0 +1,18 @@
+//@only-target: wasi
+//@compile-flags: -Zmiri-isolation-error=warn-nobacktrace
+
+use std::fs::{self, File};
+use std::io::ErrorKind;
+use std::time::Instant;
+
+fn main() {
+    // test `path_open`
+    assert_eq!(File::create("/foo.txt").unwrap_err().kind(), ErrorKind::PermissionDenied);
+
+    // test `path_unlink_file`
+    assert_eq!(fs::remove_file("/foo.txt").unwrap_err().kind(), ErrorKind::PermissionDenied);
+
+    // The monotonic clock does not need the host.
+    let before = Instant::now();
+    assert!(Instant::now() >= before);
+}
@@ -0,0 +1,60 @@
+//@only-target: wasi
+//@compile-flags: -Zmiri-disable-isolation
+
+#![feature(wasi_ext)]
+
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::os::wasi::preopens::preopened_dirs;
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+#[path = "../../utils/mod.rs"]
+mod utils;
+
+fn main() {
+    test_preopens();
+    test_file();
+    test_clocks();
+}
+
+fn test_preopens() {
+    let dirs = preopened_dirs();
+    assert_eq!(dirs.len(), 1);
+    assert_eq!(dirs[0].path(), Path::new("/"));
+}
+
+fn test_file() {
+    let path = utils::prepare("miri_test_wasi_fs.txt");
+    let bytes = b"Hello, World!\n";
+
+    let mut file = File::create(&path).unwrap();
+    file.write_all(bytes).unwrap();
+    drop(file);
+
+    let mut file = File::open(&path).unwrap();
+    assert_eq!(file.metadata().unwrap().len(), bytes.len() as u64);
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents).unwrap();
+    assert_eq!(contents, bytes);
+    assert_eq!(file.seek(SeekFrom::Start(7)).unwrap(), 7);
+    let mut rest = String::new();
+    file.read_to_string(&mut rest).unwrap();
+    assert_eq!(rest, "World!\n");
+    drop(file);
+
+    assert_eq!(fs::read(&path).unwrap(), bytes);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(File::open(&path).unwrap_err().kind(), std::io::ErrorKind::NotFound);
+}
+
+fn test_clocks() {
+    let now = SystemTime::now();
+    assert!(now.duration_since(UNIX_EPOCH).unwrap().as_secs() > 0);
+
+    let before = Instant::now();
+    for _ in 0..10 {
+        drop(vec![42]);
+    }
+    assert!(Instant::now() > before);
+}
@@ -1,7 +1,7 @@
 //! Collection of assertions and assertion-related helpers.
 
 use std::panic;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::{fs, regex};
 
@@ -115,3 +115,25 @@ pub fn assert_dirs_are_equal(dir1: impl AsRef<Path>, dir2: impl AsRef<Path>) {
         }
     });
 }
+
+/// Assert that `build` produces byte-for-byte identical output when it is run twice, each time
+/// in a different fresh directory. `build` is given the directory to build in and returns the
+/// path of the artifact to compare.
+#[track_caller]
+pub fn assert_build_is_reproducible<F: Fn(&Path) -> PathBuf>(build: F) {
+    let mut artifacts = Vec::new();
+    // The names have different lengths so that the length of the build path doesn't leak into
+    // the output either, e.g. through padding.
+    for dir in ["first-build", "second-build-with-a-longer-path"] {
+        fs::create_dir(dir);
+        artifacts.push(build(Path::new(dir)));
+    }
+
+    let (first, second) = (&artifacts[0], &artifacts[1]);
+    assert!(
+        fs::read(first) == fs::read(second),
+        "`{}` and `{}` have different content",
+        first.display(),
+        second.display(),
+    );
+}
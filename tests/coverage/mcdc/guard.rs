@@ -0,0 +1,29 @@
+#![feature(coverage_attribute)]
+//@ edition: 2021
+//@ ignore-llvm-version: 19 - 99
+//@ compile-flags: -Zcoverage-options=mcdc
+//@ llvm-cov-flags: --show-branches=count --show-mcdc
+
+fn mcdc_match_guard(x: Option<u32>, a: bool) {
+    match x {
+        Some(y) if y > 0 && a => {
+            say("positive and a");
+        }
+        _ => {
+            say("something else");
+        }
+    }
+}
+
+#[coverage(off)]
+fn say(message: &str) {
+    core::hint::black_box(message);
+}
+
+#[coverage(off)]
+fn main() {
+    mcdc_match_guard(Some(1), true);
+    mcdc_match_guard(Some(1), false);
+    mcdc_match_guard(Some(0), true);
+    mcdc_match_guard(None, true);
+}
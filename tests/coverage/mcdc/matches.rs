@@ -0,0 +1,25 @@
+#![feature(coverage_attribute)]
+//@ edition: 2021
+//@ ignore-llvm-version: 19 - 99
+//@ compile-flags: -Zcoverage-options=mcdc
+//@ llvm-cov-flags: --show-branches=count --show-mcdc
+
+fn matches_in_decision(a: bool, x: Option<u32>) {
+    if a && matches!(x, Some(_)) {
+        say("a and some");
+    } else {
+        say("not both");
+    }
+}
+
+#[coverage(off)]
+fn say(message: &str) {
+    core::hint::black_box(message);
+}
+
+#[coverage(off)]
+fn main() {
+    matches_in_decision(true, Some(1));
+    matches_in_decision(true, None);
+    matches_in_decision(false, Some(1));
+}
@@ -0,0 +1,16 @@
+#[no_mangle]
+pub extern "C" fn foo_init() {}
+
+#[no_mangle]
+pub extern "C" fn foo_run() -> u32 {
+    1
+}
+
+#[no_mangle]
+pub extern "C" fn foo_run_v2() -> u32 {
+    2
+}
+
+pub fn rust_api() -> u32 {
+    3
+}
@@ -0,0 +1,59 @@
+// `-Zsymbol-versions` assigns version tags to the symbols exported from a cdylib through the
+// linker version script, and `-Zexport-symbol-map` writes the exported symbols, with their
+// demangled names and versions, to a JSON file. The version file is recorded in the dep-info,
+// so that build systems rebuild the library when it changes.
+
+//@ only-linux
+//@ ignore-cross-compile
+
+use run_make_support::serde_json::{self, Value};
+use run_make_support::{dynamic_lib_name, llvm_readobj, rfs, rustc};
+
+fn main() {
+    rustc()
+        .input("foo.rs")
+        .crate_type("cdylib")
+        .arg("-Zsymbol-versions=versions.txt")
+        .arg("-Zexport-symbol-map=cdylib.json")
+        .emit("link,dep-info")
+        .run();
+
+    let deps = rfs::read_to_string("foo.d");
+    assert!(deps.contains("versions.txt"), "{deps}");
+
+    let out =
+        llvm_readobj().arg("--dyn-symbols").input(dynamic_lib_name("foo")).run().stdout_utf8();
+    assert!(out.contains("foo_init@@FOO_1.0"), "{out}");
+    assert!(out.contains("foo_run@@FOO_1.0"), "{out}");
+    assert!(out.contains("foo_run_v2@@FOO_2.0"), "{out}");
+
+    let map: Value = serde_json::from_str(&rfs::read_to_string("cdylib.json")).unwrap();
+    let version_of = |symbol: &str| {
+        let entry = map["cdylib"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|entry| entry["symbol"] == symbol)
+            .unwrap_or_else(|| panic!("`{symbol}` is missing from {map:#}"));
+        entry["version"].as_str().unwrap().to_string()
+    };
+    assert_eq!(version_of("foo_init"), "FOO_1.0");
+    assert_eq!(version_of("foo_run"), "FOO_1.0");
+    assert_eq!(version_of("foo_run_v2"), "FOO_2.0");
+
+    // Rust symbols exported from a dylib are listed with their demangled paths.
+    rustc()
+        .input("foo.rs")
+        .crate_type("dylib")
+        .out_dir("dylib")
+        .arg("-Zexport-symbol-map=dylib.json")
+        .run();
+    let map: Value = serde_json::from_str(&rfs::read_to_string("dylib.json")).unwrap();
+    let demangled: Vec<&str> = map["dylib"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|entry| entry["demangled"].as_str())
+        .collect();
+    assert!(demangled.contains(&"foo::rust_api"), "{map:#}");
+}
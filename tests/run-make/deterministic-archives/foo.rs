@@ -0,0 +1,21 @@
+pub mod a {
+    pub fn a() -> u32 {
+        1
+    }
+}
+
+pub mod b {
+    pub fn b() -> u32 {
+        super::a::a() + 1
+    }
+}
+
+pub mod c {
+    pub fn c<T: Default>() -> T {
+        T::default()
+    }
+}
+
+pub fn all() -> u32 {
+    a::a() + b::b() + c::c::<u32>()
+}
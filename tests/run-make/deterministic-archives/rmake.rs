@@ -0,0 +1,73 @@
+// `-Zdeterministic-archives` should rename the Rust object files in rlibs and staticlibs so that
+// their names no longer contain codegen unit names, while keeping the metadata member in place.
+// The archives should stay byte-for-byte reproducible across build directories as long as the
+// build directory is remapped, which is already the case without the flag.
+
+//@ ignore-cross-compile
+
+use std::path::{Path, PathBuf};
+
+use run_make_support::object::read::archive::ArchiveFile;
+use run_make_support::{
+    assert_build_is_reproducible, cwd, rfs, run_in_tmpdir, rust_lib_name, rustc, static_lib_name,
+};
+
+fn main() {
+    for crate_type in ["rlib", "staticlib"] {
+        for deterministic_archives in [true, false] {
+            run_in_tmpdir(|| {
+                assert_build_is_reproducible(|dir| build(dir, crate_type, deterministic_archives));
+            });
+        }
+    }
+
+    run_in_tmpdir(|| {
+        rfs::create_dir("inspect");
+        let rlib = rfs::read(build(Path::new("inspect"), "rlib", true));
+        let names = member_names(&rlib);
+        let objects: Vec<&String> = names.iter().filter(|name| name.ends_with(".rcgu.o")).collect();
+        assert!(!objects.is_empty(), "no object files in {names:?}");
+        for (index, name) in objects.iter().enumerate() {
+            assert_eq!(**name, format!("foo.{index}.rcgu.o"), "members: {names:?}");
+        }
+        assert!(names.iter().any(|name| name == "lib.rmeta"), "members: {names:?}");
+
+        // Without the flag, the object files keep their codegen unit names.
+        rfs::create_dir("plain");
+        let plain_names = member_names(&rfs::read(build(Path::new("plain"), "rlib", false)));
+        let plain_objects: Vec<&String> =
+            plain_names.iter().filter(|name| name.ends_with(".rcgu.o")).collect();
+        assert_eq!(plain_objects.len(), objects.len(), "members: {plain_names:?}");
+        for (index, name) in plain_objects.iter().enumerate() {
+            assert_ne!(**name, format!("foo.{index}.rcgu.o"), "members: {plain_names:?}");
+        }
+    });
+}
+
+fn member_names(data: &[u8]) -> Vec<String> {
+    ArchiveFile::parse(data)
+        .unwrap()
+        .members()
+        .map(|member| String::from_utf8(member.unwrap().name().to_vec()).unwrap())
+        .collect()
+}
+
+fn build(dir: &Path, crate_type: &str, deterministic_archives: bool) -> PathBuf {
+    rfs::copy("foo.rs", dir.join("foo.rs"));
+    let dir = cwd().join(dir);
+    let mut rustc = rustc();
+    rustc
+        .current_dir(&dir)
+        .input("foo.rs")
+        .crate_type(crate_type)
+        .codegen_units(4)
+        .arg(format!("--remap-path-prefix={}=/build", dir.display()));
+    if deterministic_archives {
+        rustc.arg("-Zdeterministic-archives");
+    }
+    rustc.run();
+    dir.join(match crate_type {
+        "rlib" => rust_lib_name("foo"),
+        _ => static_lib_name("foo"),
+    })
+}
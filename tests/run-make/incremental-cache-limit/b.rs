@@ -0,0 +1,3 @@
+pub fn b() -> u32 {
+    2
+}
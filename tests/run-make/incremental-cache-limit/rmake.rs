@@ -0,0 +1,56 @@
+//! This checks that `--print=incremental-cache` lists the session directories of every crate in
+//! an incremental compilation directory with their work products, and that
+//! `-Zincremental-cache-limit` deletes the sessions of other crates once the directory grows
+//! beyond the limit.
+
+use run_make_support::rustc;
+
+/// Returns the line of every session, with the lines of its work products.
+fn print_sessions() -> Vec<(String, Vec<String>)> {
+    let stdout = rustc()
+        .arg("-Zunstable-options")
+        .print("incremental-cache")
+        .incremental("incr")
+        .run()
+        .stdout_utf8();
+    let mut lines: Vec<&str> = stdout.lines().collect();
+    let total = lines.pop().unwrap();
+    let mut sessions: Vec<(String, Vec<String>)> = Vec::new();
+    for line in lines {
+        match line.strip_prefix("    ") {
+            Some(work_product) => sessions.last_mut().unwrap().1.push(work_product.to_string()),
+            None => sessions.push((line.to_string(), Vec::new())),
+        }
+    }
+    assert!(total.starts_with(&format!("total sessions={} size=", sessions.len())), "{stdout}");
+    sessions
+}
+
+fn main() {
+    rustc().input("a.rs").crate_type("lib").incremental("incr").run();
+    rustc().input("b.rs").crate_type("lib").incremental("incr").run();
+
+    let sessions = print_sessions();
+    assert_eq!(sessions.len(), 2, "{sessions:?}");
+    for ((session, work_products), crate_name) in sessions.iter().zip(["a-", "b-"]) {
+        assert!(session.starts_with(crate_name), "{session}");
+        assert!(session.contains(" finalized size="), "{session}");
+        assert!(session.ends_with(&format!("work-products={}", work_products.len())), "{session}");
+        assert!(!work_products.is_empty(), "{session}");
+        for work_product in work_products {
+            assert!(work_product.contains(".o"), "{session}: {work_product}");
+        }
+    }
+
+    // With a limit of zero, every session except the current one is deleted.
+    rustc()
+        .input("a.rs")
+        .crate_type("lib")
+        .incremental("incr")
+        .arg("-Zincremental-cache-limit=0")
+        .run();
+
+    let sessions = print_sessions();
+    assert_eq!(sessions.len(), 1, "{sessions:?}");
+    assert!(sessions[0].0.starts_with("a-"), "{sessions:?}");
+}
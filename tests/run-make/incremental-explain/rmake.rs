@@ -0,0 +1,38 @@
+//! This checks that `-Zincremental-explain` reports, for a query that is re-executed after a
+//! source change, the chain of red dependencies that leads back to the changed input.
+
+use run_make_support::serde_json::{self, Value};
+use run_make_support::{rfs, rustc};
+
+const UNCHANGED: &str = "pub fn unchanged() -> u32 {\n    1\n}\n";
+
+fn main() {
+    rfs::write("lib.rs", format!("{UNCHANGED}pub fn changed() -> u32 {{\n    2\n}}\n"));
+    rustc().input("lib.rs").crate_type("lib").incremental("incr").run();
+
+    rfs::write("lib.rs", format!("{UNCHANGED}pub fn changed() -> u32 {{\n    3\n}}\n"));
+    rustc()
+        .input("lib.rs")
+        .crate_type("lib")
+        .incremental("incr")
+        .arg("-Zincremental-explain=explain.json")
+        .run();
+
+    let report: Value = serde_json::from_str(&rfs::read_to_string("explain.json")).unwrap();
+    assert_eq!(report["previous_session_loaded"], true);
+
+    let reexecuted = report["reexecuted"].as_array().unwrap();
+    let is_typeck_of = |entry: &Value, name: &str| {
+        entry["kind"] == "typeck"
+            && entry["node"].as_str().unwrap().ends_with(&format!("::{name})"))
+    };
+    let typeck_changed = reexecuted
+        .iter()
+        .find(|entry| is_typeck_of(entry, "changed"))
+        .unwrap_or_else(|| panic!("`typeck(changed)` was not reported: {report:#}"));
+    assert_eq!(typeck_changed["cause"], "input-changed");
+    assert!(!typeck_changed["chain"].as_array().unwrap().is_empty());
+
+    // `unchanged` doesn't depend on anything that changed, so it is not type-checked again.
+    assert!(!reexecuted.iter().any(|entry| is_typeck_of(entry, "unchanged")), "{report:#}");
+}
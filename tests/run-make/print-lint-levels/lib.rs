@@ -0,0 +1,2 @@
+#![forbid(unsafe_code)]
+#![cfg_attr(all(), allow(dead_code))]
@@ -0,0 +1,31 @@
+//! This checks the output of `--print=lint-levels`, which lists every lint with
+//! its crate-level level and where that level came from.
+
+use run_make_support::rustc;
+
+fn main() {
+    let stdout = rustc()
+        .input("lib.rs")
+        .arg("-Zunstable-options")
+        .print("lint-levels")
+        .arg("-Dunused")
+        .arg("--force-warn=missing_docs")
+        .run()
+        .stdout_utf8();
+
+    let level_of = |lint: &str| {
+        stdout
+            .lines()
+            .find_map(|line| line.strip_prefix(lint)?.strip_prefix('='))
+            .unwrap_or_else(|| panic!("`{lint}` is missing from:\n{stdout}"))
+    };
+
+    assert_eq!(level_of("unsafe_code"), "forbid (crate attribute: unsafe_code at lib.rs:1:11)");
+    assert_eq!(level_of("dead_code"), "allow (crate attribute: dead_code at lib.rs:2:26)");
+    assert_eq!(level_of("unused_variables"), "deny (command line: -D unused)");
+    assert_eq!(level_of("missing_docs"), "force-warn (force-warn: --force-warn missing-docs)");
+    assert_eq!(level_of("non_snake_case"), "warn (default)");
+
+    let names: Vec<_> = stdout.lines().map(|line| line.split_once('=').unwrap().0).collect();
+    assert!(names.is_sorted());
+}
@@ -0,0 +1,15 @@
+#[repr(C)]
+pub struct Pair {
+    pub a: u8,
+    pub b: u32,
+}
+
+pub fn pair() -> Option<Pair> {
+    None
+}
+
+pub struct Big(pub std::num::NonZero<u128>);
+
+pub fn big() -> Option<Big> {
+    None
+}
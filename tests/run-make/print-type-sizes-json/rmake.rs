@@ -0,0 +1,20 @@
+// `-Zprint-type-sizes-format=json` writes the type layouts of a crate to
+// `<crate>.type_sizes.json` instead of printing them. This test checks that
+// the file is written and contains the def-path, padding and niche of a type,
+// with 128-bit niche ranges written as strings so they don't lose precision.
+
+use run_make_support::{rfs, rustc};
+
+fn main() {
+    rustc()
+        .crate_type("lib")
+        .input("foo.rs")
+        .arg("-Zprint-type-sizes")
+        .arg("-Zprint-type-sizes-format=json")
+        .run();
+    let json = rfs::read_to_string("foo.type_sizes.json");
+    assert!(json.contains(r#""def_path": "Pair""#));
+    assert!(json.contains(r#""padding_before": 3"#));
+    assert!(json.contains(r#""def_path": "Big""#));
+    assert!(json.contains(r#""valid_range_end": "340282366920938463463374607431768211455""#));
+}
@@ -0,0 +1,26 @@
+//! `--error-format=sarif` must write a single SARIF log, even if diagnostics are emitted both
+//! before the session is created and during the compilation.
+
+use run_make_support::serde_json::{self, Value};
+use run_make_support::{rfs, rustc};
+
+fn main() {
+    rfs::write("lib.rs", "pub fn f() {\n    let unused = 1;\n}\n");
+    let output = rustc()
+        .input("lib.rs")
+        .crate_type("lib")
+        .error_format("sarif")
+        .arg("-Zunstable-options")
+        // Warned about while the command line is parsed, so before the session exists.
+        .arg("-Zremark-dir=remarks")
+        .run();
+
+    let stderr = output.stderr_utf8();
+    assert_eq!(stderr.lines().count(), 1, "{stderr}");
+    let log: Value = serde_json::from_str(&stderr).unwrap();
+    let results = log["runs"][0]["results"].as_array().unwrap();
+    let is_remark_dir_warning =
+        |result: &Value| result["message"]["text"].as_str().unwrap().contains("-Z remark-dir");
+    assert!(results.iter().any(is_remark_dir_warning), "{stderr}");
+    assert!(results.iter().any(|result| result["ruleId"] == "unused_variables"), "{stderr}");
+}
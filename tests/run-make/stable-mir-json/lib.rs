@@ -0,0 +1,12 @@
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+pub fn norm(p: &Point) -> i32 {
+    p.x * p.x + p.y * p.y
+}
+
+pub fn total(points: &[Point]) -> i32 {
+    points.iter().map(norm).sum()
+}
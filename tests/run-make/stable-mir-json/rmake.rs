@@ -0,0 +1,44 @@
+// `-Zunpretty=stable-mir-json` dumps the stable MIR of a crate as JSON. Types and spans are
+// serialized as indices, so this test checks that the tables in the output resolve every
+// type and span used by the local declarations of the exported bodies. The mono items must
+// include the instantiations of upstream generic functions that the crate calls.
+
+use run_make_support::rustc;
+use run_make_support::serde_json::{self, Value};
+
+fn main() {
+    let out = rustc()
+        .crate_type("lib")
+        .input("lib.rs")
+        .arg("-Zunpretty=stable-mir-json")
+        .run()
+        .stdout_utf8();
+    let krate: Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(krate["name"], "lib");
+
+    let table = |name: &str| -> Vec<u64> {
+        krate[name].as_array().unwrap().iter().map(|entry| entry[0].as_u64().unwrap()).collect()
+    };
+    let types = table("types");
+    let spans = table("spans");
+
+    let items = krate["items"].as_array().unwrap();
+    let norm = items.iter().find(|item| item["name"] == "norm").unwrap();
+    assert_eq!(norm["kind"], "Fn");
+    for local in norm["body"]["locals"].as_array().unwrap() {
+        assert!(types.contains(&local["ty"].as_u64().unwrap()));
+        assert!(spans.contains(&local["span"].as_u64().unwrap()));
+    }
+
+    let mono_items = krate["mono_items"].as_array().unwrap();
+    let sum = mono_items
+        .iter()
+        .find(|item| item["name"].as_str().is_some_and(|name| name.contains("Iterator::sum")))
+        .unwrap();
+    for local in sum["body"]["locals"].as_array().unwrap() {
+        assert!(types.contains(&local["ty"].as_u64().unwrap()));
+    }
+
+    let defs = krate["defs"].as_array().unwrap();
+    assert!(defs.iter().any(|def| def[1] == "Point"));
+}
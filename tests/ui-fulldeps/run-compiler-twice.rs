@@ -73,6 +73,7 @@ fn compile(code: String, output: PathBuf, sysroot: PathBuf, linker: Option<&Path
         override_queries: None,
         make_codegen_backend: None,
         registry: rustc_driver::diagnostics_registry(),
+        sarif_log: None,
         using_internal_features: std::sync::Arc::default(),
         expanded_args: Default::default(),
     };
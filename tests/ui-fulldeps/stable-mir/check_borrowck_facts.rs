@@ -0,0 +1,172 @@
+//@ run-pass
+//! Test that borrow-checker facts are available through the stable mir APIs.
+
+//@ ignore-stage1
+//@ ignore-cross-compile
+//@ ignore-remote
+//@ ignore-windows-gnu mingw has troubles with linking https://github.com/rust-lang/rust/pull/116837
+
+#![feature(rustc_private)]
+#![feature(assert_matches)]
+
+#[macro_use]
+extern crate rustc_smir;
+extern crate rustc_driver;
+extern crate rustc_interface;
+extern crate stable_mir;
+
+use rustc_smir::rustc_internal;
+use stable_mir::mir::borrowck::{BorrowckFacts, PointKind};
+use stable_mir::mir::{BorrowKind, TerminatorKind};
+use stable_mir::ty::{RegionKind, RigidTy, TyKind};
+use stable_mir::{CrateItem, CrateItems};
+use std::assert_matches::assert_matches;
+use std::io::Write;
+use std::ops::ControlFlow;
+
+const CRATE_NAME: &str = "input";
+
+/// This function uses the Stable MIR APIs to get information about the test crate.
+fn test_stable_mir() -> ControlFlow<()> {
+    let items = stable_mir::all_local_items();
+
+    let facts = get_item(&items, "borrows").unwrap().borrowck_facts().unwrap();
+    check_points(&facts);
+    check_loans(&facts);
+    check_move_paths(&facts);
+
+    let facts = get_item(&items, "loops").unwrap().borrowck_facts().unwrap();
+    check_points(&facts);
+    assert!(facts.body.blocks.iter().any(|block| {
+        matches!(block.terminator.kind, TerminatorKind::FalseUnwind { .. })
+    }));
+
+    // Coroutines are borrow-checked before they are turned into state machines.
+    let facts = get_item(&items, "coroutine::{closure#0}").unwrap().borrowck_facts().unwrap();
+    check_points(&facts);
+    assert!(facts.body.blocks.iter().any(|block| {
+        matches!(block.terminator.kind, TerminatorKind::Yield { drop: Some(_), .. })
+    }));
+    assert!(
+        facts
+            .body
+            .blocks
+            .iter()
+            .any(|block| matches!(block.terminator.kind, TerminatorKind::CoroutineDrop))
+    );
+
+    // Only the facts of the requested bodies are recorded.
+    assert!(get_item(&items, "not_requested").unwrap().borrowck_facts().is_err());
+
+    ControlFlow::Continue(())
+}
+
+/// Every statement and terminator has a start and a mid point, and all facts refer to them.
+fn check_points(facts: &BorrowckFacts) {
+    let locations: usize = facts.body.blocks.iter().map(|block| block.statements.len() + 1).sum();
+    assert_eq!(facts.points.len(), 2 * locations);
+    assert_eq!(facts.points[0].block, 0);
+    assert_eq!(facts.points[0].kind, PointKind::Start);
+    assert_eq!(facts.points[1].kind, PointKind::Mid);
+
+    let input = &facts.input_facts;
+    assert!(!input.cfg_edge.is_empty());
+    assert!(!input.universal_region.is_empty());
+    for &(from, to) in &input.cfg_edge {
+        assert!(from < facts.points.len() && to < facts.points.len());
+    }
+}
+
+fn check_loans(facts: &BorrowckFacts) {
+    assert!(!facts.loans.is_empty());
+    assert_eq!(facts.input_facts.loan_issued_at.len(), facts.loans.len());
+    for &(region, loan, point) in &facts.input_facts.loan_issued_at {
+        assert_eq!(facts.loans[loan].region, region);
+        assert_eq!(facts.loans[loan].reserve_point, point);
+    }
+    assert!(facts.loans.iter().any(|loan| loan.kind == BorrowKind::Shared));
+
+    // Regions of the borrow-checked body are inference variables.
+    let TyKind::RigidTy(RigidTy::Ref(region, ..)) = facts.body.arg_locals()[0].ty.kind() else {
+        unreachable!()
+    };
+    assert_matches!(region.kind, RegionKind::ReVar(_));
+
+    for &(loan, point) in &facts.loans_out_of_scope_at {
+        assert!(loan < facts.loans.len() && point < facts.points.len());
+    }
+}
+
+fn check_move_paths(facts: &BorrowckFacts) {
+    let arg = facts.input_facts.path_is_var.iter().find(|&&(_, local)| local == 1).unwrap();
+    let path = &facts.move_paths[arg.0];
+    assert_eq!(path.place.local, 1);
+    assert!(path.place.projection.is_empty());
+    assert_eq!(path.parent, None);
+}
+
+fn get_item<'a>(items: &'a CrateItems, name: &str) -> Option<&'a CrateItem> {
+    items.iter().find(|crate_item| crate_item.name() == name)
+}
+
+/// This test will generate and analyze a dummy crate using the stable mir.
+/// For that, it will first write the dummy crate into a file.
+/// Then it will create a `StableMir` using custom arguments and then
+/// it will run the compiler.
+fn main() {
+    let path = "borrowck_facts_input.rs";
+    generate_input(&path).unwrap();
+    let args = vec![
+        "rustc".to_string(),
+        "--crate-type=lib".to_string(),
+        "--edition=2021".to_string(),
+        "--crate-name".to_string(),
+        CRATE_NAME.to_string(),
+        path.to_string(),
+    ];
+    run!(args, test_stable_mir, borrowck_facts = |name| name != "not_requested").unwrap();
+}
+
+fn generate_input(path: &str) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    write!(
+        file,
+        r#"
+        #![feature(coroutines, coroutine_trait, stmt_expr_attributes)]
+
+        use std::ops::Coroutine;
+
+        pub fn borrows(v: &mut Vec<u8>) -> u8 {{
+            let first = &v[0];
+            let x = *first;
+            v.push(x);
+            x
+        }}
+
+        pub fn loops(mut n: u32) -> u32 {{
+            let mut acc = 0;
+            while n > 0 {{
+                acc += n;
+                n -= 1;
+            }}
+            acc
+        }}
+
+        pub fn coroutine() -> impl Coroutine<u32, Yield = u32, Return = u32> {{
+            #[coroutine]
+            |mut sum: u32| {{
+                let mut values = vec![1, 2, 3];
+                for value in values.drain(..) {{
+                    sum += yield value;
+                }}
+                sum
+            }}
+        }}
+
+        pub fn not_requested(x: &u8) -> u8 {{
+            *x
+        }}
+        "#
+    )?;
+    Ok(())
+}
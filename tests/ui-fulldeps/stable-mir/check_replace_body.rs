@@ -0,0 +1,145 @@
+//@ run-pass
+//! Test that the body of an item can be replaced through the stable mir APIs.
+
+//@ ignore-stage1
+//@ ignore-cross-compile
+//@ ignore-remote
+//@ ignore-windows-gnu mingw has troubles with linking https://github.com/rust-lang/rust/pull/116837
+
+#![feature(rustc_private)]
+
+#[macro_use]
+extern crate rustc_smir;
+extern crate rustc_driver;
+extern crate rustc_interface;
+extern crate stable_mir;
+
+use rustc_smir::rustc_internal;
+use stable_mir::mir::mono::Instance;
+use stable_mir::mir::{
+    Body, ConstOperand, Operand, Rvalue, Statement, StatementKind, TerminatorKind,
+};
+use stable_mir::ty::{MirConst, UintTy};
+use stable_mir::{CrateItem, CrateItems};
+use std::io::Write;
+use std::ops::ControlFlow;
+use std::process::Command;
+
+const CRATE_NAME: &str = "input";
+const OUTPUT: &str = "replace_body_output";
+
+/// This function uses the Stable MIR APIs to replace the body of `answer`.
+fn test_stable_mir() -> ControlFlow<()> {
+    let items = stable_mir::all_local_items();
+    let answer = get_item(&items, "answer").unwrap();
+    let body = answer.body();
+    assert_eq!(returned_value(&body), Some(42));
+
+    // References to blocks or locals that do not exist are rejected.
+    let mut invalid = body.clone();
+    invalid.blocks[0].terminator.kind = TerminatorKind::Goto { target: 100 };
+    let error = answer.replace_body(&invalid).unwrap_err();
+    assert!(error.to_string().contains("invalid basic block"), "{error}");
+    let mut invalid = body.clone();
+    invalid.blocks[0]
+        .statements
+        .push(Statement { kind: StatementKind::StorageLive(100), span: body.span });
+    let error = answer.replace_body(&invalid).unwrap_err();
+    assert!(error.to_string().contains("invalid local"), "{error}");
+    assert_eq!(returned_value(&answer.body()), Some(42));
+
+    let new_body = change_returned_value(body, 10);
+    answer.replace_body(&new_body).unwrap();
+    assert_eq!(returned_value(&answer.body()), Some(10));
+
+    let instance = Instance::try_from(*answer).unwrap();
+    assert_eq!(returned_value(&instance.body().unwrap()), Some(10));
+
+    // Only the bodies of functions can be replaced.
+    let constant = get_item(&items, "LIMIT").unwrap();
+    let error = constant.replace_body(&new_body).unwrap_err();
+    assert!(error.to_string().contains("Only the body of"), "{error}");
+
+    ControlFlow::Continue(())
+}
+
+/// Find the constant assigned to the return place.
+fn returned_value(body: &Body) -> Option<u128> {
+    body.blocks.iter().flat_map(|block| &block.statements).find_map(|statement| {
+        match &statement.kind {
+            StatementKind::Assign(place, Rvalue::Use(Operand::Constant(constant)))
+                if place.local == 0 =>
+            {
+                Some(constant.const_.eval_target_usize().unwrap() as u128)
+            }
+            _ => None,
+        }
+    })
+}
+
+fn change_returned_value(mut body: Body, value: u128) -> Body {
+    for block in &mut body.blocks {
+        for statement in &mut block.statements {
+            match &mut statement.kind {
+                StatementKind::Assign(place, Rvalue::Use(Operand::Constant(constant)))
+                    if place.local == 0 =>
+                {
+                    *constant = ConstOperand {
+                        span: constant.span,
+                        user_ty: None,
+                        const_: MirConst::try_from_uint(value, UintTy::Usize).unwrap(),
+                    };
+                }
+                _ => {}
+            }
+        }
+    }
+    body
+}
+
+fn get_item<'a>(items: &'a CrateItems, name: &str) -> Option<&'a CrateItem> {
+    items.iter().find(|crate_item| crate_item.name() == name)
+}
+
+/// This test will generate and analyze a dummy crate using the stable mir.
+/// For that, it will first write the dummy crate into a file.
+/// Then it will create a `StableMir` using custom arguments and then
+/// it will run the compiler, and finally run the generated binary.
+fn main() {
+    let path = "replace_body_input.rs";
+    generate_input(&path).unwrap();
+    let args = vec![
+        "rustc".to_string(),
+        "--crate-type=bin".to_string(),
+        "--crate-name".to_string(),
+        CRATE_NAME.to_string(),
+        "-o".to_string(),
+        OUTPUT.to_string(),
+        path.to_string(),
+    ];
+    run!(args, test_stable_mir).unwrap();
+
+    let status = Command::new(format!("./{OUTPUT}")).status().unwrap();
+    assert_eq!(status.code(), Some(10));
+}
+
+fn generate_input(path: &str) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    write!(
+        file,
+        r#"
+        #[inline(never)]
+        fn answer() -> usize {{
+            42
+        }}
+
+        const LIMIT: usize = 20;
+
+        fn main() {{
+            assert!(answer() < LIMIT);
+            std::process::exit(answer() as i32);
+        }}
+        "#
+    )?;
+    Ok(())
+}
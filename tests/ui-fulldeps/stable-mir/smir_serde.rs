@@ -1,5 +1,5 @@
 //@ run-pass
-//! Test that users are able to use serialize stable MIR constructs.
+//! Test that users are able to serialize stable MIR constructs and deserialize them again.
 
 //@ ignore-stage1
 //@ ignore-cross-compile
@@ -23,9 +23,10 @@
 use rustc_middle::ty::TyCtxt;
 use rustc_smir::rustc_internal;
 use stable_mir::mir::Body;
+use stable_mir::{CrateItem, Symbol};
 use std::io::{Write, BufWriter};
 use std::ops::ControlFlow;
-use serde_json::to_string;
+use serde_json::{from_str, to_string};
 
 
 const CRATE_NAME: &str = "input";
@@ -35,14 +36,21 @@ fn serialize_to_json(_tcx: TyCtxt<'_>) -> ControlFlow<()> {
     let mut writer = BufWriter::new(std::fs::File::create(path)
         .expect("Failed to create path"));
     let local_crate = stable_mir::local_crate();
-    let items: Vec<Body> = stable_mir::all_local_items()
+    let items: Vec<(CrateItem, Body)> = stable_mir::all_local_items()
         .iter()
-        .map(|item| { item.body() })
+        .map(|item| { (*item, item.body()) })
         .collect();
     let crate_data = ( local_crate.name, items );
-    writer.write_all(to_string(&crate_data)
-        .expect("serde_json failed")
-        .as_bytes()).expect("JSON serialization failed");
+    let json = to_string(&crate_data).expect("serde_json failed");
+    writer.write_all(json.as_bytes()).expect("JSON serialization failed");
+
+    // Deserializing the output has to give back the same items and bodies.
+    let round_trip: (Symbol, Vec<(CrateItem, Body)>) =
+        from_str(&json).expect("JSON deserialization failed");
+    assert_eq!(round_trip.0, CRATE_NAME);
+    assert_eq!(round_trip.1.iter().map(|(item, _)| *item).collect::<Vec<_>>(),
+               stable_mir::all_local_items());
+    assert_eq!(to_string(&round_trip).unwrap(), json);
     ControlFlow::Continue(())
 }
 
@@ -68,6 +76,11 @@ fn generate_input(path: &str) -> std::io::Result<()> {
         file,
         r#"
     pub fn main() {{
+        let _ = add(1, 2);
+    }}
+
+    pub fn add(a: u32, b: u32) -> u32 {{
+        a + b
     }}
     "#
     )?;
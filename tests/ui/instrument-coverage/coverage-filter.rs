@@ -0,0 +1,19 @@
+//@ revisions: good invalid missing
+//@ compile-flags -Cinstrument-coverage -Zno-profiler-runtime
+
+//@ [good] check-pass
+//@ [good] compile-flags: -Zcoverage-options=filter={{src-base}}/instrument-coverage/coverage-filter.txt
+
+//@ [invalid] check-fail
+//@ [invalid] compile-flags: -Zcoverage-options=filter={{src-base}}/instrument-coverage/coverage-filter-invalid.txt
+
+//@ [missing] check-fail
+//@ [missing] compile-flags: -Zcoverage-options=filter={{src-base}}/instrument-coverage/does-not-exist.txt
+
+mod generated {
+    pub fn parse() {}
+}
+
+fn main() {
+    generated::parse();
+}
@@ -0,0 +1,42 @@
+//@ edition: 2021
+//@ check-pass
+//@ compile-flags: -Cinstrument-coverage -Zcoverage-options=mcdc -Zno-profiler-runtime
+
+// Check that match guards and the scrutinees of `let` expressions are given
+// their own MC/DC decisions, instead of adding their conditions to a decision
+// that is still being lowered around them.
+//
+// Each decision below has at most 6 conditions, but merging any of them with
+// an enclosing decision would exceed that limit and cause a warning.
+//
+// (Decision depth is tracked in `compiler/rustc_mir_build/src/build/coverageinfo/mcdc.rs`.)
+
+#![feature(let_chains)]
+
+fn guard_in_logical_op(a: bool, b: bool, c: bool, d: bool, x: Option<bool>) -> bool {
+    a && b && c && d && matches!(x, Some(y) if y && a && b && c)
+}
+
+fn guard_in_match(x: Option<u32>, a: bool, b: bool, c: bool, d: bool, e: bool) -> u32 {
+    match x {
+        Some(y) if y > 0 && a && b && c && d && e => 1,
+        _ => 0,
+    }
+}
+
+fn scrutinee_in_let_chain(a: bool, b: bool, c: bool, d: bool, x: Option<u32>) {
+    if let true = (a && b && c && d)
+        && let Some(y) = x
+        && y > 0
+        && a
+        && b
+    {
+        core::hint::black_box("hello");
+    }
+}
+
+fn main() {
+    guard_in_logical_op(true, true, true, true, Some(true));
+    guard_in_match(Some(1), true, true, true, true, true);
+    scrutinee_in_let_chain(true, true, true, true, Some(1));
+}
@@ -0,0 +1,4 @@
+//@ check-pass
+//@ compile-flags: -Zincremental-explain=explain.json
+
+fn main() {}